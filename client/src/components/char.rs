@@ -15,8 +15,6 @@ use crate::audio::sound_sys::AudioCommandCollectorComponent;
 use crate::components::controller::{
     CameraComponent, HumanInputComponent, LocalPlayerController, SkillKey,
 };
-use crate::components::skills::skills::Skills;
-use crate::components::status::status::Statuses;
 use crate::grf::SpriteResource;
 use crate::render::render_command::RenderCommandCollector;
 use crate::runtime_assets::ecs::create_ecs_world;
@@ -415,16 +413,8 @@ impl CharacterEntityBuilder {
 #[derive(Eq, PartialEq, Hash)]
 pub struct ComponentRadius(pub i32);
 
-#[derive(Clone, Debug, PartialEq)]
-pub struct CastingSkillData {
-    pub target_area_pos: Option<Vec2>,
-    pub char_to_skill_dir_when_casted: Vec2,
-    pub target_entity: Option<LocalCharEntityId>,
-    pub cast_started: LocalTime,
-    pub cast_ends: LocalTime,
-    pub can_move: bool,
-    pub skill: Skills,
-}
+pub type CastingSkillData =
+    rustarok_common::components::skills::CastingSkillData<LocalCharEntityId>;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum ClientCharState {
@@ -444,7 +434,7 @@ pub fn get_sprite_index(state: &CharState<LocalCharEntityId>, is_monster: bool)
         (CharState::Attacking { .. }, false) => CharActionIndex::Attacking3 as usize,
         (CharState::ReceivingDamage, false) => CharActionIndex::ReceivingDamage as usize,
         (CharState::Dead, false) => CharActionIndex::Dead as usize,
-        //        (CharState::CastingSkill { .. }, false) => CharActionIndex::CastingSpell as usize,
+        (CharState::CastingSkill { .. }, false) => CharActionIndex::CastingSpell as usize,
 
         // monster
         (CharState::Idle, true) => MonsterActionIndex::Idle as usize,
@@ -453,7 +443,7 @@ pub fn get_sprite_index(state: &CharState<LocalCharEntityId>, is_monster: bool)
         (CharState::Attacking { .. }, true) => MonsterActionIndex::Attack as usize,
         (CharState::ReceivingDamage, true) => MonsterActionIndex::ReceivingDamage as usize,
         (CharState::Dead, true) => MonsterActionIndex::Die as usize,
-        //        (CharState::CastingSkill { .. }, true) => MonsterActionIndex::Attack as usize,
+        (CharState::CastingSkill { .. }, true) => MonsterActionIndex::Attack as usize,
     }
 }
 
@@ -524,8 +514,6 @@ impl Component for NpcComponent {
 pub struct CharacterStateComponent {
     y: f32,
     prev_state: CharState<LocalCharEntityId>,
-    // TODO: the whole Statuses struct needs for simulation but not for state representation. Extract the array from it for serialization
-    pub statuses: Statuses,
     pub body_handle: DefaultBodyHandle,
     pub collider_handle: DefaultColliderHandle,
 }
@@ -565,72 +553,15 @@ impl CharacterStateComponent {
         team: Team,
         base_attrs: CharAttributes,
     ) -> CharacterStateComponent {
-        let statuses = Statuses::new();
         CharacterStateComponent {
             y,
             prev_state: CharState::Idle,
-            statuses,
             // hack, remove these
             body_handle: DefaultBodyHandle::from_raw_parts(1, 2),
             collider_handle: DefaultBodyHandle::from_raw_parts(1, 2),
         }
     }
 
-    pub fn recalc_attribs_based_on_statuses(&mut self, dev_configs: &CommonConfigs) {
-        // TODO2
-        //        let base_attributes = CharAttributes::get_base_attributes(self.job_id, dev_configs);
-        //        let modifier_collector = self.statuses.calc_attributes();
-        //        self.calculated_attribs = base_attributes.apply(modifier_collector);
-        //
-        //        self.attrib_bonuses = self
-        //            .calculated_attribs
-        //            .differences(&base_attributes, modifier_collector);
-    }
-
-    // for tests
-    #[allow(dead_code)]
-    pub fn get_status_count(&self) -> usize {
-        self.statuses.count()
-    }
-
-    pub fn update_statuses(
-        &mut self,
-        self_char_id: LocalCharEntityId,
-        sys_vars: &mut SystemVariables,
-        time: &EngineTime,
-        entities: &Entities,
-        updater: &mut LazyUpdate,
-        phyisics_world: &mut PhysicEngine,
-        dev_configs: &CommonConfigs,
-    ) {
-        // TODO: refactor this
-        //                 a hack so statuses and self can be mut at the same time
-        let mut mut_statuses = std::mem::replace(&mut self.statuses, Statuses::new());
-        // TODO2 status
-        //        let bit_indices_of_changed_statuses = mut_statuses.update(
-        //            self_char_id,
-        //            self,
-        //            phyisics_world,
-        //            sys_vars,
-        //            time,
-        //            entities,
-        //            updater,
-        //        );
-        //        std::mem::replace(&mut self.statuses, mut_statuses);
-        //        if bit_indices_of_changed_statuses > 0 {
-        //            self.statuses
-        //                .remove_statuses(bit_indices_of_changed_statuses);
-        //            self.recalc_attribs_based_on_statuses(dev_configs);
-        //            // TODO2
-        //            //            log::trace!(
-        //            //                "Status expired. Attributes({:?}): mod: {:?}, attribs: {:?}",
-        //            //                self_char_id,
-        //            //                self.attrib_bonuses(),
-        //            //                self.calculated_attribs()
-        //            //            );
-        //        }
-    }
-
     pub fn set_y(&mut self, y: f32) {
         self.y = y;
     }
@@ -654,8 +585,7 @@ impl CharacterStateComponent {
     pub fn went_from_casting_to_idle(&self, current_state: &CharState<LocalCharEntityId>) -> bool {
         match current_state {
             CharState::Idle => match self.prev_state {
-                // TODO2
-                //                CharState::CastingSkill(_) => true,
+                CharState::CastingSkill(_) => true,
                 _ => false,
             },
             _ => false,
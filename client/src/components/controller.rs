@@ -1,10 +1,12 @@
 use crate::cam::Camera;
 use crate::components::char::{SpriteBoundingRect, SpriteRenderDescriptorComponent};
-use crate::components::skills::skills::Skills;
+use crate::components::skills::skills::{SkillTargetType, Skills};
 
 use crate::LocalTime;
 use rustarok_common::common::{v2, v3, Mat3, Mat4, Vec2, Vec2u};
-use rustarok_common::components::char::{CharDir, ControllerEntityId, LocalCharEntityId, Team};
+use rustarok_common::components::char::{
+    CharDir, ControllerEntityId, JobId, LocalCharEntityId, Team,
+};
 use rustarok_common::components::controller::{ControllerComponent, PlayerIntention};
 use sdl2::keyboard::Scancode;
 use serde::Deserialize;
@@ -231,6 +233,23 @@ impl EntitiesBelowCursor {
         self.enemy.get(0).map(|it| *it)
     }
 
+    /// The entity which would be the target of the skill if it was casted right now
+    pub fn get_skill_target(
+        &self,
+        skill_target_type: SkillTargetType,
+        self_id: LocalCharEntityId,
+    ) -> Option<LocalCharEntityId> {
+        match skill_target_type {
+            SkillTargetType::NoTarget | SkillTargetType::Area | SkillTargetType::Directional => {
+                None
+            }
+            SkillTargetType::AnyEntity => self.get_enemy_or_friend(),
+            SkillTargetType::OnlyAllyButNoSelf => self.get_friend_except(self_id),
+            SkillTargetType::OnlyAllyAndSelf => self.get_friend(),
+            SkillTargetType::OnlyEnemy => self.get_enemy(),
+        }
+    }
+
     pub fn get_all(&self) -> (&[LocalCharEntityId], &[LocalCharEntityId]) {
         (self.friendly.as_slice(), self.enemy.as_slice())
     }
@@ -304,6 +323,33 @@ impl HumanInputComponent {
         self.skills_for_keys[skill_key as usize] = Some(skill);
     }
 
+    /// Mounting goes to Y, the other skills of the job to Q, W, E, R, D, 1, 2 and 3
+    pub fn assign_skills_of_job(&mut self, job_id: JobId) {
+        self.skills_for_keys = Default::default();
+        self.assign_skill(SkillKey::A, Skills::AttackMove);
+        let mut keys = [
+            SkillKey::Q,
+            SkillKey::W,
+            SkillKey::E,
+            SkillKey::R,
+            SkillKey::D,
+            SkillKey::Num1,
+            SkillKey::Num2,
+            SkillKey::Num3,
+        ]
+        .iter();
+        for skill in job_id.skills() {
+            let key = if *skill == Skills::Mounting {
+                Some(&SkillKey::Y)
+            } else {
+                keys.next()
+            };
+            if let Some(key) = key {
+                self.assign_skill(*key, *skill);
+            }
+        }
+    }
+
     pub fn mouse_pos(&self) -> Vec2u {
         Vec2u::new(self.last_mouse_x, self.last_mouse_y)
     }
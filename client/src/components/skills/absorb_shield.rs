@@ -1,19 +1,8 @@
-use crate::components::char::ActionPlayMode;
-use crate::components::skills::skills::{
-    FinishCast, SkillDef, SkillManifestation, SkillTargetType,
-};
-use crate::components::status::status::{
-    ApplyStatusComponent, StatusEnum, StatusStackingResult, StatusUpdateResult,
-};
-use crate::effect::StrEffectType;
-use crate::render::render_command::RenderCommandCollector;
-use crate::render::render_sys::RenderDesktopClientSystem;
-use crate::systems::{AssetResources, SystemVariables};
-use rustarok_common::attack::{
-    HpModificationRequest, HpModificationResult, HpModificationResultType, HpModificationType,
-};
-use rustarok_common::common::{EngineTime, LocalTime, Vec2};
-use rustarok_common::components::char::LocalCharEntityId;
+use crate::components::skills::skills::{FinishCast, SkillDef, SkillManifestation};
+use crate::components::status::status::{ApplyStatusComponent, StatusEnum};
+use crate::systems::SystemVariables;
+use rustarok_common::common::EngineTime;
+use rustarok_common::components::status::skill_statuses::AbsorbStatus;
 use rustarok_common::config::CommonConfigs;
 use specs::world::WorldExt;
 
@@ -51,105 +40,4 @@ impl SkillDef for AbsorbShieldSkill {
             ));
         None
     }
-
-    fn get_skill_target_type(&self) -> SkillTargetType {
-        SkillTargetType::OnlyAllyAndSelf
-    }
-}
-
-#[derive(Clone, Debug)]
-pub struct AbsorbStatus {
-    pub caster_entity_id: LocalCharEntityId,
-    pub started: LocalTime,
-    pub animation_started: LocalTime,
-    pub until: LocalTime,
-    pub absorbed_damage: u32,
-}
-
-impl AbsorbStatus {
-    pub fn new(caster_entity_id: LocalCharEntityId, now: LocalTime, duration: f32) -> AbsorbStatus {
-        AbsorbStatus {
-            caster_entity_id,
-            started: now,
-            animation_started: now.add_seconds(-1.9),
-            until: now.add_seconds(duration),
-            absorbed_damage: 0,
-        }
-    }
-
-    pub fn update(
-        &mut self,
-        now: LocalTime,
-        self_char_id: LocalCharEntityId,
-        hp_mod_requests: &mut Vec<HpModificationRequest>,
-    ) -> StatusUpdateResult {
-        if self.until.has_already_passed(now) {
-            if self.absorbed_damage > 0 {
-                hp_mod_requests.push(HpModificationRequest {
-                    src_entity: self.caster_entity_id,
-                    dst_entity: self_char_id,
-                    typ: HpModificationType::Heal(self.absorbed_damage),
-                });
-            }
-            StatusUpdateResult::RemoveIt
-        } else {
-            if self
-                .animation_started
-                .add_seconds(2.0)
-                .has_already_passed(now)
-            {
-                self.animation_started = now.add_seconds(-1.9);
-            }
-            StatusUpdateResult::KeepIt
-        }
-    }
-
-    pub fn hp_mod_is_calculated_but_not_applied_yet(
-        &mut self,
-        outcome: HpModificationResult,
-    ) -> HpModificationResult {
-        return match outcome.typ {
-            HpModificationResultType::Ok(hp_mod_req) => match hp_mod_req {
-                HpModificationType::BasicDamage(value, _, _)
-                | HpModificationType::SpellDamage(value, _)
-                | HpModificationType::Poison(value) => {
-                    self.absorbed_damage += value;
-                    outcome.absorbed()
-                }
-                HpModificationType::Heal(_) => outcome,
-            },
-            HpModificationResultType::Blocked | HpModificationResultType::Absorbed => outcome,
-        };
-    }
-
-    pub fn render(
-        &self,
-        now: LocalTime,
-        assets: &AssetResources,
-        char_pos: Vec2,
-        render_commands: &mut RenderCommandCollector,
-    ) {
-        RenderDesktopClientSystem::render_str(
-            StrEffectType::Ramadan,
-            self.animation_started,
-            &char_pos,
-            assets,
-            now,
-            render_commands,
-            ActionPlayMode::Repeat,
-        );
-    }
-
-    pub fn stack(&self, _other: &StatusEnum) -> StatusStackingResult {
-        // I think it should be overwritten only when the caster_entity_id is the same
-        // otherwise other players should get the healed credits for their armors
-        //        let other_absorb = unsafe { Statuses::hack_cast::<AbsorbStatus>(&other) };
-        //        if other_absorb.until.is_later_than(self.until) {
-        //            self.until = other_absorb.until;
-        //            self.started = other_absorb.started;
-        //            self.caster_entity_id = other_absorb.caster_entity_id;
-        //            self.animation_started = other_absorb.animation_started;
-        //        }
-        StatusStackingResult::AddTheNewStatus
-    }
 }
@@ -4,11 +4,10 @@ use crate::audio::sound_sys::AudioCommandCollectorComponent;
 use crate::components::char::CharacterStateComponent;
 use crate::components::skills::skills::{
     FinishCast, SkillDef, SkillManifestation, SkillManifestationComponent,
-    SkillManifestationUpdateParam, SkillTargetType,
+    SkillManifestationUpdateParam,
 };
 use crate::components::status::status::{
-    ApplyStatusComponent, StatusEnum, StatusEnumDiscriminants, StatusUpdateParams,
-    StatusUpdateResult,
+    ApplyStatusComponent, StatusEnum, StatusEnumDiscriminants,
 };
 use crate::render::render_command::RenderCommandCollector;
 use crate::runtime_assets::map::PhysicEngine;
@@ -51,10 +50,6 @@ impl SkillDef for AssaPhasePrismSkill {
             configs.swap_duration_unit_per_second,
         )))
     }
-
-    fn get_skill_target_type(&self) -> SkillTargetType {
-        SkillTargetType::Directional
-    }
 }
 
 struct AssaPhasePrismSkillManifestation {
@@ -197,39 +192,3 @@ impl SkillManifestation for AssaPhasePrismSkillManifestation {
             .add(assets.sprites.fire_particle);
     }
 }
-
-#[derive(Clone, Debug)]
-pub struct AssaPhasePrismStatus {
-    pub caster_entity_id: LocalCharEntityId,
-    pub started_at: LocalTime,
-    pub ends_at: LocalTime,
-    pub start_pos: Vec2,
-    pub vector: Vec2,
-}
-
-impl AssaPhasePrismStatus {
-    pub fn update(&mut self, params: StatusUpdateParams) -> StatusUpdateResult {
-        // TODO2 physics
-        //        if let Some(body) = params
-        //            .physics_world
-        //            .bodies
-        //            .rigid_body_mut(params.target_char.body_handle)
-        //        {
-        //            if self.ends_at.has_already_passed(params.time.now()) {
-        //                params.target_char.set_collidable(params.physics_world);
-        //                StatusUpdateResult::RemoveIt
-        //            } else {
-        //                let duration_percentage = params
-        //                    .time
-        //                    .now()
-        //                    .percentage_between(self.started_at, self.ends_at);
-        //                let pos = self.start_pos + self.vector * duration_percentage;
-        //                body.set_position(Isometry2::translation(pos.x, pos.y));
-        //                StatusUpdateResult::KeepIt
-        //            }
-        //        } else {
-        //            StatusUpdateResult::RemoveIt
-        //        }
-        StatusUpdateResult::RemoveIt
-    }
-}
@@ -4,8 +4,8 @@ use specs::{Entity, LazyUpdate, ReadStorage};
 use crate::audio::sound_sys::AudioCommandCollectorComponent;
 use crate::components::char::{ActionPlayMode, CharacterStateComponent};
 use crate::components::skills::skills::{
-    FinishCast, SkillDef, SkillManifestation, SkillManifestationComponent,
-    SkillManifestationUpdateParam, SkillTargetType, Skills,
+    render_casting_box, FinishCast, SkillDef, SkillManifestation, SkillManifestationComponent,
+    SkillManifestationUpdateParam,
 };
 use crate::components::StrEffectComponent;
 use crate::effect::StrEffectType;
@@ -56,10 +56,6 @@ impl SkillDef for BrutalTestSkill {
         )))
     }
 
-    fn get_skill_target_type(&self) -> SkillTargetType {
-        SkillTargetType::Area
-    }
-
     fn render_target_selection(
         &self,
         is_castable: bool,
@@ -68,7 +64,7 @@ impl SkillDef for BrutalTestSkill {
         render_commands: &mut RenderCommandCollector,
         configs: &CommonConfigs,
     ) {
-        Skills::render_casting_box(
+        render_casting_box(
             is_castable,
             &v2(
                 configs.skills.brutal_test_skill.width,
@@ -1,6 +1,4 @@
-use crate::components::skills::skills::{
-    FinishCast, SkillDef, SkillManifestation, SkillTargetType,
-};
+use crate::components::skills::skills::{FinishCast, SkillDef, SkillManifestation};
 use crate::components::status::status::RemoveStatusComponent;
 use crate::systems::SystemVariables;
 use rustarok_common::components::char::StatusNature;
@@ -29,8 +27,4 @@ impl SkillDef for CureSkill {
             ));
         None
     }
-
-    fn get_skill_target_type(&self) -> SkillTargetType {
-        SkillTargetType::OnlyAllyAndSelf
-    }
 }
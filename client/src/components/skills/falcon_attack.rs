@@ -4,7 +4,7 @@ use crate::audio::sound_sys::AudioCommandCollectorComponent;
 use crate::components::char::{CharacterStateComponent, SpriteRenderDescriptorComponent};
 use crate::components::skills::skills::{
     FinishCast, SkillDef, SkillManifestation, SkillManifestationComponent,
-    SkillManifestationUpdateParam, SkillTargetType,
+    SkillManifestationUpdateParam,
 };
 use crate::components::status::attrib_mod::WalkingSpeedModifierStatus;
 use crate::components::status::status::{ApplyStatusComponent, StatusEnum};
@@ -95,10 +95,6 @@ impl SkillDef for FalconAttackSkill {
 
         None
     }
-
-    fn get_skill_target_type(&self) -> SkillTargetType {
-        SkillTargetType::Directional
-    }
 }
 
 struct FalconAttackSkillManifestation {
@@ -1,13 +1,10 @@
 use crate::components::char::{CharacterStateComponent, SpriteRenderDescriptorComponent};
 use crate::components::controller::LocalPlayerController;
-use crate::components::skills::skills::{
-    FinishCast, SkillDef, SkillManifestation, SkillTargetType,
-};
-use crate::components::status::status::{StatusUpdateParams, StatusUpdateResult};
+use crate::components::skills::skills::{FinishCast, SkillDef, SkillManifestation};
 use crate::render::render_command::RenderCommandCollector;
 use crate::systems::falcon_ai_sys::FalconComponent;
 use crate::systems::{AssetResources, SystemVariables};
-use rustarok_common::common::{EngineTime, LocalTime, Vec2};
+use rustarok_common::components::status::skill_statuses::FalconCarryStatus;
 use specs::prelude::*;
 
 pub struct FalconCarrySkill;
@@ -80,59 +77,28 @@ impl SkillDef for FalconCarrySkill {
         //        }
         //        None
     }
-
-    fn get_skill_target_type(&self) -> SkillTargetType {
-        SkillTargetType::OnlyAllyAndSelf
-    }
-}
-
-#[derive(Clone, Debug)]
-pub struct FalconCarryStatus {
-    pub started_at: LocalTime,
-    pub ends_at: LocalTime,
-    pub carry_owner: bool,
-    pub end_pos: Vec2,
 }
 
-impl FalconCarryStatus {
-    pub fn update(&mut self, params: StatusUpdateParams) -> StatusUpdateResult {
-        if self.ends_at.has_already_passed(params.time.now()) {
-            // TODO2 physics
-            //            params.target_char.set_collidable(params.physics_world);
-            StatusUpdateResult::RemoveIt
-        } else {
-            StatusUpdateResult::KeepIt
-        }
-    }
-
-    pub fn render(&self, assets: &AssetResources, render_commands: &mut RenderCommandCollector) {
-        if !self.carry_owner {
-            render_commands
-                .circle_3d()
-                .radius(0.5)
-                .color(&[0, 255, 0, 255])
-                .pos_2d(&self.end_pos)
-                .y(0.05)
-                .add();
-
-            render_commands
-                .horizontal_texture_3d()
-                .rotation_rad(3.14)
-                .color_rgb(&[0, 255, 0])
-                .scale(0.5)
-                .pos(&self.end_pos)
-                .add(assets.sprites.falcon.textures[2])
-        }
-    }
+pub fn render_falcon_carry_status(
+    status: &FalconCarryStatus,
+    assets: &AssetResources,
+    render_commands: &mut RenderCommandCollector,
+) {
+    if !status.carry_owner {
+        render_commands
+            .circle_3d()
+            .radius(0.5)
+            .color(&[0, 255, 0, 255])
+            .pos_2d(&status.end_pos)
+            .y(0.05)
+            .add();
 
-    pub fn get_status_completion_percent(&self, now: LocalTime) -> Option<(LocalTime, f32)> {
-        if self.carry_owner {
-            Some((
-                self.ends_at,
-                now.percentage_between(self.started_at, self.ends_at),
-            ))
-        } else {
-            None
-        }
+        render_commands
+            .horizontal_texture_3d()
+            .rotation_rad(3.14)
+            .color_rgb(&[0, 255, 0])
+            .scale(0.5)
+            .pos(&status.end_pos)
+            .add(assets.sprites.falcon.textures[2])
     }
 }
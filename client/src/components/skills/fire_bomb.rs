@@ -1,23 +1,15 @@
 use nalgebra::Isometry2;
 
-use crate::components::char::{ActionPlayMode, CharacterStateComponent};
-use crate::components::skills::skills::{
-    FinishCast, SkillDef, SkillManifestation, SkillTargetType,
-};
+use crate::components::char::CharacterStateComponent;
+use crate::components::skills::skills::{FinishCast, SkillDef, SkillManifestation};
 use crate::components::status::status::{
-    ApplyStatusComponent, ApplyStatusInAreaComponent, StatusEnum, StatusUpdateParams,
-    StatusUpdateResult,
+    ApplyStatusComponent, ApplyStatusInAreaComponent, StatusEnum,
 };
 use crate::components::StrEffectComponent;
-use crate::effect::StrEffectType;
-use crate::render::render_command::RenderCommandCollector;
-use crate::render::render_sys::RenderDesktopClientSystem;
-use crate::systems::{AssetResources, SystemVariables};
-use crate::LocalTime;
-use rustarok_common::common::{EngineTime, Vec2};
-use rustarok_common::components::char::{
-    LocalCharEntityId, StaticCharDataComponent, StatusNature, Team,
-};
+use crate::systems::SystemVariables;
+use rustarok_common::common::EngineTime;
+use rustarok_common::components::char::{StaticCharDataComponent, StatusNature};
+use rustarok_common::components::status::skill_statuses::FireBombStatus;
 use rustarok_common::config::CommonConfigs;
 use specs::world::WorldExt;
 
@@ -62,95 +54,4 @@ impl SkillDef for FireBombSkill {
         }
         None
     }
-
-    fn get_skill_target_type(&self) -> SkillTargetType {
-        SkillTargetType::OnlyEnemy
-    }
-}
-
-#[derive(Clone, Debug)]
-pub struct FireBombStatus {
-    pub caster_entity_id: LocalCharEntityId,
-    pub caster_team: Team,
-    pub damage: u32,
-    pub started: LocalTime,
-    pub until: LocalTime,
-    pub spread_count: u8,
-}
-
-impl FireBombStatus {
-    pub fn update(&mut self, params: StatusUpdateParams) -> StatusUpdateResult {
-        StatusUpdateResult::RemoveIt
-        // TODO2
-        //        let now = params.time.now();
-        //        if self.until.has_already_passed(now) {
-        //            let area_shape = Box::new(ncollide2d::shape::Ball::new(2.0));
-        //            let area_isom = Isometry2::new(params.target_char.pos(), 0.0);
-        //            params
-        //                .sys_vars
-        //                .area_hp_mod_requests
-        //                .push(AreaAttackComponent {
-        //                    area_shape: area_shape.clone(),
-        //                    area_isom: area_isom.clone(),
-        //                    source_entity_id: self.caster_entity_id,
-        //                    typ: HpModificationType::SpellDamage(self.damage, DamageDisplayType::Combo(10)),
-        //                    except: None,
-        //                });
-        //            if self.spread_count < 1 {
-        //                params
-        //                    .sys_vars
-        //                    .apply_area_statuses
-        //                    .push(ApplyStatusInAreaComponent {
-        //                        source_entity_id: self.caster_entity_id,
-        //                        status: StatusEnum::FireBombStatus(FireBombStatus {
-        //                            caster_entity_id: self.caster_entity_id,
-        //                            started: now,
-        //                            until: now.add_seconds(2.0),
-        //                            damage: self.damage,
-        //                            spread_count: self.spread_count + 1,
-        //                            caster_team: self.caster_team,
-        //                        }),
-        //                        area_shape: area_shape.clone(),
-        //                        area_isom: area_isom.clone(),
-        //                        except: Some(params.self_char_id),
-        //                        nature: StatusNature::Harmful,
-        //                        caster_team: self.caster_team,
-        //                    });
-        //            }
-        //            let effect_comp = StrEffectComponent {
-        //                effect_id: StrEffectType::FirePillarBomb.into(),
-        //                pos: params.target_char.pos(),
-        //                start_time: now.add_seconds(-0.5),
-        //                die_at: Some(now.add_seconds(1.0)),
-        //                play_mode: ActionPlayMode::Repeat,
-        //            };
-        //            params.updater.insert(params.entities.create(), effect_comp);
-        //
-        //            StatusUpdateResult::RemoveIt
-        //        } else {
-        //            StatusUpdateResult::KeepIt
-        //        }
-    }
-
-    pub fn render(
-        &self,
-        char_pos: Vec2,
-        now: LocalTime,
-        assets: &AssetResources,
-        render_commands: &mut RenderCommandCollector,
-    ) {
-        RenderDesktopClientSystem::render_str(
-            StrEffectType::FireWall,
-            self.started,
-            &char_pos,
-            assets,
-            now,
-            render_commands,
-            ActionPlayMode::Repeat,
-        );
-    }
-
-    pub fn get_status_completion_percent(&self, now: LocalTime) -> Option<(LocalTime, f32)> {
-        Some((self.until, now.percentage_between(self.started, self.until)))
-    }
 }
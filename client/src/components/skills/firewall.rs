@@ -9,8 +9,8 @@ use crate::audio::sound_sys::AudioCommandCollectorComponent;
 use crate::client::SimulationTime;
 use crate::components::char::{ActionPlayMode, CharacterStateComponent};
 use crate::components::skills::skills::{
-    FinishCast, SkillDef, SkillManifestation, SkillManifestationComponent,
-    SkillManifestationUpdateParam, SkillTargetType, Skills,
+    render_casting_box, FinishCast, SkillDef, SkillManifestation, SkillManifestationComponent,
+    SkillManifestationUpdateParam,
 };
 use crate::components::StrEffectComponent;
 use crate::effect::StrEffectType;
@@ -72,10 +72,6 @@ impl SkillDef for FireWallSkill {
         }
     }
 
-    fn get_skill_target_type(&self) -> SkillTargetType {
-        SkillTargetType::Area
-    }
-
     fn render_target_selection(
         &self,
         is_castable: bool,
@@ -84,7 +80,7 @@ impl SkillDef for FireWallSkill {
         render_commands: &mut RenderCommandCollector,
         configs: &CommonConfigs,
     ) {
-        Skills::render_casting_box(
+        render_casting_box(
             is_castable,
             &v2(configs.skills.firewall.width as f32, 1.0),
             skill_pos,
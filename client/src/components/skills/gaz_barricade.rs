@@ -1,6 +1,6 @@
 use crate::components::char::{CharacterStateComponent, NpcComponent};
 use crate::components::skills::skills::{
-    FinishCast, SkillDef, SkillManifestation, SkillTargetType, Skills,
+    render_casting_box, FinishCast, SkillDef, SkillManifestation,
 };
 use crate::render::render_command::RenderCommandCollector;
 
@@ -60,10 +60,6 @@ impl SkillDef for GazBarricadeSkill {
         //        return None;
     }
 
-    fn get_skill_target_type(&self) -> SkillTargetType {
-        SkillTargetType::Area
-    }
-
     fn render_target_selection(
         &self,
         is_castable: bool,
@@ -73,7 +69,7 @@ impl SkillDef for GazBarricadeSkill {
         _configs: &CommonConfigs,
     ) {
         let pos2d = { Vec2::new((skill_pos.x as i32) as f32, (skill_pos.y as i32) as f32) };
-        Skills::render_casting_box(
+        render_casting_box(
             is_castable,
             &v2(1.0, 1.0),
             &pos2d,
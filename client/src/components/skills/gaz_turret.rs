@@ -3,9 +3,7 @@ use crate::components::char::{
     TurretControllerComponent,
 };
 use crate::components::controller::LocalPlayerController;
-use crate::components::skills::skills::{
-    FinishCast, SkillDef, SkillManifestation, SkillTargetType,
-};
+use crate::components::skills::skills::{FinishCast, SkillDef, SkillManifestation};
 use crate::runtime_assets::map::PhysicEngine;
 
 use rustarok_common::components::char::{
@@ -67,10 +65,6 @@ impl SkillDef for GazTurretSkill {
         }
         None
     }
-
-    fn get_skill_target_type(&self) -> SkillTargetType {
-        SkillTargetType::Area
-    }
 }
 
 pub struct GazDestroyTurretSkill;
@@ -107,10 +101,6 @@ impl SkillDef for GazDestroyTurretSkill {
 
         None
     }
-
-    fn get_skill_target_type(&self) -> SkillTargetType {
-        SkillTargetType::OnlyAllyButNoSelf
-    }
 }
 
 pub struct GazTurretTargetSkill;
@@ -134,8 +124,4 @@ impl SkillDef for GazTurretTargetSkill {
 
         None
     }
-
-    fn get_skill_target_type(&self) -> SkillTargetType {
-        SkillTargetType::OnlyEnemy
-    }
 }
@@ -6,7 +6,7 @@ use crate::components::char::{
 };
 use crate::components::skills::skills::{
     FinishCast, SkillDef, SkillManifestation, SkillManifestationComponent,
-    SkillManifestationUpdateParam, SkillTargetType,
+    SkillManifestationUpdateParam,
 };
 use crate::components::status::status::{ApplyStatusInAreaComponent, StatusEnum};
 use crate::components::status::stun::StunStatus;
@@ -55,10 +55,6 @@ impl SkillDef for GazXplodiumChargeSkill {
                 .clone(),
         )))
     }
-
-    fn get_skill_target_type(&self) -> SkillTargetType {
-        SkillTargetType::Area
-    }
 }
 
 struct GazXplodiumChargeSkillManifestation {
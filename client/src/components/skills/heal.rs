@@ -2,7 +2,7 @@ use specs::{LazyUpdate, ReadStorage};
 
 use crate::components::skills::skills::{
     FinishCast, SkillDef, SkillManifestation, SkillManifestationComponent,
-    SkillManifestationUpdateParam, SkillTargetType,
+    SkillManifestationUpdateParam,
 };
 
 use crate::audio::sound_sys::AudioCommandCollectorComponent;
@@ -58,10 +58,6 @@ impl SkillDef for HealSkill {
             });
         return Some(Box::new(HealSkillManifest::new(target_entity_id, now)));
     }
-
-    fn get_skill_target_type(&self) -> SkillTargetType {
-        SkillTargetType::OnlyAllyAndSelf
-    }
 }
 
 pub struct HealSkillManifest {
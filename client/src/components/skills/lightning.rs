@@ -5,7 +5,7 @@ use crate::audio::sound_sys::AudioCommandCollectorComponent;
 use crate::components::char::{ActionPlayMode, CharacterStateComponent};
 use crate::components::skills::skills::{
     FinishCast, SkillDef, SkillManifestation, SkillManifestationComponent,
-    SkillManifestationUpdateParam, SkillTargetType,
+    SkillManifestationUpdateParam,
 };
 use crate::components::StrEffectComponent;
 use crate::effect::StrEffectType;
@@ -15,7 +15,7 @@ use crate::LocalTime;
 use rustarok_common::attack::{AreaAttackComponent, DamageDisplayType, HpModificationType};
 use rustarok_common::common::{EngineTime, Vec2};
 use rustarok_common::components::char::{LocalCharEntityId, StaticCharDataComponent};
-use rustarok_common::config::CommonConfigs;
+use rustarok_common::config::{CommonConfigs, LightningSkillConfig};
 use specs::world::WorldExt;
 
 pub struct LightningSkill;
@@ -32,35 +32,33 @@ impl SkillDef for LightningSkill {
         params: &FinishCast,
         ecs_world: &mut specs::world::World,
     ) -> Option<Box<dyn SkillManifestation>> {
-        let sys_vars = ecs_world.read_resource::<SystemVariables>();
+        let configs = &ecs_world.read_resource::<CommonConfigs>().skills.lightning;
         Some(Box::new(LightningManifest::new(
             params.caster_entity_id,
             &params.skill_pos.unwrap(),
             &params.char_to_skill_dir,
+            configs.clone(),
             ecs_world.read_resource::<EngineTime>().now(),
             &ecs_world.entities(),
         )))
     }
 
-    fn get_skill_target_type(&self) -> SkillTargetType {
-        SkillTargetType::Area
-    }
-
     fn render_target_selection(
         &self,
         _is_castable: bool,
         skill_pos: &Vec2,
         char_to_skill_dir: &Vec2,
         render_commands: &mut RenderCommandCollector,
-        _configs: &CommonConfigs,
+        configs: &CommonConfigs,
     ) {
-        for i in 0..3 {
-            let pos = skill_pos + char_to_skill_dir * i as f32 * 2.2;
+        let configs = &configs.skills.lightning;
+        for step in &configs.strike_steps {
+            let pos = skill_pos + char_to_skill_dir * (*step as f32 * configs.strike_distance);
             render_commands
                 .circle_3d()
                 .pos_2d(&pos)
                 .y(0.0)
-                .radius(1.0)
+                .radius(configs.strike_radius)
                 .color(&[0, 255, 0, 255])
                 .add()
         }
@@ -72,6 +70,7 @@ pub struct LightningManifest {
     pub effect_id: Entity,
     pub pos: Vec2,
     pub dir_vector: Vec2,
+    pub configs: LightningSkillConfig,
     pub created_at: LocalTime,
     pub next_action_at: LocalTime,
     pub next_damage_at: LocalTime,
@@ -84,6 +83,7 @@ impl LightningManifest {
         caster_entity_id: LocalCharEntityId,
         skill_center: &Vec2,
         dir_vector: &Vec2,
+        configs: LightningSkillConfig,
         now: LocalTime,
         entities: &specs::Entities,
     ) -> LightningManifest {
@@ -97,83 +97,49 @@ impl LightningManifest {
             last_skill_pos: *skill_center,
             action_count: 0,
             dir_vector: *dir_vector,
+            configs,
         }
     }
+
+    fn strike_pos(&self, step: u8) -> Vec2 {
+        return self.pos + self.dir_vector * (step as f32 * self.configs.strike_distance);
+    }
 }
 
 impl SkillManifestation for LightningManifest {
     fn update(&mut self, mut params: SkillManifestationUpdateParam) {
         let now = params.time().now();
-        if self.created_at.add_seconds(12.0).has_already_passed(now) {
+        let duration = self.configs.strike_steps.len() as f32
+            * self.configs.strike_interval_seconds
+            + self.configs.first_strike_delay_seconds;
+        if self
+            .created_at
+            .add_seconds(duration)
+            .has_already_passed(now)
+        {
             params.remove_component::<SkillManifestationComponent>(params.self_entity_id);
             params.remove_component::<StrEffectComponent>(params.self_entity_id);
         } else {
             if self.next_action_at.has_already_passed(now) {
                 params.remove_component::<StrEffectComponent>(self.effect_id);
-                let effect_comp = match self.action_count {
-                    0 => StrEffectComponent {
-                        effect_id: StrEffectType::Lightning.into(),
-                        pos: self.pos,
-                        start_time: now.add_seconds(-0.5),
-                        die_at: Some(now.add_seconds(1.0)),
-                        play_mode: ActionPlayMode::Repeat,
-                    },
-                    1 => {
-                        let pos = self.pos + self.dir_vector * 2.2;
-                        StrEffectComponent {
-                            effect_id: StrEffectType::Lightning.into(),
-                            pos,
-                            start_time: now.add_seconds(-0.5),
-                            die_at: Some(now.add_seconds(1.0)),
-                            play_mode: ActionPlayMode::Repeat,
-                        }
-                    }
-                    2 => {
-                        let pos = self.pos + self.dir_vector * 2.0 * 2.2;
-                        StrEffectComponent {
-                            effect_id: StrEffectType::Lightning.into(),
-                            pos,
-                            start_time: now.add_seconds(-0.5),
-                            die_at: Some(now.add_seconds(1.0)),
-                            play_mode: ActionPlayMode::Repeat,
-                        }
-                    }
-                    3 => {
-                        let pos = self.pos + self.dir_vector * 2.0 * 2.2;
-                        StrEffectComponent {
-                            effect_id: StrEffectType::Lightning.into(),
-                            pos,
-                            start_time: now.add_seconds(-0.5),
-                            die_at: Some(now.add_seconds(1.0)),
-                            play_mode: ActionPlayMode::Repeat,
-                        }
-                    }
-                    4 => {
-                        let pos = self.pos + self.dir_vector * 2.2;
-                        StrEffectComponent {
-                            effect_id: StrEffectType::Lightning.into(),
-                            pos,
-                            start_time: now.add_seconds(-0.5),
-                            die_at: Some(now.add_seconds(1.0)),
-                            play_mode: ActionPlayMode::Repeat,
-                        }
-                    }
-                    5 => StrEffectComponent {
-                        effect_id: StrEffectType::Lightning.into(),
-                        pos: self.pos,
-                        start_time: now.add_seconds(-0.5),
-                        die_at: Some(now.add_seconds(1.0)),
-                        play_mode: ActionPlayMode::Repeat,
-                    },
-                    _ => {
+                let step = match self.configs.strike_steps.get(self.action_count as usize) {
+                    Some(step) => *step,
+                    None => {
                         return;
                     }
                 };
+                let effect_comp = StrEffectComponent {
+                    effect_id: StrEffectType::Lightning.into(),
+                    pos: self.strike_pos(step),
+                    start_time: now.add_seconds(-0.5),
+                    die_at: Some(now.add_seconds(self.configs.first_strike_delay_seconds)),
+                    play_mode: ActionPlayMode::Repeat,
+                };
                 self.last_skill_pos = effect_comp.pos.clone();
                 params.insert_comp(self.effect_id, effect_comp);
                 self.action_count += 1;
-                self.next_action_at = now.add_seconds(1.5);
-                self.next_damage_at = now.add_seconds(1.0);
+                self.next_action_at = now.add_seconds(self.configs.strike_interval_seconds);
+                self.next_damage_at = now.add_seconds(self.configs.first_strike_delay_seconds);
             }
             if self.next_damage_at.has_already_passed(now) {
                 params.add_area_hp_mod_request(AreaAttackComponent {
@@ -181,7 +147,10 @@ impl SkillManifestation for LightningManifest {
                     //                    area_shape: Box::new(ncollide2d::shape::Ball::new(1.0)),
                     //                    area_isom: Isometry2::new(self.last_skill_pos, 0.0),
                     source_entity_id: self.caster_entity_id,
-                    typ: HpModificationType::SpellDamage(120, DamageDisplayType::SingleNumber),
+                    typ: HpModificationType::SpellDamage(
+                        self.configs.damage,
+                        DamageDisplayType::SingleNumber,
+                    ),
                     except: None,
                 });
                 self.next_damage_at = self.next_damage_at.add_seconds(0.6);
@@ -197,28 +166,19 @@ impl SkillManifestation for LightningManifest {
         render_commands: &mut RenderCommandCollector,
         _audio_commands: &mut AudioCommandCollectorComponent,
     ) {
-        for i in self.action_count..3 {
-            let pos = self.pos + self.dir_vector * i as f32 * 2.2;
+        for step in self
+            .configs
+            .strike_steps
+            .iter()
+            .skip(self.action_count as usize)
+        {
             render_commands
                 .circle_3d()
-                .pos_2d(&pos)
+                .pos_2d(&self.strike_pos(*step))
                 .y(0.0)
-                .radius(1.0)
+                .radius(self.configs.strike_radius)
                 .color(&[0, 255, 0, 255])
                 .add();
         }
-        // backwards
-        if self.action_count >= 4 {
-            for i in self.action_count..6 {
-                let pos = self.pos + self.dir_vector * (5 - i) as f32 * 2.2;
-                render_commands
-                    .circle_3d()
-                    .pos_2d(&pos)
-                    .y(0.0)
-                    .radius(1.0)
-                    .color(&[0, 255, 0, 255])
-                    .add();
-            }
-        }
     }
 }
@@ -1,19 +1,16 @@
 use specs::LazyUpdate;
 
-use crate::components::char::{ActionPlayMode, CharacterStateComponent};
-use crate::components::skills::skills::{
-    FinishCast, SkillDef, SkillManifestation, SkillTargetType,
-};
+use crate::components::char::ActionPlayMode;
+use crate::components::skills::skills::{FinishCast, SkillDef, SkillManifestation};
 use crate::components::status::status::{
-    ApplyStatusComponent, RemoveStatusComponent, RemoveStatusComponentPayload, StatusEnum,
-    StatusEnumDiscriminants,
+    calc_mounted_speedup, ApplyStatusComponent, RemoveStatusComponent,
+    RemoveStatusComponentPayload, StatusEnum, StatusEnumDiscriminants,
 };
 use crate::components::StrEffectComponent;
 use crate::effect::StrEffectType;
-use crate::systems::atk_calc::AttackSystem;
 use crate::systems::SystemVariables;
 use rustarok_common::common::EngineTime;
-use rustarok_common::components::char::StaticCharDataComponent;
+use rustarok_common::components::char::{LocalCharStateComp, StaticCharDataComponent};
 use rustarok_common::config::CommonConfigs;
 use specs::world::WorldExt;
 
@@ -48,7 +45,7 @@ impl SkillDef for MountingSkill {
         }
         let mut sys_vars = ecs_world.write_resource::<SystemVariables>();
         if let Some(target_char) = ecs_world
-            .read_storage::<CharacterStateComponent>()
+            .read_storage::<LocalCharStateComp>()
             .get(params.caster_entity_id.into())
         {
             if target_char.statuses.is_mounted() {
@@ -65,8 +62,8 @@ impl SkillDef for MountingSkill {
                     .get(params.caster_entity_id.into())
                     .unwrap();
 
-                let mounted_speedup = AttackSystem::calc_mounted_speedup(
-                    target_char_static,
+                let mounted_speedup = calc_mounted_speedup(
+                    target_char_static.job_id,
                     &ecs_world.read_resource::<CommonConfigs>(),
                 );
                 sys_vars
@@ -82,8 +79,4 @@ impl SkillDef for MountingSkill {
         }
         None
     }
-
-    fn get_skill_target_type(&self) -> SkillTargetType {
-        SkillTargetType::NoTarget
-    }
 }
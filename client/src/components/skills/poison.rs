@@ -1,9 +1,7 @@
 use specs::LazyUpdate;
 
 use crate::components::char::ActionPlayMode;
-use crate::components::skills::skills::{
-    FinishCast, SkillDef, SkillManifestation, SkillTargetType,
-};
+use crate::components::skills::skills::{FinishCast, SkillDef, SkillManifestation};
 use crate::components::status::status::{ApplyStatusComponent, PoisonStatus, StatusEnum};
 use crate::components::StrEffectComponent;
 use crate::effect::StrEffectType;
@@ -56,8 +54,4 @@ impl SkillDef for PosionSkill {
             ));
         None
     }
-
-    fn get_skill_target_type(&self) -> SkillTargetType {
-        SkillTargetType::OnlyEnemy
-    }
 }
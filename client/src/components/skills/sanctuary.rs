@@ -3,8 +3,8 @@ use nalgebra::Isometry2;
 use crate::audio::sound_sys::AudioCommandCollectorComponent;
 use crate::components::char::CharacterStateComponent;
 use crate::components::skills::skills::{
-    FinishCast, SkillDef, SkillManifestation, SkillManifestationComponent,
-    SkillManifestationUpdateParam, SkillTargetType, Skills,
+    render_casting_box, FinishCast, SkillDef, SkillManifestation, SkillManifestationComponent,
+    SkillManifestationUpdateParam,
 };
 use crate::render::opengl_render_sys::Trimesh3dType;
 use crate::render::render_command::RenderCommandCollector;
@@ -42,21 +42,18 @@ impl SkillDef for SanctuarySkill {
         )))
     }
 
-    fn get_skill_target_type(&self) -> SkillTargetType {
-        SkillTargetType::Area
-    }
-
     fn render_target_selection(
         &self,
         is_castable: bool,
         skill_pos: &Vec2,
         _char_to_skill_dir: &Vec2,
         render_commands: &mut RenderCommandCollector,
-        _configs: &CommonConfigs,
+        configs: &CommonConfigs,
     ) {
-        Skills::render_casting_box(
+        let configs = &configs.skills.sanctuary;
+        render_casting_box(
             is_castable,
-            &v2(5.0, 5.0),
+            &v2(configs.width, configs.height),
             skill_pos,
             &v2(0.0, 0.0),
             render_commands,
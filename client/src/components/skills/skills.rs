@@ -3,10 +3,7 @@ use std::sync::{Arc, Mutex};
 
 use nalgebra::Vector2;
 use nphysics2d::object::DefaultColliderHandle;
-use serde::Deserialize;
-use serde::Serialize;
 use specs::prelude::*;
-use strum_macros::EnumIter;
 
 use crate::components::char::{ActionPlayMode, CastingSkillData, CharacterStateComponent};
 use crate::components::skills::absorb_shield::ABSORB_SHIELD_SKILL;
@@ -40,10 +37,9 @@ use crate::render::render_sys::RenderDesktopClientSystem;
 use crate::systems::{AssetResources, Collision, SystemVariables};
 use crate::{LocalTime, PhysicEngine};
 use rustarok_common::attack::{ApplyForceComponent, AreaAttackComponent, HpModificationRequest};
-use rustarok_common::components::char::{
-    LocalCharEntityId, LocalCharStateComp, StaticCharDataComponent, Team,
-};
-use rustarok_common::config::{CommonConfigs, SkillCastingAttributes};
+use rustarok_common::components::char::{LocalCharStateComp, StaticCharDataComponent};
+pub use rustarok_common::components::skills::{FinishCast, SkillTargetType, Skills};
+use rustarok_common::config::CommonConfigs;
 
 pub type WorldCollisions = HashMap<(DefaultColliderHandle, DefaultColliderHandle), Collision>;
 
@@ -202,16 +198,6 @@ unsafe impl Sync for SkillManifestationComponent {}
 
 unsafe impl Send for SkillManifestationComponent {}
 
-pub struct FinishCast {
-    pub skill: Skills,
-    pub caster_entity_id: LocalCharEntityId,
-    pub caster_pos: Vec2,
-    pub caster_team: Team,
-    pub skill_pos: Option<Vec2>,
-    pub char_to_skill_dir: Vec2,
-    pub target_entity: Option<LocalCharEntityId>,
-}
-
 pub trait SkillDef {
     fn get_icon_path(&self) -> &'static str;
     fn finish_cast(
@@ -220,7 +206,6 @@ pub trait SkillDef {
         ecs_world: &mut World,
     ) -> Option<Box<dyn SkillManifestation>>;
 
-    fn get_skill_target_type(&self) -> SkillTargetType;
     fn render_casting(
         &self,
         char_pos: &Vec2,
@@ -269,32 +254,6 @@ pub trait SkillDef {
     }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, EnumIter, Serialize, Deserialize)]
-pub enum Skills {
-    AttackMove,
-    FireWall,
-    BrutalTestSkill,
-    Lightning,
-    Heal,
-    Mounting,
-    Poison,
-    Cure,
-    FireBomb,
-    AbsorbShield,
-    WizPyroBlast,
-    AssaBladeDash,
-    AssaPhasePrism,
-    GazXplodiumCharge,
-    GazTurret,
-    GazBarricade,
-    GazDestroyTurret,
-    GazTurretTarget,
-    FalconCarry,
-    FalconAttack,
-    Sanctuary,
-    ExoSkeleton,
-}
-
 pub struct AttackMoveSkill;
 
 pub const ATTACK_MOVE_SKILL: &'static AttackMoveSkill = &AttackMoveSkill;
@@ -311,14 +270,16 @@ impl SkillDef for AttackMoveSkill {
     ) -> Option<Box<dyn SkillManifestation>> {
         None
     }
+}
 
-    fn get_skill_target_type(&self) -> SkillTargetType {
-        SkillTargetType::Area
-    }
+/// The skill definitions (rendering, client side effects) live only in the client,
+/// the rest of `Skills` is shared with the server
+pub trait SkillDefinition {
+    fn get_definition(&self) -> &'static dyn SkillDef;
 }
 
-impl Skills {
-    pub fn get_definition(&self) -> &'static dyn SkillDef {
+impl SkillDefinition for Skills {
+    fn get_definition(&self) -> &'static dyn SkillDef {
         match self {
             Skills::WizPyroBlast => WIZ_PYRO_BLAST_SKILL,
             Skills::FireWall => FIRE_WALL_SKILL,
@@ -344,133 +305,34 @@ impl Skills {
             Skills::GazBarricade => GAZ_BARRICADE_SKILL,
         }
     }
-
-    pub fn get_cast_attributes<'a>(
-        &'a self,
-        configs: &'a CommonConfigs,
-        char_state: &StaticCharDataComponent,
-    ) -> &'a SkillCastingAttributes {
-        match self {
-            Skills::WizPyroBlast => &configs.skills.wiz_pyroblast.attributes,
-            Skills::FireWall => &configs.skills.firewall.attributes,
-            Skills::Heal => &configs.skills.heal.attributes,
-            Skills::BrutalTestSkill => &configs.skills.brutal_test_skill.attributes,
-            Skills::Lightning => &configs.skills.lightning.attributes,
-            Skills::Mounting => {
-                // TODO2 statuses
-                //                if char_state.statuses.is_mounted() {
-                &configs.skills.unmounting
-                //                } else {
-                //                    &configs.skills.mounting
-                //                }
-            }
-            Skills::Poison => &configs.skills.poison.attributes,
-            Skills::Cure => &configs.skills.cure,
-            Skills::FireBomb => &configs.skills.firebomb.attributes,
-            Skills::AbsorbShield => &configs.skills.absorb_shield.attributes,
-            Skills::AssaBladeDash => &configs.skills.assa_blade_dash.attributes,
-            Skills::AssaPhasePrism => &configs.skills.assa_phase_prism.attributes,
-            Skills::GazXplodiumCharge => &configs.skills.gaz_xplodium_charge.attributes,
-            Skills::GazTurret => &configs.skills.gaz_turret.attributes,
-            Skills::GazDestroyTurret => &configs.skills.gaz_destroy_turret,
-            Skills::GazTurretTarget => &configs.skills.gaz_destroy_turret,
-            // TODO
-            //            Skills::GazTurretTarget => &SkillCastingAttributes {
-            //                casting_time: LocalTime::from(0.0),
-            //                cast_delay: LocalTime::from(0.0),
-            //                casting_range: 999_999_999.0,
-            //                width: None,
-            //            },
-            Skills::FalconCarry => &configs.skills.falcon_carry.attributes,
-            Skills::FalconAttack => &configs.skills.falcon_attack.attributes,
-            Skills::Sanctuary => &configs.skills.sanctuary.attributes,
-            Skills::ExoSkeleton => &configs.skills.exoskeleton.attributes,
-            Skills::AttackMove => &configs.skills.gaz_destroy_turret,
-            // TODO
-            //            Skills::AttackMove => &SkillCastingAttributes {
-            //                casting_time: LocalTime::from(0.0),
-            //                cast_delay: LocalTime::from(0.0),
-            //                casting_range: 200_000_000.0,
-            //                width: None,
-            //            },
-            Skills::GazBarricade => &configs.skills.gaz_barricade.attributes,
-        }
-    }
-
-    pub fn limit_vector_into_range(char_pos: &Vec2, mouse_pos: &Vec2, range: f32) -> (Vec2, Vec2) {
-        let dir2d = mouse_pos - char_pos;
-        let dir_vector = dir2d.normalize();
-        let pos = char_pos + dir_vector * dir2d.magnitude().min(range);
-        return (pos, dir_vector);
-    }
-
-    pub fn render_casting_box(
-        is_castable: bool,
-        casting_area_size: &Vec2,
-        skill_pos: &Vec2,
-        char_to_skill_dir: &Vec2,
-        render_commands: &mut RenderCommandCollector,
-    ) {
-        let angle = char_to_skill_dir.angle(&Vector2::y());
-        let angle = if char_to_skill_dir.x > 0.0 {
-            angle
-        } else {
-            -angle
-        };
-        let skill_pos = v2_to_v3(skill_pos);
-
-        render_commands
-            .rectangle_3d()
-            .pos(&skill_pos)
-            .rotation_rad(angle)
-            .color(
-                &(if is_castable {
-                    [0, 255, 0, 255]
-                } else {
-                    [179, 179, 179, 255]
-                }),
-            )
-            .size(casting_area_size.x, casting_area_size.y)
-            .add()
-    }
-
-    pub fn is_casting_allowed_based_on_target(
-        skill_target_type: SkillTargetType,
-        skill_casting_range: f32,
-        caster_id: LocalCharEntityId,
-        target_entity: Option<LocalCharEntityId>,
-        target_distance: f32,
-    ) -> bool {
-        match skill_target_type {
-            SkillTargetType::Area => true,
-            SkillTargetType::Directional => true,
-            SkillTargetType::NoTarget => true,
-            SkillTargetType::AnyEntity => {
-                target_entity.is_some() && skill_casting_range >= target_distance
-            }
-            SkillTargetType::OnlyAllyButNoSelf => {
-                target_entity.map(|it| it != caster_id).unwrap_or(false)
-                    && skill_casting_range >= target_distance
-            }
-            SkillTargetType::OnlyAllyAndSelf => {
-                target_entity.is_some() && skill_casting_range >= target_distance
-            }
-            SkillTargetType::OnlyEnemy => {
-                target_entity.is_some() && skill_casting_range >= target_distance
-            }
-        }
-    }
 }
 
-#[derive(Eq, PartialEq)]
-#[allow(dead_code)]
-pub enum SkillTargetType {
-    /// casts immediately
-    NoTarget,
-    Area,
-    Directional,
-    AnyEntity,
-    OnlyAllyButNoSelf,
-    OnlyAllyAndSelf,
-    OnlyEnemy,
+pub fn render_casting_box(
+    is_castable: bool,
+    casting_area_size: &Vec2,
+    skill_pos: &Vec2,
+    char_to_skill_dir: &Vec2,
+    render_commands: &mut RenderCommandCollector,
+) {
+    let angle = char_to_skill_dir.angle(&Vector2::y());
+    let angle = if char_to_skill_dir.x > 0.0 {
+        angle
+    } else {
+        -angle
+    };
+    let skill_pos = v2_to_v3(skill_pos);
+
+    render_commands
+        .rectangle_3d()
+        .pos(&skill_pos)
+        .rotation_rad(angle)
+        .color(
+            &(if is_castable {
+                [0, 255, 0, 255]
+            } else {
+                [179, 179, 179, 255]
+            }),
+        )
+        .size(casting_area_size.x, casting_area_size.y)
+        .add()
 }
@@ -7,7 +7,7 @@ use crate::components::char::{
 };
 use crate::components::skills::skills::{
     FinishCast, SkillDef, SkillManifestation, SkillManifestationComponent,
-    SkillManifestationUpdateParam, SkillTargetType,
+    SkillManifestationUpdateParam,
 };
 use crate::components::status::status::{ApplyStatusComponent, StatusEnum};
 use crate::effect::StrEffectType;
@@ -20,6 +20,7 @@ use rustarok_common::common::{v2, EngineTime, Vec2};
 use rustarok_common::components::char::{
     CharDir, LocalCharEntityId, LocalCharStateComp, StaticCharDataComponent,
 };
+use rustarok_common::components::status::skill_statuses::PyroBlastTargetStatus;
 use rustarok_common::config::{CommonConfigs, SkillConfigPyroBlastInner};
 use specs::world::WorldExt;
 
@@ -65,10 +66,6 @@ impl SkillDef for WizPyroBlastSkill {
         )))
     }
 
-    fn get_skill_target_type(&self) -> SkillTargetType {
-        SkillTargetType::OnlyEnemy
-    }
-
     fn render_casting(
         &self,
         char_pos: &Vec2,
@@ -246,26 +243,3 @@ impl SkillManifestation for PyroBlastManifest {
         );
     }
 }
-
-#[derive(Clone, Debug)]
-pub struct PyroBlastTargetStatus {
-    pub caster_entity_id: LocalCharEntityId,
-    pub splash_radius: f32,
-}
-
-impl PyroBlastTargetStatus {
-    pub fn render(
-        &self,
-        char_pos: Vec2,
-        now: LocalTime,
-        assets: &AssetResources,
-        render_commands: &mut RenderCommandCollector,
-    ) {
-        render_commands
-            .horizontal_texture_3d()
-            .pos(&char_pos)
-            .rotation_rad(now.as_millis() as f32 % 6.28)
-            .fix_size(self.splash_radius * 2.0)
-            .add(assets.sprites.magic_target);
-    }
-}
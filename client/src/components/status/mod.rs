@@ -1,10 +1,8 @@
 #[macro_use]
 pub mod status;
-pub mod attack_heal_status;
-pub mod attrib_mod;
-pub mod death_status;
 pub mod heal_area;
-pub mod reflect_damage_status;
-pub mod sacrafice_status;
 pub mod status_applier_area;
-pub mod stun;
+
+pub use rustarok_common::components::status::{
+    attack_heal_status, attrib_mod, death_status, reflect_damage_status, sacrafice_status, stun,
+};
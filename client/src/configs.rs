@@ -1,18 +1,27 @@
 use crate::components::controller::CastMode;
 use rustarok_common::char_attr::CharAttributes;
 use rustarok_common::common::Percentage;
+use rustarok_common::components::char::{JobId, Team};
 use rustarok_common::config::{CommonConfigs, DevConfigStats, SkillsConfig};
+use rustarok_common::packets::Transport;
 use serde::Deserialize;
 
 #[derive(Debug, Deserialize)]
 pub struct AppConfig {
     pub username: String,
+    /// the job of the character which is created after joining the server
+    pub job: JobId,
+    /// Left or Right, without it the server balances the teams
+    pub team: Option<Team>,
+    /// the room is created if it does not exist, without it the default room is joined
+    pub room: Option<String>,
     pub max_fps: usize,
     pub log_level: String,
     pub resolution_w: u32,
     pub resolution_h: u32,
     pub grf_paths: Vec<String>,
     pub server_addr: String,
+    pub transport: Transport,
     pub load_models: bool,
     pub load_sprites: bool,
     pub cast_mode: CastMode,
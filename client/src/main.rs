@@ -34,32 +34,37 @@ use strum;
 
 use rustarok_common::attack::{ApplyForceComponent, AreaAttackComponent, HpModificationRequest};
 use rustarok_common::common::{
-    measure_time, v2, EngineTime, LocalTime, ServerTime, SimulationTick, Vec2,
+    measure_time, EngineTime, LocalTime, ServerTime, SimulationTick, Vec2,
 };
 use rustarok_common::components::char::EntityTarget;
 use rustarok_common::components::char::{
-    create_common_player_entity, CharDir, CharOutlook, CollisionGroup, ControllerEntityId, JobId,
+    create_common_player_entity, CharOutlook, CollisionGroup, ControllerEntityId, JobId,
     LocalCharEntityId, LocalCharStateComp, ServerCharState, ServerEntityId, Sex,
     StaticCharDataComponent, Team,
 };
 use rustarok_common::components::job_ids::JobSpriteId;
 use rustarok_common::config::CommonConfigs;
 use rustarok_common::console::CommandArguments;
-use rustarok_common::packets::from_server::{FromServerPacket, ServerEntityStateLocal};
+use rustarok_common::map::MapWalkingInfo;
+use rustarok_common::packets::from_server::{
+    FromServerPacket, ServerEntityStateLocal, SessionToken,
+};
 use rustarok_common::packets::to_server::ToServerPacket;
-use rustarok_common::packets::{NetworkTrafficEvent, PacketHandlerThread, SocketBuffer, SocketId};
+use rustarok_common::packets::udp::connect_udp;
+use rustarok_common::packets::{
+    NetworkTrafficEvent, PacketHandlerThread, SocketBuffer, SocketId, Transport,
+};
 use rustarok_common::systems::char_state_sys::CharacterStateUpdateSystem;
+use rustarok_common::systems::minion_ai::MinionAi;
 
 use crate::audio::sound_sys::{AudioCommandCollectorComponent, SoundSystem};
 use crate::client::SimulationTime;
 use crate::components::char::{
-    create_client_entity, create_client_minion_entity, CharActionIndex, CharacterEntityBuilder,
-    CharacterStateComponent, HasServerIdComponent, SpriteRenderDescriptorComponent,
-};
-use crate::components::controller::{
-    CameraComponent, HumanInputComponent, LocalPlayerController, SkillKey,
+    create_client_entity, create_client_minion_entity, CharacterEntityBuilder,
+    CharacterStateComponent, HasServerIdComponent,
 };
-use crate::components::skills::skills::{SkillManifestationComponent, Skills};
+use crate::components::controller::{CameraComponent, HumanInputComponent, LocalPlayerController};
+use crate::components::skills::skills::{FinishCast, SkillDefinition, SkillManifestationComponent};
 use crate::components::MinionComponent;
 use crate::configs::AppConfig;
 use crate::grf::asset_loader::GrfEntryLoader;
@@ -80,7 +85,7 @@ use crate::systems::camera_system::CameraSystem;
 use crate::systems::console_system::{
     CommandDefinition, ConsoleComponent, ConsoleRenderSystem, ConsoleSystem,
 };
-use crate::systems::falcon_ai_sys::{FalconAiSystem, FalconComponent};
+use crate::systems::falcon_ai_sys::{add_falcon, remove_falcon, FalconAiSystem, FalconComponent};
 use crate::systems::frame_cleanup_system::FrameCleanupSystem;
 use crate::systems::imgui_sys::{draw_imgui, ImguiData, ImguiSys};
 use crate::systems::input_sys::InputConsumerSystem;
@@ -122,12 +127,8 @@ mod components;
 mod render;
 mod systems;
 
-pub const SIMULATION_FREQ: usize = 31;
-pub const SIMULATION_DURATION_MS: usize = 1000 / SIMULATION_FREQ;
-pub const MAX_SECONDS_ALLOWED_FOR_SINGLE_SIMULATION_FRAME: f32 =
-    SIMULATION_DURATION_MS as f32 / 1000.0;
-pub const MAX_DURATION_ALLOWED_FOR_SINGLE_SIMULATION_FRAME: Duration =
-    Duration::from_millis(SIMULATION_DURATION_MS as u64);
+/// a restarted client continues the session which is stored here
+const SESSION_TOKEN_FILE: &str = "session_token";
 
 struct DelayedPacketReceiver {
     packets: Vec<(Instant, FromServerPacket)>,
@@ -182,39 +183,116 @@ fn main() {
         PacketHandlerThread::<FromServerPacket, ToServerPacket>::start_thread(1);
 
     log::info!("Connecting to server");
-    let server_socket = packet_handler_thread
-        .handle_socket(TcpStream::connect(config.server_addr.clone()).unwrap());
+    let server_socket = match config.transport {
+        Transport::Tcp => packet_handler_thread
+            .handle_socket(TcpStream::connect(config.server_addr.clone()).unwrap()),
+        Transport::Udp => packet_handler_thread
+            .handle_udp_socket(connect_udp(config.server_addr.as_str()).unwrap()),
+    };
 
+    // the token of the previous session, the server gives back the character if it is still alive
+    let prev_session_token = std::fs::read_to_string(SESSION_TOKEN_FILE)
+        .ok()
+        .and_then(|it| it.trim().parse::<u64>().ok())
+        .map(SessionToken);
     log::info!("sending welcome msg");
     packet_handler_thread.send(
         server_socket,
-        ToServerPacket::Welcome {
-            name: "sharp".to_owned(),
-        },
+        ToServerPacket::welcome(config.username.clone(), config.job, prev_session_token),
     );
 
     log::info!("waiting for welcome response...");
-    let (map_name, start_x, start_y, common_configs) = {
+    let (map_name, start_x, start_y, tick_rate, common_configs) = {
         let mut tmp_vec = Vec::with_capacity(64);
+        let mut tmp_map_name = String::new();
+        let mut tmp_start_x = 0.0;
+        let mut tmp_start_y = 0.0;
+        let mut tmp_tick_rate = 0;
         'outer1: loop {
             packet_handler_thread.receive_into(&mut tmp_vec);
-            let mut tmp_map_name = String::new();
-            let mut tmp_start_x = 0.0;
-            let mut tmp_start_y = 0.0;
             for (socket_id, packet) in tmp_vec.drain(..) {
                 match packet {
+                    NetworkTrafficEvent::Packet(FromServerPacket::LoginAccepted {
+                        name,
+                        session_token,
+                        resumed_room,
+                    }) => {
+                        log::info!("logged in as {}", name);
+                        if let Err(e) =
+                            std::fs::write(SESSION_TOKEN_FILE, session_token.0.to_string())
+                        {
+                            log::warn!("Could not save the session token: {}", e);
+                        }
+                        // otherwise the server puts us back into our previous room
+                        if resumed_room.is_none() {
+                            packet_handler_thread.send(server_socket, ToServerPacket::ListRooms);
+                        }
+                    }
+                    NetworkTrafficEvent::Packet(FromServerPacket::RoomList(rooms)) => {
+                        for room in &rooms {
+                            log::info!(
+                                "Room '{}' on {}: {}/{} players",
+                                room.name,
+                                room.map_name,
+                                room.player_count,
+                                room.max_players
+                            );
+                        }
+                        // without configured room the first one is joined, which is the default
+                        let room_to_join = match &config.room {
+                            Some(room_name) => rooms.iter().find(|it| &it.name == room_name),
+                            None => rooms.first(),
+                        };
+                        let packet = if let Some(room) = room_to_join {
+                            ToServerPacket::JoinRoom(room.id)
+                        } else {
+                            ToServerPacket::CreateRoom {
+                                name: config.room.clone().unwrap_or_default(),
+                                map_name: None,
+                            }
+                        };
+                        packet_handler_thread.send(server_socket, packet);
+                    }
+                    NetworkTrafficEvent::Packet(FromServerPacket::RoomError(e)) => {
+                        log::error!("Could not join the room: {:?}", e);
+                        return;
+                    }
+                    NetworkTrafficEvent::Packet(FromServerPacket::LoginRejected(reason)) => {
+                        log::error!("Could not join the server: {:?}", reason);
+                        return;
+                    }
                     NetworkTrafficEvent::Packet(FromServerPacket::Init {
                         map_name,
                         start_x,
                         start_y,
+                        tick_rate,
                     }) => {
                         tmp_map_name = map_name;
                         tmp_start_x = start_x;
                         tmp_start_y = start_y;
+                        tmp_tick_rate = tick_rate as usize;
                         log::info!("answer received!!!");
                     }
                     NetworkTrafficEvent::Packet(FromServerPacket::Configs(configs)) => {
-                        break 'outer1 (tmp_map_name, tmp_start_x, tmp_start_y, configs);
+                        break 'outer1 (
+                            tmp_map_name,
+                            tmp_start_x,
+                            tmp_start_y,
+                            tmp_tick_rate,
+                            configs,
+                        );
+                    }
+                    NetworkTrafficEvent::Packet(FromServerPacket::ServerFull) => {
+                        log::error!("Could not join the server: the server is full");
+                        return;
+                    }
+                    NetworkTrafficEvent::LocalError(e) => {
+                        log::error!("Could not join the server: {}", e);
+                        return;
+                    }
+                    NetworkTrafficEvent::Disconnected => {
+                        log::error!("The server has closed the connection");
+                        return;
                     }
                     _ => {}
                 }
@@ -306,27 +384,36 @@ fn main() {
 
     log::info!(">>> register systems");
 
-    let mut ecs_client_dispatcher =
-        { ClientEcs::new(Some(opengl_render_sys), maybe_sound_system, false) };
+    let mut ecs_client_dispatcher = {
+        ClientEcs::new(
+            Some(opengl_render_sys),
+            maybe_sound_system,
+            tick_rate,
+            false,
+        )
+    };
     log::info!("<<< register systems");
     log::info!(">>> add resources");
     // TODO: remove these
     ecs_world.insert(Vec::<HpModificationRequest>::with_capacity(128));
     ecs_world.insert(Vec::<AreaAttackComponent>::with_capacity(128));
     ecs_world.insert(Vec::<ApplyForceComponent>::with_capacity(128));
+    ecs_world.insert(Vec::<FinishCast>::with_capacity(128));
 
     ecs_world.insert(sys_vars);
     ecs_world.insert(common_configs);
     ecs_world.insert(ClientCommandId::new());
-    ecs_world.insert(ImguiData::new(config.max_fps));
+    ecs_world.insert(ImguiData::new(config.max_fps, tick_rate));
     if config.load_sprites {
         asset_loader.load_sprites(&gl, &mut asset_db);
     }
     ecs_world.insert(gl.clone());
+    // the movement prediction has to be blocked by the same cells as on the server
+    ecs_world.insert(MapWalkingInfo::from_gat(&map_render_data.gat));
     ecs_world.insert(map_render_data);
     ecs_world.insert(RenderCommandCollector::new());
     ecs_world.insert(command_buffer);
-    ecs_world.insert(SimulationTime::new(SIMULATION_FREQ as usize));
+    ecs_world.insert(SimulationTime::new(tick_rate));
     ecs_world.insert(SnapshotStorage::new());
     ecs_world.insert(OutPacketCollector::new());
 
@@ -346,13 +433,7 @@ fn main() {
     {
         let mut human_player = HumanInputComponent::new();
         human_player.cast_mode = config.cast_mode;
-        human_player.assign_skill(SkillKey::A, Skills::AttackMove);
-
-        human_player.assign_skill(SkillKey::Q, Skills::FireWall);
-        human_player.assign_skill(SkillKey::W, Skills::AbsorbShield);
-        human_player.assign_skill(SkillKey::E, Skills::Heal);
-        human_player.assign_skill(SkillKey::R, Skills::BrutalTestSkill);
-        human_player.assign_skill(SkillKey::Y, Skills::Mounting);
+        human_player.assign_skills_of_job(config.job);
 
         ecs_world.insert(RenderCommandCollector::new());
         ecs_world.insert(AudioCommandCollectorComponent::new());
@@ -448,6 +529,8 @@ fn main() {
         ecs_world.insert(server_tick);
         console_print(&mut ecs_world, &format!("avg ping: {}", avg_ping));
 
+        let team = ecs_world.read_resource::<AppConfig>().team;
+        packet_handler_thread.send(server_socket, ToServerPacket::SelectTeam(team));
         packet_handler_thread.send(server_socket, ToServerPacket::ReadyForGame);
         // first ACK packet is for initializing our world state
         'outer3: loop {
@@ -461,6 +544,7 @@ fn main() {
                         typ,
                         outlook,
                         job_id,
+                        has_falcon,
                         state,
                     }) => {
                         log::info!(">>> create player");
@@ -484,23 +568,10 @@ fn main() {
                                 .controller
                                 .controlled_entity = Some(desktop_client_char);
 
-                            // add falcon to it
-                            let _falcon_id = ecs_world
-                                .create_entity()
-                                .with(FalconComponent::new(
-                                    desktop_client_char,
-                                    state.pos.x,
-                                    state.pos.y,
-                                ))
-                                .with(SpriteRenderDescriptorComponent {
-                                    action_index: CharActionIndex::Idle as usize,
-                                    fps_multiplier: 1.0,
-                                    animation_started: LocalTime::from(0.0),
-                                    forced_duration: None,
-                                    direction: CharDir::South,
-                                    animation_ends_at: LocalTime::from(0.0),
-                                })
-                                .build();
+                            set_falcon(&mut ecs_world, desktop_client_char, has_falcon);
+                            ecs_world
+                                .write_resource::<HumanInputComponent>()
+                                .assign_skills_of_job(job_id);
 
                             ecs_world.maintain();
                         }
@@ -576,8 +647,18 @@ fn main() {
                         }
                         NetworkTrafficEvent::Disconnected => { // TODO
                         }
+                        NetworkTrafficEvent::Congested { .. }
+                        | NetworkTrafficEvent::CongestionEnded
+                        | NetworkTrafficEvent::Closed => {}
                         NetworkTrafficEvent::Packet(p) => match p {
-                            FromServerPacket::Init { .. } => panic!(),
+                            FromServerPacket::LoginAccepted { .. }
+                            | FromServerPacket::LoginRejected(..)
+                            | FromServerPacket::RoomList(..)
+                            | FromServerPacket::RoomError(..)
+                            | FromServerPacket::Init { .. }
+                            | FromServerPacket::ServerFull => {
+                                log::error!("Unexpected packet during the game: {:?}", p);
+                            }
                             FromServerPacket::Pong { server_time, .. } => {
                                 let ping = ping_sent.elapsed().as_millis() as usize;
                                 avg_ping = (avg_ping + ping) / 2;
@@ -610,8 +691,41 @@ fn main() {
                                     );
                                 }
                             }
-                            FromServerPacket::Ack { cid, mut entries } => {
+                            FromServerPacket::Ack {
+                                cid,
+                                tick,
+                                baseline,
+                                entries,
+                            } => {
                                 let snapshots = &mut ecs_world.write_resource::<SnapshotStorage>();
+                                let entries = match snapshots
+                                    .reconstruct_server_states(tick, baseline, entries)
+                                {
+                                    Ok(entries) => entries,
+                                    Err(e) => {
+                                        log::warn!(
+                                            "Snapshot {:?} could not be reconstructed: {:?}",
+                                            tick,
+                                            e
+                                        );
+                                        // ask for a full snapshot
+                                        packet_handler_thread
+                                            .send(server_socket, ToServerPacket::SnapshotAck(None));
+                                        continue;
+                                    }
+                                };
+                                packet_handler_thread
+                                    .send(server_socket, ToServerPacket::SnapshotAck(Some(tick)));
+                                let entries = match snapshots.order_as_predicted_entities(entries) {
+                                    Some(entries) => entries,
+                                    None => {
+                                        log::debug!(
+                                            "Snapshot {:?} does not match the known entities",
+                                            tick
+                                        );
+                                        continue;
+                                    }
+                                };
 
                                 // TODO: replace in place
                                 let entries = entries
@@ -644,6 +758,17 @@ fn main() {
                                 typ,
                                 outlook,
                                 job_id,
+                                has_falcon,
+                                state,
+                            }
+                            | FromServerPacket::EntityAppeared {
+                                id,
+                                name,
+                                team,
+                                typ,
+                                outlook,
+                                job_id,
+                                has_falcon,
                                 state,
                             } => {
                                 log::info!(">>> create player");
@@ -660,6 +785,7 @@ fn main() {
                                     );
 
                                     server_to_local_ids.insert(id, char_entity_id);
+                                    set_falcon(&mut ecs_world, char_entity_id, has_falcon);
                                 }
                                 ecs_world.maintain();
 
@@ -676,13 +802,106 @@ fn main() {
                                     );
                             }
                             FromServerPacket::PlayerDisconnected(disconnecting_entity_id) => {
-                                let disconnecting_entity_local_id =
-                                    server_to_local_ids[&disconnecting_entity_id];
+                                // she might not have been in our area of interest
+                                if let Some(disconnecting_entity_local_id) =
+                                    server_to_local_ids.remove(&disconnecting_entity_id)
+                                {
+                                    log::info!(
+                                        "{} has been disconnected",
+                                        disconnecting_entity_local_id
+                                    );
+                                    remove_falcon(&mut ecs_world, disconnecting_entity_local_id);
+                                    ecs_world
+                                        .write_resource::<SnapshotStorage>()
+                                        .remove_predicting_entity(disconnecting_entity_id);
+                                    ecs_world.delete_entity(disconnecting_entity_local_id.into());
+                                }
+                            }
+                            FromServerPacket::EntityDied(id) => {
+                                let name = server_to_local_ids
+                                    .get(&id)
+                                    .and_then(|local_id| {
+                                        ecs_world
+                                            .read_storage::<StaticCharDataComponent>()
+                                            .get((*local_id).into())
+                                            .map(|it| it.name.clone())
+                                    })
+                                    .unwrap_or_default();
+                                log::info!("{} has died", name);
+                                ecs_world
+                                    .write_resource::<ConsoleComponent>()
+                                    .print(&format!("{} has died", name));
+                            }
+                            FromServerPacket::MatchPhaseChanged(phase) => {
+                                log::info!("Match phase: {:?}", phase);
+                            }
+                            FromServerPacket::ScoreChanged(score) => {
                                 log::info!(
-                                    "{} has been disconnected",
-                                    disconnecting_entity_local_id
+                                    "Score: {} - {}",
+                                    score.left_team_kills,
+                                    score.right_team_kills
                                 );
-                                ecs_world.delete_entity(disconnecting_entity_local_id.into());
+                            }
+                            FromServerPacket::EntityRespawned(id) => {
+                                // the new state arrives in the next Ack
+                                log::info!("{:?} has respawned", server_to_local_ids.get(&id));
+                            }
+                            FromServerPacket::ConsoleReply { command, result } => {
+                                let mut console = ecs_world.write_resource::<ConsoleComponent>();
+                                match result {
+                                    Ok(output) => {
+                                        for line in output.lines() {
+                                            console.print(line);
+                                        }
+                                    }
+                                    Err(e) => console.error(&format!("{}: {}", command, e)),
+                                }
+                            }
+                            FromServerPacket::CharChanged {
+                                id,
+                                team,
+                                outlook,
+                                job_id,
+                                has_falcon,
+                            } => {
+                                if let Some(local_id) = server_to_local_ids.get(&id) {
+                                    if let Some(static_data) = ecs_world
+                                        .write_storage::<StaticCharDataComponent>()
+                                        .get_mut((*local_id).into())
+                                    {
+                                        static_data.team = team;
+                                        static_data.outlook = outlook;
+                                        static_data.job_id = job_id;
+                                    }
+                                    let is_controlled = ecs_world
+                                        .read_resource::<LocalPlayerController>()
+                                        .controller
+                                        .controlled_entity
+                                        == Some(*local_id);
+                                    if is_controlled {
+                                        ecs_world
+                                            .write_resource::<HumanInputComponent>()
+                                            .assign_skills_of_job(job_id);
+                                    }
+                                    set_falcon(&mut ecs_world, *local_id, has_falcon);
+                                }
+                            }
+                            FromServerPacket::IntentionRejected { cid, reason } => {
+                                // the prediction is corrected by the next Ack
+                                log::warn!("Command({}) has been rejected: {:?}", cid, reason);
+                            }
+                            FromServerPacket::EntityRemoved(id)
+                            | FromServerPacket::EntityDisappeared(id) => {
+                                if let Some(removed_entity_local_id) =
+                                    server_to_local_ids.remove(&id)
+                                {
+                                    log::info!("{} has been removed", removed_entity_local_id);
+                                    remove_falcon(&mut ecs_world, removed_entity_local_id);
+                                    ecs_world
+                                        .write_resource::<SnapshotStorage>()
+                                        .remove_predicting_entity(id);
+                                    ecs_world.delete_entity(removed_entity_local_id.into());
+                                }
                             }
                         },
                     }
@@ -915,6 +1134,31 @@ fn main() {
     }
 }
 
+/// The server decides who has a falcon, it is added or removed here accordingly
+fn set_falcon(ecs_world: &mut World, char_id: LocalCharEntityId, has_falcon: bool) {
+    if let Some(static_data) = ecs_world
+        .write_storage::<StaticCharDataComponent>()
+        .get_mut(char_id.into())
+    {
+        static_data.has_falcon = has_falcon;
+    }
+    let falcon_exists = ecs_world
+        .read_storage::<FalconComponent>()
+        .join()
+        .any(|falcon| falcon.owner_entity_id == char_id);
+    if has_falcon && !falcon_exists {
+        let pos = ecs_world
+            .read_storage::<LocalCharStateComp>()
+            .get(char_id.into())
+            .map(|it| it.pos());
+        if let Some(pos) = pos {
+            add_falcon(ecs_world, char_id, pos);
+        }
+    } else if !has_falcon && falcon_exists {
+        remove_falcon(ecs_world, char_id);
+    }
+}
+
 fn send_packets(
     packet_handler_thread: &mut PacketHandlerThread<FromServerPacket, ToServerPacket>,
     server_socket: SocketId,
@@ -973,10 +1217,14 @@ impl<'a, 'b> ClientEcs<'a, 'b> {
     pub fn new(
         opengl_render_sys: Option<OpenGlRenderSystem<'b, 'b>>,
         maybe_sound_system: Option<SoundSystem>,
+        simulation_freq: usize,
         for_test: bool,
     ) -> ClientEcs<'a, 'b> {
         ClientEcs {
-            simulation_dispatcher: ClientEcs::create_with_simulation_systems(for_test),
+            simulation_dispatcher: ClientEcs::create_with_simulation_systems(
+                simulation_freq,
+                for_test,
+            ),
             render_dispatcher: ClientEcs::create_without_simulation_systems(
                 opengl_render_sys,
                 maybe_sound_system,
@@ -1075,12 +1323,15 @@ impl<'a, 'b> ClientEcs<'a, 'b> {
         ecs_dispatcher
     }
 
-    fn create_with_simulation_systems(for_test: bool) -> Dispatcher<'a, 'b> {
+    fn create_with_simulation_systems(
+        simulation_freq: usize,
+        for_test: bool,
+    ) -> Dispatcher<'a, 'b> {
         let ecs_dispatcher = {
             let mut ecs_dispatcher_builder = specs::DispatcherBuilder::new();
             if !for_test {
                 ecs_dispatcher_builder = ecs_dispatcher_builder.with(
-                    IntentionSenderSystem::new(SIMULATION_FREQ),
+                    IntentionSenderSystem::new(simulation_freq),
                     "intention_sender",
                     &[],
                 );
@@ -1179,14 +1430,8 @@ fn update_desktop_inputs(video: &mut Video, ecs_world: &mut World) -> bool {
 
 fn spawn_minions(ecs_world: &mut World) -> () {
     {
-        let entity_id = create_client_minion_entity(
-            ecs_world,
-            v2(
-                MinionAiSystem::CHECKPOINTS[0][0] as f32,
-                MinionAiSystem::CHECKPOINTS[0][1] as f32,
-            ),
-            Team::Right,
-        );
+        let entity_id =
+            create_client_minion_entity(ecs_world, MinionAi::gate_of(Team::Right), Team::Right);
 
         let mut storage = ecs_world.write_storage();
         storage
@@ -1194,14 +1439,8 @@ fn spawn_minions(ecs_world: &mut World) -> () {
             .unwrap();
     }
     {
-        let entity_id = create_client_minion_entity(
-            ecs_world,
-            v2(
-                MinionAiSystem::CHECKPOINTS[5][0] as f32,
-                MinionAiSystem::CHECKPOINTS[5][1] as f32,
-            ),
-            Team::Left,
-        );
+        let entity_id =
+            create_client_minion_entity(ecs_world, MinionAi::gate_of(Team::Left), Team::Left);
         let mut storage = ecs_world.write_storage();
         storage
             .insert(entity_id.into(), MinionComponent { fountain_up: false })
@@ -1265,9 +1504,7 @@ fn execute_console_command(
 fn execute_finished_skill_castings(ecs_world: &mut World) {
     // TODO: avoid allocating new vec
     let finished_casts = std::mem::replace(
-        &mut ecs_world
-            .write_resource::<SystemVariables>()
-            .just_finished_skill_casts,
+        &mut *ecs_world.write_resource::<Vec<FinishCast>>(),
         Vec::with_capacity(128),
     );
     for finished_cast in &finished_casts {
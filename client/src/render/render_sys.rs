@@ -8,7 +8,10 @@ use crate::components::char::{
 use crate::components::controller::{
     CameraComponent, EntitiesBelowCursor, HumanInputComponent, LocalPlayerController, SkillKey,
 };
-use crate::components::skills::skills::{SkillManifestationComponent, SkillTargetType, Skills};
+use crate::components::skills::skills::{
+    SkillDefinition, SkillManifestationComponent, SkillTargetType, Skills,
+};
+use crate::components::status::status::StatusesRenderer;
 use crate::components::{
     FlyingNumberComponent, FlyingNumberType, SoundEffectComponent, StrEffectComponent,
 };
@@ -166,7 +169,7 @@ impl RenderDesktopClientSystem {
         }
 
         {
-            if let Some((controlled_char, controlled_auth_char)) = &controlled_char {
+            if let Some((_controlled_char, controlled_auth_char)) = &controlled_char {
                 {
                     let _stopwatch =
                         system_benchmark.start_measurement("render.select_skill_target");
@@ -174,13 +177,13 @@ impl RenderDesktopClientSystem {
                     if let Some((_skill_key, skill)) = local_player.select_skill_target {
                         let skill_def = skill.get_definition();
                         let skill_cast_attr =
-                            skill.get_cast_attributes(&dev_configs, controlled_char);
+                            skill.get_cast_attributes(&dev_configs, controlled_auth_char);
                         let (skill_3d_pos, dir_vector) = Skills::limit_vector_into_range(
                             &char_pos,
                             &input.mouse_world_pos,
                             skill_cast_attr.casting_range,
                         );
-                        if skill_def.get_skill_target_type() != SkillTargetType::Directional {
+                        if skill.get_skill_target_type() != SkillTargetType::Directional {
                             render_commands
                                 .circle_3d()
                                 .pos_2d(&char_pos)
@@ -188,7 +191,7 @@ impl RenderDesktopClientSystem {
                                 .radius(skill_cast_attr.casting_range)
                                 .color(&[0, 255, 0, 255])
                                 .add();
-                            if skill_def.get_skill_target_type() == SkillTargetType::Area {
+                            if skill.get_skill_target_type() == SkillTargetType::Area {
                                 let is_castable = controlled_auth_char
                                     .skill_cast_allowed_at
                                     .get(skill as usize)
@@ -335,7 +338,7 @@ impl RenderDesktopClientSystem {
         desktop_target: &Option<&EntityTarget<LocalCharEntityId>>,
     ) -> bool {
         return if let Some((_skill_key, skill)) = select_skill_target {
-            match skill.get_definition().get_skill_target_type() {
+            match skill.get_skill_target_type() {
                 SkillTargetType::AnyEntity => entities_below_cursor
                     .get_enemy_or_friend()
                     .map(|it| it == rendering_entity_id)
@@ -468,7 +471,7 @@ impl RenderDesktopClientSystem {
                 predicted_pos
             };
 
-            let color = client_char_state.statuses.calc_render_color(time.now());
+            let color = auth_state.statuses.calc_render_color(time.now());
             match static_char_data.outlook {
                 CharOutlook::Human {
                     job_sprite_id,
@@ -480,7 +483,7 @@ impl RenderDesktopClientSystem {
                         .as_ref()
                         .map(|it| it.0.team)
                         .unwrap_or(Team::Right);
-                    let body_sprite = client_char_state
+                    let body_sprite = auth_state
                         .statuses
                         .calc_body_sprite(assets, static_char_data.job_id, sex)
                         .unwrap_or(
@@ -723,24 +726,26 @@ impl RenderDesktopClientSystem {
                 }
             }
 
-            // TODO2 casting
-            //            if let CharState::CastingSkill(casting_info) = auth_state.state() {
-            //                let skill = casting_info.skill;
-            //                skill.get_definition().render_casting(
-            //                    &char_state.pos(),
-            //                    &casting_info,
-            //                    assets,
-            //                    time,
-            //                    dev_configs,
-            //                    render_commands,
-            //                    &char_state_storage,
-            //                );
-            //            }
+            if let CharState::CastingSkill(casting_info) = auth_state.state() {
+                let skill = casting_info.skill;
+                skill.get_definition().render_casting(
+                    &pos_2d,
+                    &casting_info,
+                    assets,
+                    time,
+                    dev_configs,
+                    render_commands,
+                    auth_char_state_storage,
+                );
+            }
 
-            // TODO2 statuses
-            //            static_char_data
-            //                .statuses
-            //                .render(&static_char_data, assets, time, render_commands);
+            auth_state.statuses.render(
+                static_char_data,
+                auth_state,
+                assets,
+                time.now(),
+                render_commands,
+            );
             predictable_entity_index += 1;
         }
     }
@@ -1497,17 +1502,16 @@ impl RenderDesktopClientSystem {
         };
 
         // draw status remaining time indicator
-        // TODO2 statuses
-        //        if let Some(perc) = static_char_data
-        //            .statuses
-        //            .calc_largest_remaining_status_time_percent(now)
-        //        {
-        //            let orange = [255, 140, 0, 255];
-        //            let w = bar_w - 4;
-        //            draw_rect(2, bottom_bar_y + 2, w, 2, &[0, 0, 0, 255]); // black bg
-        //            let inner_w = (w as f32 * (1.0 - perc)) as i32;
-        //            draw_rect(2, bottom_bar_y + 2, inner_w, 2, &orange);
-        //        }
+        if let Some(perc) = char_state
+            .statuses
+            .calc_largest_remaining_status_time_percent(now)
+        {
+            let orange = [255, 140, 0, 255];
+            let w = bar_w - 4;
+            draw_rect(2, bottom_bar_y + 2, w, 2, &[0, 0, 0, 255]); // black bg
+            let inner_w = (w as f32 * (1.0 - perc)) as i32;
+            draw_rect(2, bottom_bar_y + 2, inner_w, 2, &orange);
+        }
 
         // draw status indicator icons
         const ICON_WIDTH: i32 = 24;
@@ -15,6 +15,7 @@ use rustarok_common::components::char::{
     LocalCharEntityId, LocalCharStateComp, StaticCharDataComponent,
 };
 use rustarok_common::components::controller::ControllerComponent;
+use rustarok_common::pathfinding::WalkingPathComponent;
 use specs::world::WorldExt;
 use specs::World;
 
@@ -35,6 +36,7 @@ pub fn create_ecs_world() -> World {
 
     ecs_world.register::<LocalCharStateComp>();
     ecs_world.register::<StaticCharDataComponent>();
+    ecs_world.register::<WalkingPathComponent>();
     // TODO2 remove it as soon as Falcon logic goes to the server code
     ecs_world.register::<ControllerComponent>();
 
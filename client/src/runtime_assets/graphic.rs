@@ -5,13 +5,13 @@ use sdl2::ttf::Sdl2TtfContext;
 use strum::IntoEnumIterator;
 
 use crate::components::controller::SkillKey;
-use crate::components::skills::skills::Skills;
+use crate::components::skills::skills::{SkillDefinition, Skills};
 use crate::grf::asset_loader::GrfEntryLoader;
 use crate::grf::database::AssetDatabase;
 use crate::grf::texture::TextureId;
 use crate::my_gl::{Gl, MyGlEnum};
-use crate::systems::console_commands::STATUS_NAMES;
 use crate::video::Video;
+use rustarok_common::components::status::status::STATUS_NAMES;
 
 pub struct Texts {
     pub skill_name_texts: HashMap<Skills, TextureId>,
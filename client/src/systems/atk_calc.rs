@@ -17,9 +17,9 @@ use rustarok_common::attack::{
     HpModificationResult, HpModificationResultType, HpModificationType, WeaponType,
 };
 use rustarok_common::common::SimulationTick;
-use rustarok_common::common::{percentage, EngineTime, Percentage, Vec2};
+use rustarok_common::common::{EngineTime, Vec2};
 use rustarok_common::components::char::{
-    JobId, LocalCharEntityId, LocalCharStateComp, StaticCharDataComponent,
+    LocalCharEntityId, LocalCharStateComp, StaticCharDataComponent,
 };
 use rustarok_common::config::CommonConfigs;
 
@@ -164,21 +164,13 @@ impl<'a> System<'a> for AttackSystem {
                         .get(hp_mod_req.dst_entity.into())
                         .unwrap();
                     let is_valid = dst_auth_state.state().is_alive()
-                        && match hp_mod_req.typ {
-                            HpModificationType::Heal(_) => {
-                                src_static_data.team.can_support(dst_static_data.team)
-                            }
-                            _ => src_static_data.team.can_attack(dst_static_data.team),
-                        };
+                        && hp_mod_req
+                            .is_allowed_between(src_static_data.team, dst_static_data.team);
                     if !is_valid {
                         log::warn!("Invalid hp_mod_req: {:?}", hp_mod_req);
                     }
                     if is_valid {
-                        Some(AttackCalculation::apply_armor_calc(
-                            src_char_state,
-                            dst_char_state,
-                            hp_mod_req,
-                        ))
+                        Some(hp_mod_req.apply_armor(dst_char_state.calculated_attribs()))
                     } else {
                         None
                     }
@@ -194,23 +186,17 @@ impl<'a> System<'a> for AttackSystem {
             for hp_mod_req_result in hp_mod_req_results.into_iter() {
                 dbg!(&hp_mod_req_result);
                 let (hp_mod_req_result, char_pos) = {
-                    let attacked_entity_state =
-                        char_state_storage.get_mut(attacked_id.into()).unwrap();
+                    let attacked_entity_auth_state =
+                        auth_char_state_storage.get_mut(attacked_id.into()).unwrap();
                     let hp_mod_req_result = AttackCalculation::alter_requests_by_attacked_statuses(
                         hp_mod_req_result,
-                        attacked_entity_state,
+                        attacked_entity_auth_state,
                         &mut hp_mod_requests,
                     );
 
-                    let attacked_entity_auth_state =
-                        auth_char_state_storage.get_mut(attacked_id.into()).unwrap();
-                    AttackCalculation::apply_damage(
-                        attacked_entity_auth_state,
-                        &hp_mod_req_result,
-                        time.now(),
-                    );
+                    attacked_entity_auth_state.apply_hp_mod(&hp_mod_req_result, time.now());
 
-                    attacked_entity_state
+                    attacked_entity_auth_state
                         .statuses
                         .hp_mod_has_been_applied_on_me(
                             attacked_id,
@@ -220,15 +206,13 @@ impl<'a> System<'a> for AttackSystem {
                     // TODO: rather than this, create a common component which
                     // contains all the necessary info from which an other system will be able to
                     // generate the render and audio commands
-                    let attacked_entity_auth_state =
-                        auth_char_state_storage.get(attacked_id.into()).unwrap();
                     (hp_mod_req_result, attacked_entity_auth_state.pos())
                 };
 
+                if let Some(attacker_entity_auth_state) =
+                    auth_char_state_storage.get_mut(attacker_id.into())
                 {
-                    let attacker_entity_state =
-                        char_state_storage.get_mut(attacker_id.into()).unwrap();
-                    attacker_entity_state
+                    attacker_entity_auth_state
                         .statuses
                         .hp_mod_has_been_applied_on_enemy(
                             attacker_id,
@@ -274,18 +258,18 @@ impl<'a> System<'a> for AttackSystem {
         AttackSystem::add_new_statuses(
             status_changes,
             &mut auth_char_state_storage,
-            &static_char_data_storage,
-            &sys_vars,
-            &time,
-            &entities,
-            &mut updater,
-            &mut physics_world,
+            &mut static_char_data_storage,
             &dev_configs,
         );
 
         let status_changes =
             std::mem::replace(&mut sys_vars.remove_statuses, Vec::with_capacity(128));
-        AttackSystem::remove_statuses(status_changes, &mut char_state_storage, &dev_configs);
+        AttackSystem::remove_statuses(
+            status_changes,
+            &mut auth_char_state_storage,
+            &static_char_data_storage,
+            &dev_configs,
+        );
         sys_vars.remove_statuses.clear();
     }
 }
@@ -295,7 +279,7 @@ pub struct AttackCalculation;
 impl AttackCalculation {
     pub fn alter_requests_by_attacked_statuses(
         outcome: HpModificationResult,
-        attacked_entity_state: &mut CharacterStateComponent,
+        attacked_entity_state: &mut LocalCharStateComp,
         hp_mod_reqs: &mut Vec<HpModificationRequest>,
     ) -> HpModificationResult {
         // Allow statuses to affect incoming damages/heals
@@ -385,45 +369,6 @@ impl AttackCalculation {
         return result_statuses;
     }
 
-    pub fn apply_armor_calc(
-        _src: &LocalCharStateComp,
-        dst: &LocalCharStateComp,
-        hp_mod_req: HpModificationRequest,
-    ) -> HpModificationResult {
-        return match hp_mod_req.typ {
-            HpModificationType::SpellDamage(base_dmg, _damage_render_type) => {
-                let dmg = dst
-                    .calculated_attribs()
-                    .armor
-                    .subtract_me_from(base_dmg as i32);
-                if dmg <= 0 {
-                    hp_mod_req.blocked()
-                } else {
-                    hp_mod_req.allow(dmg as u32)
-                }
-            }
-            HpModificationType::BasicDamage(base_dmg, _damage_render_type, _weapon_type) => {
-                let atk = dbg!(base_dmg);
-                let atk = dbg!(dst.calculated_attribs().armor).subtract_me_from(atk as i32);
-                dbg!(atk);
-                if atk <= 0 {
-                    hp_mod_req.blocked()
-                } else {
-                    hp_mod_req.allow(atk as u32)
-                }
-            }
-            HpModificationType::Heal(healed) => hp_mod_req.allow(healed),
-            HpModificationType::Poison(dmg) => {
-                let atk = dst.calculated_attribs().armor.subtract_me_from(dmg as i32);
-                if atk <= 0 {
-                    hp_mod_req.blocked()
-                } else {
-                    hp_mod_req.allow(dmg)
-                }
-            }
-        };
-    }
-
     pub fn make_sound(
         entities: &Entities,
         pos: Vec2,
@@ -460,42 +405,6 @@ impl AttackCalculation {
         }
     }
 
-    fn apply_damage(
-        auth_char_comp: &mut LocalCharStateComp,
-        outcome: &HpModificationResult,
-        now: LocalTime,
-    ) {
-        match outcome.typ {
-            HpModificationResultType::Ok(hp_req_mod_type) => match hp_req_mod_type {
-                HpModificationType::Heal(val) => {
-                    auth_char_comp.hp = auth_char_comp
-                        .calculated_attribs()
-                        .max_hp
-                        .min(auth_char_comp.hp + val as i32);
-                }
-                HpModificationType::BasicDamage(val, _display_type, _weapon_type) => {
-                    auth_char_comp
-                        .cannot_control_until
-                        .run_at_least_until(now, 100);
-                    auth_char_comp.set_receiving_damage();
-                    auth_char_comp.hp -= val as i32;
-                }
-                HpModificationType::Poison(val) => {
-                    auth_char_comp.hp -= val as i32;
-                }
-                HpModificationType::SpellDamage(val, _display_type) => {
-                    auth_char_comp
-                        .cannot_control_until
-                        .run_at_least_until(now, 100);
-                    auth_char_comp.set_receiving_damage();
-                    auth_char_comp.hp -= val as i32;
-                }
-            },
-            HpModificationResultType::Blocked => {}
-            HpModificationResultType::Absorbed => {}
-        }
-    }
-
     pub fn add_flying_damage_entity(
         outcome: &HpModificationResult,
         entities: &Entities,
@@ -547,46 +456,26 @@ impl AttackSystem {
     fn add_new_statuses(
         status_changes: Vec<ApplyStatusComponent>,
         char_state_storage: &mut WriteStorage<LocalCharStateComp>,
-        char_static_state_storage: &WriteStorage<StaticCharDataComponent>,
-        sys_vars: &SystemVariables,
-        time: &EngineTime,
-        entities: &Entities,
-        updater: &mut LazyUpdate,
-        physics_world: &mut PhysicEngine,
+        char_static_state_storage: &mut WriteStorage<StaticCharDataComponent>,
         dev_configs: &CommonConfigs,
     ) {
-        for mut status_change in status_changes.into_iter() {
-            if let Some(target_char) =
-                char_state_storage.get_mut(status_change.target_entity_id.into())
-            {
+        for status_change in status_changes.into_iter() {
+            let target_entity_id = status_change.target_entity_id;
+            if let (Some(target_char), Some(target_static_data)) = (
+                char_state_storage.get_mut(target_entity_id.into()),
+                char_static_state_storage.get_mut(target_entity_id.into()),
+            ) {
                 if target_char.hp <= 0 {
                     continue;
                 }
-                let target_entity_id = status_change.target_entity_id;
                 log::debug!(
                     "Applying state '{:?}' on {:?}",
                     status_change.status,
-                    status_change.target_entity_id
+                    target_entity_id
                 );
 
-                status_change.status.on_apply(
-                    status_change.target_entity_id,
-                    target_char,
-                    entities,
-                    updater,
-                    &sys_vars.assets,
-                    time,
-                    physics_world,
-                );
-                // TODO2 statuses
-                // target_char.statuses.add(status_change.status);
-                target_char.recalc_attribs_based_on_statuses(
-                    char_static_state_storage
-                        .get(status_change.target_entity_id.into())
-                        .unwrap()
-                        .job_id,
-                    dev_configs,
-                );
+                // TODO2 the client side effects of on_apply (sounds, str effects, collisions)
+                target_char.add_status(status_change.status, target_static_data, dev_configs);
                 log::trace!(
                     "Status added. Attributes({:?}): current: {:?}",
                     target_entity_id,
@@ -596,25 +485,17 @@ impl AttackSystem {
         }
     }
 
-    pub fn calc_mounted_speedup(
-        target_char: &StaticCharDataComponent,
-        configs: &CommonConfigs,
-    ) -> Percentage {
-        return match target_char.job_id {
-            JobId::CRUSADER => configs.stats.player.crusader.mounted_speedup,
-            _ => percentage(30),
-        };
-    }
-
     fn remove_statuses(
         status_changes: Vec<RemoveStatusComponent>,
-        char_state_storage: &mut WriteStorage<CharacterStateComponent>,
+        char_state_storage: &mut WriteStorage<LocalCharStateComp>,
+        char_static_state_storage: &WriteStorage<StaticCharDataComponent>,
         dev_configs: &CommonConfigs,
     ) {
         for status_change in status_changes.into_iter() {
-            if let Some(target_char) =
-                char_state_storage.get_mut(status_change.target_entity_id.into())
-            {
+            if let (Some(target_char), Some(target_static_data)) = (
+                char_state_storage.get_mut(status_change.target_entity_id.into()),
+                char_static_state_storage.get(status_change.target_entity_id.into()),
+            ) {
                 match &status_change.status {
                     RemoveStatusComponentPayload::RemovingStatusType(status_type) => {
                         target_char.statuses.remove_by_nature(*status_type);
@@ -623,13 +504,13 @@ impl AttackSystem {
                         target_char.statuses.remove(*discr)
                     }
                 }
-                // TODO2 statuses: target_char legyen Auth
-                //                target_char.recalc_attribs_based_on_statuses(dev_configs);
-                //                log::trace!(
-                //                    "Status removed. Attributes({:?}):  current: {:?}",
-                //                    status_change.target_entity_id,
-                //                    target_char.calculated_attribs()
-                //                );
+                target_char
+                    .recalc_attribs_based_on_statuses(target_static_data.job_id, dev_configs);
+                log::trace!(
+                    "Status removed. Attributes({:?}):  current: {:?}",
+                    status_change.target_entity_id,
+                    target_char.calculated_attribs()
+                );
             }
         }
     }
@@ -1,14 +1,10 @@
 use crate::components::char::{
-    create_client_dummy_entity, create_client_entity, create_client_guard_entity, CharActionIndex,
-    CharacterEntityBuilder, CharacterStateComponent, NpcComponent, SpriteRenderDescriptorComponent,
+    create_client_dummy_entity, create_client_entity, create_client_guard_entity,
+    CharacterEntityBuilder, CharacterStateComponent, NpcComponent,
 };
 use crate::components::controller::{CameraComponent, HumanInputComponent};
-use crate::components::skills::absorb_shield::AbsorbStatus;
-use crate::components::skills::fire_bomb::FireBombStatus;
 use crate::components::skills::skills::SkillManifestationComponent;
-use crate::components::status::attrib_mod::ArmorModifierStatus;
 use crate::components::status::heal_area::HealApplierArea;
-use crate::components::status::status::{ApplyStatusComponent, PoisonStatus, StatusEnum};
 use crate::components::status::status_applier_area::StatusApplierArea;
 use crate::configs::AppConfig;
 use crate::consts::PLAYABLE_CHAR_SPRITES;
@@ -18,23 +14,23 @@ use crate::systems::console_system::{
     CommandParamType, ConsoleComponent, ConsoleEntry, ConsoleSystem, ConsoleWordType,
     OwnedAutocompletionProvider,
 };
-use crate::systems::falcon_ai_sys::FalconComponent;
 use crate::systems::imgui_sys::ImguiData;
 use crate::systems::input_sys_scancodes::ScancodeNames;
 use crate::systems::{RenderMatrices, SystemVariables};
-use crate::{CollisionGroup, LocalTime, PhysicEngine};
+use crate::{CollisionGroup, PhysicEngine};
 use nalgebra::Isometry2;
 use rand::Rng;
 use rustarok_common::attack::{
     DamageDisplayType, HpModificationRequest, HpModificationType, WeaponType,
 };
 use rustarok_common::char_attr::CharAttributes;
-use rustarok_common::common::{percentage, v2, v2u, EngineTime, Vec2};
+use rustarok_common::common::{v2, v2u, EngineTime, Vec2};
 use rustarok_common::components::char::{
-    create_common_player_entity, CharDir, CharOutlook, CharState, JobId, LocalCharEntityId,
+    create_common_player_entity, CharOutlook, CharState, JobId, LocalCharEntityId,
     LocalCharStateComp, MonsterId, Sex, StaticCharDataComponent, Team,
 };
 use rustarok_common::components::job_ids::JobSpriteId;
+use rustarok_common::components::status::status::STATUS_NAMES;
 use rustarok_common::config::CommonConfigs;
 use rustarok_common::console::CommandArguments;
 use rustarok_common::packets::to_server::ToServerPacket;
@@ -117,66 +113,9 @@ pub(super) fn cmd_set_job() -> CommandDefinition {
                 }
             },
         ),
-        action: Box::new(|self_char_id, args, ecs_world, _video| {
-            let job_name = args.as_str(0).unwrap();
-            let username = args.as_str(1);
-
-            let target_char_id = if let Some(username) = username {
-                ConsoleSystem::get_char_id_by_name(ecs_world, username)
-            } else {
-                self_char_id
-            };
-            if let Some(target_char_id) = target_char_id {
-                if let Some(target_char) = ecs_world
-                    .write_storage::<CharacterStateComponent>()
-                    .get_mut(target_char_id.into())
-                {
-                    let mut auth_state_storage = ecs_world.write_storage::<LocalCharStateComp>();
-                    let auth_state = auth_state_storage.get_mut(target_char_id.into()).unwrap();
-                    if let Ok(job_id) = JobId::from_str(job_name) {
-                        // TODO4
-                        //                        attach_human_player_components(
-                        //                            &target_char.name,
-                        //                            target_char_id,
-                        //                            &ecs_world.read_resource::<LazyUpdate>(),
-                        //                            &mut ecs_world.write_resource::<PhysicEngine>(),
-                        //                            ecs_world
-                        //                                .read_resource::<SystemVariables>()
-                        //                                .matrices
-                        //                                .projection,
-                        //                            auth_state.pos(),
-                        //                            Sex::Male,
-                        //                            job_id,
-                        //                            1,
-                        //                            target_char.team,
-                        //                            &ecs_world.read_resource::<CommonConfigs>(),
-                        //                            ecs_world
-                        //                                .read_resource::<SystemVariables>()
-                        //                                .matrices
-                        //                                .resolution_w,
-                        //                            ecs_world
-                        //                                .read_resource::<SystemVariables>()
-                        //                                .matrices
-                        //                                .resolution_h,
-                        //                            ecs_world
-                        //                                .read_storage::<HasServerIdComponent>()
-                        //                                .get(target_char_id.into())
-                        //                                .unwrap()
-                        //                                .server_id,
-                        //                        );
-                        Ok(())
-                    } else {
-                        return Err("Invalid JobId".to_owned());
-                    }
-                } else {
-                    Err(format!(
-                        "The character component does not exist: {:?}",
-                        target_char_id
-                    ))
-                }
-            } else {
-                Err("The user was not found".to_owned())
-            }
+        action: Box::new(|_self_char_id, args, ecs_world, _video| {
+            send_to_server(ecs_world, args.clone());
+            Ok(())
         }),
     }
 }
@@ -208,36 +147,9 @@ pub(super) fn cmd_set_outlook() -> CommandDefinition {
                 }
             },
         ),
-        action: Box::new(|self_char_id, args, ecs_world, _video| {
-            let job_name = args.as_str(0).unwrap();
-            let username = args.as_str(1);
-
-            let target_char_id = if let Some(username) = username {
-                ConsoleSystem::get_char_id_by_name(ecs_world, username)
-            } else {
-                self_char_id
-            };
-            if let Some(target_char_id) = target_char_id {
-                if let Some(target_char) = ecs_world
-                    .write_storage::<StaticCharDataComponent>()
-                    .get_mut(target_char_id.into())
-                {
-                    if let Some(outlook) = get_outlook(job_name, Some(&target_char.outlook)) {
-                        // TODO3 broadcasting
-                        target_char.outlook = outlook;
-                        Ok(())
-                    } else {
-                        return Err("Invalid JobId/MonsterId".to_owned());
-                    }
-                } else {
-                    Err(format!(
-                        "The character component does not exist: {:?}",
-                        target_char_id
-                    ))
-                }
-            } else {
-                Err("The user was not found".to_owned())
-            }
+        action: Box::new(|_self_char_id, args, ecs_world, _video| {
+            send_to_server(ecs_world, args.clone());
+            Ok(())
         }),
     }
 }
@@ -324,6 +236,18 @@ fn send_to_server(ecs_world: &mut specs::World, args: CommandArguments) {
         .push(ToServerPacket::ConsoleCommand(args));
 }
 
+pub(super) fn cmd_admin() -> CommandDefinition {
+    CommandDefinition {
+        name: "admin".to_string(),
+        arguments: vec![("password", CommandParamType::String, true)],
+        autocompletion: BasicAutocompletionProvider::new(|_index| None),
+        action: Box::new(|_self_char_id, args, ecs_world, _video| {
+            send_to_server(ecs_world, args.clone());
+            Ok(())
+        }),
+    }
+}
+
 pub(super) fn cmd_kill_all() -> CommandDefinition {
     CommandDefinition {
         name: "kill_all".to_string(),
@@ -411,33 +335,8 @@ pub(super) fn cmd_list_players() -> CommandDefinition {
         name: "list_players".to_string(),
         arguments: vec![],
         autocompletion: BasicAutocompletionProvider::new(|_index| None),
-        action: Box::new(|_self_char_id, _args, ecs_world, _video| {
-            print_console(
-                ecs_world,
-                ConsoleEntry::new().add(
-                    &format!(
-                        "{:<15}{:>15}{:>17}{:>15}{:>15}",
-                        "name", "traffic(sum)", "traffic/sec[KB]", "ping[ms]", "server fps",
-                    ),
-                    ConsoleWordType::CommandName,
-                ),
-            );
-            let (prev_bytes_per_second, sum_sent_bytes, ping, sending_fps) =
-                None.unwrap_or((0, 0, 0, 1.0f32));
-            // TODO cmd
-            // let string = format!(
-            //     "{:<15}{:>15}{:>17}{:>15}{:>15}",
-            //     &ecs_world.read_resource::<HumanInputComponent>().username,
-            //     humanize_bytes(sum_sent_bytes),
-            //     format!("{:>8.2}", prev_bytes_per_second as f32 / KIB as f32),
-            //     ping,
-            //     (1.0 / sending_fps).round() as u32
-            // );
-            // print_console(
-            //     ecs_world,
-            //     ConsoleEntry::new().add(&string, ConsoleWordType::Normal),
-            // );
-
+        action: Box::new(|_self_char_id, args, ecs_world, _video| {
+            send_to_server(ecs_world, args.clone());
             Ok(())
         }),
     }
@@ -572,29 +471,9 @@ pub(super) fn cmd_heal() -> CommandDefinition {
                 }
             },
         ),
-        action: Box::new(|self_char_id, args, ecs_world, _video| {
-            let value = args.as_int(0).unwrap().max(0);
-            let username = args.as_str(1);
-            let entity_id = if let Some(username) = username {
-                ConsoleSystem::get_char_id_by_name(ecs_world, username)
-            } else {
-                self_char_id
-            };
-
-            if let Some(entity_id) = entity_id {
-                if let Some(self_char_id) = self_char_id {
-                    let mut hp_mod_requests =
-                        ecs_world.write_resource::<Vec<HpModificationRequest>>();
-                    hp_mod_requests.push(HpModificationRequest {
-                        src_entity: self_char_id,
-                        dst_entity: entity_id,
-                        typ: HpModificationType::Heal(value as u32),
-                    });
-                }
-                Ok(())
-            } else {
-                Err("The user was not found".to_owned())
-            }
+        action: Box::new(|_self_char_id, args, ecs_world, _video| {
+            send_to_server(ecs_world, args.clone());
+            Ok(())
         }),
     }
 }
@@ -622,129 +501,13 @@ pub(super) fn cmd_spawn_area() -> CommandDefinition {
                 None
             }
         }),
-        action: Box::new(|self_char_id, args, ecs_world, _video| {
-            let name = args.as_str(0).unwrap();
-            let value = args.as_int(1).unwrap_or(0);
-            let width = args.as_int(2).unwrap_or(2).max(0) as u16;
-            let height = args.as_int(3).unwrap_or(3).max(0) as u16;
-            let interval = args.as_int(4).unwrap_or(500) as f32 / 1000.0;
-            let time = args.as_int(5).unwrap_or(500);
-            let x = args.as_int(6).map(|it| it as f32);
-            let y = args.as_int(7).map(|it| it as f32);
-
-            if let Some(self_char_id) = self_char_id {
-                let (pos, caster_team) = {
-                    let (hero_pos, team) = {
-                        let storage = ecs_world.read_storage::<LocalCharStateComp>();
-                        let storage2 = ecs_world.read_storage::<StaticCharDataComponent>();
-                        let char_state = storage.get(self_char_id.into()).unwrap();
-                        let char_state2 = storage2.get(self_char_id.into()).unwrap();
-                        (char_state.pos(), char_state2.team)
-                    };
-                    (v2(x.unwrap_or(hero_pos.x), y.unwrap_or(hero_pos.y)), team)
-                };
-                let area_status_id = ecs_world.create_entity().build();
-                ecs_world
-                    .write_storage()
-                    .insert(
-                        area_status_id,
-                        SkillManifestationComponent::new(
-                            area_status_id,
-                            match name {
-                                "heal" => Box::new(HealApplierArea::new(
-                                    "Heal",
-                                    HpModificationType::Heal(value.max(0) as u32),
-                                    &pos,
-                                    v2u(width, height),
-                                    interval,
-                                    self_char_id,
-                                    &mut ecs_world.write_resource::<PhysicEngine>(),
-                                )),
-                                "damage" => Box::new(HealApplierArea::new(
-                                    "Damage",
-                                    HpModificationType::BasicDamage(
-                                        value.max(0) as u32,
-                                        DamageDisplayType::SingleNumber,
-                                        WeaponType::Sword,
-                                    ),
-                                    &pos,
-                                    v2u(width, height),
-                                    interval,
-                                    self_char_id,
-                                    &mut ecs_world.write_resource::<PhysicEngine>(),
-                                )),
-                                _ => {
-                                    let name = name.to_owned();
-                                    Box::new(StatusApplierArea::new(
-                                        name.to_owned(),
-                                        move |now| {
-                                            create_status_payload(
-                                                &name,
-                                                self_char_id,
-                                                now,
-                                                time,
-                                                value,
-                                                caster_team,
-                                            )
-                                            .unwrap()
-                                        },
-                                        &pos,
-                                        v2u(width, height),
-                                        self_char_id,
-                                        &mut ecs_world.write_resource::<PhysicEngine>(),
-                                    ))
-                                }
-                            },
-                        ),
-                    )
-                    .unwrap();
-                Ok(())
-            } else {
-                Err("char required".to_owned())
-            }
+        action: Box::new(|_self_char_id, args, ecs_world, _video| {
+            send_to_server(ecs_world, args.clone());
+            Ok(())
         }),
     }
 }
 
-fn create_status_payload(
-    name: &str,
-    self_char_id: LocalCharEntityId,
-    now: LocalTime,
-    time: i32,
-    value: i32,
-    caster_team: Team,
-) -> Result<StatusEnum, String> {
-    match name {
-        "absorb" => Ok(StatusEnum::AbsorbStatus(AbsorbStatus::new(
-            self_char_id,
-            now,
-            time as f32 / 1000.0,
-        ))),
-        "firebomb" => Ok(StatusEnum::FireBombStatus(FireBombStatus {
-            caster_entity_id: self_char_id,
-            started: now,
-            until: now.add_seconds(time as f32 / 1000.0),
-            damage: value.max(1) as u32,
-            spread_count: 0,
-            caster_team,
-        })),
-        "poison" => Ok(StatusEnum::PoisonStatus(PoisonStatus {
-            poison_caster_entity_id: self_char_id,
-            started: now,
-            until: now.add_seconds(time as f32 / 1000.0),
-            next_damage_at: now,
-            damage: value.max(1) as u32,
-        })),
-        "armor" => Ok(StatusEnum::ArmorModifierStatus(ArmorModifierStatus::new(
-            now,
-            percentage(value),
-        ))),
-        _ => Err("Status not found".to_owned()),
-    }
-}
-
-pub const STATUS_NAMES: &'static [&'static str] = &["absorb", "poison", "firebomb", "armor"];
-
 pub(super) fn cmd_add_status() -> CommandDefinition {
     CommandDefinition {
         name: "add_status".to_string(),
@@ -765,46 +528,9 @@ pub(super) fn cmd_add_status() -> CommandDefinition {
                 }
             },
         ),
-        action: Box::new(|self_char_id, args, ecs_world, _video| {
-            let status_name = args.as_str(0).unwrap();
-            let duration = args.as_int(1).unwrap();
-            let value = args.as_int(2).unwrap_or(0);
-
-            let username = args.as_str(3);
-            let entity_id = if let Some(username) = username {
-                ConsoleSystem::get_char_id_by_name(ecs_world, username)
-            } else {
-                self_char_id
-            };
-
-            if let Some(entity_id) = entity_id {
-                if let Some(self_char_id) = self_char_id {
-                    let mut sys_vars = ecs_world.write_resource::<SystemVariables>();
-                    let now = ecs_world.read_resource::<EngineTime>().now();
-                    let team = ecs_world
-                        .read_storage::<StaticCharDataComponent>()
-                        .get(self_char_id.into())
-                        .unwrap()
-                        .team;
-                    sys_vars.apply_statuses.push(ApplyStatusComponent {
-                        source_entity_id: self_char_id,
-                        target_entity_id: entity_id,
-                        status: create_status_payload(
-                            status_name,
-                            entity_id,
-                            now,
-                            duration,
-                            value,
-                            team,
-                        )?,
-                    });
-                    Ok(())
-                } else {
-                    Err("Char requried".to_owned())
-                }
-            } else {
-                Err("The user was not found".to_owned())
-            }
+        action: Box::new(|_self_char_id, args, ecs_world, _video| {
+            send_to_server(ecs_world, args.clone());
+            Ok(())
         }),
     }
 }
@@ -827,7 +553,7 @@ pub(super) fn cmd_list_statuses() -> CommandDefinition {
             if let Some(entity_id) = entity_id {
                 let entry = {
                     let mut entry = ConsoleEntry::new();
-                    let char_storage = ecs_world.read_storage::<CharacterStateComponent>();
+                    let char_storage = ecs_world.read_storage::<LocalCharStateComp>();
                     if let Some(target_char) = char_storage.get(entity_id.into()) {
                         for status in target_char.statuses.get_statuses().iter() {
                             if let Some(status) = status {
@@ -865,46 +591,9 @@ pub(super) fn cmd_set_team() -> CommandDefinition {
                 }
             },
         ),
-        action: Box::new(|self_char_id, args, ecs_world, _video| {
-            let new_team = match args.as_str(0).unwrap() {
-                "left" => Team::Left,
-                _ => Team::Right,
-            };
-            let username = args.as_str(1);
-
-            let target_entity_id = if let Some(username) = username {
-                ConsoleSystem::get_char_id_by_name(ecs_world, username)
-            } else {
-                self_char_id
-            };
-
-            if let Some(target_char_id) = target_entity_id {
-                let mut char_storage = ecs_world.write_storage::<StaticCharDataComponent>();
-                let char_state = char_storage.get_mut(target_char_id.into()).unwrap();
-
-                // TODO3 physics
-                //                if let Some(collider) = ecs_world
-                //                    .write_resource::<PhysicEngine>()
-                //                    .colliders
-                //                    .get_mut(char_state.collider_handle)
-                //                {
-                //                    let mut cg = collider.collision_groups().clone();
-                //                    cg.modify_membership(char_state.team.get_collision_group() as usize, false);
-                //                    cg.modify_membership(new_team.get_collision_group() as usize, true);
-                //                    cg.modify_blacklist(
-                //                        char_state.team.get_barricade_collision_group() as usize,
-                //                        true,
-                //                    );
-                //                    cg.modify_blacklist(new_team.get_barricade_collision_group() as usize, false);
-                //                    collider.set_collision_groups(cg);
-                //                }
-
-                // TODO3 broadcasting
-                char_state.team = new_team;
-                Ok(())
-            } else {
-                Err("The user was not found".to_owned())
-            }
+        action: Box::new(|_self_char_id, args, ecs_world, _video| {
+            send_to_server(ecs_world, args.clone());
+            Ok(())
         }),
     }
 }
@@ -995,50 +684,8 @@ pub(super) fn cmd_resurrect() -> CommandDefinition {
             },
         ),
         action: Box::new(|_self_char_id, args, ecs_world, _video| {
-            let username = args.as_str(0).unwrap();
-            let target_entity_id = ConsoleSystem::get_char_id_by_name(ecs_world, username);
-            if let Some(target_char_id) = target_entity_id {
-                let pos2d = {
-                    // remove death status (that is the only status a death character has)
-                    let mut char_storage = ecs_world.write_storage::<CharacterStateComponent>();
-                    let char_state = char_storage.get_mut(target_char_id.into()).unwrap();
-                    let mut auth_char_storage = ecs_world.write_storage::<LocalCharStateComp>();
-                    let auth_state = auth_char_storage.get_mut(target_char_id.into()).unwrap();
-                    char_state.statuses.remove_all();
-                    auth_state.set_state(CharState::Idle, auth_state.dir());
-
-                    // give him max hp/sp
-                    auth_state.hp = CharAttributes::get_base_attributes(
-                        ecs_world
-                            .write_storage::<StaticCharDataComponent>()
-                            .get(target_char_id.into())
-                            .unwrap()
-                            .job_id,
-                        &ecs_world.read_resource(),
-                    )
-                    .max_hp;
-                    auth_state.pos()
-                };
-
-                // give him back it's physic component
-                // TODO
-                //                let physics_component = CharacterEntityBuilder::new(target_char_id, "tmp")
-                //                    .physics(
-                //                        pos2d,
-                //                        &mut ecs_world.write_resource::<PhysicEngine>(),
-                //                        |builder| builder.collision_group(CollisionGroup::Minion).circle(1.0),
-                //                    )
-                //                    .physics_handles
-                //                    .unwrap();
-                //                let mut char_storage = ecs_world.write_storage::<CharacterStateComponent>();
-                //                let char_state = char_storage.get_mut(target_char_id.into()).unwrap();
-                //                char_state.collider_handle = physics_component.0;
-                //                char_state.body_handle = physics_component.1;
-
-                Ok(())
-            } else {
-                Err("The user was not found".to_owned())
-            }
+            send_to_server(ecs_world, args.clone());
+            Ok(())
         }),
     }
 }
@@ -1066,40 +713,9 @@ pub(super) fn cmd_clone_char() -> CommandDefinition {
         autocompletion: AutocompletionProviderWithUsernameCompletion::new(
             |_index, username_completor, input| Some(username_completor(input)),
         ),
-        action: Box::new(|self_char_id, args, ecs_world, _video| {
-            let username = args.as_str(1);
-
-            let target_char_id = if let Some(username) = username {
-                ConsoleSystem::get_char_id_by_name(ecs_world, username)
-            } else {
-                self_char_id
-            };
-
-            if let Some(target_char_id) = target_char_id {
-                // create a new entity with the same outlook
-                let char_entity_id = LocalCharEntityId::from(ecs_world.create_entity().build());
-
-                let char_storage = ecs_world.read_storage::<CharacterStateComponent>();
-                let auth_char_storage = ecs_world.read_storage::<LocalCharStateComp>();
-
-                let cloning_char = char_storage.get(target_char_id.into()).unwrap();
-                let auth_cloning_char = auth_char_storage.get(target_char_id.into()).unwrap();
-                let updater = &ecs_world.read_resource::<LazyUpdate>();
-
-                // TODO2 we need a server_id as last param
-                //                create_client_player_entity(ecs_world,
-                //                "Clone",
-                //                                            cloning_char.job_id,
-                //                                            auth_cloning_char.pos(),
-                //                                            cloning_char.team,
-                //                                            cloning_char.outlook.clone(),
-                //
-                //                );
-
-                Ok(())
-            } else {
-                Err("The character was not found".to_owned())
-            }
+        action: Box::new(|_self_char_id, args, ecs_world, _video| {
+            send_to_server(ecs_world, args.clone());
+            Ok(())
         }),
     }
 }
@@ -1250,19 +866,9 @@ pub(super) fn cmd_goto() -> CommandDefinition {
                     let char_state = storage.get(target_char_id.into()).unwrap();
                     char_state.pos()
                 };
-                if let Some(self_char_id) = self_char_id {
-                    let self_body_handle = ecs_world
-                        .read_storage::<CharacterStateComponent>()
-                        .get(self_char_id.into())
-                        .map(|it| it.body_handle)
-                        .unwrap();
-                    let physics_world = &mut ecs_world.write_resource::<PhysicEngine>();
-                    if let Some(self_body) = physics_world.bodies.rigid_body_mut(self_body_handle) {
-                        self_body.set_position(Isometry2::translation(target_pos.x, target_pos.y));
-                        Ok(())
-                    } else {
-                        Err("No rigid body was found for this user".to_owned())
-                    }
+                if self_char_id.is_some() {
+                    send_to_server(ecs_world, args.clone());
+                    Ok(())
                 } else {
                     ecs_world
                         .write_resource::<CameraComponent>()
@@ -1336,33 +942,9 @@ pub(super) fn cmd_set_pos() -> CommandDefinition {
                 }
             },
         ),
-        action: Box::new(|self_char_id, args, ecs_world, _video| {
-            let x = args.as_int(0).unwrap();
-            let z = args.as_int(1).unwrap();
-            let username = args.as_str(2);
-            let y = args.as_f32(3).unwrap_or(0.0);
-
-            let char_id = if let Some(username) = username {
-                ConsoleSystem::get_char_id_by_name(ecs_world, username)
-            } else {
-                self_char_id
-            };
-
-            let mut char_storage = ecs_world.write_storage::<CharacterStateComponent>();
-            if let Some(char_state) = char_id.and_then(|it| char_storage.get_mut(it.into())) {
-                let body_handle = char_state.body_handle;
-
-                let physics_world = &mut ecs_world.write_resource::<PhysicEngine>();
-                if let Some(body) = physics_world.bodies.rigid_body_mut(body_handle) {
-                    body.set_position(Isometry2::translation(x as f32, z as f32));
-                    char_state.set_y(y);
-                    Ok(())
-                } else {
-                    Err("No rigid body was found for this user".to_owned())
-                }
-            } else {
-                Err("The user was not found".to_owned())
-            }
+        action: Box::new(|_self_char_id, args, ecs_world, _video| {
+            send_to_server(ecs_world, args.clone());
+            Ok(())
         }),
     }
 }
@@ -1380,38 +962,9 @@ pub(super) fn cmd_add_falcon() -> CommandDefinition {
                 }
             },
         ),
-        action: Box::new(|self_char_id, args, ecs_world, _video| {
-            let username = args.as_str(0);
-
-            let char_id = if let Some(username) = username {
-                ConsoleSystem::get_char_id_by_name(ecs_world, username)
-            } else {
-                self_char_id
-            };
-
-            if let Some(char_id) = char_id {
-                let pos = ecs_world
-                    .read_storage::<LocalCharStateComp>()
-                    .get(char_id.into())
-                    .map(|it| it.pos())
-                    .unwrap();
-
-                let _falcon_id = ecs_world
-                    .create_entity()
-                    .with(FalconComponent::new(char_id, pos.x, pos.y))
-                    .with(SpriteRenderDescriptorComponent {
-                        action_index: CharActionIndex::Idle as usize,
-                        fps_multiplier: 1.0,
-                        animation_started: LocalTime::from(0.0),
-                        forced_duration: None,
-                        direction: CharDir::South,
-                        animation_ends_at: LocalTime::from(0.0),
-                    })
-                    .build();
-                Ok(())
-            } else {
-                Err("The user was not found".to_owned())
-            }
+        action: Box::new(|_self_char_id, args, ecs_world, _video| {
+            send_to_server(ecs_world, args.clone());
+            Ok(())
         }),
     }
 }
@@ -1429,37 +982,9 @@ pub(super) fn cmd_remove_falcon() -> CommandDefinition {
                 }
             },
         ),
-        action: Box::new(|self_char_id, args, ecs_world, _video| {
-            let username = args.as_str(0);
-
-            let char_id = if let Some(username) = username {
-                ConsoleSystem::get_char_id_by_name(ecs_world, username)
-            } else {
-                self_char_id
-            };
-
-            if let Some(char_id) = char_id {
-                let mut delete_falcon_id = None;
-                for (falcon_id, falcon) in (
-                    &ecs_world.entities(),
-                    &ecs_world.read_storage::<FalconComponent>(),
-                )
-                    .join()
-                {
-                    if falcon.owner_entity_id == char_id {
-                        delete_falcon_id = Some(falcon_id);
-                        break;
-                    }
-                }
-                if let Some(falcon_id) = delete_falcon_id {
-                    ecs_world.delete_entity(falcon_id).expect("");
-                    return Ok(());
-                } else {
-                    Err("The user does not have a falcon".to_owned())
-                }
-            } else {
-                Err("The user was not found".to_owned())
-            }
+        action: Box::new(|_self_char_id, args, ecs_world, _video| {
+            send_to_server(ecs_world, args.clone());
+            Ok(())
         }),
     }
 }
@@ -3,11 +3,11 @@ use crate::components::controller::HumanInputComponent;
 use crate::render::opengl_render_sys::{NORMAL_FONT_H, NORMAL_FONT_W};
 use crate::render::render_command::{Font, RenderCommandCollector, UiLayer2d};
 use crate::systems::console_commands::{
-    cmd_add_falcon, cmd_add_status, cmd_bind_key, cmd_clear, cmd_clone_char, cmd_control_char,
-    cmd_disable_collision, cmd_enable_collision, cmd_follow_char, cmd_get_pos, cmd_goto, cmd_heal,
-    cmd_inspect, cmd_kill_all, cmd_list_entities, cmd_list_players, cmd_list_statuses,
-    cmd_reload_configs, cmd_remove_falcon, cmd_resurrect, cmd_set_config, cmd_set_damping,
-    cmd_set_fullscreen, cmd_set_job, cmd_set_mass, cmd_set_outlook, cmd_set_pos,
+    cmd_add_falcon, cmd_add_status, cmd_admin, cmd_bind_key, cmd_clear, cmd_clone_char,
+    cmd_control_char, cmd_disable_collision, cmd_enable_collision, cmd_follow_char, cmd_get_pos,
+    cmd_goto, cmd_heal, cmd_inspect, cmd_kill_all, cmd_list_entities, cmd_list_players,
+    cmd_list_statuses, cmd_reload_configs, cmd_remove_falcon, cmd_resurrect, cmd_set_config,
+    cmd_set_damping, cmd_set_fullscreen, cmd_set_job, cmd_set_mass, cmd_set_outlook, cmd_set_pos,
     cmd_set_resolution, cmd_set_team, cmd_spawn_area, cmd_spawn_entity, cmd_toggle_console,
 };
 use crate::systems::SystemVariables;
@@ -633,6 +633,7 @@ impl ConsoleSystem {
         ConsoleSystem::add_command(&mut command_defs, cmd_spawn_entity());
         ConsoleSystem::add_command(&mut command_defs, cmd_reload_configs());
         ConsoleSystem::add_command(&mut command_defs, cmd_heal());
+        ConsoleSystem::add_command(&mut command_defs, cmd_admin());
         ConsoleSystem::add_command(&mut command_defs, cmd_kill_all());
         ConsoleSystem::add_command(&mut command_defs, cmd_goto());
         ConsoleSystem::add_command(&mut command_defs, cmd_follow_char());
@@ -2,7 +2,6 @@ use crate::components::char::{
     CharActionIndex, CharacterStateComponent, SpriteRenderDescriptorComponent,
 };
 use crate::components::controller::LocalPlayerController;
-use crate::components::skills::falcon_carry::FalconCarryStatus;
 use crate::components::status::status::{ApplyStatusComponent, StatusEnum};
 use crate::runtime_assets::map::PhysicEngine;
 use crate::systems::{SystemFrameDurations, SystemVariables};
@@ -12,6 +11,7 @@ use rustarok_common::components::char::{
     CharDir, ControllerEntityId, LocalCharEntityId, LocalCharStateComp,
 };
 use rustarok_common::components::controller::{ControllerComponent, PlayerIntention};
+use rustarok_common::components::status::skill_statuses::FalconCarryStatus;
 use specs::prelude::*;
 use vek::QuadraticBezier3;
 
@@ -139,6 +139,42 @@ impl FalconComponent {
 
 impl FalconAiSystem {}
 
+/// The server decides who has a falcon (`StaticCharDataComponent::has_falcon`),
+/// the clients only show it
+pub fn add_falcon(ecs_world: &mut specs::World, owner_entity_id: LocalCharEntityId, pos: Vec2) {
+    ecs_world
+        .create_entity()
+        .with(FalconComponent::new(owner_entity_id, pos.x, pos.y))
+        .with(SpriteRenderDescriptorComponent {
+            action_index: CharActionIndex::Idle as usize,
+            fps_multiplier: 1.0,
+            animation_started: LocalTime::from(0.0),
+            forced_duration: None,
+            direction: CharDir::South,
+            animation_ends_at: LocalTime::from(0.0),
+        })
+        .build();
+}
+
+pub fn remove_falcon(ecs_world: &mut specs::World, owner_entity_id: LocalCharEntityId) {
+    let falcon_id = (
+        &ecs_world.entities(),
+        &ecs_world.read_storage::<FalconComponent>(),
+    )
+        .join()
+        .find(|(_falcon_id, falcon)| falcon.owner_entity_id == owner_entity_id)
+        .map(|(falcon_id, _falcon)| falcon_id);
+    if let Some(falcon_id) = falcon_id {
+        if let Err(e) = ecs_world.delete_entity(falcon_id) {
+            log::error!(
+                "Could not remove the falcon of {:?}: {:?}",
+                owner_entity_id,
+                e
+            );
+        }
+    }
+}
+
 pub const FALCON_FLY_HEIGHT: f32 = 5.0;
 pub const FALCON_LOWERED_HEIGHT: f32 = 2.0;
 
@@ -1,7 +1,6 @@
 use crate::components::char::HasServerIdComponent;
 use crate::components::controller::LocalPlayerController;
 use crate::strum::IntoEnumIterator;
-use crate::SIMULATION_FREQ;
 use imgui::*;
 use rustarok_common::common::{EngineTime, LocalTime};
 use rustarok_common::components::char::TargetId;
@@ -21,6 +20,8 @@ const PING_COUNT: usize = 16;
 pub struct ImguiData {
     entity_under_cursor: Option<LocalCharEntityId>,
     max_fps: usize,
+    /// the tick rate of the server
+    simulation_freq: usize,
     show_network_window: bool,
     show_entity_window: bool,
     pings: [f32; PING_COUNT],
@@ -36,10 +37,11 @@ pub struct ImguiData {
 }
 
 impl ImguiData {
-    pub fn new(max_fps: usize) -> ImguiData {
+    pub fn new(max_fps: usize, simulation_freq: usize) -> ImguiData {
         ImguiData {
             entity_under_cursor: None,
             max_fps,
+            simulation_freq,
             show_network_window: false,
             show_entity_window: false,
             pings: [0.0; PING_COUNT],
@@ -705,13 +707,13 @@ fn draw_network_window(data: &mut ImguiData, ui: &imgui::Ui) {
                 1.0,
                 [1.0, 0.0, 0.0, 1.0], // red at zero
             );
-            rollback_graph(&data.rollbacks_per_second, ui);
+            rollback_graph(&data.rollbacks_per_second, data.simulation_freq, ui);
             simple_value(
                 "Simulations/s",
                 data.simulation_duration,
                 ui,
                 0.0,
-                (SIMULATION_FREQ + 20) as f32,
+                (data.simulation_freq + 20) as f32,
                 0.0,
                 [1.0, 0.0, 0.0, 1.0],
             );
@@ -719,10 +721,10 @@ fn draw_network_window(data: &mut ImguiData, ui: &imgui::Ui) {
     data.show_network_window = opened;
 }
 
-fn rollback_graph(data: &[f32], ui: &imgui::Ui) {
+fn rollback_graph(data: &[f32], simulation_freq: usize, ui: &imgui::Ui) {
     let cur_value = *data.last().unwrap();
     let caption = im_str!("Rollbacks {}", cur_value);
-    let max = SIMULATION_FREQ as f32;
+    let max = simulation_freq as f32;
 
     let color = color_gradient(
         0.0,   // start
@@ -1,4 +1,4 @@
-use crate::components::controller::{CameraComponent, CameraMode, HumanInputComponent, SkillKey};
+use crate::components::controller::{CameraComponent, CameraMode, HumanInputComponent};
 use crate::components::skills::skills::{SkillTargetType, Skills};
 use crate::systems::RenderMatrices;
 use crate::systems::SystemVariables;
@@ -200,51 +200,15 @@ impl InputConsumerSystem {
             matrices.resolution_h,
         );
         input.mouse_world_pos = mouse_world_pos;
-
-        if input.is_key_just_released(Scancode::F12) {
-            match input.get_skill_for_key(SkillKey::Q) {
-                Some(Skills::FireWall) => {
-                    input.assign_skill(SkillKey::Q, Skills::Poison);
-                    input.assign_skill(SkillKey::W, Skills::FireBomb);
-                    input.assign_skill(SkillKey::E, Skills::Cure);
-                    input.assign_skill(SkillKey::R, Skills::Lightning);
-                    input.assign_skill(SkillKey::Num1, Skills::Sanctuary);
-                    input.assign_skill(SkillKey::Num2, Skills::ExoSkeleton);
-                    input.assign_skill(SkillKey::Num3, Skills::GazBarricade);
-                }
-                Some(Skills::Poison) => {
-                    input.assign_skill(SkillKey::Q, Skills::WizPyroBlast);
-                    input.assign_skill(SkillKey::W, Skills::AssaBladeDash);
-                    input.assign_skill(SkillKey::E, Skills::AssaPhasePrism);
-                    input.assign_skill(SkillKey::R, Skills::GazXplodiumCharge);
-                }
-                Some(Skills::WizPyroBlast) => {
-                    input.assign_skill(SkillKey::Q, Skills::GazTurret);
-                    input.assign_skill(SkillKey::D, Skills::GazDestroyTurret);
-                    input.assign_skill(SkillKey::Num1, Skills::GazTurretTarget);
-                    input.assign_skill(SkillKey::Num2, Skills::FalconCarry);
-                    input.assign_skill(SkillKey::Num3, Skills::FalconAttack);
-                }
-                Some(Skills::GazTurret) => {
-                    input.assign_skill(SkillKey::Q, Skills::FireWall);
-                    input.assign_skill(SkillKey::W, Skills::AbsorbShield);
-                    input.assign_skill(SkillKey::E, Skills::Heal);
-                    input.assign_skill(SkillKey::R, Skills::BrutalTestSkill);
-                }
-                _ => {}
-            }
-        }
     }
 }
 
 impl InputConsumerSystem {
     pub fn target_selection_or_casting(skill: Skills, mouse_pos: Vec2) -> Option<PlayerIntention> {
         // NoTarget skills have to be casted immediately without selecting target
-        if skill.get_definition().get_skill_target_type() == SkillTargetType::NoTarget {
+        if skill.get_skill_target_type() == SkillTargetType::NoTarget {
             log::debug!("Skill '{:?}' is no target, so cast it", skill);
-            // TODO2
-            //Some(PlayerIntention::Casting(skill, false, mouse_pos))
-            None
+            Some(PlayerIntention::Casting(skill, false, mouse_pos, None))
         } else {
             None
         }
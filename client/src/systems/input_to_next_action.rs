@@ -9,12 +9,13 @@ use crate::runtime_assets::map::MapRenderData;
 use crate::systems::input_sys::InputConsumerSystem;
 use crate::systems::{SystemFrameDurations, SystemVariables};
 use crate::LocalTime;
-use rustarok_common::common::EngineTime;
 use rustarok_common::common::SimulationTick;
+use rustarok_common::common::{EngineTime, Vec2};
 use rustarok_common::components::char::{
     LocalCharEntityId, LocalCharStateComp, StaticCharDataComponent, Team,
 };
 use rustarok_common::components::controller::PlayerIntention;
+use rustarok_common::config::CommonConfigs;
 use rustarok_common::systems::intention_applier::ControllerIntentionToCharTarget;
 use sdl2::keyboard::Scancode;
 use specs::prelude::*;
@@ -150,11 +151,11 @@ impl InputToNextActionSystem {
                     PlayerIntention::MoveTo(_) => {}
                     PlayerIntention::MoveTowardsMouse(_) => {}
                     PlayerIntention::Attack(_) => {}
-                    PlayerIntention::AttackTowards(_) => {} // TODO2
-                                                            //                        PlayerIntention::Casting(_, _, _) => {
-                                                            //                            log::debug!("...but the console is open");
-                                                            //                            controller.next_action = None;
-                                                            //                        }
+                    PlayerIntention::AttackTowards(_) => {}
+                    PlayerIntention::Casting(..) => {
+                        log::debug!("...but the console is open");
+                        local_player.controller.intention = None;
+                    }
                 }
             }
         }
@@ -196,13 +197,28 @@ pub struct ClientIntentionToCharTargetSystem;
 impl<'a> System<'a> for ClientIntentionToCharTargetSystem {
     type SystemData = (
         WriteStorage<'a, LocalCharStateComp>,
+        ReadStorage<'a, StaticCharDataComponent>,
         ReadExpect<'a, LocalPlayerController>,
+        ReadExpect<'a, CommonConfigs>,
+        ReadExpect<'a, EngineTime>,
     );
 
-    fn run(&mut self, (mut auth_char_state_storage, local_player): Self::SystemData) {
+    fn run(
+        &mut self,
+        (
+            mut auth_char_state_storage,
+            static_char_data_storage,
+            local_player,
+            configs,
+            time,
+        ): Self::SystemData,
+    ) {
         ControllerIntentionToCharTarget::controller_intention_to_char_target(
             &local_player.controller,
             &mut auth_char_state_storage,
+            &static_char_data_storage,
+            &configs,
+            time.now(),
         )
     }
 }
@@ -216,15 +232,15 @@ impl InputToNextActionSystem {
         static_char_data_storage: &ReadStorage<StaticCharDataComponent>,
         self_team: Team,
     ) -> (CursorFrame, [u8; 3]) {
-        return if let Some((skill_key, skill)) = local_player.select_skill_target {
+        return if let Some((_skill_key, skill)) = local_player.select_skill_target {
             let is_castable = auth_char_state_storage
                 .get(controlled_entity.into())
                 .unwrap()
-                .skill_cast_allowed_at[skill_key as usize]
+                .skill_cast_allowed_at[skill as usize]
                 .has_already_passed(now);
             if !is_castable {
                 (CURSOR_STOP, [255, 255, 255])
-            } else if skill.get_definition().get_skill_target_type() != SkillTargetType::Area {
+            } else if skill.get_skill_target_type() != SkillTargetType::Area {
                 (CURSOR_TARGET, [255, 255, 255])
             } else {
                 (CURSOR_CLICK, [255, 255, 255])
@@ -257,6 +273,28 @@ impl InputToNextActionSystem {
 }
 
 impl InputToNextActionSystem {
+    fn casting_intention(
+        local_player: &LocalPlayerController,
+        skill: Skills,
+        is_self_cast: bool,
+        mouse_world_pos: Vec2,
+    ) -> Option<PlayerIntention> {
+        let self_id = local_player.controller.controlled_entity?;
+        let target_entity = if is_self_cast {
+            None
+        } else {
+            local_player
+                .entities_below_cursor
+                .get_skill_target(skill.get_skill_target_type(), self_id)
+        };
+        return Some(PlayerIntention::Casting(
+            skill,
+            is_self_cast,
+            mouse_world_pos,
+            target_entity,
+        ));
+    }
+
     fn determine_intention(
         auth_char_state_storage: &ReadStorage<LocalCharStateComp>,
         input: &HumanInputComponent,
@@ -282,13 +320,15 @@ impl InputToNextActionSystem {
                     CastMode::Normal => {
                         if input.left_mouse_released {
                             log::debug!("Player wants to cast {:?}", skill);
-                            // TODO2
-                            //                                Some(PlayerIntention::Casting(
-                            //                                    skill,
-                            //                                    false,
-                            //                                    input.mouse_world_pos,
-                            //                                ))
-                            (None, None)
+                            (
+                                InputToNextActionSystem::casting_intention(
+                                    local_player,
+                                    skill,
+                                    false,
+                                    input.mouse_world_pos,
+                                ),
+                                None,
+                            )
                         } else if input.right_mouse_pressed
                             || input.is_key_just_pressed(Scancode::Escape)
                         {
@@ -322,16 +362,15 @@ impl InputToNextActionSystem {
                     CastMode::OnKeyRelease => {
                         if input.is_key_just_released(casting_skill_key.scancode()) {
                             log::debug!("Player wants to cast {:?}", skill);
-                            // TODO2
-                            //                                Some(
-                            //                                    PlayerIntention::Casting(
-                            //                                        input.get_skill_for_key(casting_skill_key)
-                            //                                            .expect("'is_casting_selection' must be Some only if the casting skill is valid! "),
-                            //                                        false,
-                            //                                        input.mouse_world_pos,
-                            //                                    )
-                            //                                )
-                            (None, None)
+                            (
+                                InputToNextActionSystem::casting_intention(
+                                    local_player,
+                                    skill,
+                                    false,
+                                    input.mouse_world_pos,
+                                ),
+                                None,
+                            )
                         } else if input.right_mouse_pressed
                             || input.is_key_just_pressed(Scancode::Escape)
                         {
@@ -378,13 +417,15 @@ impl InputToNextActionSystem {
                 }
                 CastMode::OnKeyPress => {
                     log::debug!("Player wants to cast {:?}, alt={:?}", skill, alt_down);
-                    // TODO2
-                    //                        Some(PlayerIntention::Casting(
-                    //                            skill,
-                    //                            alt_down,
-                    //                            input.mouse_world_pos,
-                    //                        ))
-                    (None, None)
+                    (
+                        InputToNextActionSystem::casting_intention(
+                            local_player,
+                            skill,
+                            alt_down,
+                            input.mouse_world_pos,
+                        ),
+                        None,
+                    )
                 }
             }
         } else if let Some((_skill_key, skill)) = just_released_skill_key.and_then(|skill_key| {
@@ -395,9 +436,15 @@ impl InputToNextActionSystem {
             // can get here only when alt was down and OnKeyRelease
             if alt_down {
                 log::debug!("Player wants to cast {:?}, SELF", skill);
-                // TODO2
-                //                    Some(PlayerIntention::Casting(skill, true, input.mouse_world_pos))
-                (None, local_player.select_skill_target)
+                (
+                    InputToNextActionSystem::casting_intention(
+                        local_player,
+                        skill,
+                        true,
+                        input.mouse_world_pos,
+                    ),
+                    local_player.select_skill_target,
+                )
             } else {
                 (None, local_player.select_skill_target)
             }
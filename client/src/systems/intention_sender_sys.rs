@@ -69,6 +69,18 @@ impl<'a> System<'a> for IntentionSenderSystem {
                     PlayerIntention::Attack(local_id) => ToServerPlayerIntention::Attack(
                         server_id_storage.get((*local_id).into()).unwrap().server_id,
                     ),
+                    PlayerIntention::Casting(skill, is_self_cast, pos, local_id) => {
+                        ToServerPlayerIntention::Casting(
+                            *skill,
+                            *is_self_cast,
+                            *pos,
+                            local_id.and_then(|local_id| {
+                                server_id_storage
+                                    .get(local_id.into())
+                                    .map(|it| it.server_id)
+                            }),
+                        )
+                    }
                 },
             });
         }
@@ -1,4 +1,4 @@
-use crate::components::skills::skills::{FinishCast, Skills};
+use crate::components::skills::skills::Skills;
 use crate::components::status::status::{
     ApplyStatusComponent, ApplyStatusInAreaComponent, RemoveStatusComponent,
 };
@@ -174,7 +174,6 @@ pub struct SystemVariables {
     pub assets: AssetResources,
     pub matrices: RenderMatrices,
     pub apply_statuses: Vec<ApplyStatusComponent>,
-    pub just_finished_skill_casts: Vec<FinishCast>,
     pub apply_area_statuses: Vec<ApplyStatusInAreaComponent>,
     pub remove_statuses: Vec<RemoveStatusComponent>,
 }
@@ -203,7 +202,6 @@ impl SystemVariables {
             },
             matrices: render_matrices,
             apply_statuses: Vec::with_capacity(128),
-            just_finished_skill_casts: Vec::with_capacity(128),
             apply_area_statuses: Vec::with_capacity(128),
             remove_statuses: Vec::with_capacity(128),
         }
@@ -134,9 +134,9 @@ impl<'a> System<'a> for UpdateCharSpriteBasedOnStateSystem {
                 let forced_duration = match &state {
                     CharState::Attacking { .. } => Some(auth_state.attack_delay_ends_at.minus(now)),
                     // HACK: '100.0', so the first frame is rendered during casting :)
-                    //                    CharState::CastingSkill(casting_info) => {
-                    //                        Some(casting_info.cast_ends.add_seconds(100.0))
-                    //                    }
+                    CharState::CastingSkill(casting_info) => {
+                        Some(casting_info.cast_ends.add_seconds(100.0))
+                    }
                     _ => None,
                 };
                 sprite.forced_duration = forced_duration;
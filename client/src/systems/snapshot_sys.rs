@@ -5,7 +5,11 @@ use rustarok_common::components::char::{
     CharState, LocalCharEntityId, LocalCharStateComp, ServerEntityId,
 };
 use rustarok_common::components::controller::PlayerIntention;
-use rustarok_common::packets::from_server::ServerEntityStateLocal;
+use rustarok_common::packets::from_server::{ServerEntityState, ServerEntityStateLocal};
+use rustarok_common::packets::snapshot_delta::{
+    CharStateDelta, EncodedCharState, EncodedWorldSnapshot, SnapshotHistory,
+};
+use rustarok_common::packets::to_server::PacketReadErr;
 
 use crate::components::char::HasServerIdComponent;
 use std::collections::HashMap;
@@ -75,6 +79,8 @@ pub struct SnapshotStorage {
     // last_predicted_index + 1
     snapshots_for_each_char: Vec<CharSnapshots>,
     intentions: [(u32, Option<PlayerIntention>); SnapshotStorage::SNAPSHOT_COUNT],
+    // the last received snapshots from the server, the deltas are applied on them
+    received_snapshots: SnapshotHistory,
 }
 
 pub enum ServerAckResult {
@@ -115,6 +121,7 @@ impl SnapshotStorage {
                 arr
             },
             snapshots_for_each_char: Vec::with_capacity(64),
+            received_snapshots: SnapshotHistory::new(),
         }
     }
 
@@ -137,6 +144,11 @@ impl SnapshotStorage {
         });
     }
 
+    pub fn remove_predicting_entity(&mut self, server_id: ServerEntityId) {
+        self.snapshots_for_each_char
+            .retain(|it| it.server_id != server_id);
+    }
+
     pub fn set_client_last_command_id(&mut self, cid: u32) {
         self.client_last_command_id = cid;
     }
@@ -214,6 +226,61 @@ impl SnapshotStorage {
         return ((self.tail - self.last_acknowledged_index) - 1) as usize;
     }
 
+    /// Reconstructs the full states from the deltas of an Ack packet, and keeps them
+    /// as a possible baseline for the next ones.
+    /// The result is in the order of the deltas, so it can be passed to `ack_arrived`.
+    pub fn reconstruct_server_states(
+        &mut self,
+        tick: SimulationTick,
+        baseline_tick: Option<SimulationTick>,
+        deltas: Vec<CharStateDelta>,
+    ) -> Result<Vec<ServerEntityState>, PacketReadErr> {
+        let baseline = match baseline_tick {
+            Some(baseline_tick) => Some(
+                self.received_snapshots
+                    .get(baseline_tick)
+                    .ok_or(PacketReadErr::InvalidValues)?,
+            ),
+            None => None,
+        };
+        let mut snapshot = EncodedWorldSnapshot::new(tick);
+        let mut states = Vec::with_capacity(deltas.len());
+        for delta in &deltas {
+            let encoded_state = EncodedCharState::apply_delta(
+                baseline.and_then(|baseline| baseline.get(&delta.id)),
+                delta,
+            )?;
+            states.push(ServerEntityState {
+                id: delta.id,
+                char_snapshot: encoded_state.decode()?,
+            });
+            snapshot.push(delta.id, encoded_state);
+        }
+        self.received_snapshots.push(snapshot);
+        return Ok(states);
+    }
+
+    /// Orders the states of a snapshot as the predicted entities are stored, so they can be
+    /// passed to `ack_arrived`.
+    /// None if the snapshot does not contain exactly the predicted entities. It can happen for
+    /// a short time, because the snapshots and the `EntityAppeared`/`EntityDisappeared`
+    /// packets are sent on different channels.
+    pub fn order_as_predicted_entities(
+        &self,
+        states: Vec<ServerEntityState>,
+    ) -> Option<Vec<ServerEntityState>> {
+        if states.len() != self.snapshots_for_each_char.len() {
+            return None;
+        }
+        let mut states: HashMap<ServerEntityId, ServerEntityState> =
+            states.into_iter().map(|it| (it.id, it)).collect();
+        return self
+            .snapshots_for_each_char
+            .iter()
+            .map(|char_snapshots| states.remove(&char_snapshots.server_id))
+            .collect();
+    }
+
     pub fn ack_arrived(
         &mut self,
         client_tick: SimulationTick,
@@ -471,6 +538,7 @@ impl SnapshotStorage {
                     CharState::Dead => false,
                     CharState::Attacking { .. } => false,
                     CharState::StandBy => false,
+                    CharState::CastingSkill(..) => false,
                     CharState::Walking(..) => {
                         // if the pos are the same but the target pos differs, don't repredict
                         // TODO but set the new target pos for remote entities
@@ -480,6 +548,7 @@ impl SnapshotStorage {
             }
             _ => std::mem::discriminant(predicted_state) == std::mem::discriminant(acked_state),
         };
+        matches &= acked.statuses.has_same_statuses_as(&predicted.statuses);
         if !matches {
             log::trace!(
                 "predicted: ({}, {}, {:?}) !!!=== acked: v({}, {}, {:?})",
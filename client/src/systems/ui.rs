@@ -282,10 +282,10 @@ impl RenderUI {
         assets: &AssetResources,
         time: &EngineTime,
     ) {
-        if let Some((skill_key, skill)) = controller.select_skill_target {
+        if let Some((_skill_key, skill)) = controller.select_skill_target {
             let texture = assets.texts.skill_name_texts[&skill];
             let not_castable =
-                char_state.skill_cast_allowed_at[skill_key as usize].has_not_passed_yet(time.now());
+                char_state.skill_cast_allowed_at[skill as usize].has_not_passed_yet(time.now());
             render_commands
                 .sprite_2d()
                 .color(
@@ -335,8 +335,8 @@ impl RenderUI {
         for skill_key in main_keys.iter() {
             if let Some(skill) = input.get_skill_for_key(*skill_key) {
                 // inner border
-                let not_castable = char_state.skill_cast_allowed_at[*skill_key as usize]
-                    .has_not_passed_yet(time.now());
+                let not_castable =
+                    char_state.skill_cast_allowed_at[skill as usize].has_not_passed_yet(time.now());
                 let border_color = if not_castable {
                     [179, 179, 179, 255] // grey
                 } else {
@@ -453,8 +453,8 @@ impl RenderUI {
         for skill_key in main_keys.iter() {
             if let Some(skill) = input.get_skill_for_key(*skill_key) {
                 // inner border
-                let not_castable = char_state.skill_cast_allowed_at[*skill_key as usize]
-                    .has_not_passed_yet(time.now());
+                let not_castable =
+                    char_state.skill_cast_allowed_at[skill as usize].has_not_passed_yet(time.now());
                 let border_color = if not_castable {
                     [179, 179, 179, 255] // grey
                 } else {
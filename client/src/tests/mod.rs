@@ -131,7 +131,7 @@ impl<'a> CharAsserter<'a> {
     }
 
     pub fn has_no_active_status(self) -> CharAsserter<'a> {
-        assert_eq!(0, get_char!(self).get_status_count());
+        assert_eq!(0, get_char!(self).statuses.count());
         self
     }
 
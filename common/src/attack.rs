@@ -1,6 +1,6 @@
 use crate::char_attr::CharAttributes;
 use crate::common::{v2, EngineTime, LocalTime, Percentage, Vec2};
-use crate::components::char::LocalCharEntityId;
+use crate::components::char::{LocalCharEntityId, Team};
 use serde::Deserialize;
 use serde::Serialize;
 
@@ -130,6 +130,43 @@ impl HpModificationRequest {
             typ: HpModificationResultType::Blocked,
         }
     }
+
+    pub fn is_allowed_between(&self, src_team: Team, dst_team: Team) -> bool {
+        return match self.typ {
+            HpModificationType::Heal(_) => src_team.can_support(dst_team),
+            _ => src_team.can_attack(dst_team),
+        };
+    }
+
+    pub fn apply_armor(self, dst_attribs: &CharAttributes) -> HpModificationResult {
+        return match self.typ {
+            HpModificationType::SpellDamage(base_dmg, _damage_render_type) => {
+                let dmg = dst_attribs.armor.subtract_me_from(base_dmg as i32);
+                if dmg <= 0 {
+                    self.blocked()
+                } else {
+                    self.allow(dmg as u32)
+                }
+            }
+            HpModificationType::BasicDamage(base_dmg, _damage_render_type, _weapon_type) => {
+                let atk = dst_attribs.armor.subtract_me_from(base_dmg as i32);
+                if atk <= 0 {
+                    self.blocked()
+                } else {
+                    self.allow(atk as u32)
+                }
+            }
+            HpModificationType::Heal(healed) => self.allow(healed),
+            HpModificationType::Poison(dmg) => {
+                let atk = dst_attribs.armor.subtract_me_from(dmg as i32);
+                if atk <= 0 {
+                    self.blocked()
+                } else {
+                    self.allow(dmg)
+                }
+            }
+        };
+    }
 }
 
 #[derive(Debug)]
@@ -170,6 +207,7 @@ pub struct AreaAttackComponent {
 pub struct ApplyForceComponent {
     pub src_entity: LocalCharEntityId,
     pub dst_entity: LocalCharEntityId,
+    /// the velocity of the push in units per second
     pub force: Vec2,
     pub duration: f32,
 }
@@ -250,6 +250,12 @@ pub struct CharAttributeModifierCollector {
     durations: BonusDurations,
 }
 
+impl Default for CharAttributeModifierCollector {
+    fn default() -> Self {
+        CharAttributeModifierCollector::new()
+    }
+}
+
 impl CharAttributeModifierCollector {
     pub fn new() -> CharAttributeModifierCollector {
         CharAttributeModifierCollector {
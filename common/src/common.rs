@@ -33,6 +33,10 @@ impl SimulationTick {
         self.0
     }
 
+    pub fn from_u64(tick: u64) -> SimulationTick {
+        SimulationTick(tick)
+    }
+
     pub fn prev(&self) -> SimulationTick {
         SimulationTick(self.0 - 1)
     }
@@ -173,10 +177,31 @@ impl ServerTime {
     }
 }
 
-#[derive(Debug, Copy, Clone, Deserialize, Serialize, Eq, PartialEq, Ord, PartialOrd)]
-#[serde(from = "f32")]
+/// Serialized as seconds, so configs can define durations like `1.5`.
+/// Serialization and deserialization must be symmetric because it is sent over the network
+/// as well.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
 pub struct LocalTime(u32);
 
+impl Serialize for LocalTime {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_f64(self.0 as f64 / 1000.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for LocalTime {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let seconds = f64::deserialize(deserializer)?;
+        Ok(LocalTime((seconds * 1000.0).round() as u32))
+    }
+}
+
 impl From<f32> for LocalTime {
     fn from(value: f32) -> Self {
         LocalTime((value * 1000f32) as u32)
@@ -374,4 +399,18 @@ mod tests {
         assert_eq!(percentage(5).div(10).as_f32(), 0.005);
         assert_eq!(percentage(-5).div(10).as_f32(), -0.005);
     }
+
+    #[test]
+    fn test_local_time_serialization_is_symmetric() {
+        for millis in &[0, 1, 999, 1_500, 123_456_789] {
+            let time = LocalTime::from(*millis);
+            let bytes = bincode::serialize(&time).unwrap();
+            let deserialized: LocalTime = bincode::deserialize(&bytes).unwrap();
+            assert_eq!(deserialized, time);
+        }
+        let from_config: LocalTime = serde_json::from_str("1.5").unwrap();
+        assert_eq!(from_config.as_millis(), 1_500);
+        let from_config: LocalTime = serde_json::from_str("2").unwrap();
+        assert_eq!(from_config.as_millis(), 2_000);
+    }
 }
@@ -1,8 +1,14 @@
-use crate::attack::{BasicAttackType, WeaponType};
+use crate::attack::{
+    BasicAttackType, HpModificationRequest, HpModificationResult, HpModificationResultType,
+    HpModificationType, WeaponType,
+};
 use crate::char_attr::CharAttributes;
-use crate::common::{float_cmp, v2, LocalTime, ServerTime, Vec2};
+use crate::common::{float_cmp, v2, EngineTime, LocalTime, ServerTime, Vec2};
 use crate::components::controller::PlayerIntention;
 use crate::components::job_ids::JobSpriteId;
+use crate::components::skills::{CastingSkillData, Skills, SKILLS_COUNT};
+use crate::components::status::death_status::DeathStatus;
+use crate::components::status::status::{StatusEnum, Statuses};
 use crate::config::CommonConfigs;
 use crate::packets::SocketBuffer;
 use serde::export::fmt::{Debug, Display, Error};
@@ -98,7 +104,7 @@ pub enum CharState<T: TargetId> {
     },
     ReceivingDamage,
     Dead,
-    //    CastingSkill(CastingSkillData),
+    CastingSkill(CastingSkillData<T>),
 }
 
 impl<T: TargetId> Display for CharState<T> {
@@ -114,6 +120,9 @@ impl<T: TargetId> Display for CharState<T> {
                 damage_occurs_at,
                 basic_attack,
             } => write!(f, "Attacking({})", target.as_u64()),
+            CharState::CastingSkill(casting_info) => {
+                write!(f, "CastingSkill({:?})", casting_info.skill)
+            }
         }
     }
 }
@@ -130,6 +139,13 @@ impl<T: TargetId> CharState<T> {
         }
     }
 
+    pub fn is_casting(&self) -> bool {
+        match self {
+            CharState::CastingSkill(_) => true,
+            _ => false,
+        }
+    }
+
     pub fn is_alive(&self) -> bool {
         match self {
             CharState::Dead => false,
@@ -152,6 +168,7 @@ impl<T: TargetId> CharState<T> {
             CharState::Attacking { .. } => "Attacking",
             CharState::ReceivingDamage => "ReceivingDamage",
             CharState::Dead => "Dead",
+            CharState::CastingSkill(..) => "CastingSkill",
         }
     }
 }
@@ -205,6 +222,96 @@ pub enum JobId {
 }
 
 impl JobId {
+    /// the jobs the players can choose from
+    pub fn is_playable(&self) -> bool {
+        match self {
+            JobId::TargetDummy
+            | JobId::HealingDummy
+            | JobId::MeleeMinion
+            | JobId::Barricade
+            | JobId::RangedMinion
+            | JobId::Turret
+            | JobId::Guard => false,
+            _ => true,
+        }
+    }
+
+    /// The skills the characters of the job can cast, AttackMove is an order of every job
+    pub fn skills(&self) -> &'static [Skills] {
+        match self {
+            JobId::CRUSADER => &[
+                Skills::FireWall,
+                Skills::AbsorbShield,
+                Skills::Heal,
+                Skills::BrutalTestSkill,
+                Skills::Mounting,
+            ],
+            JobId::SWORDMAN => &[
+                Skills::BrutalTestSkill,
+                Skills::ExoSkeleton,
+                Skills::Mounting,
+            ],
+            JobId::KNIGHT => &[
+                Skills::BrutalTestSkill,
+                Skills::ExoSkeleton,
+                Skills::AbsorbShield,
+                Skills::Mounting,
+            ],
+            JobId::ARCHER => &[Skills::FalconAttack, Skills::Poison],
+            JobId::RANGER => &[Skills::FalconCarry, Skills::FalconAttack, Skills::Poison],
+            JobId::ASSASSIN => &[
+                Skills::AssaBladeDash,
+                Skills::AssaPhasePrism,
+                Skills::Poison,
+            ],
+            JobId::ROGUE => &[Skills::Poison, Skills::FireBomb, Skills::AssaBladeDash],
+            JobId::WIZARD => &[
+                Skills::WizPyroBlast,
+                Skills::FireWall,
+                Skills::Lightning,
+                Skills::FireBomb,
+            ],
+            JobId::SAGE => &[
+                Skills::FireWall,
+                Skills::Lightning,
+                Skills::Cure,
+                Skills::AbsorbShield,
+            ],
+            JobId::ALCHEMIST => &[
+                Skills::Poison,
+                Skills::FireBomb,
+                Skills::Cure,
+                Skills::GazXplodiumCharge,
+            ],
+            JobId::BLACKSMITH => &[
+                Skills::GazTurret,
+                Skills::GazDestroyTurret,
+                Skills::GazTurretTarget,
+                Skills::GazBarricade,
+            ],
+            JobId::PRIEST => &[
+                Skills::Heal,
+                Skills::Cure,
+                Skills::Sanctuary,
+                Skills::AbsorbShield,
+            ],
+            JobId::MONK => &[Skills::Heal, Skills::ExoSkeleton, Skills::Lightning],
+            JobId::GUNSLINGER => &[
+                Skills::GazXplodiumCharge,
+                Skills::GazTurret,
+                Skills::GazDestroyTurret,
+                Skills::GazTurretTarget,
+            ],
+            JobId::TargetDummy
+            | JobId::HealingDummy
+            | JobId::MeleeMinion
+            | JobId::Barricade
+            | JobId::RangedMinion
+            | JobId::Turret
+            | JobId::Guard => &[],
+        }
+    }
+
     pub fn get_basic_attack_type(&self) -> BasicAttackType {
         match self {
             JobId::GUNSLINGER => BasicAttackType::Ranged {
@@ -283,6 +390,8 @@ pub struct StaticCharDataComponent {
     pub typ: CharType,
     pub outlook: CharOutlook,
     pub job_id: JobId,
+    /// the falcon is only a visual of the clients, it follows the character
+    pub has_falcon: bool,
 }
 
 impl StaticCharDataComponent {
@@ -300,6 +409,7 @@ impl StaticCharDataComponent {
             typ,
             outlook,
             job_id,
+            has_falcon: false,
         }
     }
 }
@@ -312,10 +422,11 @@ pub struct ServerCharState {
     pub target: Option<EntityTarget<ServerEntityId>>,
     pub calculated_attribs: CharAttributes,
     pub attack_delay_ends_at: ServerTime,
-    // TODO [SkillKey::Count]
-    pub skill_cast_allowed_at: [ServerTime; 6],
+    /// indexed by `Skills`
+    pub skill_cast_allowed_at: [ServerTime; SKILLS_COUNT],
     pub cannot_control_until: ServerTime,
     pub hp: i32,
+    pub statuses: Statuses,
 }
 
 #[derive(Component, Clone, Debug, Serialize, Deserialize)]
@@ -326,10 +437,11 @@ pub struct LocalCharStateComp {
     pub target: Option<EntityTarget<LocalCharEntityId>>,
     calculated_attribs: CharAttributes,
     pub attack_delay_ends_at: LocalTime,
-    // TODO [SkillKey::Count]
-    pub skill_cast_allowed_at: [LocalTime; 6],
+    /// indexed by `Skills`
+    pub skill_cast_allowed_at: [LocalTime; SKILLS_COUNT],
     pub cannot_control_until: LocalTime,
     pub hp: i32,
+    pub statuses: Statuses,
 }
 
 impl PartialEq for LocalCharStateComp {
@@ -360,9 +472,10 @@ impl Default for LocalCharStateComp {
             target: None,
             calculated_attribs: Default::default(),
             attack_delay_ends_at: LocalTime::from(0.0),
-            skill_cast_allowed_at: [LocalTime::from(0.0); 6],
+            skill_cast_allowed_at: [LocalTime::from(0.0); SKILLS_COUNT],
             cannot_control_until: LocalTime::from(0.0),
             hp: 0,
+            statuses: Statuses::new(),
         }
     }
 }
@@ -377,18 +490,25 @@ impl LocalCharStateComp {
             hp: base_attributes.max_hp,
             calculated_attribs: base_attributes,
             attack_delay_ends_at: LocalTime::from(0.0),
-            skill_cast_allowed_at: [LocalTime::from(0.0); 6],
+            skill_cast_allowed_at: [LocalTime::from(0.0); SKILLS_COUNT],
             cannot_control_until: LocalTime::from(0.0),
+            statuses: Statuses::new(),
         }
     }
 
     // it is here so that the client module does not have to have access to all the fields
+    // Entities outside of the client's area of interest are not in `map`,
+    // references to them are dropped
     pub fn server_to_local(
         server_char_state: ServerCharState,
         now: LocalTime,
         server_to_local_time_diff: i64,
         map: &HashMap<ServerEntityId, LocalCharEntityId>,
     ) -> LocalCharStateComp {
+        // the times inside the states are server times too
+        let to_local_time = |server_time: LocalTime| {
+            ServerTime(server_time.as_millis()).to_local_time(now, server_to_local_time_diff)
+        };
         LocalCharStateComp {
             pos: server_char_state.pos,
             dir: server_char_state.dir,
@@ -400,53 +520,78 @@ impl LocalCharStateComp {
                     target,
                     damage_occurs_at,
                     basic_attack,
-                } => CharState::Attacking {
-                    target: map[&target],
-                    damage_occurs_at,
-                    basic_attack,
+                } => match map.get(&target) {
+                    Some(target) => CharState::Attacking {
+                        target: *target,
+                        damage_occurs_at: to_local_time(damage_occurs_at),
+                        basic_attack,
+                    },
+                    None => CharState::Idle,
                 },
                 CharState::ReceivingDamage => CharState::ReceivingDamage,
                 CharState::Dead => CharState::Dead,
+                CharState::CastingSkill(casting_info) => {
+                    CharState::CastingSkill(CastingSkillData {
+                        target_area_pos: casting_info.target_area_pos,
+                        char_to_skill_dir_when_casted: casting_info.char_to_skill_dir_when_casted,
+                        target_entity: casting_info
+                            .target_entity
+                            .and_then(|it| map.get(&it).copied()),
+                        cast_started: to_local_time(casting_info.cast_started),
+                        cast_ends: to_local_time(casting_info.cast_ends),
+                        can_move: casting_info.can_move,
+                        skill: casting_info.skill,
+                    })
+                }
             },
             target: match server_char_state.target {
                 None => None,
                 Some(EntityTarget::Pos(v)) => Some(EntityTarget::Pos(v)),
-                Some(EntityTarget::PosWhileAttacking(v, maybe_target_id)) => Some(
-                    EntityTarget::PosWhileAttacking(v, maybe_target_id.map(|it| map[&it])),
-                ),
-                Some(EntityTarget::OtherEntity(target_id)) => {
-                    Some(EntityTarget::OtherEntity(map[&target_id]))
+                Some(EntityTarget::PosWhileAttacking(v, maybe_target_id)) => {
+                    Some(EntityTarget::PosWhileAttacking(
+                        v,
+                        maybe_target_id.and_then(|it| map.get(&it).copied()),
+                    ))
                 }
+                Some(EntityTarget::OtherEntity(target_id)) => map
+                    .get(&target_id)
+                    .map(|target_id| EntityTarget::OtherEntity(*target_id)),
             },
             calculated_attribs: server_char_state.calculated_attribs,
             attack_delay_ends_at: server_char_state
                 .attack_delay_ends_at
                 .to_local_time(now, server_to_local_time_diff),
-            skill_cast_allowed_at: [
-                server_char_state.skill_cast_allowed_at[0]
-                    .to_local_time(now, server_to_local_time_diff),
-                server_char_state.skill_cast_allowed_at[1]
-                    .to_local_time(now, server_to_local_time_diff),
-                server_char_state.skill_cast_allowed_at[2]
-                    .to_local_time(now, server_to_local_time_diff),
-                server_char_state.skill_cast_allowed_at[3]
-                    .to_local_time(now, server_to_local_time_diff),
-                server_char_state.skill_cast_allowed_at[4]
-                    .to_local_time(now, server_to_local_time_diff),
-                server_char_state.skill_cast_allowed_at[5]
-                    .to_local_time(now, server_to_local_time_diff),
-            ],
+            skill_cast_allowed_at: {
+                let mut skill_cast_allowed_at = [LocalTime::from(0.0); SKILLS_COUNT];
+                for (local, server) in skill_cast_allowed_at
+                    .iter_mut()
+                    .zip(server_char_state.skill_cast_allowed_at.iter())
+                {
+                    *local = server.to_local_time(now, server_to_local_time_diff);
+                }
+                skill_cast_allowed_at
+            },
             cannot_control_until: server_char_state
                 .cannot_control_until
                 .to_local_time(now, server_to_local_time_diff),
             hp: server_char_state.hp,
+            statuses: {
+                let mut statuses = server_char_state.statuses;
+                statuses.convert_times_and_ids(
+                    &|server_time| {
+                        ServerTime(server_time.as_millis())
+                            .to_local_time(now, server_to_local_time_diff)
+                    },
+                    &|server_id| map.get(&ServerEntityId(server_id)).copied(),
+                );
+                statuses
+            },
         }
     }
 
     pub fn can_cast(&self, sys_time: LocalTime) -> bool {
         let can_cast_by_state = match &self.state {
-            // TODO2
-            //        CharState::CastingSkill(_) => false,
+            CharState::CastingSkill(_) => false,
             CharState::Idle => true,
             CharState::Walking(_pos) => true,
             CharState::StandBy => true,
@@ -454,15 +599,14 @@ impl LocalCharStateComp {
             CharState::ReceivingDamage => false,
             CharState::Dead => false,
         };
-        can_cast_by_state && self.cannot_control_until.has_already_passed(sys_time)
-        // TODO2
-        //        && char_state.statuses.can_cast()
+        can_cast_by_state
+            && self.cannot_control_until.has_already_passed(sys_time)
+            && self.statuses.can_cast()
     }
 
     pub fn can_move(&self, sys_time: LocalTime) -> bool {
         let can_move_by_state = match &self.state {
-            // TODO2
-            //        CharState::CastingSkill(casting_info) => casting_info.can_move,
+            CharState::CastingSkill(casting_info) => casting_info.can_move,
             CharState::Idle => true,
             CharState::Walking(_pos) => true,
             CharState::StandBy => true,
@@ -470,23 +614,98 @@ impl LocalCharStateComp {
             CharState::ReceivingDamage => true,
             CharState::Dead => false,
         };
-        can_move_by_state && self.cannot_control_until.has_already_passed(sys_time)
-        // TODO2
-        //        && char_state.statuses.can_move()
+        can_move_by_state
+            && self.cannot_control_until.has_already_passed(sys_time)
+            && self.statuses.can_move()
     }
 
     pub fn recalc_attribs_based_on_statuses(&mut self, job_id: JobId, dev_configs: &CommonConfigs) {
-        // TODO2
         let base_attributes = CharAttributes::get_base_attributes(job_id, dev_configs);
-        //        let modifier_collector = self.statuses.calc_attributes();
-        //        self.calculated_attribs = base_attributes.apply(modifier_collector);
-        self.calculated_attribs = base_attributes.clone();
-        //
+        let modifier_collector = self.statuses.calc_attributes();
+        self.calculated_attribs = base_attributes.apply(modifier_collector);
+        // TODO2
         //        self.attrib_bonuses = self
         //            .calculated_attribs
         //            .differences(&base_attributes, modifier_collector);
     }
 
+    pub fn update_statuses(
+        &mut self,
+        self_char_id: LocalCharEntityId,
+        static_data: &mut StaticCharDataComponent,
+        time: &EngineTime,
+        hp_mod_requests: &mut Vec<HpModificationRequest>,
+        dev_configs: &CommonConfigs,
+    ) {
+        let bit_indices_of_changed_statuses =
+            self.statuses
+                .update(self_char_id, static_data, time, hp_mod_requests);
+        if bit_indices_of_changed_statuses > 0 {
+            self.statuses
+                .remove_statuses(bit_indices_of_changed_statuses);
+            self.recalc_attribs_based_on_statuses(static_data.job_id, dev_configs);
+            log::trace!(
+                "Status expired. Attributes({:?}): attribs: {:?}",
+                self_char_id,
+                self.calculated_attribs()
+            );
+        }
+    }
+
+    pub fn add_status(
+        &mut self,
+        mut status: StatusEnum,
+        static_data: &mut StaticCharDataComponent,
+        dev_configs: &CommonConfigs,
+    ) {
+        status.on_apply(self, static_data);
+        self.statuses.add(status);
+        self.recalc_attribs_based_on_statuses(static_data.job_id, dev_configs);
+    }
+
+    pub fn apply_hp_mod(&mut self, outcome: &HpModificationResult, now: LocalTime) {
+        match outcome.typ {
+            HpModificationResultType::Ok(hp_req_mod_type) => match hp_req_mod_type {
+                HpModificationType::Heal(val) => {
+                    self.hp = self.calculated_attribs.max_hp.min(self.hp + val as i32);
+                }
+                HpModificationType::BasicDamage(val, _display_type, _weapon_type) => {
+                    self.cannot_control_until.run_at_least_until(now, 100);
+                    self.set_receiving_damage();
+                    self.hp -= val as i32;
+                }
+                HpModificationType::Poison(val) => {
+                    self.hp -= val as i32;
+                }
+                HpModificationType::SpellDamage(val, _display_type) => {
+                    self.cannot_control_until.run_at_least_until(now, 100);
+                    self.set_receiving_damage();
+                    self.hp -= val as i32;
+                }
+            },
+            HpModificationResultType::Blocked => {}
+            HpModificationResultType::Absorbed => {}
+        }
+    }
+
+    pub fn die(&mut self, now: LocalTime, is_npc: bool) {
+        self.set_state(CharState::Dead, self.dir);
+        self.target = None;
+        self.statuses.remove_all();
+        self.statuses
+            .add(StatusEnum::DeathStatus(DeathStatus::new(now, is_npc)));
+    }
+
+    pub fn respawn(&mut self, pos: Vec2, job_id: JobId, dev_configs: &CommonConfigs) {
+        self.statuses.remove_all();
+        self.recalc_attribs_based_on_statuses(job_id, dev_configs);
+        self.hp = self.calculated_attribs.max_hp;
+        self.pos = pos;
+        self.target = None;
+        self.cannot_control_until = LocalTime::from(0.0);
+        self.set_state(CharState::Idle, CharDir::South);
+    }
+
     pub fn calculated_attribs(&self) -> &CharAttributes {
         &self.calculated_attribs
     }
@@ -529,9 +748,8 @@ impl LocalCharStateComp {
 
     pub fn set_receiving_damage(&mut self) {
         match &self.state {
-            // TODO2
-            //            | CharState::CastingSkill(_)
-            CharState::Idle
+            CharState::CastingSkill(_)
+            | CharState::Idle
             | CharState::Walking(_)
             | CharState::StandBy
             | CharState::ReceivingDamage => {
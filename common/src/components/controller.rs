@@ -1,5 +1,6 @@
 use crate::common::Vec2;
 use crate::components::char::{LocalCharEntityId, ServerEntityId};
+use crate::components::skills::Skills;
 use serde::Deserialize;
 use serde::Serialize;
 use specs::prelude::*;
@@ -13,8 +14,9 @@ pub enum PlayerIntention {
     Attack(LocalCharEntityId),
     /// Move to the coordination, attack any enemy on the way.
     AttackTowards(Vec2),
-    //    /// bool = is self cast
-    //    Casting(Skills, bool, Vec2),
+    /// bool = is self cast, Vec2 = mouse world position,
+    /// the entity is the one which was below the cursor when the skill was casted
+    Casting(Skills, bool, Vec2, Option<LocalCharEntityId>),
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -26,8 +28,9 @@ pub enum ToServerPlayerIntention {
     Attack(ServerEntityId),
     /// Move to the coordination, attack any enemy on the way.
     AttackTowards(Vec2),
-    //    /// bool = is self cast
-    //    Casting(Skills, bool, Vec2),
+    /// bool = is self cast, Vec2 = mouse world position,
+    /// the entity is the one which was below the cursor when the skill was casted
+    Casting(Skills, bool, Vec2, Option<ServerEntityId>),
 }
 
 // It can be a player, an AI, script etc
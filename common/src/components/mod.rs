@@ -1,3 +1,5 @@
 pub mod char;
 pub mod controller;
 pub mod job_ids;
+pub mod skills;
+pub mod status;
@@ -0,0 +1,183 @@
+use crate::common::{LocalTime, Vec2};
+use crate::components::char::{LocalCharEntityId, LocalCharStateComp, TargetId, Team};
+use crate::config::{CommonConfigs, SkillCastingAttributes};
+use serde::Deserialize;
+use serde::Serialize;
+use strum_macros::EnumCount;
+use strum_macros::EnumIter;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, EnumIter, EnumCount, Serialize, Deserialize)]
+pub enum Skills {
+    AttackMove,
+    FireWall,
+    BrutalTestSkill,
+    Lightning,
+    Heal,
+    Mounting,
+    Poison,
+    Cure,
+    FireBomb,
+    AbsorbShield,
+    WizPyroBlast,
+    AssaBladeDash,
+    AssaPhasePrism,
+    GazXplodiumCharge,
+    GazTurret,
+    GazBarricade,
+    GazDestroyTurret,
+    GazTurretTarget,
+    FalconCarry,
+    FalconAttack,
+    Sanctuary,
+    ExoSkeleton,
+}
+
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+#[allow(dead_code)]
+pub enum SkillTargetType {
+    /// casts immediately
+    NoTarget,
+    Area,
+    Directional,
+    AnyEntity,
+    OnlyAllyButNoSelf,
+    OnlyAllyAndSelf,
+    OnlyEnemy,
+}
+
+impl SkillTargetType {
+    /// Area and direction based skills ignore the target entity, so any team is accepted for them
+    pub fn is_target_team_allowed(&self, caster_team: Team, target_team: Team) -> bool {
+        match self {
+            SkillTargetType::NoTarget
+            | SkillTargetType::Area
+            | SkillTargetType::Directional
+            | SkillTargetType::AnyEntity => true,
+            SkillTargetType::OnlyAllyButNoSelf | SkillTargetType::OnlyAllyAndSelf => {
+                caster_team.is_ally_to(target_team)
+            }
+            SkillTargetType::OnlyEnemy => caster_team.is_enemy_to(target_team),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CastingSkillData<T: TargetId> {
+    pub target_area_pos: Option<Vec2>,
+    pub char_to_skill_dir_when_casted: Vec2,
+    pub target_entity: Option<T>,
+    pub cast_started: LocalTime,
+    pub cast_ends: LocalTime,
+    pub can_move: bool,
+    pub skill: Skills,
+}
+
+pub struct FinishCast {
+    pub skill: Skills,
+    pub caster_entity_id: LocalCharEntityId,
+    pub caster_pos: Vec2,
+    pub caster_team: Team,
+    pub skill_pos: Option<Vec2>,
+    pub char_to_skill_dir: Vec2,
+    pub target_entity: Option<LocalCharEntityId>,
+}
+
+impl Skills {
+    pub fn get_skill_target_type(&self) -> SkillTargetType {
+        match self {
+            Skills::AttackMove => SkillTargetType::Area,
+            Skills::FireWall => SkillTargetType::Area,
+            Skills::BrutalTestSkill => SkillTargetType::Area,
+            Skills::Lightning => SkillTargetType::Area,
+            Skills::Heal => SkillTargetType::OnlyAllyAndSelf,
+            Skills::Mounting => SkillTargetType::NoTarget,
+            Skills::Poison => SkillTargetType::OnlyEnemy,
+            Skills::Cure => SkillTargetType::OnlyAllyAndSelf,
+            Skills::FireBomb => SkillTargetType::OnlyEnemy,
+            Skills::AbsorbShield => SkillTargetType::OnlyAllyAndSelf,
+            Skills::WizPyroBlast => SkillTargetType::OnlyEnemy,
+            Skills::AssaBladeDash => SkillTargetType::Directional,
+            Skills::AssaPhasePrism => SkillTargetType::Directional,
+            Skills::GazXplodiumCharge => SkillTargetType::Area,
+            Skills::GazTurret => SkillTargetType::Area,
+            Skills::GazBarricade => SkillTargetType::Area,
+            Skills::GazDestroyTurret => SkillTargetType::OnlyAllyButNoSelf,
+            Skills::GazTurretTarget => SkillTargetType::OnlyEnemy,
+            Skills::FalconCarry => SkillTargetType::OnlyAllyAndSelf,
+            Skills::FalconAttack => SkillTargetType::Directional,
+            Skills::Sanctuary => SkillTargetType::Area,
+            Skills::ExoSkeleton => SkillTargetType::NoTarget,
+        }
+    }
+
+    pub fn get_cast_attributes<'a>(
+        &'a self,
+        configs: &'a CommonConfigs,
+        char_state: &LocalCharStateComp,
+    ) -> &'a SkillCastingAttributes {
+        match self {
+            Skills::WizPyroBlast => &configs.skills.wiz_pyroblast.attributes,
+            Skills::FireWall => &configs.skills.firewall.attributes,
+            Skills::Heal => &configs.skills.heal.attributes,
+            Skills::BrutalTestSkill => &configs.skills.brutal_test_skill.attributes,
+            Skills::Lightning => &configs.skills.lightning.attributes,
+            Skills::Mounting => {
+                if char_state.statuses.is_mounted() {
+                    &configs.skills.unmounting
+                } else {
+                    &configs.skills.mounting
+                }
+            }
+            Skills::Poison => &configs.skills.poison.attributes,
+            Skills::Cure => &configs.skills.cure,
+            Skills::FireBomb => &configs.skills.firebomb.attributes,
+            Skills::AbsorbShield => &configs.skills.absorb_shield.attributes,
+            Skills::AssaBladeDash => &configs.skills.assa_blade_dash.attributes,
+            Skills::AssaPhasePrism => &configs.skills.assa_phase_prism.attributes,
+            Skills::GazXplodiumCharge => &configs.skills.gaz_xplodium_charge.attributes,
+            Skills::GazTurret => &configs.skills.gaz_turret.attributes,
+            Skills::GazDestroyTurret => &configs.skills.gaz_destroy_turret,
+            Skills::GazTurretTarget => &configs.skills.gaz_turret_target,
+            Skills::FalconCarry => &configs.skills.falcon_carry.attributes,
+            Skills::FalconAttack => &configs.skills.falcon_attack.attributes,
+            Skills::Sanctuary => &configs.skills.sanctuary.attributes,
+            Skills::ExoSkeleton => &configs.skills.exoskeleton.attributes,
+            Skills::AttackMove => &configs.skills.attack_move,
+            Skills::GazBarricade => &configs.skills.gaz_barricade.attributes,
+        }
+    }
+
+    pub fn limit_vector_into_range(char_pos: &Vec2, mouse_pos: &Vec2, range: f32) -> (Vec2, Vec2) {
+        let dir2d = mouse_pos - char_pos;
+        let dir_vector = dir2d.normalize();
+        let pos = char_pos + dir_vector * dir2d.magnitude().min(range);
+        return (pos, dir_vector);
+    }
+
+    pub fn is_casting_allowed_based_on_target(
+        skill_target_type: SkillTargetType,
+        skill_casting_range: f32,
+        caster_id: LocalCharEntityId,
+        target_entity: Option<LocalCharEntityId>,
+        target_distance: f32,
+    ) -> bool {
+        match skill_target_type {
+            SkillTargetType::Area => true,
+            SkillTargetType::Directional => true,
+            SkillTargetType::NoTarget => true,
+            SkillTargetType::AnyEntity => {
+                target_entity.is_some() && skill_casting_range >= target_distance
+            }
+            SkillTargetType::OnlyAllyButNoSelf => {
+                target_entity.map(|it| it != caster_id).unwrap_or(false)
+                    && skill_casting_range >= target_distance
+            }
+            SkillTargetType::OnlyAllyAndSelf => {
+                target_entity.is_some() && skill_casting_range >= target_distance
+            }
+            SkillTargetType::OnlyEnemy => {
+                target_entity.is_some() && skill_casting_range >= target_distance
+            }
+        }
+    }
+}
@@ -1,9 +1,10 @@
+use crate::char_attr::{CharAttributeModifier, CharAttributeModifierCollector};
+use crate::common::{LocalTime, Percentage};
 use crate::components::status::status::{StatusUpdateParams, StatusUpdateResult};
-use crate::LocalTime;
-use rustarok_common::char_attr::{CharAttributeModifier, CharAttributeModifierCollector};
-use rustarok_common::common::Percentage;
+use serde::Deserialize;
+use serde::Serialize;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ArmorModifierStatus {
     pub started: LocalTime,
     pub until: LocalTime,
@@ -38,7 +39,7 @@ impl ArmorModifierStatus {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct WalkingSpeedModifierStatus {
     pub started: LocalTime,
     pub until: LocalTime,
@@ -1,4 +1,4 @@
-use crate::LocalTime;
+use crate::common::LocalTime;
 use serde::Deserialize;
 use serde::Serialize;
 
@@ -0,0 +1,8 @@
+pub mod attack_heal_status;
+pub mod attrib_mod;
+pub mod death_status;
+pub mod reflect_damage_status;
+pub mod sacrafice_status;
+pub mod skill_statuses;
+pub mod status;
+pub mod stun;
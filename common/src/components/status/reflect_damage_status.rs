@@ -1,18 +1,14 @@
-use crate::components::char::ActionPlayMode;
-use crate::components::status::status::{StatusUpdateParams, StatusUpdateResult};
-use crate::effect::StrEffectType;
-use crate::render::render_command::RenderCommandCollector;
-use crate::render::render_sys::RenderDesktopClientSystem;
-use crate::systems::AssetResources;
-use crate::LocalTime;
-use rustarok_common::attack::{
+use crate::attack::{
     DamageDisplayType, HpModificationRequest, HpModificationResult, HpModificationResultType,
     HpModificationType,
 };
-use rustarok_common::common::{Percentage, Vec2};
-use rustarok_common::components::char::LocalCharEntityId;
+use crate::common::{LocalTime, Percentage};
+use crate::components::char::LocalCharEntityId;
+use crate::components::status::status::{StatusUpdateParams, StatusUpdateResult};
+use serde::Deserialize;
+use serde::Serialize;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ReflectDamageStatus {
     pub started: LocalTime,
     pub until: LocalTime,
@@ -67,7 +63,6 @@ impl ReflectDamageStatus {
                 HpModificationType::BasicDamage(value, _, weapon_type) => {
                     let reflected_value = self.reflected_amount.of(value as i32) as u32;
                     self.reflected_damage += reflected_value;
-                    dbg!(reflected_value);
                     hp_mod_reqs.push(HpModificationRequest {
                         src_entity: self_id,
                         dst_entity: outcome.src_entity,
@@ -84,24 +79,6 @@ impl ReflectDamageStatus {
         }
     }
 
-    pub fn render(
-        &self,
-        char_pos: Vec2,
-        now: LocalTime,
-        assets: &AssetResources,
-        render_commands: &mut RenderCommandCollector,
-    ) {
-        RenderDesktopClientSystem::render_str(
-            StrEffectType::Ramadan,
-            self.animation_started,
-            &char_pos,
-            assets,
-            now,
-            render_commands,
-            ActionPlayMode::Repeat,
-        );
-    }
-
     pub fn get_status_completion_percent(&self, now: LocalTime) -> Option<(LocalTime, f32)> {
         Some((self.until, now.percentage_between(self.started, self.until)))
     }
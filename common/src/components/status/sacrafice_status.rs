@@ -1,17 +1,13 @@
-use crate::components::char::ActionPlayMode;
-use crate::components::status::status::{StatusUpdateParams, StatusUpdateResult};
-use crate::effect::StrEffectType;
-use crate::render::render_command::RenderCommandCollector;
-use crate::render::render_sys::RenderDesktopClientSystem;
-use crate::systems::AssetResources;
-use crate::LocalTime;
-use rustarok_common::attack::{
+use crate::attack::{
     HpModificationRequest, HpModificationResult, HpModificationResultType, HpModificationType,
 };
-use rustarok_common::common::{Percentage, Vec2};
-use rustarok_common::components::char::LocalCharEntityId;
+use crate::common::{LocalTime, Percentage};
+use crate::components::char::LocalCharEntityId;
+use crate::components::status::status::{StatusUpdateParams, StatusUpdateResult};
+use serde::Deserialize;
+use serde::Serialize;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SacrificeStatus {
     pub sacrifice_caster_id: LocalCharEntityId,
     pub started: LocalTime,
@@ -95,7 +91,7 @@ impl SacrificeStatus {
                     });
                     // decrease the damage on the original target
                     outcome.typ = HpModificationResultType::Ok(HpModificationType::BasicDamage(
-                        value - dbg!(absorbed_value),
+                        value - absorbed_value,
                         display_type,
                         weapon_typ,
                     ));
@@ -107,24 +103,6 @@ impl SacrificeStatus {
         }
     }
 
-    pub fn render(
-        &self,
-        char_pos: Vec2,
-        now: LocalTime,
-        assets: &AssetResources,
-        render_commands: &mut RenderCommandCollector,
-    ) {
-        RenderDesktopClientSystem::render_str(
-            StrEffectType::Ramadan,
-            self.animation_started,
-            &char_pos,
-            assets,
-            now,
-            render_commands,
-            ActionPlayMode::Repeat,
-        );
-    }
-
     pub fn get_status_completion_percent(&self, now: LocalTime) -> Option<(LocalTime, f32)> {
         Some((self.until, now.percentage_between(self.started, self.until)))
     }
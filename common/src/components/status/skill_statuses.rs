@@ -0,0 +1,298 @@
+use crate::attack::{
+    BasicAttackType, HpModificationRequest, HpModificationResult, HpModificationResultType,
+    HpModificationType, WeaponType,
+};
+use crate::char_attr::{CharAttributeModifier, CharAttributeModifierCollector};
+use crate::common::{LocalTime, Percentage, Vec2};
+use crate::components::char::{LocalCharEntityId, StaticCharDataComponent, Team};
+use crate::components::status::status::{
+    StatusEnum, StatusStackingResult, StatusUpdateParams, StatusUpdateResult,
+};
+use crate::config::AssaBladeDashSkillConfig;
+use serde::Deserialize;
+use serde::Serialize;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AbsorbStatus {
+    pub caster_entity_id: LocalCharEntityId,
+    pub started: LocalTime,
+    pub animation_started: LocalTime,
+    pub until: LocalTime,
+    pub absorbed_damage: u32,
+}
+
+impl AbsorbStatus {
+    pub fn new(caster_entity_id: LocalCharEntityId, now: LocalTime, duration: f32) -> AbsorbStatus {
+        AbsorbStatus {
+            caster_entity_id,
+            started: now,
+            animation_started: now.add_seconds(-1.9),
+            until: now.add_seconds(duration),
+            absorbed_damage: 0,
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        now: LocalTime,
+        self_char_id: LocalCharEntityId,
+        hp_mod_requests: &mut Vec<HpModificationRequest>,
+    ) -> StatusUpdateResult {
+        if self.until.has_already_passed(now) {
+            if self.absorbed_damage > 0 {
+                hp_mod_requests.push(HpModificationRequest {
+                    src_entity: self.caster_entity_id,
+                    dst_entity: self_char_id,
+                    typ: HpModificationType::Heal(self.absorbed_damage),
+                });
+            }
+            StatusUpdateResult::RemoveIt
+        } else {
+            if self
+                .animation_started
+                .add_seconds(2.0)
+                .has_already_passed(now)
+            {
+                self.animation_started = now.add_seconds(-1.9);
+            }
+            StatusUpdateResult::KeepIt
+        }
+    }
+
+    pub fn hp_mod_is_calculated_but_not_applied_yet(
+        &mut self,
+        outcome: HpModificationResult,
+    ) -> HpModificationResult {
+        return match outcome.typ {
+            HpModificationResultType::Ok(hp_mod_req) => match hp_mod_req {
+                HpModificationType::BasicDamage(value, _, _)
+                | HpModificationType::SpellDamage(value, _)
+                | HpModificationType::Poison(value) => {
+                    self.absorbed_damage += value;
+                    outcome.absorbed()
+                }
+                HpModificationType::Heal(_) => outcome,
+            },
+            HpModificationResultType::Blocked | HpModificationResultType::Absorbed => outcome,
+        };
+    }
+
+    pub fn stack(&self, _other: &StatusEnum) -> StatusStackingResult {
+        // I think it should be overwritten only when the caster_entity_id is the same
+        // otherwise other players should get the healed credits for their armors
+        //        let other_absorb = unsafe { Statuses::hack_cast::<AbsorbStatus>(&other) };
+        //        if other_absorb.until.is_later_than(self.until) {
+        //            self.until = other_absorb.until;
+        //            self.started = other_absorb.started;
+        //            self.caster_entity_id = other_absorb.caster_entity_id;
+        //            self.animation_started = other_absorb.animation_started;
+        //        }
+        StatusStackingResult::AddTheNewStatus
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FireBombStatus {
+    pub caster_entity_id: LocalCharEntityId,
+    pub caster_team: Team,
+    pub damage: u32,
+    pub started: LocalTime,
+    pub until: LocalTime,
+    pub spread_count: u8,
+}
+
+impl FireBombStatus {
+    pub fn update(&mut self, params: StatusUpdateParams) -> StatusUpdateResult {
+        // TODO2: the explosion (area damage and spreading the bomb onto the nearby enemies)
+        // requires area attacks which are not supported in common yet
+        if self.until.has_already_passed(params.time.now()) {
+            StatusUpdateResult::RemoveIt
+        } else {
+            StatusUpdateResult::KeepIt
+        }
+    }
+
+    pub fn get_status_completion_percent(&self, now: LocalTime) -> Option<(LocalTime, f32)> {
+        Some((self.until, now.percentage_between(self.started, self.until)))
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PyroBlastTargetStatus {
+    pub caster_entity_id: LocalCharEntityId,
+    pub splash_radius: f32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AssaBladeDashStatus {
+    pub caster_entity_id: LocalCharEntityId,
+    pub started_at: LocalTime,
+    pub ends_at: LocalTime,
+    pub start_pos: Vec2,
+    pub center: Vec2,
+    pub rot_radian: f32,
+    pub half_duration: u32,
+    pub vector: Vec2,
+    pub shadow1_pos: Vec2,
+    pub shadow2_pos: Vec2,
+    pub forward_damage_done: bool,
+    pub backward_damage_done: bool,
+    pub configs: AssaBladeDashSkillConfig,
+}
+
+impl AssaBladeDashStatus {
+    /// The server moves the character, the shadows only follow her
+    pub fn update(&mut self, params: StatusUpdateParams) -> StatusUpdateResult {
+        let now = params.time.now();
+        if self.ends_at.has_already_passed(now) {
+            return StatusUpdateResult::RemoveIt;
+        }
+        let duration_percentage = now.percentage_between(self.started_at, self.ends_at);
+        if duration_percentage < 0.5 {
+            let forward_perc = duration_percentage * 2.0;
+            self.shadow1_pos = self.start_pos + self.vector * (forward_perc - 0.1).max(0.0);
+            self.shadow2_pos = self.start_pos + self.vector * (forward_perc - 0.2).max(0.0);
+        } else {
+            let backward_perc = (1.0 - duration_percentage) * 2.0;
+            self.shadow1_pos = self.start_pos + self.vector * (backward_perc + 0.1).min(1.0);
+            self.shadow2_pos = self.start_pos + self.vector * (backward_perc + 0.2).min(1.0);
+        }
+        return StatusUpdateResult::KeepIt;
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AssaPhasePrismStatus {
+    pub caster_entity_id: LocalCharEntityId,
+    pub started_at: LocalTime,
+    pub ends_at: LocalTime,
+    pub start_pos: Vec2,
+    pub vector: Vec2,
+}
+
+impl AssaPhasePrismStatus {
+    /// The server swaps the positions of the characters
+    pub fn update(&mut self, params: StatusUpdateParams) -> StatusUpdateResult {
+        if self.ends_at.has_already_passed(params.time.now()) {
+            StatusUpdateResult::RemoveIt
+        } else {
+            StatusUpdateResult::KeepIt
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FalconCarryStatus {
+    pub started_at: LocalTime,
+    pub ends_at: LocalTime,
+    pub carry_owner: bool,
+    pub end_pos: Vec2,
+}
+
+impl FalconCarryStatus {
+    pub fn update(&mut self, params: StatusUpdateParams) -> StatusUpdateResult {
+        if self.ends_at.has_already_passed(params.time.now()) {
+            StatusUpdateResult::RemoveIt
+        } else {
+            StatusUpdateResult::KeepIt
+        }
+    }
+
+    pub fn get_status_completion_percent(&self, now: LocalTime) -> Option<(LocalTime, f32)> {
+        if self.carry_owner {
+            Some((
+                self.ends_at,
+                now.percentage_between(self.started_at, self.ends_at),
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExoSkeletonStatus {
+    started: LocalTime,
+    pub until: LocalTime,
+    armor: Percentage,
+    attack_range: Percentage,
+    movement_speed: Percentage,
+    attack_damage: Percentage,
+    attack_speed: Percentage,
+}
+
+impl ExoSkeletonStatus {
+    pub fn new(
+        now: LocalTime,
+        duration: f32,
+        armor: Percentage,
+        attack_range: Percentage,
+        movement_speed: Percentage,
+        attack_damage: Percentage,
+        attack_speed: Percentage,
+    ) -> ExoSkeletonStatus {
+        ExoSkeletonStatus {
+            started: now,
+            until: now.add_seconds(duration),
+            armor,
+            attack_range,
+            movement_speed,
+            attack_damage,
+            attack_speed,
+        }
+    }
+}
+
+impl ExoSkeletonStatus {
+    pub fn on_apply(&mut self, target_char: &mut StaticCharDataComponent) {
+        target_char.basic_attack_type = BasicAttackType::Ranged {
+            bullet_type: WeaponType::SilverBullet,
+        };
+    }
+
+    pub fn calc_attribs(&self, modifiers: &mut CharAttributeModifierCollector) {
+        modifiers.change_armor(
+            CharAttributeModifier::AddPercentage(self.armor),
+            self.started,
+            self.until,
+        );
+        modifiers.change_walking_speed(
+            CharAttributeModifier::AddPercentage(self.movement_speed),
+            self.started,
+            self.until,
+        );
+        modifiers.change_attack_range(
+            CharAttributeModifier::AddPercentage(self.attack_range),
+            self.started,
+            self.until,
+        );
+        modifiers.change_attack_damage(
+            CharAttributeModifier::IncreaseByPercentage(self.attack_damage),
+            self.started,
+            self.until,
+        );
+        modifiers.change_attack_speed(
+            CharAttributeModifier::AddPercentage(self.attack_speed),
+            self.started,
+            self.until,
+        );
+    }
+
+    pub fn update(&mut self, params: StatusUpdateParams) -> StatusUpdateResult {
+        if self.until.has_already_passed(params.time.now()) {
+            params.target_char.basic_attack_type = BasicAttackType::MeleeSimple;
+            StatusUpdateResult::RemoveIt
+        } else {
+            StatusUpdateResult::KeepIt
+        }
+    }
+
+    pub fn get_status_completion_percent(&self, now: LocalTime) -> Option<(LocalTime, f32)> {
+        Some((self.until, now.percentage_between(self.started, self.until)))
+    }
+
+    pub fn convert_times(&mut self, convert: &dyn Fn(LocalTime) -> LocalTime) {
+        self.started = convert(self.started);
+        self.until = convert(self.until);
+    }
+}
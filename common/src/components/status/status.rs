@@ -0,0 +1,1127 @@
+use crate::attack::{
+    ApplyForceComponent, HpModificationRequest, HpModificationResult, HpModificationType,
+};
+use crate::char_attr::{CharAttributeModifier, CharAttributeModifierCollector};
+use crate::common::{percentage, EngineTime, LocalTime, Percentage};
+use crate::components::char::{
+    CharState, JobId, LocalCharEntityId, LocalCharStateComp, StaticCharDataComponent, StatusNature,
+};
+use crate::components::status::attack_heal_status::AttackHealStatus;
+use crate::components::status::attrib_mod::{ArmorModifierStatus, WalkingSpeedModifierStatus};
+use crate::components::status::death_status::DeathStatus;
+use crate::components::status::reflect_damage_status::ReflectDamageStatus;
+use crate::components::status::sacrafice_status::SacrificeStatus;
+use crate::components::status::skill_statuses::{
+    AbsorbStatus, AssaBladeDashStatus, AssaPhasePrismStatus, ExoSkeletonStatus, FalconCarryStatus,
+    FireBombStatus, PyroBlastTargetStatus,
+};
+use crate::components::status::stun::StunStatus;
+use crate::config::CommonConfigs;
+use serde::Deserialize;
+use serde::Serialize;
+use strum_macros::EnumCount;
+use strum_macros::EnumDiscriminants;
+
+#[derive(Debug)]
+pub enum StatusStackingResult {
+    DontAddTheNewStatus,
+    AddTheNewStatus,
+    Replace,
+}
+
+pub struct StatusUpdateParams<'a> {
+    pub self_char_id: LocalCharEntityId,
+    pub target_char: &'a mut StaticCharDataComponent,
+    pub hp_mod_requests: &'a mut Vec<HpModificationRequest>,
+    pub time: &'a EngineTime,
+}
+
+const NONSTACKABLE_STATUS_COUNT: usize = 6;
+
+/// The statuses which can be applied by name, e.g. from the console
+pub const STATUS_NAMES: &'static [&'static str] = &["absorb", "poison", "firebomb", "armor"];
+
+#[allow(variant_size_differences)]
+#[derive(Clone, Debug, EnumCount, EnumDiscriminants, Serialize, Deserialize)]
+pub enum StatusEnum {
+    MountedStatus {
+        speedup: Percentage,
+    },
+    DeathStatus(DeathStatus),
+    AssaBladeDashStatus(AssaBladeDashStatus),
+    AssaPhasePrismStatus(AssaPhasePrismStatus),
+    FalconCarryStatus(FalconCarryStatus),
+    ExoSkeletonStatus(ExoSkeletonStatus),
+
+    // stackable statuses
+    AbsorbStatus(AbsorbStatus),
+    FireBombStatus(FireBombStatus),
+    PyroBlastTargetStatus(PyroBlastTargetStatus),
+    #[allow(dead_code)]
+    AttackHealStatus(AttackHealStatus), // TODO: stackable?
+    ArmorModifierStatus(ArmorModifierStatus),
+    WalkingSpeedModifierStatus(WalkingSpeedModifierStatus),
+    #[allow(dead_code)]
+    ReflectDamageStatus(ReflectDamageStatus), // TODO: stackable?,
+    #[allow(dead_code)]
+    SacrificeStatus(SacrificeStatus),
+    PoisonStatus(PoisonStatus),
+    StunStatus(StunStatus),
+}
+
+impl StatusEnum {
+    // TODO: const fn
+    fn can_target_move(&self) -> bool {
+        match self {
+            StatusEnum::AbsorbStatus(_)
+            | StatusEnum::ExoSkeletonStatus(_)
+            | StatusEnum::FireBombStatus(_)
+            | StatusEnum::PyroBlastTargetStatus(_)
+            | StatusEnum::AttackHealStatus(_)
+            | StatusEnum::ArmorModifierStatus(_)
+            | StatusEnum::WalkingSpeedModifierStatus(_)
+            | StatusEnum::ReflectDamageStatus(_)
+            | StatusEnum::SacrificeStatus(_)
+            | StatusEnum::PoisonStatus(_)
+            | StatusEnum::MountedStatus { .. } => true,
+            StatusEnum::DeathStatus(_)
+            | StatusEnum::AssaBladeDashStatus(_)
+            | StatusEnum::FalconCarryStatus(_)
+            | StatusEnum::StunStatus(_)
+            | StatusEnum::AssaPhasePrismStatus(_) => false,
+        }
+    }
+
+    fn can_target_cast(&self) -> bool {
+        match self {
+            StatusEnum::AbsorbStatus(_)
+            | StatusEnum::ExoSkeletonStatus(_)
+            | StatusEnum::FireBombStatus(_)
+            | StatusEnum::PyroBlastTargetStatus(_)
+            | StatusEnum::AttackHealStatus(_)
+            | StatusEnum::ArmorModifierStatus(_)
+            | StatusEnum::WalkingSpeedModifierStatus(_)
+            | StatusEnum::ReflectDamageStatus(_)
+            | StatusEnum::SacrificeStatus(_)
+            | StatusEnum::PoisonStatus(_)
+            | StatusEnum::AssaBladeDashStatus(_)
+            | StatusEnum::MountedStatus { .. } => true,
+            StatusEnum::DeathStatus(_)
+            | StatusEnum::FalconCarryStatus(_)
+            | StatusEnum::StunStatus(_)
+            | StatusEnum::AssaPhasePrismStatus(_) => false,
+        }
+    }
+
+    fn can_target_be_controlled(&self) -> bool {
+        match self {
+            StatusEnum::AbsorbStatus(_)
+            | StatusEnum::ExoSkeletonStatus(_)
+            | StatusEnum::FireBombStatus(_)
+            | StatusEnum::PyroBlastTargetStatus(_)
+            | StatusEnum::AttackHealStatus(_)
+            | StatusEnum::ArmorModifierStatus(_)
+            | StatusEnum::WalkingSpeedModifierStatus(_)
+            | StatusEnum::ReflectDamageStatus(_)
+            | StatusEnum::SacrificeStatus(_)
+            | StatusEnum::PoisonStatus(_)
+            | StatusEnum::StunStatus(_)
+            | StatusEnum::MountedStatus { .. } => true,
+            StatusEnum::DeathStatus(_)
+            | StatusEnum::AssaBladeDashStatus(_)
+            | StatusEnum::FalconCarryStatus(_)
+            | StatusEnum::AssaPhasePrismStatus(_) => false,
+        }
+    }
+
+    // TODO: remove it
+    fn typ(&self) -> StatusNature {
+        match self {
+            StatusEnum::AbsorbStatus(_) => StatusNature::Supportive,
+            StatusEnum::MountedStatus { .. } => StatusNature::Supportive,
+            StatusEnum::DeathStatus(_) => StatusNature::Supportive,
+            StatusEnum::AssaBladeDashStatus(_) => StatusNature::Supportive,
+            StatusEnum::AssaPhasePrismStatus(_) => StatusNature::Supportive,
+            StatusEnum::FalconCarryStatus(_) => StatusNature::Supportive,
+            StatusEnum::ExoSkeletonStatus(_) => StatusNature::Supportive,
+            StatusEnum::FireBombStatus(_) => StatusNature::Supportive,
+            StatusEnum::PyroBlastTargetStatus(_) => StatusNature::Supportive,
+            StatusEnum::AttackHealStatus(_) => StatusNature::Supportive,
+            StatusEnum::ArmorModifierStatus(_) => StatusNature::Supportive,
+            StatusEnum::WalkingSpeedModifierStatus(_) => StatusNature::Supportive,
+            StatusEnum::ReflectDamageStatus(_) => StatusNature::Supportive,
+            StatusEnum::SacrificeStatus(_) => StatusNature::Supportive,
+            StatusEnum::PoisonStatus(_) => StatusNature::Supportive,
+            StatusEnum::StunStatus(_) => StatusNature::Supportive,
+        }
+    }
+
+    pub fn on_apply(
+        &mut self,
+        target_char: &mut LocalCharStateComp,
+        target_static_data: &mut StaticCharDataComponent,
+    ) {
+        // TODO2 the client side effects (sounds, str effects) and the collision changes are missing
+        match self {
+            StatusEnum::FalconCarryStatus(_) => {
+                target_char.set_state(CharState::StandBy, target_char.dir());
+            }
+            StatusEnum::ExoSkeletonStatus(status) => status.on_apply(target_static_data),
+            StatusEnum::StunStatus(status) => status.on_apply(target_char),
+            StatusEnum::AbsorbStatus(_)
+            | StatusEnum::AssaBladeDashStatus(_)
+            | StatusEnum::MountedStatus { .. }
+            | StatusEnum::DeathStatus(_)
+            | StatusEnum::AssaPhasePrismStatus(_)
+            | StatusEnum::FireBombStatus(_)
+            | StatusEnum::PyroBlastTargetStatus(_)
+            | StatusEnum::AttackHealStatus(_)
+            | StatusEnum::ArmorModifierStatus(_)
+            | StatusEnum::WalkingSpeedModifierStatus(_)
+            | StatusEnum::ReflectDamageStatus(_)
+            | StatusEnum::SacrificeStatus(_)
+            | StatusEnum::PoisonStatus(_) => {}
+        }
+    }
+
+    pub fn get_render_color(&self, now: LocalTime) -> [u8; 4] {
+        match self {
+            StatusEnum::AbsorbStatus(_)
+            | StatusEnum::MountedStatus { .. }
+            | StatusEnum::FireBombStatus(_)
+            | StatusEnum::PyroBlastTargetStatus(_)
+            | StatusEnum::AttackHealStatus(_)
+            | StatusEnum::ArmorModifierStatus(_)
+            | StatusEnum::WalkingSpeedModifierStatus(_)
+            | StatusEnum::ReflectDamageStatus(_)
+            | StatusEnum::SacrificeStatus(_)
+            | StatusEnum::FalconCarryStatus(_)
+            | StatusEnum::ExoSkeletonStatus(_)
+            | StatusEnum::StunStatus(_) => [255, 255, 255, 255],
+            StatusEnum::AssaBladeDashStatus(_) => [0, 0, 0, 0],
+            StatusEnum::AssaPhasePrismStatus(_) => [0, 255, 255, 255],
+            StatusEnum::PoisonStatus(_) => [128, 255, 128, 255],
+            StatusEnum::DeathStatus(status) => [
+                255,
+                255,
+                255,
+                if status.is_npc {
+                    255 - (now.percentage_between(status.started, status.remove_char_at) * 255.0)
+                        as u8
+                } else {
+                    255
+                },
+            ],
+        }
+    }
+
+    pub fn calc_attribs(&self, modifiers: &mut CharAttributeModifierCollector) {
+        match self {
+            StatusEnum::AbsorbStatus(_) => {}
+            StatusEnum::MountedStatus { speedup } => {
+                // it is applied directly on the base moving speed, since it is called first
+                modifiers.change_walking_speed(
+                    CharAttributeModifier::IncreaseByPercentage(*speedup),
+                    LocalTime::from(0.0),
+                    LocalTime::from(0.0),
+                );
+            }
+            StatusEnum::ArmorModifierStatus(status) => {
+                status.calc_attribs(modifiers);
+            }
+            StatusEnum::WalkingSpeedModifierStatus(status) => {
+                status.calc_attribs(modifiers);
+            }
+            StatusEnum::ExoSkeletonStatus(status) => status.calc_attribs(modifiers),
+            StatusEnum::FireBombStatus(_)
+            | StatusEnum::PyroBlastTargetStatus(_)
+            | StatusEnum::AttackHealStatus(_)
+            | StatusEnum::ReflectDamageStatus(_)
+            | StatusEnum::SacrificeStatus(_)
+            | StatusEnum::FalconCarryStatus(_)
+            | StatusEnum::AssaBladeDashStatus(_)
+            | StatusEnum::AssaPhasePrismStatus(_)
+            | StatusEnum::PoisonStatus(_)
+            | StatusEnum::DeathStatus(_)
+            | StatusEnum::StunStatus(_) => {}
+        }
+    }
+
+    pub fn update(&mut self, mut params: StatusUpdateParams) -> StatusUpdateResult {
+        match self {
+            StatusEnum::AbsorbStatus(status) => status.update(
+                params.time.now(),
+                params.self_char_id,
+                &mut params.hp_mod_requests,
+            ),
+            StatusEnum::AssaBladeDashStatus(status) => status.update(params),
+            StatusEnum::AssaPhasePrismStatus(status) => status.update(params),
+            StatusEnum::FalconCarryStatus(status) => status.update(params),
+            StatusEnum::FireBombStatus(status) => status.update(params),
+            StatusEnum::ExoSkeletonStatus(status) => status.update(params),
+            StatusEnum::AttackHealStatus(status) => status.update(params),
+            StatusEnum::ArmorModifierStatus(status) => status.update(params),
+            StatusEnum::WalkingSpeedModifierStatus(status) => status.update(params),
+            StatusEnum::ReflectDamageStatus(status) => status.update(params),
+            StatusEnum::SacrificeStatus(status) => status.update(params),
+            StatusEnum::PoisonStatus(status) => status.update(params),
+            StatusEnum::StunStatus(status) => status.update(params),
+            StatusEnum::MountedStatus { .. }
+            | StatusEnum::DeathStatus(_)
+            | StatusEnum::PyroBlastTargetStatus(_) => StatusUpdateResult::KeepIt,
+        }
+    }
+
+    pub fn hp_mod_is_calculated_but_not_applied_yet(
+        &mut self,
+        outcome: HpModificationResult,
+        hp_mod_reqs: &mut Vec<HpModificationRequest>,
+    ) -> HpModificationResult {
+        match self {
+            StatusEnum::AbsorbStatus(status) => {
+                status.hp_mod_is_calculated_but_not_applied_yet(outcome)
+            }
+            StatusEnum::SacrificeStatus(status) => {
+                status.hp_mod_is_calculated_but_not_applied_yet(outcome, hp_mod_reqs)
+            }
+            StatusEnum::ArmorModifierStatus(_)
+            | StatusEnum::WalkingSpeedModifierStatus(_)
+            | StatusEnum::ExoSkeletonStatus(_)
+            | StatusEnum::FireBombStatus(_)
+            | StatusEnum::PyroBlastTargetStatus(_)
+            | StatusEnum::AttackHealStatus(_)
+            | StatusEnum::ReflectDamageStatus(_)
+            | StatusEnum::FalconCarryStatus(_)
+            | StatusEnum::AssaBladeDashStatus(_)
+            | StatusEnum::AssaPhasePrismStatus(_)
+            | StatusEnum::PoisonStatus(_)
+            | StatusEnum::DeathStatus(_)
+            | StatusEnum::StunStatus(_)
+            | StatusEnum::MountedStatus { .. } => outcome,
+        }
+    }
+
+    pub fn hp_mod_has_been_applied_on_me(
+        &mut self,
+        self_id: LocalCharEntityId,
+        outcome: &HpModificationResult,
+        hp_mod_reqs: &mut Vec<HpModificationRequest>,
+    ) {
+        match self {
+            StatusEnum::ReflectDamageStatus(status) => {
+                status.hp_mod_has_been_applied_on_me(self_id, outcome, hp_mod_reqs)
+            }
+            StatusEnum::SacrificeStatus(_)
+            | StatusEnum::ArmorModifierStatus(_)
+            | StatusEnum::WalkingSpeedModifierStatus(_)
+            | StatusEnum::ExoSkeletonStatus(_)
+            | StatusEnum::FireBombStatus(_)
+            | StatusEnum::PyroBlastTargetStatus(_)
+            | StatusEnum::AttackHealStatus(_)
+            | StatusEnum::FalconCarryStatus(_)
+            | StatusEnum::AssaBladeDashStatus(_)
+            | StatusEnum::AssaPhasePrismStatus(_)
+            | StatusEnum::PoisonStatus(_)
+            | StatusEnum::DeathStatus(_)
+            | StatusEnum::StunStatus(_)
+            | StatusEnum::MountedStatus { .. }
+            | StatusEnum::AbsorbStatus(_) => {}
+        }
+    }
+
+    pub fn hp_mod_has_been_applied_on_enemy(
+        &mut self,
+        self_id: LocalCharEntityId,
+        outcome: &HpModificationResult,
+        hp_mod_reqs: &mut Vec<HpModificationRequest>,
+    ) {
+        match self {
+            StatusEnum::AttackHealStatus(status) => {
+                status.hp_mod_has_been_applied_on_enemy(self_id, outcome, hp_mod_reqs)
+            }
+            StatusEnum::SacrificeStatus(_)
+            | StatusEnum::ReflectDamageStatus(_)
+            | StatusEnum::ArmorModifierStatus(_)
+            | StatusEnum::WalkingSpeedModifierStatus(_)
+            | StatusEnum::ExoSkeletonStatus(_)
+            | StatusEnum::FireBombStatus(_)
+            | StatusEnum::PyroBlastTargetStatus(_)
+            | StatusEnum::FalconCarryStatus(_)
+            | StatusEnum::AssaBladeDashStatus(_)
+            | StatusEnum::AssaPhasePrismStatus(_)
+            | StatusEnum::PoisonStatus(_)
+            | StatusEnum::DeathStatus(_)
+            | StatusEnum::StunStatus(_)
+            | StatusEnum::MountedStatus { .. }
+            | StatusEnum::AbsorbStatus(_) => {}
+        }
+    }
+
+    pub fn allow_push(&self, _push: &ApplyForceComponent) -> bool {
+        match self {
+            StatusEnum::ExoSkeletonStatus(_)
+            | StatusEnum::FireBombStatus(_)
+            | StatusEnum::PyroBlastTargetStatus(_)
+            | StatusEnum::AttackHealStatus(_)
+            | StatusEnum::ArmorModifierStatus(_)
+            | StatusEnum::WalkingSpeedModifierStatus(_)
+            | StatusEnum::ReflectDamageStatus(_)
+            | StatusEnum::SacrificeStatus(_)
+            | StatusEnum::PoisonStatus(_)
+            | StatusEnum::StunStatus(_)
+            | StatusEnum::MountedStatus { .. } => true,
+            StatusEnum::DeathStatus(_)
+            | StatusEnum::AbsorbStatus(_)
+            | StatusEnum::AssaBladeDashStatus(_)
+            | StatusEnum::FalconCarryStatus(_)
+            | StatusEnum::AssaPhasePrismStatus(_) => false,
+        }
+    }
+
+    pub fn stack(&mut self, other: &StatusEnum) -> StatusStackingResult {
+        match self {
+            StatusEnum::FireBombStatus(_)
+            | StatusEnum::PyroBlastTargetStatus(_)
+            | StatusEnum::AttackHealStatus(_)
+            | StatusEnum::ArmorModifierStatus(_)
+            | StatusEnum::WalkingSpeedModifierStatus(_)
+            | StatusEnum::ReflectDamageStatus(_)
+            | StatusEnum::PoisonStatus(_)
+            | StatusEnum::StunStatus(_) => StatusStackingResult::AddTheNewStatus,
+            StatusEnum::AbsorbStatus(status) => status.stack(other),
+            StatusEnum::DeathStatus(_)
+            | StatusEnum::SacrificeStatus(_)
+            | StatusEnum::ExoSkeletonStatus(_)
+            | StatusEnum::AssaBladeDashStatus(_)
+            | StatusEnum::MountedStatus { .. }
+            | StatusEnum::FalconCarryStatus(_) => StatusStackingResult::DontAddTheNewStatus,
+            StatusEnum::AssaPhasePrismStatus(_) => StatusStackingResult::Replace,
+        }
+    }
+
+    pub fn get_status_completion_percent(&self, now: LocalTime) -> Option<(LocalTime, f32)> {
+        match self {
+            StatusEnum::AbsorbStatus(status) => Some((
+                status.until,
+                now.percentage_between(status.started, status.until),
+            )),
+            StatusEnum::MountedStatus { .. } => None,
+            StatusEnum::ExoSkeletonStatus(status) => status.get_status_completion_percent(now),
+            StatusEnum::FireBombStatus(status) => status.get_status_completion_percent(now),
+            StatusEnum::PyroBlastTargetStatus(_) => None,
+            StatusEnum::AttackHealStatus(status) => status.get_status_completion_percent(now),
+            StatusEnum::ArmorModifierStatus(_) => None,
+            StatusEnum::WalkingSpeedModifierStatus(_) => None,
+            StatusEnum::ReflectDamageStatus(status) => status.get_status_completion_percent(now),
+            StatusEnum::SacrificeStatus(status) => status.get_status_completion_percent(now),
+            StatusEnum::PoisonStatus(status) => status.get_status_completion_percent(now),
+            StatusEnum::StunStatus(status) => status.get_status_completion_percent(now),
+            StatusEnum::DeathStatus(_) => None,
+            StatusEnum::AssaBladeDashStatus(_) => None,
+            StatusEnum::FalconCarryStatus(status) => status.get_status_completion_percent(now),
+            StatusEnum::AssaPhasePrismStatus(_) => None,
+        }
+    }
+
+    /// The statuses in a snapshot contain the times and entity ids of the server,
+    /// they have to be converted before the client can use them.
+    /// None if an entity of the status is unknown to the client.
+    pub fn convert_times_and_ids(
+        &mut self,
+        convert_time: &dyn Fn(LocalTime) -> LocalTime,
+        convert_id: &dyn Fn(LocalCharEntityId) -> Option<LocalCharEntityId>,
+    ) -> Option<()> {
+        match self {
+            StatusEnum::MountedStatus { .. } => {}
+            StatusEnum::DeathStatus(status) => {
+                status.started = convert_time(status.started);
+                status.remove_char_at = convert_time(status.remove_char_at);
+            }
+            StatusEnum::AssaBladeDashStatus(status) => {
+                status.caster_entity_id = convert_id(status.caster_entity_id)?;
+                status.started_at = convert_time(status.started_at);
+                status.ends_at = convert_time(status.ends_at);
+            }
+            StatusEnum::AssaPhasePrismStatus(status) => {
+                status.caster_entity_id = convert_id(status.caster_entity_id)?;
+                status.started_at = convert_time(status.started_at);
+                status.ends_at = convert_time(status.ends_at);
+            }
+            StatusEnum::FalconCarryStatus(status) => {
+                status.started_at = convert_time(status.started_at);
+                status.ends_at = convert_time(status.ends_at);
+            }
+            StatusEnum::ExoSkeletonStatus(status) => status.convert_times(convert_time),
+            StatusEnum::AbsorbStatus(status) => {
+                status.caster_entity_id = convert_id(status.caster_entity_id)?;
+                status.started = convert_time(status.started);
+                status.animation_started = convert_time(status.animation_started);
+                status.until = convert_time(status.until);
+            }
+            StatusEnum::FireBombStatus(status) => {
+                status.caster_entity_id = convert_id(status.caster_entity_id)?;
+                status.started = convert_time(status.started);
+                status.until = convert_time(status.until);
+            }
+            StatusEnum::PyroBlastTargetStatus(status) => {
+                status.caster_entity_id = convert_id(status.caster_entity_id)?;
+            }
+            StatusEnum::AttackHealStatus(status) => {
+                status.started = convert_time(status.started);
+                status.until = convert_time(status.until);
+                status.animation_started = convert_time(status.animation_started);
+            }
+            StatusEnum::ArmorModifierStatus(status) => {
+                status.started = convert_time(status.started);
+                status.until = convert_time(status.until);
+            }
+            StatusEnum::WalkingSpeedModifierStatus(status) => {
+                status.started = convert_time(status.started);
+                status.until = convert_time(status.until);
+            }
+            StatusEnum::ReflectDamageStatus(status) => {
+                status.started = convert_time(status.started);
+                status.until = convert_time(status.until);
+                status.animation_started = convert_time(status.animation_started);
+            }
+            StatusEnum::SacrificeStatus(status) => {
+                status.sacrifice_caster_id = convert_id(status.sacrifice_caster_id)?;
+                status.started = convert_time(status.started);
+                status.until = convert_time(status.until);
+                status.animation_started = convert_time(status.animation_started);
+            }
+            StatusEnum::PoisonStatus(status) => {
+                status.poison_caster_entity_id = convert_id(status.poison_caster_entity_id)?;
+                status.started = convert_time(status.started);
+                status.until = convert_time(status.until);
+                status.next_damage_at = convert_time(status.next_damage_at);
+            }
+            StatusEnum::StunStatus(status) => {
+                status.caster_entity_id = convert_id(status.caster_entity_id)?;
+                status.started = convert_time(status.started);
+                status.until = convert_time(status.until);
+            }
+        }
+        return Some(());
+    }
+}
+
+// if you change the size, the update function has to be changed as well
+const STATUS_ARRAY_SIZE: usize = 32;
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Statuses {
+    statuses: [Option<StatusEnum>; STATUS_ARRAY_SIZE],
+    first_free_index: usize,
+    #[serde(skip)]
+    cached_modifier_collector: CharAttributeModifierCollector,
+}
+
+unsafe impl Sync for Statuses {}
+
+unsafe impl Send for Statuses {}
+
+impl Statuses {
+    pub fn new() -> Statuses {
+        Statuses {
+            statuses: Default::default(),
+            first_free_index: NONSTACKABLE_STATUS_COUNT,
+            cached_modifier_collector: CharAttributeModifierCollector::new(),
+        }
+    }
+
+    pub fn get_statuses(&self) -> &[Option<StatusEnum>; STATUS_ARRAY_SIZE] {
+        &self.statuses
+    }
+
+    pub fn can_move(&self) -> bool {
+        let mut allow = true;
+        for can_move in self
+            .statuses
+            .iter()
+            .take(self.first_free_index)
+            .map(|it| it.as_ref().map(|it| it.can_target_move()).unwrap_or(true))
+        {
+            allow &= can_move;
+        }
+        return allow;
+    }
+
+    pub fn can_cast(&self) -> bool {
+        let mut allow = true;
+        for can_cast in self
+            .statuses
+            .iter()
+            .take(self.first_free_index)
+            .map(|it| it.as_ref().map(|it| it.can_target_cast()).unwrap_or(true))
+        {
+            allow &= can_cast;
+        }
+        return allow;
+    }
+
+    pub fn can_be_controlled(&self) -> bool {
+        let mut allow = true;
+        for can_be_controlled in self.statuses.iter().take(self.first_free_index).map(|it| {
+            it.as_ref()
+                .map(|it| it.can_target_be_controlled())
+                .unwrap_or(true)
+        }) {
+            allow &= can_be_controlled;
+        }
+        return allow;
+    }
+
+    pub fn allow_push(&mut self, push: &ApplyForceComponent) -> bool {
+        let mut allow = true;
+        for allow_push in self
+            .statuses
+            .iter_mut()
+            .take(self.first_free_index)
+            .map(|it| it.as_ref().map(|it| it.allow_push(push)).unwrap_or(true))
+        {
+            allow &= allow_push;
+        }
+        return allow;
+    }
+
+    pub fn hp_mod_has_been_applied_on_enemy(
+        &mut self,
+        self_id: LocalCharEntityId,
+        outcome: &HpModificationResult,
+        hp_mod_reqs: &mut Vec<HpModificationRequest>,
+    ) {
+        for status in self
+            .statuses
+            .iter_mut()
+            .take(self.first_free_index)
+            .filter(|it| it.is_some())
+        {
+            status.as_mut().unwrap().hp_mod_has_been_applied_on_enemy(
+                self_id,
+                &outcome,
+                hp_mod_reqs,
+            );
+        }
+    }
+
+    pub fn hp_mod_is_calculated_but_not_applied_yet(
+        &mut self,
+        mut outcome: HpModificationResult,
+        hp_mod_reqs: &mut Vec<HpModificationRequest>,
+    ) -> HpModificationResult {
+        for status in self
+            .statuses
+            .iter_mut()
+            .take(self.first_free_index)
+            .filter(|it| it.is_some())
+        {
+            outcome = status
+                .as_mut()
+                .unwrap()
+                .hp_mod_is_calculated_but_not_applied_yet(outcome, hp_mod_reqs);
+        }
+        return outcome;
+    }
+
+    pub fn hp_mod_has_been_applied_on_me(
+        &mut self,
+        self_id: LocalCharEntityId,
+        outcome: &HpModificationResult,
+        hp_mod_reqs: &mut Vec<HpModificationRequest>,
+    ) {
+        for status in self
+            .statuses
+            .iter_mut()
+            .take(self.first_free_index)
+            .filter(|it| it.is_some())
+        {
+            status
+                .as_mut()
+                .unwrap()
+                .hp_mod_has_been_applied_on_me(self_id, &outcome, hp_mod_reqs);
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        self_char_id: LocalCharEntityId,
+        static_char_data: &mut StaticCharDataComponent,
+        time: &EngineTime,
+        hp_mod_requests: &mut Vec<HpModificationRequest>,
+    ) -> u32 {
+        let mut changed: u32 = 0;
+        for (i, status) in self
+            .statuses
+            .iter_mut()
+            .enumerate()
+            .take(self.first_free_index)
+            .filter(|(_i, it)| it.is_some())
+        {
+            let result = status.as_mut().unwrap().update(StatusUpdateParams {
+                self_char_id,
+                target_char: static_char_data,
+                hp_mod_requests,
+                time,
+            });
+            match result {
+                StatusUpdateResult::RemoveIt => {
+                    changed |= 1 << i;
+                }
+                StatusUpdateResult::KeepIt => {}
+            }
+        }
+
+        return changed;
+    }
+
+    pub fn remove_statuses(&mut self, bit_indices: u32) {
+        for i in 0..32 {
+            if ((1 << i) & bit_indices) > 0 {
+                self.statuses[i] = None;
+            }
+        }
+        self.move_free_index();
+    }
+
+    pub fn calc_attributes(&mut self) -> &CharAttributeModifierCollector {
+        self.cached_modifier_collector.clear();
+        for status in &mut self
+            .statuses
+            .iter()
+            .take(self.first_free_index)
+            .filter(|it| it.is_some())
+        {
+            status
+                .as_ref()
+                .unwrap()
+                .calc_attribs(&mut self.cached_modifier_collector);
+        }
+        return &self.cached_modifier_collector;
+    }
+
+    pub fn calc_render_color(&self, now: LocalTime) -> [u8; 4] {
+        let mut ret = [255, 255, 255, 255];
+        for status in &mut self
+            .statuses
+            .iter()
+            .take(self.first_free_index)
+            .filter(|it| it.is_some())
+        {
+            let status_color = status.as_ref().unwrap().get_render_color(now);
+            for i in 0..4 {
+                ret[i] = (ret[i] as u32 * status_color[i] as u32 / 255) as u8;
+            }
+        }
+        return ret;
+    }
+
+    pub fn calc_largest_remaining_status_time_percent(&self, now: LocalTime) -> Option<f32> {
+        let mut ret: Option<(LocalTime, f32)> = None;
+        for status in &mut self
+            .statuses
+            .iter()
+            .take(self.first_free_index)
+            .filter(|it| it.is_some())
+        {
+            let rem: Option<(LocalTime, f32)> =
+                status.as_ref().unwrap().get_status_completion_percent(now);
+            ret = if let Some((status_ends_at, _status_remaining_time)) = rem {
+                if let Some((current_ends_at, _current_rem_time)) = ret {
+                    if current_ends_at.has_not_passed_yet(status_ends_at) {
+                        rem
+                    } else {
+                        ret
+                    }
+                } else {
+                    rem
+                }
+            } else {
+                ret
+            };
+        }
+        return ret.map(|it| it.1);
+    }
+
+    pub fn is_mounted(&self) -> bool {
+        self.statuses[StatusEnumDiscriminants::MountedStatus as usize].is_some()
+    }
+
+    pub fn add(&mut self, new_status: StatusEnum) {
+        log::debug!("Try to add status: {:?}", new_status);
+        if self.first_free_index >= STATUS_ARRAY_SIZE {
+            log::error!("There is no more space for new Status!");
+            return;
+        }
+
+        let mut current_index = self.first_free_index;
+        let mut stack_type = StatusStackingResult::AddTheNewStatus;
+        let adding_status_type = StatusEnumDiscriminants::from(&new_status) as usize;
+        for i in 0..self.first_free_index {
+            if i < NONSTACKABLE_STATUS_COUNT {
+                let target_slot_type = i;
+                if target_slot_type == adding_status_type {
+                    stack_type = self.statuses[i]
+                        .as_mut()
+                        .map(|it| it.stack(&new_status))
+                        .unwrap_or(StatusStackingResult::Replace);
+                    current_index = i;
+                    log::trace!(
+                        "Found NONSTACKABLE slot. stack_type: {:?}, index: {}",
+                        stack_type,
+                        current_index
+                    );
+                    break;
+                }
+            } else {
+                if let Some(status) = self.statuses[i].as_mut() {
+                    let target_slot_type = {
+                        let s: &StatusEnum = status;
+                        StatusEnumDiscriminants::from(s) as usize
+                    };
+                    if target_slot_type == adding_status_type {
+                        stack_type = status.stack(&new_status);
+                        current_index = i;
+                        log::trace!(
+                            "Found STACKABLE slot. stack_type: {:?}, index: {}",
+                            stack_type,
+                            current_index
+                        );
+                        break;
+                    }
+                }
+            }
+        }
+
+        log::trace!("stack_type: {:?}, index: {}", stack_type, current_index);
+        match stack_type {
+            StatusStackingResult::Replace => {
+                self.statuses[current_index] = Some(new_status);
+            }
+            StatusStackingResult::AddTheNewStatus => {
+                self.statuses[self.first_free_index] = Some(new_status);
+                self.first_free_index += 1;
+            }
+            StatusStackingResult::DontAddTheNewStatus => {
+                return;
+            }
+        }
+    }
+
+    pub fn remove_all(&mut self) {
+        log::debug!("Remove all status");
+        for status in self.statuses.iter_mut().take(self.first_free_index) {
+            *status = None;
+        }
+        self.first_free_index = NONSTACKABLE_STATUS_COUNT;
+    }
+
+    pub fn remove_by_nature(&mut self, status_type: StatusNature) {
+        log::debug!("remove_by_nature: {:?}", status_type);
+        for status in self.statuses.iter_mut().take(self.first_free_index) {
+            let should_remove = status
+                .as_ref()
+                .map(|it| it.typ() == status_type)
+                .unwrap_or(false);
+            if should_remove {
+                *status = None;
+            }
+        }
+        self.move_free_index();
+    }
+
+    pub fn remove(&mut self, discr: StatusEnumDiscriminants) {
+        log::debug!("remove: {:?}", discr);
+        for status in self.statuses.iter_mut().take(self.first_free_index) {
+            let should_remove = status
+                .as_ref()
+                .map(|it| StatusEnumDiscriminants::from(it) == discr)
+                .unwrap_or(false);
+            if should_remove {
+                *status = None;
+            }
+        }
+        self.move_free_index();
+    }
+
+    fn move_free_index(&mut self) {
+        while self.first_free_index > NONSTACKABLE_STATUS_COUNT
+            && self.statuses[self.first_free_index - 1].is_none()
+        {
+            self.first_free_index -= 1;
+        }
+    }
+
+    pub fn remove_if<F>(&mut self, predicate: F)
+    where
+        F: Fn(&StatusEnum) -> bool,
+    {
+        for status in self.statuses.iter_mut().take(self.first_free_index) {
+            let should_remove = status.as_ref().map(|it| predicate(it)).unwrap_or(false);
+            if should_remove {
+                *status = None;
+            }
+        }
+        self.move_free_index();
+    }
+
+    #[allow(dead_code)]
+    pub fn get_status(&self, requested_type_id: StatusEnumDiscriminants) -> Option<&StatusEnum> {
+        for status in self.statuses.iter().filter(|it| it.is_some()) {
+            let status = status.as_ref().unwrap();
+            let type_id: StatusEnumDiscriminants = status.into();
+            if requested_type_id == type_id {
+                return Some(status);
+            }
+        }
+        return None;
+    }
+
+    #[allow(dead_code)]
+    // for tests
+    pub fn count(&self) -> usize {
+        let secondary_status_count = self.first_free_index - NONSTACKABLE_STATUS_COUNT;
+        let main_status_count = self
+            .statuses
+            .iter()
+            .take(NONSTACKABLE_STATUS_COUNT)
+            .filter(|it| it.is_some())
+            .count();
+        return main_status_count + secondary_status_count;
+    }
+
+    /// Only the type and the number of the statuses are compared, it is enough to detect
+    /// when a predicted status differs from the one the server has.
+    pub fn has_same_statuses_as(&self, other: &Statuses) -> bool {
+        let mut counts: [i32; STATUSENUM_COUNT] = [0; STATUSENUM_COUNT];
+        for status in self.statuses.iter().filter_map(|it| it.as_ref()) {
+            counts[StatusEnumDiscriminants::from(status) as usize] += 1;
+        }
+        for status in other.statuses.iter().filter_map(|it| it.as_ref()) {
+            counts[StatusEnumDiscriminants::from(status) as usize] -= 1;
+        }
+        return counts.iter().all(|it| *it == 0);
+    }
+
+    /// The statuses whose entities are unknown are dropped
+    pub fn convert_times_and_ids(
+        &mut self,
+        convert_time: &dyn Fn(LocalTime) -> LocalTime,
+        convert_id: &dyn Fn(LocalCharEntityId) -> Option<LocalCharEntityId>,
+    ) {
+        for slot in self.statuses.iter_mut().take(self.first_free_index) {
+            let converted = slot
+                .as_mut()
+                .map(|status| {
+                    status
+                        .convert_times_and_ids(convert_time, convert_id)
+                        .is_some()
+                })
+                .unwrap_or(true);
+            if !converted {
+                let discr: StatusEnumDiscriminants = slot.as_ref().unwrap().into();
+                log::warn!("{:?} has been dropped, its entity is unknown", discr);
+                *slot = None;
+            }
+        }
+        self.move_free_index();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::prelude::*;
+
+    #[test]
+    fn non_stackable_statuses() {
+        let mut statuses = Statuses::new();
+
+        assert_eq!(statuses.first_free_index, NONSTACKABLE_STATUS_COUNT);
+        assert!(statuses.statuses[0].is_none());
+        statuses.add(StatusEnum::MountedStatus {
+            speedup: percentage(0),
+        });
+        assert_eq!(statuses.first_free_index, NONSTACKABLE_STATUS_COUNT);
+        assert!(statuses.statuses[0].is_some());
+        statuses.add(StatusEnum::MountedStatus {
+            speedup: percentage(0),
+        });
+        assert_eq!(statuses.first_free_index, NONSTACKABLE_STATUS_COUNT);
+        assert!(statuses.statuses[0].is_some());
+
+        statuses.remove(StatusEnumDiscriminants::MountedStatus);
+        assert_eq!(statuses.first_free_index, NONSTACKABLE_STATUS_COUNT);
+        assert!(statuses.statuses[0].is_none());
+    }
+
+    #[test]
+    fn stackable_statuses() {
+        let mut statuses = Statuses::new();
+
+        assert_eq!(statuses.first_free_index, NONSTACKABLE_STATUS_COUNT);
+        assert!(statuses.statuses[NONSTACKABLE_STATUS_COUNT].is_none());
+
+        let status = StatusEnum::WalkingSpeedModifierStatus(WalkingSpeedModifierStatus {
+            started: LocalTime::from(0.0),
+            until: LocalTime::from(0.0),
+            modifier: percentage(0),
+        });
+
+        statuses.add(status.clone());
+        assert!(statuses.statuses[NONSTACKABLE_STATUS_COUNT].is_some());
+        assert_eq!(statuses.first_free_index, NONSTACKABLE_STATUS_COUNT + 1);
+
+        statuses.add(status.clone());
+        assert_eq!(statuses.first_free_index, NONSTACKABLE_STATUS_COUNT + 2);
+        assert!(statuses.statuses[NONSTACKABLE_STATUS_COUNT].is_some());
+        assert!(statuses.statuses[NONSTACKABLE_STATUS_COUNT + 1].is_some());
+
+        statuses.remove(StatusEnumDiscriminants::WalkingSpeedModifierStatus);
+        assert_eq!(statuses.first_free_index, NONSTACKABLE_STATUS_COUNT);
+        assert!(statuses.statuses[NONSTACKABLE_STATUS_COUNT].is_none());
+        assert!(statuses.statuses[NONSTACKABLE_STATUS_COUNT + 1].is_none());
+    }
+
+    #[test]
+    fn statuses_survive_serialization() {
+        let mut world = specs::World::new();
+        let caster = LocalCharEntityId::from(world.create_entity().build());
+        let mut statuses = Statuses::new();
+        statuses.add(StatusEnum::PoisonStatus(PoisonStatus {
+            poison_caster_entity_id: caster,
+            started: LocalTime::from(1_000u32),
+            until: LocalTime::from(3_000u32),
+            next_damage_at: LocalTime::from(2_000u32),
+            damage: 30,
+        }));
+
+        let bytes = bincode::serialize(&statuses).unwrap();
+        let deserialized: Statuses = bincode::deserialize(&bytes).unwrap();
+        assert!(deserialized.has_same_statuses_as(&statuses));
+        assert_eq!(deserialized.count(), 1);
+        match deserialized.get_status(StatusEnumDiscriminants::PoisonStatus) {
+            Some(StatusEnum::PoisonStatus(poison)) => {
+                assert_eq!(poison.until.as_millis(), 3_000);
+                assert_eq!(poison.next_damage_at.as_millis(), 2_000);
+            }
+            _ => panic!("PoisonStatus is missing"),
+        }
+    }
+
+    #[test]
+    fn statuses_of_unknown_entities_are_dropped_at_conversion() {
+        let mut world = specs::World::new();
+        let known = LocalCharEntityId::from(world.create_entity().build());
+        let unknown = LocalCharEntityId::from(world.create_entity().build());
+        let mut statuses = Statuses::new();
+        statuses.add(StatusEnum::MountedStatus {
+            speedup: percentage(0),
+        });
+        statuses.add(StatusEnum::PoisonStatus(PoisonStatus {
+            poison_caster_entity_id: unknown,
+            started: LocalTime::from(1_000u32),
+            until: LocalTime::from(3_000u32),
+            next_damage_at: LocalTime::from(2_000u32),
+            damage: 30,
+        }));
+        assert_eq!(statuses.count(), 2);
+        statuses.convert_times_and_ids(&|time| time, &|id| Some(id).filter(|it| *it == known));
+        assert_eq!(statuses.count(), 1);
+        assert!(statuses.is_mounted());
+        assert!(statuses
+            .get_status(StatusEnumDiscriminants::PoisonStatus)
+            .is_none());
+    }
+}
+
+pub enum StatusUpdateResult {
+    RemoveIt,
+    KeepIt,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PoisonStatus {
+    pub poison_caster_entity_id: LocalCharEntityId,
+    pub started: LocalTime,
+    pub until: LocalTime,
+    pub next_damage_at: LocalTime,
+    pub damage: u32,
+}
+
+impl PoisonStatus {
+    pub fn update(&mut self, params: StatusUpdateParams) -> StatusUpdateResult {
+        if self.until.has_already_passed(params.time.now()) {
+            StatusUpdateResult::RemoveIt
+        } else {
+            if self.next_damage_at.has_already_passed(params.time.now()) {
+                params.hp_mod_requests.push(HpModificationRequest {
+                    src_entity: self.poison_caster_entity_id,
+                    dst_entity: params.self_char_id,
+                    typ: HpModificationType::Poison(30),
+                });
+                self.next_damage_at = params.time.now().add_seconds(1.0);
+            }
+            StatusUpdateResult::KeepIt
+        }
+    }
+
+    pub fn get_status_completion_percent(&self, now: LocalTime) -> Option<(LocalTime, f32)> {
+        Some((self.until, now.percentage_between(self.started, self.until)))
+    }
+}
+
+pub fn calc_mounted_speedup(job_id: JobId, configs: &CommonConfigs) -> Percentage {
+    return match job_id {
+        JobId::CRUSADER => configs.stats.player.crusader.mounted_speedup,
+        _ => percentage(30),
+    };
+}
+
+pub struct ApplyStatusComponent {
+    pub source_entity_id: LocalCharEntityId,
+    pub target_entity_id: LocalCharEntityId,
+    pub status: StatusEnum,
+}
+
+pub enum RemoveStatusComponentPayload {
+    RemovingStatusType(StatusNature),
+    RemovingStatusDiscr(StatusEnumDiscriminants),
+}
+
+pub struct RemoveStatusComponent {
+    pub source_entity_id: LocalCharEntityId,
+    pub target_entity_id: LocalCharEntityId,
+    pub status: RemoveStatusComponentPayload,
+}
+
+unsafe impl Sync for ApplyStatusComponent {}
+
+unsafe impl Send for ApplyStatusComponent {}
+
+impl ApplyStatusComponent {
+    pub fn from_status(
+        source_entity_id: LocalCharEntityId,
+        target_entity_id: LocalCharEntityId,
+        m: StatusEnum,
+    ) -> ApplyStatusComponent {
+        ApplyStatusComponent {
+            source_entity_id,
+            target_entity_id,
+            status: m,
+        }
+    }
+}
+
+impl RemoveStatusComponent {
+    pub fn by_status_nature(
+        source_entity_id: LocalCharEntityId,
+        target_entity_id: LocalCharEntityId,
+        status_type: StatusNature,
+    ) -> RemoveStatusComponent {
+        RemoveStatusComponent {
+            source_entity_id,
+            target_entity_id,
+            status: RemoveStatusComponentPayload::RemovingStatusType(status_type),
+        }
+    }
+}
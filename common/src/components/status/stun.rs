@@ -0,0 +1,40 @@
+use crate::common::LocalTime;
+use crate::components::char::{CharState, LocalCharEntityId, LocalCharStateComp};
+use crate::components::status::status::{StatusUpdateParams, StatusUpdateResult};
+use serde::Deserialize;
+use serde::Serialize;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StunStatus {
+    pub caster_entity_id: LocalCharEntityId,
+    pub started: LocalTime,
+    pub until: LocalTime,
+}
+
+impl StunStatus {
+    pub fn new(caster_entity_id: LocalCharEntityId, now: LocalTime, duration: f32) -> StunStatus {
+        StunStatus {
+            caster_entity_id,
+            started: now,
+            until: now.add_seconds(duration),
+        }
+    }
+}
+
+impl StunStatus {
+    pub fn on_apply(&mut self, target_char: &mut LocalCharStateComp) {
+        target_char.set_state(CharState::StandBy, target_char.dir());
+    }
+
+    pub fn update(&mut self, params: StatusUpdateParams) -> StatusUpdateResult {
+        if self.until.has_already_passed(params.time.now()) {
+            StatusUpdateResult::RemoveIt
+        } else {
+            StatusUpdateResult::KeepIt
+        }
+    }
+
+    pub fn get_status_completion_percent(&self, now: LocalTime) -> Option<(LocalTime, f32)> {
+        Some((self.until, now.percentage_between(self.started, self.until)))
+    }
+}
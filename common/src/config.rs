@@ -45,9 +45,11 @@ pub struct SkillCastingAttributes {
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct SkillConfigFireWall {
+    /// the speed of the pushed characters in units per second
     pub pushback_force: f32,
     pub damage: u32,
     pub width: u16,
+    pub depth: f32,
     pub duration_seconds: f32,
     pub force_duration_seconds: f32,
     pub attributes: SkillCastingAttributes,
@@ -87,12 +89,22 @@ pub struct SkillConfigSanctuarySkill {
     pub heal: u32,
     pub heal_freq_seconds: f32,
     pub duration: f32,
+    pub width: f32,
+    pub height: f32,
     pub attributes: SkillCastingAttributes,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct LightningSkillConfig {
     pub attributes: SkillCastingAttributes,
+    pub damage: u32,
+    pub strike_radius: f32,
+    pub strike_distance: f32,
+    pub first_strike_delay_seconds: f32,
+    pub strike_interval_seconds: f32,
+    /// the distance of the strikes from the skill position in `strike_distance` units,
+    /// in the order they hit
+    pub strike_steps: Vec<u8>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -106,6 +118,8 @@ pub struct PoisonSkillConfig {
 pub struct FireBombSkillConfig {
     pub attributes: SkillCastingAttributes,
     pub damage: u32,
+    /// the bomb explodes and spreads to the nearby characters after this
+    pub duration_seconds: f32,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -138,6 +152,7 @@ pub struct AssaPhasePrismSkillConfig {
     pub attributes: SkillCastingAttributes,
     pub duration_seconds: f32,
     pub swap_duration_unit_per_second: f32,
+    pub min_swap_duration: f32,
     pub damage: u32,
 }
 
@@ -145,6 +160,8 @@ pub struct AssaPhasePrismSkillConfig {
 pub struct FalconCarry {
     pub attributes: SkillCastingAttributes,
     pub carry_ally_duration: f32,
+    /// the falcon carries the ally after it has caught her
+    pub catch_ally_duration: f32,
     pub carry_owner_duration: f32,
 }
 
@@ -206,5 +223,7 @@ pub struct SkillsConfig {
     pub gaz_turret: GazTurretSkillConfig,
     pub gaz_barricade: GazBarricadeSkillConfig,
     pub gaz_destroy_turret: SkillCastingAttributes,
+    pub gaz_turret_target: SkillCastingAttributes,
+    pub attack_move: SkillCastingAttributes,
     pub sanctuary: SkillConfigSanctuarySkill,
 }
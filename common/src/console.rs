@@ -23,6 +23,51 @@ impl Debug for CommandArguments {
     }
 }
 
+/// A command can be executed by clients whose level is at least the required one
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum PermissionLevel {
+    Player,
+    Admin,
+}
+
+/// The answer of the server to a failed console command
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ConsoleCommandError {
+    UnknownCommand(String),
+    PermissionDenied {
+        required: PermissionLevel,
+    },
+    MissingArgument(String),
+    InvalidArgument {
+        name: String,
+        value: String,
+    },
+    CharacterNotFound(String),
+    /// the command needs a character, but the caller has none and none was named
+    NoCharacter,
+    Failed(String),
+}
+
+impl std::fmt::Display for ConsoleCommandError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConsoleCommandError::UnknownCommand(name) => write!(f, "Unknown command: {}", name),
+            ConsoleCommandError::PermissionDenied { required } => {
+                write!(f, "Permission denied, {:?} level is required", required)
+            }
+            ConsoleCommandError::MissingArgument(name) => write!(f, "Missing argument: {}", name),
+            ConsoleCommandError::InvalidArgument { name, value } => {
+                write!(f, "Invalid value for {}: {}", name, value)
+            }
+            ConsoleCommandError::CharacterNotFound(name) => {
+                write!(f, "The character was not found: {}", name)
+            }
+            ConsoleCommandError::NoCharacter => write!(f, "A character is required"),
+            ConsoleCommandError::Failed(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CommandElement {
     pub text: String,
@@ -177,4 +222,16 @@ mod tests {
             CommandArguments::new("skip 1 2   3").as_str(2).unwrap()
         );
     }
+
+    #[test]
+    fn admin_is_permitted_everything_a_player_is() {
+        assert!(PermissionLevel::Player < PermissionLevel::Admin);
+        assert_eq!(
+            "Permission denied, Admin level is required",
+            ConsoleCommandError::PermissionDenied {
+                required: PermissionLevel::Admin
+            }
+            .to_string()
+        );
+    }
 }
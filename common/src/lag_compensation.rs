@@ -0,0 +1,135 @@
+//! Server side lag compensation.
+//! The clients see the other characters where the last received snapshot put them,
+//! so the range and the hits of a player's attacks are validated against the positions
+//! of the tick the player reported in her last intention.
+use crate::common::{SimulationTick, Vec2};
+use crate::components::char::{LocalCharEntityId, LocalCharStateComp};
+use specs::prelude::*;
+use std::collections::{HashMap, VecDeque};
+
+pub struct LagCompensation {
+    /// the server never rewinds more than this many ticks
+    max_rewind_ticks: u64,
+    history: VecDeque<(SimulationTick, HashMap<LocalCharEntityId, Vec2>)>,
+    /// the tick the controlling client was at when it sent its last intention
+    rewind_to: HashMap<LocalCharEntityId, SimulationTick>,
+}
+
+impl LagCompensation {
+    pub fn new(max_rewind_ticks: u64) -> LagCompensation {
+        LagCompensation {
+            max_rewind_ticks,
+            history: VecDeque::with_capacity(max_rewind_ticks as usize + 1),
+            rewind_to: HashMap::with_capacity(64),
+        }
+    }
+
+    /// Stores the positions of the characters at the end of `tick`
+    pub fn record(&mut self, tick: SimulationTick, ecs_world: &specs::World) {
+        let mut positions = if self.history.len() > self.max_rewind_ticks as usize {
+            let (_tick, mut oldest) = self.history.pop_front().unwrap();
+            oldest.clear();
+            oldest
+        } else {
+            HashMap::with_capacity(64)
+        };
+        for (char_id, char_state) in (
+            &ecs_world.entities(),
+            &ecs_world.read_storage::<LocalCharStateComp>(),
+        )
+            .join()
+        {
+            positions.insert(LocalCharEntityId::new(char_id), char_state.pos());
+        }
+        // the ids of the removed characters could be reused
+        self.rewind_to
+            .retain(|char_id, _tick| positions.contains_key(char_id));
+        self.history.push_back((tick, positions));
+    }
+
+    /// The client tick is clamped between `now - max_rewind_ticks` and `now`
+    pub fn set_client_tick(
+        &mut self,
+        char_id: LocalCharEntityId,
+        now: SimulationTick,
+        client_tick: SimulationTick,
+    ) {
+        let oldest_allowed = now.as_u64().saturating_sub(self.max_rewind_ticks);
+        let tick = if client_tick > now {
+            now
+        } else if client_tick.as_u64() < oldest_allowed {
+            SimulationTick::from_u64(oldest_allowed)
+        } else {
+            client_tick
+        };
+        self.rewind_to.insert(char_id, tick);
+    }
+
+    /// The position of `other_id` as `char_id`'s client saw it,
+    /// or None if there is nothing to rewind
+    pub fn rewound_pos(
+        &self,
+        char_id: LocalCharEntityId,
+        other_id: LocalCharEntityId,
+    ) -> Option<Vec2> {
+        if other_id == char_id {
+            return None;
+        }
+        let tick = self.rewind_to.get(&char_id)?;
+        let (_tick, positions) = self.history.iter().rev().find(|it| it.0 == *tick)?;
+        return positions.get(&other_id).cloned();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::v2;
+
+    fn create_char(world: &mut World, pos: Vec2) -> LocalCharEntityId {
+        let mut state = LocalCharStateComp::default();
+        state.set_pos(pos);
+        LocalCharEntityId::new(world.create_entity().with(state).build())
+    }
+
+    fn tick(n: u64) -> SimulationTick {
+        SimulationTick::from_u64(n)
+    }
+
+    #[test]
+    fn rewinds_only_the_others_and_not_further_than_the_cap() {
+        let mut world = World::new();
+        world.register::<LocalCharStateComp>();
+        let attacker = create_char(&mut world, v2(0.0, 0.0));
+        let target = create_char(&mut world, v2(10.0, 0.0));
+        let mut lag_compensation = LagCompensation::new(2);
+        for i in 0..5 {
+            world
+                .write_storage::<LocalCharStateComp>()
+                .get_mut(target.into())
+                .unwrap()
+                .set_pos(v2(10.0 + i as f32, 0.0));
+            world
+                .write_storage::<LocalCharStateComp>()
+                .get_mut(attacker.into())
+                .unwrap()
+                .set_pos(v2(i as f32, 0.0));
+            lag_compensation.record(tick(i), &world);
+        }
+        assert!(lag_compensation.rewound_pos(attacker, target).is_none());
+
+        lag_compensation.set_client_tick(attacker, tick(4), tick(3));
+        assert_eq!(
+            lag_compensation.rewound_pos(attacker, target),
+            Some(v2(13.0, 0.0))
+        );
+        assert!(lag_compensation.rewound_pos(attacker, attacker).is_none());
+
+        // too old, it is clamped to tick 2
+        lag_compensation.set_client_tick(attacker, tick(4), tick(0));
+        assert_eq!(
+            lag_compensation.rewound_pos(attacker, target),
+            Some(v2(12.0, 0.0))
+        );
+    }
+}
@@ -23,6 +23,8 @@ pub mod components;
 pub mod config;
 pub mod console;
 pub mod grf;
+pub mod lag_compensation;
 pub mod map;
 pub mod packets;
+pub mod pathfinding;
 pub mod systems;
@@ -1,5 +1,16 @@
 // remove grf::gat from common
 
+use crate::common::Vec2;
+use crate::grf::gat::{BlockingRectangle, Gat};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::io::{Read, Write};
+
+/// Map files contain only the collision data of a map (no models, textures etc),
+/// so they can be distributed without the GRF archives
+pub const MAP_FILE_EXTENSION: &str = "rmap";
+const MAP_FILE_MAGIC: &[u8; 4] = b"RMAP";
+const MAP_FILE_VERSION: u8 = 1;
+
 pub enum CellType {
     None = 1 << 0,
     Walkable = 1 << 1,
@@ -7,18 +18,207 @@ pub enum CellType {
     Snipable = 1 << 3,
 }
 
+#[derive(Clone)]
 pub struct MapWalkingInfo {
     pub width: u32,
     pub height: u32,
-    pub cells: Vec<CellType>,
+    /// `CellType` flags
+    pub cells: Vec<u8>,
 }
 
 impl MapWalkingInfo {
-    pub fn new() -> MapWalkingInfo {
+    pub fn from_gat(gat: &Gat) -> MapWalkingInfo {
         MapWalkingInfo {
-            width: 0,
-            height: 0,
-            cells: vec![],
+            width: gat.width,
+            height: gat.height,
+            cells: gat.cells.iter().map(|it| it.cell_type).collect(),
+        }
+    }
+
+    pub fn is_walkable(&self, x: usize, y: usize) -> bool {
+        if x >= self.width as usize {
+            return false;
+        }
+        return self
+            .cells
+            .get(y * self.width as usize + x)
+            .map(|it| it & CellType::Walkable as u8 != 0)
+            .unwrap_or(false);
+    }
+
+    /// Cells are run-length encoded, the rectangles are stored as in the `.cel` cache files
+    pub fn write_map_file<W: Write>(
+        &self,
+        rectangles: &[BlockingRectangle],
+        w: &mut W,
+    ) -> std::io::Result<()> {
+        w.write_all(MAP_FILE_MAGIC)?;
+        w.write_u8(MAP_FILE_VERSION)?;
+        w.write_u32::<LittleEndian>(self.width)?;
+        w.write_u32::<LittleEndian>(self.height)?;
+        let mut runs = Vec::<(u16, u8)>::with_capacity(1024);
+        for cell in &self.cells {
+            match runs.last_mut() {
+                Some((len, flags)) if *flags == *cell && *len < std::u16::MAX => *len += 1,
+                _ => runs.push((1, *cell)),
+            }
+        }
+        w.write_u32::<LittleEndian>(runs.len() as u32)?;
+        for (len, flags) in runs {
+            w.write_u16::<LittleEndian>(len)?;
+            w.write_u8(flags)?;
+        }
+        w.write_u32::<LittleEndian>(rectangles.len() as u32)?;
+        for rectangle in rectangles {
+            w.write_u32::<LittleEndian>(rectangle.area as u32)?;
+            w.write_u16::<LittleEndian>(rectangle.start_x as u16)?;
+            w.write_u16::<LittleEndian>(rectangle.bottom as u16)?;
+            w.write_u16::<LittleEndian>(rectangle.width as u16)?;
+            w.write_u16::<LittleEndian>(rectangle.height as u16)?;
+        }
+        return Ok(());
+    }
+
+    pub fn read_map_file<R: Read>(
+        r: &mut R,
+    ) -> Result<(MapWalkingInfo, Vec<BlockingRectangle>), String> {
+        let mut magic = [0u8; 4];
+        r.read_exact(&mut magic).map_err(|e| e.to_string())?;
+        if &magic != MAP_FILE_MAGIC {
+            return Err("Not a map file".to_owned());
+        }
+        let version = r.read_u8().map_err(|e| e.to_string())?;
+        if version != MAP_FILE_VERSION {
+            return Err(format!(
+                "Unsupported map file version: {}, expected: {}",
+                version, MAP_FILE_VERSION
+            ));
+        }
+        let read = |r: &mut R| -> Result<(MapWalkingInfo, Vec<BlockingRectangle>), std::io::Error> {
+            let width = r.read_u32::<LittleEndian>()?;
+            let height = r.read_u32::<LittleEndian>()?;
+            let cell_count = width as usize * height as usize;
+            // the header is not trusted, a corrupt file must not allocate gigabytes
+            let mut cells = Vec::with_capacity(cell_count.min(1024 * 1024));
+            let run_count = r.read_u32::<LittleEndian>()?;
+            for _ in 0..run_count {
+                let len = r.read_u16::<LittleEndian>()?;
+                let flags = r.read_u8()?;
+                cells.extend(std::iter::repeat(flags).take(len as usize));
+                if cells.len() > cell_count {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("more cells than {}x{}", width, height),
+                    ));
+                }
+            }
+            let rectangle_count = r.read_u32::<LittleEndian>()?;
+            let mut rectangles = Vec::with_capacity(rectangle_count.min(4096) as usize);
+            for _ in 0..rectangle_count {
+                rectangles.push(BlockingRectangle {
+                    area: r.read_u32::<LittleEndian>()? as i32,
+                    start_x: r.read_u16::<LittleEndian>()? as i32,
+                    bottom: r.read_u16::<LittleEndian>()? as i32,
+                    width: r.read_u16::<LittleEndian>()? as i32,
+                    height: r.read_u16::<LittleEndian>()? as i32,
+                });
+            }
+            Ok((
+                MapWalkingInfo {
+                    width,
+                    height,
+                    cells,
+                },
+                rectangles,
+            ))
+        };
+        let (map, rectangles) = read(r).map_err(|e| e.to_string())?;
+        if map.cells.len() != map.width as usize * map.height as usize {
+            return Err(format!(
+                "Invalid map file, {} cells instead of {}x{}",
+                map.cells.len(),
+                map.width,
+                map.height
+            ));
         }
+        return Ok((map, rectangles));
+    }
+
+    /// The world's y axis points the opposite direction than the rows of the map,
+    /// and everything outside of the map is non-walkable
+    pub fn is_walkable_pos(&self, pos: Vec2) -> bool {
+        if pos.x < 0.0 || pos.y > 0.0 {
+            return false;
+        }
+        return self.is_walkable(pos.x as usize, -pos.y as usize);
+    }
+
+    /// Regardless of walkability, in world coordinates
+    pub fn contains_pos(&self, pos: Vec2) -> bool {
+        return pos.x >= 0.0
+            && pos.x < self.width as f32
+            && pos.y <= 0.0
+            && -pos.y < self.height as f32;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::v2;
+
+    #[test]
+    fn positions_outside_of_the_map_are_not_walkable() {
+        let walkable = CellType::Walkable as u8 | CellType::Snipable as u8;
+        let map = MapWalkingInfo {
+            width: 2,
+            height: 2,
+            cells: vec![walkable, CellType::None as u8, walkable, walkable],
+        };
+        assert!(map.is_walkable_pos(v2(0.5, -0.5)));
+        assert!(!map.is_walkable_pos(v2(1.5, -0.5)));
+        assert!(map.is_walkable_pos(v2(1.5, -1.5)));
+        assert!(!map.is_walkable_pos(v2(2.5, -1.5)));
+        assert!(!map.is_walkable_pos(v2(-0.5, -0.5)));
+        assert!(!map.is_walkable_pos(v2(0.5, 0.5)));
+        assert!(!map.is_walkable_pos(v2(0.5, -2.5)));
+
+        assert!(map.contains_pos(v2(1.5, -0.5)));
+        assert!(!map.contains_pos(v2(0.5, -2.5)));
+        assert!(!map.contains_pos(v2(std::f32::NAN, -0.5)));
+    }
+
+    #[test]
+    fn map_file_roundtrip() {
+        let walkable = CellType::Walkable as u8;
+        let map = MapWalkingInfo {
+            width: 3,
+            height: 2,
+            cells: vec![walkable, walkable, 0, 0, 0, walkable],
+        };
+        let rectangles = vec![BlockingRectangle {
+            area: 3,
+            start_x: 2,
+            bottom: 0,
+            width: 1,
+            height: 3,
+        }];
+        let mut file = Vec::new();
+        map.write_map_file(&rectangles, &mut file).unwrap();
+
+        let (read_map, read_rectangles) =
+            MapWalkingInfo::read_map_file(&mut file.as_slice()).unwrap();
+        assert_eq!(read_map.width, 3);
+        assert_eq!(read_map.height, 2);
+        assert_eq!(read_map.cells, map.cells);
+        assert_eq!(read_rectangles, rectangles);
+
+        // the runs contain more cells than the header
+        let mut too_many_cells = file.clone();
+        too_many_cells[5] = 1;
+        assert!(MapWalkingInfo::read_map_file(&mut too_many_cells.as_slice()).is_err());
+
+        file[4] = MAP_FILE_VERSION + 1;
+        assert!(MapWalkingInfo::read_map_file(&mut file.as_slice()).is_err());
     }
 }
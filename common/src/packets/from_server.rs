@@ -4,7 +4,9 @@ use crate::components::char::{
     ServerEntityId, Team,
 };
 use crate::config::CommonConfigs;
-use crate::packets::to_server::{Packet, PacketReadErr};
+use crate::console::ConsoleCommandError;
+use crate::packets::snapshot_delta::CharStateDelta;
+use crate::packets::to_server::{Delivery, Packet, PacketReadErr};
 use crate::packets::SocketBuffer;
 use serde::export::TryFrom;
 use serde::Deserialize;
@@ -27,21 +29,121 @@ pub struct ServerEntityStateLocal {
     pub char_snapshot: LocalCharStateComp,
 }
 
+/// Identifies the player between connections, with it a reconnecting client can continue
+/// controlling its character
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub struct SessionToken(pub u64);
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum LoginRejectReason {
+    /// too short or too long, or contains not allowed characters
+    InvalidName,
+    NameIsTaken,
+    /// not a playable job
+    InvalidJob,
+}
+
+/// Why an intention of the client has been dropped by the server
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub enum IntentionRejection {
+    /// the client does not control a character
+    NoCharacter,
+    /// more intentions have been sent than allowed, it is not answered
+    RateLimited,
+    /// NaN or infinite coordinates
+    InvalidNumber,
+    OutOfMap,
+    TargetNotFound,
+    /// the target is the character itself or its ally
+    TargetNotEnemy,
+    TargetIsDead,
+    /// not sent since the server executes every skill, kept for the compatibility of the protocol
+    SkillNotAvailable,
+    /// the job of the character does not have the skill
+    SkillNotOwned,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub struct RoomId(pub u32);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RoomInfo {
+    pub id: RoomId,
+    pub name: String,
+    pub map_name: String,
+    pub player_count: usize,
+    pub max_players: usize,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum RoomError {
+    NotFound,
+    Full,
+    /// empty, too long, or it is already used by another room
+    InvalidName,
+    /// the map is not allowed for rooms or it could not be loaded
+    InvalidMap,
+    TooManyRooms,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum MatchPhase {
+    /// Waiting for enough players, the kills are not counted
+    Lobby {
+        required_players: usize,
+    },
+    Countdown {
+        remaining_seconds: f32,
+    },
+    InProgress {
+        remaining_seconds: Option<f32>,
+    },
+    /// None is a draw
+    PostGame {
+        winner: Option<Team>,
+    },
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MatchScore {
+    pub left_team_kills: u32,
+    pub right_team_kills: u32,
+}
+
 #[derive(Clone, Debug, EnumDiscriminants, EnumCount, Serialize, Deserialize)]
 pub enum FromServerPacket {
+    LoginAccepted {
+        /// the name of a resumed session can differ from the requested one
+        name: String,
+        session_token: SessionToken,
+        /// the room of the resumed session, it is joined automatically and `Init` follows
+        resumed_room: Option<RoomId>,
+    },
+    /// The connection is closed by the server after it
+    LoginRejected(LoginRejectReason),
+    RoomList(Vec<RoomInfo>),
+    /// The answer to `CreateRoom` or `JoinRoom` if it failed
+    RoomError(RoomError),
+    /// The room has been joined
     Init {
         map_name: String,
         start_x: f32,
         start_y: f32,
+        /// simulation ticks per second of the server, the client simulates with the same rate
+        tick_rate: u32,
     },
     Configs(CommonConfigs),
     Pong {
         server_time: ServerTime,
         server_tick: SimulationTick,
     },
+    /// The states of the characters, delta compressed against the `baseline` snapshot,
+    /// which was acknowledged by the client. Without baseline, every state is sent fully.
     Ack {
         cid: u32,
-        entries: Vec<ServerEntityState>,
+        tick: SimulationTick,
+        baseline: Option<SimulationTick>,
+        entries: Vec<CharStateDelta>,
     },
     NewEntity {
         id: ServerEntityId,
@@ -50,16 +152,49 @@ pub enum FromServerPacket {
         typ: CharType,
         outlook: CharOutlook,
         job_id: JobId,
+        has_falcon: bool,
         state: ServerCharState,
     },
     PlayerDisconnected(ServerEntityId),
-    // EntityDisappeared {
-    //     id: ServerEntityId,
-    // },
-    // EntityAppeared {
-    //     id: ServerEntityId,
-    // },
-    // died?
+    EntityDied(ServerEntityId),
+    EntityRespawned(ServerEntityId),
+    /// e.g. the corpse of an NPC disappeared
+    EntityRemoved(ServerEntityId),
+    /// The entity has come into the area of interest of the client
+    EntityAppeared {
+        id: ServerEntityId,
+        name: String,
+        team: Team,
+        typ: CharType,
+        outlook: CharOutlook,
+        job_id: JobId,
+        has_falcon: bool,
+        state: ServerCharState,
+    },
+    /// The entity has left the area of interest of the client
+    EntityDisappeared(ServerEntityId),
+    /// The connection is refused and closed by the server
+    ServerFull,
+    MatchPhaseChanged(MatchPhase),
+    ScoreChanged(MatchScore),
+    /// The answer to `ConsoleCommand`, the output of a successful command can be empty
+    ConsoleReply {
+        command: String,
+        result: Result<String, ConsoleCommandError>,
+    },
+    /// The static data of the entity has been changed by a console command
+    CharChanged {
+        id: ServerEntityId,
+        team: Team,
+        outlook: CharOutlook,
+        job_id: JobId,
+        has_falcon: bool,
+    },
+    /// The intention has not been applied. Too many rejections get the client kicked
+    IntentionRejected {
+        cid: u32,
+        reason: IntentionRejection,
+    },
 }
 
 impl Packet for FromServerPacket {
@@ -67,10 +202,20 @@ impl Packet for FromServerPacket {
         bincode::serialize_into(buf, self)
     }
 
-    fn read_from(buf: &mut SocketBuffer) -> Result<Self, PacketReadErr> {
-        return match bincode::deserialize_from(buf) {
+    fn read_from(payload: &[u8]) -> Result<Self, PacketReadErr> {
+        return match bincode::deserialize(payload) {
             Ok(packet) => Ok(packet),
-            Err(e) => Err(PacketReadErr::NotEnoughBytes),
+            Err(e) => {
+                log::debug!("Invalid FromServerPacket: {}", e);
+                Err(PacketReadErr::InvalidValues)
+            }
+        };
+    }
+
+    fn delivery(&self) -> Delivery {
+        return match self {
+            FromServerPacket::Ack { .. } => Delivery::UnreliableLatest,
+            _ => Delivery::ReliableOrdered,
         };
     }
 }
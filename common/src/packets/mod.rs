@@ -2,33 +2,48 @@ use crate::common::{v2, Vec2};
 use crate::grf::binary_reader::BinaryReader;
 use crate::packets::from_server::FromServerPacket;
 use crate::packets::to_server::{Packet, PacketReadErr, ToServerPacket};
+use crate::packets::udp::{UdpConnection, UdpConnectionErr, MAX_DATAGRAM_SIZE, RESEND_INTERVAL};
 use hexplay::{HexView, HexViewBuilder};
+use mio::{Events, Interest, Poll, Token, Waker};
 use serde::export::fmt::Debug;
+use serde::Deserialize;
 use std::io::prelude::*;
-use std::io::{Error, Read};
-use std::net::TcpStream;
-use std::sync::mpsc::RecvError;
-use std::time::Duration;
+use std::io::{Error, ErrorKind, Read};
+use std::net::{TcpStream, UdpSocket};
+use std::sync::mpsc::{RecvError, TryRecvError};
+use std::sync::Arc;
+use std::time::Instant;
 
 pub mod from_server;
+pub mod snapshot_delta;
 pub mod to_server;
+pub mod udp;
+
+/// It has to be increased whenever the binary representation of the packets changes
+pub const PROTOCOL_VERSION: u8 = 10;
+/// protocol version (u8) + payload length (u32)
+const FRAME_HEADER_LEN: usize = 5;
+const INITIAL_SOCKET_BUFFER_SIZE: usize = 2048;
+/// The socket ids are used as tokens for the sockets
+const WAKER_TOKEN: Token = Token(std::usize::MAX);
+/// The limit for a single connection's incoming and outgoing buffers
+pub const DEFAULT_MAX_SOCKET_BUFFER_SIZE: usize = 1024 * 1024;
 
 pub struct SocketBuffer {
-    buf: [u8; 2048],
+    buf: Vec<u8>,
     /// pointer at which the OS writes/reads the data during send/recv
     os_pointer: usize,
     /// pointer at which the application writes/reads the data during send/recv
     user_pointer: usize,
+    /// the buffer grows on demand up to this size
+    max_size: usize,
 }
 
 impl Write for SocketBuffer {
     fn write(&mut self, src: &[u8]) -> Result<usize, Error> {
-        //        pub fn write_u8(&mut self, value: u8) {
-        //            self.buff[self.user_pointer] = value;
-        //            self.user_pointer += 1;
-        //        }
-        let from = self.user_pointer;
         let len = src.len();
+        self.reserve_for_outgoing_data(len)?;
+        let from = self.user_pointer;
         let to = self.user_pointer + len;
         self.buf[from..to].copy_from_slice(src);
         self.user_pointer += len;
@@ -55,22 +70,81 @@ impl Read for SocketBuffer {
 
 impl SocketBuffer {
     pub fn new() -> SocketBuffer {
+        SocketBuffer::with_max_size(DEFAULT_MAX_SOCKET_BUFFER_SIZE)
+    }
+
+    pub fn with_max_size(max_size: usize) -> SocketBuffer {
         SocketBuffer {
-            buf: [0; 2048],
+            buf: vec![0; INITIAL_SOCKET_BUFFER_SIZE.min(max_size)],
             os_pointer: 0,
             user_pointer: 0,
+            max_size,
         }
     }
 
-    pub fn read_incoming_data(
+    pub fn read_incoming_data<S: Read>(
         &mut self,
-        socket_stream: &mut TcpStream,
+        socket_stream: &mut S,
     ) -> Result<usize, std::io::Error> {
+        if self.os_pointer == self.buf.len() {
+            self.make_room_for_incoming_data()?;
+        }
         let n = socket_stream.read(&mut self.buf[self.os_pointer..])?;
         self.os_pointer += n;
         Ok(n)
     }
 
+    /// Moves the unread data to the beginning of the buffer, and grows the buffer
+    /// if it is still full
+    fn make_room_for_incoming_data(&mut self) -> Result<(), std::io::Error> {
+        let unread_len = self.incoming_data_len();
+        self.buf.copy_within(self.user_pointer..self.os_pointer, 0);
+        self.user_pointer = 0;
+        self.os_pointer = unread_len;
+        if unread_len == self.buf.len() {
+            if self.buf.len() >= self.max_size {
+                return Err(Error::new(
+                    ErrorKind::Other,
+                    format!(
+                        "Incoming buffer limit ({} bytes) has been reached",
+                        self.max_size
+                    ),
+                ));
+            }
+            let new_len = (self.buf.len() * 2).min(self.max_size);
+            self.buf.resize(new_len, 0);
+        }
+        Ok(())
+    }
+
+    /// Makes room for `len` bytes after the unsent data, first by moving the unsent data to
+    /// the beginning of the buffer, then by growing the buffer
+    fn reserve_for_outgoing_data(&mut self, len: usize) -> Result<(), std::io::Error> {
+        if self.user_pointer + len <= self.buf.len() {
+            return Ok(());
+        }
+        let unsent_len = self.outgoing_data_len();
+        if unsent_len + len > self.max_size {
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!(
+                    "Outgoing buffer limit ({} bytes) has been reached",
+                    self.max_size
+                ),
+            ));
+        }
+        self.buf.copy_within(self.os_pointer..self.user_pointer, 0);
+        self.os_pointer = 0;
+        self.user_pointer = unsent_len;
+        if unsent_len + len > self.buf.len() {
+            let new_len = (self.buf.len() * 2)
+                .max(unsent_len + len)
+                .min(self.max_size);
+            self.buf.resize(new_len, 0);
+        }
+        Ok(())
+    }
+
     pub fn incoming_data_len(&self) -> usize {
         self.os_pointer - self.user_pointer
     }
@@ -100,14 +174,24 @@ impl SocketBuffer {
     }
 
     #[inline]
-    pub fn send_outgoing_data(
+    pub fn send_outgoing_data<S: Write>(
         &mut self,
-        socket_stream: &mut TcpStream,
+        socket_stream: &mut S,
     ) -> Result<usize, std::io::Error> {
         let mut all_sent_data = 0;
         while !self.eof() {
             let sending_buf = &self.buf[self.os_pointer..self.user_pointer];
-            let sent_data = socket_stream.write(sending_buf)?;
+            let sent_data = match socket_stream.write(sending_buf) {
+                Ok(0) => {
+                    return Err(Error::new(ErrorKind::WriteZero, "Socket has been closed"));
+                }
+                Ok(sent_data) => sent_data,
+                Err(ref e) if e.kind() == ErrorKind::WouldBlock => {
+                    // the OS buffer is full (slow connection), the rest remains in the buffer
+                    break;
+                }
+                Err(e) => return Err(e),
+            };
             self.os_pointer += sent_data;
             all_sent_data += sent_data;
         }
@@ -159,49 +243,33 @@ impl SocketBuffer {
         return result;
     }
 
-    pub fn write_u8(&mut self, value: u8) {
-        self.buf[self.user_pointer] = value;
-        self.user_pointer += 1;
+    pub fn write_u8(&mut self, value: u8) -> Result<(), Error> {
+        self.write_all(&[value])
     }
 
-    pub fn write_f32(&mut self, value: f32) {
-        unsafe {
-            *(self.buf.as_mut_ptr().offset(self.user_pointer as isize) as *mut f32) = value;
-        }
-        self.user_pointer += 4;
+    pub fn write_f32(&mut self, value: f32) -> Result<(), Error> {
+        self.write_all(&value.to_ne_bytes())
     }
 
-    pub fn write_v2(&mut self, value: &Vec2) {
-        self.write_f32(value.x);
-        self.write_f32(value.y);
+    pub fn write_v2(&mut self, value: &Vec2) -> Result<(), Error> {
+        self.write_f32(value.x)?;
+        self.write_f32(value.y)
     }
 
-    pub fn write_i32(&mut self, value: i32) {
-        unsafe {
-            *(self.buf.as_mut_ptr().offset(self.user_pointer as isize) as *mut i32) = value;
-        }
-        self.user_pointer += 4;
+    pub fn write_i32(&mut self, value: i32) -> Result<(), Error> {
+        self.write_all(&value.to_ne_bytes())
     }
 
-    pub fn write_u32(&mut self, value: u32) {
-        unsafe {
-            *(self.buf.as_mut_ptr().offset(self.user_pointer as isize) as *mut u32) = value;
-        }
-        self.user_pointer += 4;
+    pub fn write_u32(&mut self, value: u32) -> Result<(), Error> {
+        self.write_all(&value.to_ne_bytes())
     }
 
-    pub fn write_u64(&mut self, value: u64) {
-        unsafe {
-            *(self.buf.as_mut_ptr().offset(self.user_pointer as isize) as *mut u64) = value;
-        }
-        self.user_pointer += 8;
+    pub fn write_u64(&mut self, value: u64) -> Result<(), Error> {
+        self.write_all(&value.to_ne_bytes())
     }
 
-    pub fn write_u16(&mut self, value: u16) {
-        unsafe {
-            *(self.buf.as_mut_ptr().offset(self.user_pointer as isize) as *mut u16) = value;
-        }
-        self.user_pointer += 2;
+    pub fn write_u16(&mut self, value: u16) -> Result<(), Error> {
+        self.write_all(&value.to_ne_bytes())
     }
 
     pub fn read_str(&mut self) -> Result<&str, PacketReadErr> {
@@ -219,13 +287,61 @@ impl SocketBuffer {
         };
     }
 
-    pub fn write_str(&mut self, text: &str) {
-        let len = text.len();
-        self.write_u16(len as u16);
-        let start = self.user_pointer;
-        let end = self.user_pointer + text.len();
-        self.buf[start..end].clone_from_slice(text.as_bytes());
-        self.user_pointer = end;
+    pub fn write_str(&mut self, text: &str) -> Result<(), Error> {
+        self.write_u16(text.len() as u16)?;
+        self.write_all(text.as_bytes())
+    }
+
+    /// The maximum size the buffer can grow to
+    pub fn capacity(&self) -> usize {
+        self.max_size
+    }
+
+    /// Writes a frame (header + payload) into the buffer
+    pub fn write_packet<P: Packet>(&mut self, packet: &P) -> bincode::Result<()> {
+        let unsent_len = self.outgoing_data_len();
+        self.write_frame_header(0)?;
+        packet.write_into(self)?;
+        // the unsent data might have been moved inside the buffer while writing
+        let header_start = self.os_pointer + unsent_len;
+        let payload_len = (self.user_pointer - header_start - FRAME_HEADER_LEN) as u32;
+        self.buf[header_start + 1..header_start + FRAME_HEADER_LEN]
+            .copy_from_slice(&payload_len.to_le_bytes());
+        Ok(())
+    }
+
+    /// Reads a whole frame from the buffer. If the frame has not arrived yet completely,
+    /// nothing is consumed from the buffer.
+    pub fn read_packet<P: Packet>(&mut self) -> Result<P, PacketReadErr> {
+        self.ensure_size(FRAME_HEADER_LEN)?;
+        let header_start = self.user_pointer;
+        let version = self.buf[header_start];
+        if version != PROTOCOL_VERSION {
+            return Err(PacketReadErr::IncompatibleVersion(version));
+        }
+        let mut payload_len = [0; 4];
+        payload_len.copy_from_slice(&self.buf[header_start + 1..header_start + FRAME_HEADER_LEN]);
+        let payload_len = u32::from_le_bytes(payload_len) as usize;
+        if FRAME_HEADER_LEN + payload_len > self.capacity() {
+            return Err(PacketReadErr::InvalidValues);
+        }
+        self.ensure_size(FRAME_HEADER_LEN + payload_len)?;
+
+        let payload_start = header_start + FRAME_HEADER_LEN;
+        let payload_end = payload_start + payload_len;
+        self.user_pointer = payload_end;
+        return P::read_from(&self.buf[payload_start..payload_end]);
+    }
+
+    /// An empty frame, it is sent to a remote peer with an incompatible protocol version,
+    /// so it can tell the user which version we are speaking
+    pub fn write_version_frame(&mut self) -> Result<(), Error> {
+        self.write_frame_header(0)
+    }
+
+    fn write_frame_header(&mut self, payload_len: u32) -> Result<(), Error> {
+        self.write_u8(PROTOCOL_VERSION)?;
+        self.write_all(&payload_len.to_le_bytes())
     }
 
     pub fn reset(&mut self) {
@@ -244,22 +360,32 @@ impl SocketBuffer {
 }
 
 pub struct RemoteSocket {
-    socket_stream: TcpStream,
+    socket_stream: mio::net::TcpStream,
     out_buff: SocketBuffer,
     in_buff: SocketBuffer,
+    /// the remote peer can't keep up with the outgoing data
+    congested: bool,
 }
 
 impl RemoteSocket {
-    pub fn new(stream: TcpStream) -> RemoteSocket {
+    pub fn new(stream: mio::net::TcpStream, max_buffer_size: usize) -> RemoteSocket {
         RemoteSocket {
             socket_stream: stream,
-            out_buff: SocketBuffer::new(),
-            in_buff: SocketBuffer::new(),
+            out_buff: SocketBuffer::with_max_size(max_buffer_size),
+            in_buff: SocketBuffer::with_max_size(max_buffer_size),
+            congested: false,
         }
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+/// The transport of a connection, snapshots are not blocked by lost packets over UDP
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum Transport {
+    Tcp,
+    Udp,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct SocketId(usize);
 
 impl SocketId {
@@ -275,7 +401,18 @@ where
 {
     incoming_channel: std::sync::mpsc::Receiver<(SocketId, NetworkTrafficEvent<I>)>,
     outgoing_channel: std::sync::mpsc::Sender<NetworkTrafficHandlerMsg<O>>,
-    sockets: Vec<Option<()>>,
+    // wakes up the network thread when there is a new message in `outgoing_channel`
+    waker: Arc<Waker>,
+    sockets: Vec<SocketSlot>,
+}
+
+/// A closed slot is reused only after the network thread has removed its connection,
+/// so the remaining events of the old connection can't be mistaken for the new one's
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SocketSlot {
+    Free,
+    Used,
+    Closing,
 }
 
 impl<I, O> PacketHandlerThread<I, O>
@@ -284,41 +421,96 @@ where
     O: Send + Packet + Debug + 'static,
 {
     pub fn start_thread(socket_capacity: usize) -> PacketHandlerThread<I, O> {
+        PacketHandlerThread::start_thread_with_buffer_limit(
+            socket_capacity,
+            DEFAULT_MAX_SOCKET_BUFFER_SIZE,
+        )
+    }
+
+    /// `max_buffer_size`: the incoming and outgoing buffers of a connection can't grow
+    /// bigger than this. The connection is closed if its outgoing data can't fit into it.
+    pub fn start_thread_with_buffer_limit(
+        socket_capacity: usize,
+        max_buffer_size: usize,
+    ) -> PacketHandlerThread<I, O> {
         let (send_to_incoming_ch, read_from_incoming_ch) =
             std::sync::mpsc::channel::<(SocketId, NetworkTrafficEvent<I>)>();
         let (send_to_outgoing_ch, read_from_outgoing_ch) =
             std::sync::mpsc::channel::<NetworkTrafficHandlerMsg<O>>();
+        let poll = Poll::new().expect("Could not create the network event queue");
+        let waker = Arc::new(
+            Waker::new(poll.registry(), WAKER_TOKEN).expect("Could not create the network waker"),
+        );
         std::thread::spawn(move || {
-            network_traffic_handler(send_to_incoming_ch, read_from_outgoing_ch)
+            network_traffic_handler(
+                poll,
+                send_to_incoming_ch,
+                read_from_outgoing_ch,
+                max_buffer_size,
+            )
         });
         PacketHandlerThread {
             incoming_channel: read_from_incoming_ch,
             outgoing_channel: send_to_outgoing_ch,
+            waker,
             sockets: Vec::with_capacity(socket_capacity),
         }
     }
 
+    /// The ids of the closed connections are reused after their `Closed` event
+    fn allocate_socket_id(&mut self) -> SocketId {
+        return if let Some(index) = self.sockets.iter().position(|it| *it == SocketSlot::Free) {
+            self.sockets[index] = SocketSlot::Used;
+            SocketId(index)
+        } else {
+            self.sockets.push(SocketSlot::Used);
+            SocketId(self.sockets.len() - 1)
+        };
+    }
+
     pub fn handle_socket(&mut self, socket_stream: TcpStream) -> SocketId {
         socket_stream.set_nonblocking(true);
         socket_stream.set_nodelay(true);
-        // TODO: what is it? is it increasing infinitely??
-        let id = SocketId(self.sockets.len());
-        self.sockets.push(Some(()));
-        self.outgoing_channel
-            .send(NetworkTrafficHandlerMsg::NewConnection(id, socket_stream))
-            .unwrap();
+        let id = self.allocate_socket_id();
+        self.send_msg(NetworkTrafficHandlerMsg::NewConnection(id, socket_stream));
         return id;
     }
 
+    /// `socket` must be connected to the remote peer (see `udp::connect_udp` and `udp::UdpListener`)
+    pub fn handle_udp_socket(&mut self, socket: UdpSocket) -> SocketId {
+        socket.set_nonblocking(true);
+        let id = self.allocate_socket_id();
+        self.send_msg(NetworkTrafficHandlerMsg::NewUdpConnection(id, socket));
+        return id;
+    }
+
+    /// The packets which were sent before are still tried to be delivered,
+    /// then the connection is closed and its id can be reused after its `Closed` event.
+    /// It has to be called for the disconnected sockets as well.
+    pub fn close(&mut self, socket_id: SocketId) {
+        if let Some(socket) = self.sockets.get_mut(socket_id.0) {
+            if *socket == SocketSlot::Used {
+                *socket = SocketSlot::Closing;
+            }
+        }
+        self.send_msg(NetworkTrafficHandlerMsg::RemoveConnection(socket_id));
+    }
+
     pub fn send(&self, socket_id: SocketId, packet: O) {
-        self.outgoing_channel
-            .send(NetworkTrafficHandlerMsg::SendPacket(socket_id, packet))
-            .unwrap();
+        self.send_msg(NetworkTrafficHandlerMsg::SendPacket(socket_id, packet));
     }
 
-    pub fn receive_into(&self, out: &mut Vec<(SocketId, NetworkTrafficEvent<I>)>) {
+    fn send_msg(&self, msg: NetworkTrafficHandlerMsg<O>) {
+        self.outgoing_channel.send(msg).unwrap();
+        if let Err(e) = self.waker.wake() {
+            log::error!("Could not wake up the network thread: {}", e);
+        }
+    }
+
+    pub fn receive_into(&mut self, out: &mut Vec<(SocketId, NetworkTrafficEvent<I>)>) {
         loop {
             if let Ok(id_and_packet) = self.incoming_channel.try_recv() {
+                self.free_closed_slot(&id_and_packet);
                 out.push(id_and_packet)
             } else {
                 break;
@@ -327,18 +519,27 @@ where
     }
 
     pub fn receive_exact_into(
-        &self,
+        &mut self,
         out: &mut Vec<(SocketId, NetworkTrafficEvent<I>)>,
         count: usize,
     ) {
-        for i in 0..count {
+        for _i in 0..count {
             if let Ok(id_and_packet) = self.incoming_channel.try_recv() {
+                self.free_closed_slot(&id_and_packet);
                 out.push(id_and_packet)
             } else {
                 break;
             }
         }
     }
+
+    fn free_closed_slot(&mut self, (socket_id, event): &(SocketId, NetworkTrafficEvent<I>)) {
+        if let NetworkTrafficEvent::Closed = event {
+            if let Some(socket) = self.sockets.get_mut(socket_id.0) {
+                *socket = SocketSlot::Free;
+            }
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -347,6 +548,7 @@ where
     O: Send + Packet + Debug + 'static,
 {
     NewConnection(SocketId, TcpStream),
+    NewUdpConnection(SocketId, UdpSocket),
     RemoveConnection(SocketId),
     SendPacket(SocketId, O),
 }
@@ -355,144 +557,663 @@ where
 pub enum NetworkTrafficEvent<P: Send + Packet + Debug + 'static> {
     LocalError(std::io::Error),
     Disconnected,
-    OutgoingTraffic { sent_data_len: usize },
-    IncomingTraffic { received_data_len: usize },
+    OutgoingTraffic {
+        sent_data_len: usize,
+    },
+    IncomingTraffic {
+        received_data_len: usize,
+    },
+    /// The unsent data of the connection exceeded the half of its buffer limit,
+    /// the application should send less (e.g. skip snapshots) until `CongestionEnded`
+    Congested {
+        unsent_data_len: usize,
+    },
+    /// All the buffered data of a congested connection has been sent
+    CongestionEnded,
+    /// The last event of a connection after `close`, its id can be reused from now on
+    Closed,
     Packet(P),
 }
 
 fn network_traffic_handler<I, O>(
+    mut poll: Poll,
     // this is the channel the client app reads for incoming packets
     send_to_incoming_ch: std::sync::mpsc::Sender<(SocketId, NetworkTrafficEvent<I>)>,
     // the channel which is filled by the client app with outgoing packets
     read_from_outgoing_ch: std::sync::mpsc::Receiver<NetworkTrafficHandlerMsg<O>>,
+    max_buffer_size: usize,
 ) where
     I: Send + Packet + Debug + 'static,
     O: Send + Packet + Debug + 'static,
 {
     let mut sockets: Vec<Option<RemoteSocket>> = Vec::with_capacity(64);
+    // a socket id is either in `sockets` or in `udp_connections`
+    let mut udp_connections: Vec<Option<UdpConnection>> = Vec::with_capacity(64);
+    let mut datagram_buf = vec![0; MAX_DATAGRAM_SIZE];
+    let mut events = Events::with_capacity(256);
+    // sockets which got new outgoing data since the last flush
+    let mut sockets_to_flush: Vec<usize> = Vec::with_capacity(64);
     loop {
-        let command = read_from_outgoing_ch.try_recv();
-        match command {
-            Ok(NetworkTrafficHandlerMsg::NewConnection(socket_id, socket_stream)) => {
-                log::info!("adding new connection: {:?}", socket_id);
-                if socket_id.0 >= sockets.len() {
-                    sockets.push(Some(RemoteSocket::new(socket_stream)));
-                } else {
-                    sockets[socket_id.0] = Some(RemoteSocket::new(socket_stream));
-                }
-            }
-            Ok(NetworkTrafficHandlerMsg::RemoveConnection(socket_id)) => {
-                sockets[socket_id.0] = None;
+        // UDP connections have to resend their lost packets even if nothing happens
+        let timeout = if udp_connections.iter().any(|it| it.is_some()) {
+            Some(RESEND_INTERVAL)
+        } else {
+            None
+        };
+        if let Err(e) = poll.poll(&mut events, timeout) {
+            if e.kind() == std::io::ErrorKind::Interrupted {
+                continue;
             }
-            Ok(NetworkTrafficHandlerMsg::SendPacket(socket_id, packet)) => {
-                if let Some(socket) = sockets[socket_id.0].as_mut() {
-                    let socket_buffer = &mut socket.out_buff;
-                    //                    log::trace!("Outgoing Packet: {:?}", packet);
-                    if let Err(e) = packet.write_into(socket_buffer) {
-                        sockets[socket_id.0] = None;
-                        send_to_incoming_ch.send((
-                            SocketId(socket_id.0),
-                            NetworkTrafficEvent::LocalError(std::io::Error::new(
-                                std::io::ErrorKind::Other,
-                                e.to_string(),
-                            )),
-                        ));
+            log::error!("Network event polling failed: {}", e);
+            return;
+        }
+        for event in events.iter() {
+            if event.token() == WAKER_TOKEN {
+                loop {
+                    match read_from_outgoing_ch.try_recv() {
+                        Ok(msg) => handle_msg(
+                            msg,
+                            &poll,
+                            &mut sockets,
+                            &mut udp_connections,
+                            &mut sockets_to_flush,
+                            &send_to_incoming_ch,
+                            max_buffer_size,
+                        ),
+                        Err(TryRecvError::Empty) => break,
+                        Err(TryRecvError::Disconnected) => {
+                            // the application has dropped its PacketHandlerThread
+                            return;
+                        }
                     }
                 }
+                continue;
+            }
+            let i = event.token().0;
+            if let Some(Some(_)) = udp_connections.get(i) {
+                read_udp_connection::<I>(
+                    i,
+                    &mut udp_connections,
+                    &mut datagram_buf,
+                    &send_to_incoming_ch,
+                );
+                continue;
+            }
+            if event.is_writable() {
+                sockets_to_flush.push(i);
             }
-            Err(e) => {}
-        }
-        for i in 0..sockets.len() {
-            if let Some(ref mut socket) = &mut sockets[i] {
-                if !socket.out_buff.eof() {
-                    //                    log::trace!(
-                    //                        "OUTGOING\n{}",
-                    //                        socket.out_buff.get_debug_string_for_outgoing_data()
-                    //                    );
-                    let send_result = socket
+            if event.is_readable() || event.is_read_closed() {
+                read_socket::<I>(i, &mut sockets, &send_to_incoming_ch);
+            }
+        }
+        sockets_to_flush.sort();
+        sockets_to_flush.dedup();
+        for i in sockets_to_flush.drain(..) {
+            flush_socket::<I>(i, &mut sockets, &send_to_incoming_ch, max_buffer_size);
+        }
+        let now = Instant::now();
+        for i in 0..udp_connections.len() {
+            update_udp_connection::<I>(i, &mut udp_connections, &send_to_incoming_ch, now);
+        }
+    }
+}
+
+/// The socket ids are shared between the TCP and UDP connections, so the vectors may
+/// have gaps
+fn put_at<T>(vec: &mut Vec<Option<T>>, i: usize, item: T) {
+    if i >= vec.len() {
+        vec.resize_with(i + 1, || None);
+    }
+    vec[i] = Some(item);
+}
+
+fn handle_msg<I, O>(
+    msg: NetworkTrafficHandlerMsg<O>,
+    poll: &Poll,
+    sockets: &mut Vec<Option<RemoteSocket>>,
+    udp_connections: &mut Vec<Option<UdpConnection>>,
+    sockets_to_flush: &mut Vec<usize>,
+    send_to_incoming_ch: &std::sync::mpsc::Sender<(SocketId, NetworkTrafficEvent<I>)>,
+    max_buffer_size: usize,
+) where
+    I: Send + Packet + Debug + 'static,
+    O: Send + Packet + Debug + 'static,
+{
+    match msg {
+        NetworkTrafficHandlerMsg::NewConnection(socket_id, socket_stream) => {
+            log::info!("adding new connection: {:?}", socket_id);
+            let mut socket_stream = mio::net::TcpStream::from_std(socket_stream);
+            if let Err(e) = poll.registry().register(
+                &mut socket_stream,
+                Token(socket_id.0),
+                Interest::READABLE | Interest::WRITABLE,
+            ) {
+                log::error!("Could not register {:?}: {}", socket_id, e);
+                send_to_incoming_ch.send((socket_id, NetworkTrafficEvent::LocalError(e)));
+                return;
+            }
+            let socket = RemoteSocket::new(socket_stream, max_buffer_size);
+            put_at(sockets, socket_id.0, socket);
+        }
+        NetworkTrafficHandlerMsg::NewUdpConnection(socket_id, socket) => {
+            log::info!("adding new UDP connection: {:?}", socket_id);
+            let mut connection =
+                UdpConnection::new(mio::net::UdpSocket::from_std(socket), max_buffer_size);
+            if let Err(e) = poll.registry().register(
+                connection.socket_mut(),
+                Token(socket_id.0),
+                Interest::READABLE,
+            ) {
+                log::error!("Could not register {:?}: {}", socket_id, e);
+                send_to_incoming_ch.send((socket_id, NetworkTrafficEvent::LocalError(e)));
+                return;
+            }
+            put_at(udp_connections, socket_id.0, connection);
+        }
+        NetworkTrafficHandlerMsg::RemoveConnection(socket_id) => {
+            if let Some(socket) = sockets.get_mut(socket_id.0) {
+                if let Some(socket) = socket.as_mut() {
+                    // best effort, e.g. the reason of the closing
+                    let _ = socket
                         .out_buff
                         .send_outgoing_data(&mut socket.socket_stream);
-                    match send_result {
-                        Err(e) => {
-                            sockets[i] = None;
-                            send_to_incoming_ch
-                                .send((SocketId(i), NetworkTrafficEvent::LocalError(e)));
-                        }
-                        Ok(sent_bytes) => {
-                            if socket.out_buff.eof() {
-                                // all the data has been sent, the buffer is empty
-                                socket.out_buff.reset();
-                            }
-                            send_to_incoming_ch.send((
-                                SocketId(i),
-                                NetworkTrafficEvent::OutgoingTraffic {
-                                    sent_data_len: sent_bytes,
-                                },
-                            ));
-                        }
-                    }
                 }
+                *socket = None;
             }
+            if let Some(Some(connection)) = udp_connections.get_mut(socket_id.0) {
+                let now = Instant::now();
+                connection.start_closing(now);
+                if !connection.can_be_closed(now) {
+                    // `update_udp_connection` removes it and sends the Closed event
+                    return;
+                }
+                connection.close();
+                udp_connections[socket_id.0] = None;
+            }
+            send_to_incoming_ch.send((socket_id, NetworkTrafficEvent::Closed));
         }
-        'sockets_loop: for i in 0..sockets.len() {
-            if let Some(ref mut socket) = &mut sockets[i] {
-                let len = match socket.in_buff.read_incoming_data(&mut socket.socket_stream) {
-                    Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
-                        continue;
+        NetworkTrafficHandlerMsg::SendPacket(socket_id, packet) => {
+            if let Some(Some(connection)) = udp_connections.get_mut(socket_id.0) {
+                match connection.send_packet(&packet, Instant::now()) {
+                    Ok(sent_data_len) => {
+                        send_to_incoming_ch.send((
+                            socket_id,
+                            NetworkTrafficEvent::OutgoingTraffic { sent_data_len },
+                        ));
+                        check_udp_congestion(socket_id, connection, send_to_incoming_ch);
                     }
-                    Err(e) => {
-                        log::error!("Error during socket reading: {}", e);
-                        send_to_incoming_ch.send((SocketId(i), NetworkTrafficEvent::LocalError(e)));
-                        sockets[i] = None;
-                        continue;
+                    Err(ref e) if e.kind() == ErrorKind::InvalidInput => {
+                        // the connection is still usable, only this packet is lost
+                        log::error!("The packet to {:?} has been dropped: {}", socket_id, e);
                     }
-                    Ok(0) => {
-                        let socket_id = SocketId(i);
-                        send_to_incoming_ch.send((socket_id, NetworkTrafficEvent::Disconnected));
-                        sockets[i] = None;
-                        continue;
+                    Err(e) => {
+                        send_to_incoming_ch.send((socket_id, NetworkTrafficEvent::LocalError(e)));
+                        remove_udp_connection(socket_id.0, udp_connections, send_to_incoming_ch);
                     }
-                    Ok(len) => len,
-                };
-                //                log::trace!(
-                //                    "INCOMING\n{}",
-                //                    socket.in_buff.get_debug_string_for_incoming_data()
-                //                );
+                }
+            } else if let Some(Some(socket)) = sockets.get_mut(socket_id.0) {
+                let socket_buffer = &mut socket.out_buff;
+                //                    log::trace!("Outgoing Packet: {:?}", packet);
+                if let Err(e) = socket_buffer.write_packet(&packet) {
+                    sockets[socket_id.0] = None;
+                    send_to_incoming_ch.send((
+                        SocketId(socket_id.0),
+                        NetworkTrafficEvent::LocalError(std::io::Error::new(
+                            std::io::ErrorKind::Other,
+                            e.to_string(),
+                        )),
+                    ));
+                } else {
+                    sockets_to_flush.push(socket_id.0);
+                }
+            }
+        }
+    }
+}
+
+fn flush_socket<I>(
+    i: usize,
+    sockets: &mut Vec<Option<RemoteSocket>>,
+    send_to_incoming_ch: &std::sync::mpsc::Sender<(SocketId, NetworkTrafficEvent<I>)>,
+    max_buffer_size: usize,
+) where
+    I: Send + Packet + Debug + 'static,
+{
+    let socket = if let Some(Some(socket)) = sockets.get_mut(i) {
+        socket
+    } else {
+        return;
+    };
+    if socket.out_buff.eof() {
+        return;
+    }
+    //                    log::trace!(
+    //                        "OUTGOING\n{}",
+    //                        socket.out_buff.get_debug_string_for_outgoing_data()
+    //                    );
+    let send_result = socket
+        .out_buff
+        .send_outgoing_data(&mut socket.socket_stream);
+    match send_result {
+        Err(e) => {
+            sockets[i] = None;
+            send_to_incoming_ch.send((SocketId(i), NetworkTrafficEvent::LocalError(e)));
+        }
+        Ok(sent_bytes) => {
+            if socket.out_buff.eof() {
+                // all the data has been sent, the buffer is empty
+                socket.out_buff.reset();
+                if socket.congested {
+                    socket.congested = false;
+                    send_to_incoming_ch.send((SocketId(i), NetworkTrafficEvent::CongestionEnded));
+                }
+            } else if !socket.congested && socket.out_buff.outgoing_data_len() > max_buffer_size / 2
+            {
+                socket.congested = true;
                 send_to_incoming_ch.send((
                     SocketId(i),
-                    NetworkTrafficEvent::IncomingTraffic {
-                        received_data_len: len,
+                    NetworkTrafficEvent::Congested {
+                        unsent_data_len: socket.out_buff.outgoing_data_len(),
                     },
                 ));
+            }
+            send_to_incoming_ch.send((
+                SocketId(i),
+                NetworkTrafficEvent::OutgoingTraffic {
+                    sent_data_len: sent_bytes,
+                },
+            ));
+        }
+    }
+}
 
-                let socket_id = SocketId(i);
-                while !socket.in_buff.eof() {
-                    match I::read_from(&mut socket.in_buff) {
-                        Ok(packet) => {
-                            //                            log::trace!("Incoming Packet: {:?}", packet);
-                            send_to_incoming_ch
-                                .send((socket_id, NetworkTrafficEvent::Packet(packet)));
-                        }
-                        Err(err) => {
-                            match err {
-                                PacketReadErr::NotEnoughBytes => {
-                                    // ok
-                                }
-                                PacketReadErr::InvalidValues => {
-                                    log::error!("Socket({}) sent invalid values, close it", i);
-                                    sockets[i] = None;
-                                }
-                            }
-                            continue 'sockets_loop;
-                        }
-                    }
+fn read_socket<I>(
+    i: usize,
+    sockets: &mut Vec<Option<RemoteSocket>>,
+    send_to_incoming_ch: &std::sync::mpsc::Sender<(SocketId, NetworkTrafficEvent<I>)>,
+) where
+    I: Send + Packet + Debug + 'static,
+{
+    let socket_id = SocketId(i);
+    // the events are edge-triggered, so everything has to be read until WouldBlock
+    loop {
+        let socket = if let Some(Some(socket)) = sockets.get_mut(i) {
+            socket
+        } else {
+            return;
+        };
+        let len = match socket.in_buff.read_incoming_data(&mut socket.socket_stream) {
+            Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                return;
+            }
+            Err(ref e) if e.kind() == std::io::ErrorKind::Interrupted => {
+                continue;
+            }
+            Err(e) => {
+                log::error!("Error during socket reading: {}", e);
+                send_to_incoming_ch.send((socket_id, NetworkTrafficEvent::LocalError(e)));
+                sockets[i] = None;
+                return;
+            }
+            Ok(0) => {
+                send_to_incoming_ch.send((socket_id, NetworkTrafficEvent::Disconnected));
+                sockets[i] = None;
+                return;
+            }
+            Ok(len) => len,
+        };
+        //                log::trace!(
+        //                    "INCOMING\n{}",
+        //                    socket.in_buff.get_debug_string_for_incoming_data()
+        //                );
+        send_to_incoming_ch.send((
+            SocketId(i),
+            NetworkTrafficEvent::IncomingTraffic {
+                received_data_len: len,
+            },
+        ));
+
+        while !socket.in_buff.eof() {
+            match socket.in_buff.read_packet::<I>() {
+                Ok(packet) => {
+                    //                            log::trace!("Incoming Packet: {:?}", packet);
+                    send_to_incoming_ch.send((socket_id, NetworkTrafficEvent::Packet(packet)));
+                }
+                Err(PacketReadErr::NotEnoughBytes) => {
+                    // the rest of the frame has not arrived yet
+                    break;
                 }
-                if socket.in_buff.eof() {
-                    // all the data has read out, the buffer is empty
-                    socket.in_buff.reset();
+                Err(err) => {
+                    let error_msg = match err {
+                        PacketReadErr::IncompatibleVersion(remote_version) => {
+                            // let the other side know which version we are speaking
+                            let _ = socket.out_buff.write_version_frame();
+                            let _ = socket
+                                .out_buff
+                                .send_outgoing_data(&mut socket.socket_stream);
+                            format!(
+                                "Socket({}) uses protocol version {}, but version {} is required, close it",
+                                i, remote_version, PROTOCOL_VERSION
+                            )
+                        }
+                        _ => format!("Socket({}) sent an invalid packet, close it", i),
+                    };
+                    log::error!("{}", error_msg);
+                    send_to_incoming_ch.send((
+                        socket_id,
+                        NetworkTrafficEvent::LocalError(std::io::Error::new(
+                            std::io::ErrorKind::InvalidData,
+                            error_msg,
+                        )),
+                    ));
+                    sockets[i] = None;
+                    return;
                 }
             }
-        } // 'sockets_loop
-        std::thread::sleep(Duration::from_millis(10));
+        }
+        if socket.in_buff.eof() {
+            // all the data has read out, the buffer is empty
+            socket.in_buff.reset();
+        }
+    }
+}
+
+fn read_udp_connection<I>(
+    i: usize,
+    udp_connections: &mut Vec<Option<UdpConnection>>,
+    datagram_buf: &mut [u8],
+    send_to_incoming_ch: &std::sync::mpsc::Sender<(SocketId, NetworkTrafficEvent<I>)>,
+) where
+    I: Send + Packet + Debug + 'static,
+{
+    let socket_id = SocketId(i);
+    let mut packets = Vec::with_capacity(8);
+    // the events are edge-triggered, so everything has to be read until WouldBlock
+    loop {
+        let connection = if let Some(Some(connection)) = udp_connections.get_mut(i) {
+            connection
+        } else {
+            return;
+        };
+        let len = match connection.socket_mut().recv(datagram_buf) {
+            Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                break;
+            }
+            Err(ref e) if e.kind() == std::io::ErrorKind::Interrupted => {
+                continue;
+            }
+            Err(ref e) if e.kind() == std::io::ErrorKind::ConnectionRefused => {
+                // nobody listens on the other side anymore
+                send_to_incoming_ch.send((socket_id, NetworkTrafficEvent::Disconnected));
+                remove_udp_connection(i, udp_connections, send_to_incoming_ch);
+                return;
+            }
+            Err(e) => {
+                log::error!("Error during socket reading: {}", e);
+                send_to_incoming_ch.send((socket_id, NetworkTrafficEvent::LocalError(e)));
+                remove_udp_connection(i, udp_connections, send_to_incoming_ch);
+                return;
+            }
+            Ok(len) => len,
+        };
+        send_to_incoming_ch.send((
+            socket_id,
+            NetworkTrafficEvent::IncomingTraffic {
+                received_data_len: len,
+            },
+        ));
+        let result =
+            connection.process_datagram::<I>(&datagram_buf[0..len], Instant::now(), &mut packets);
+        for packet in packets.drain(..) {
+            send_to_incoming_ch.send((socket_id, NetworkTrafficEvent::Packet(packet)));
+        }
+        match result {
+            Ok(()) => {}
+            Err(UdpConnectionErr::Closed) => {
+                send_to_incoming_ch.send((socket_id, NetworkTrafficEvent::Disconnected));
+                remove_udp_connection(i, udp_connections, send_to_incoming_ch);
+                return;
+            }
+            Err(UdpConnectionErr::Packet(err)) => {
+                let error_msg = match err {
+                    PacketReadErr::IncompatibleVersion(remote_version) => format!(
+                        "Socket({}) uses protocol version {}, but version {} is required, close it",
+                        i, remote_version, PROTOCOL_VERSION
+                    ),
+                    _ => format!("Socket({}) sent an invalid packet, close it", i),
+                };
+                log::error!("{}", error_msg);
+                send_to_incoming_ch.send((
+                    socket_id,
+                    NetworkTrafficEvent::LocalError(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        error_msg,
+                    )),
+                ));
+                connection.close();
+                remove_udp_connection(i, udp_connections, send_to_incoming_ch);
+                return;
+            }
+        }
+    }
+}
+
+fn update_udp_connection<I>(
+    i: usize,
+    udp_connections: &mut Vec<Option<UdpConnection>>,
+    send_to_incoming_ch: &std::sync::mpsc::Sender<(SocketId, NetworkTrafficEvent<I>)>,
+    now: Instant,
+) where
+    I: Send + Packet + Debug + 'static,
+{
+    let socket_id = SocketId(i);
+    let connection = if let Some(Some(connection)) = udp_connections.get_mut(i) {
+        connection
+    } else {
+        return;
+    };
+    if connection.can_be_closed(now) {
+        connection.close();
+        remove_udp_connection(i, udp_connections, send_to_incoming_ch);
+        return;
+    }
+    if connection.timed_out(now) {
+        log::info!("{:?} has timed out", socket_id);
+        send_to_incoming_ch.send((socket_id, NetworkTrafficEvent::Disconnected));
+        remove_udp_connection(i, udp_connections, send_to_incoming_ch);
+        return;
+    }
+    match connection.update(now) {
+        Ok(0) => {}
+        Ok(sent_data_len) => {
+            send_to_incoming_ch.send((
+                socket_id,
+                NetworkTrafficEvent::OutgoingTraffic { sent_data_len },
+            ));
+        }
+        Err(e) => {
+            send_to_incoming_ch.send((socket_id, NetworkTrafficEvent::LocalError(e)));
+            remove_udp_connection(i, udp_connections, send_to_incoming_ch);
+            return;
+        }
+    }
+    check_udp_congestion(socket_id, connection, send_to_incoming_ch);
+}
+
+/// The Closed event of a lingering connection (see `UdpConnection::start_closing`)
+/// is sent when it is removed
+fn remove_udp_connection<I>(
+    i: usize,
+    udp_connections: &mut Vec<Option<UdpConnection>>,
+    send_to_incoming_ch: &std::sync::mpsc::Sender<(SocketId, NetworkTrafficEvent<I>)>,
+) where
+    I: Send + Packet + Debug + 'static,
+{
+    if let Some(Some(connection)) = udp_connections.get(i) {
+        if connection.is_closing() {
+            send_to_incoming_ch.send((SocketId(i), NetworkTrafficEvent::Closed));
+        }
+    }
+    udp_connections[i] = None;
+}
+
+fn check_udp_congestion<I>(
+    socket_id: SocketId,
+    connection: &mut UdpConnection,
+    send_to_incoming_ch: &std::sync::mpsc::Sender<(SocketId, NetworkTrafficEvent<I>)>,
+) where
+    I: Send + Packet + Debug + 'static,
+{
+    if connection.congested && connection.unacked_len() == 0 {
+        connection.congested = false;
+        send_to_incoming_ch.send((socket_id, NetworkTrafficEvent::CongestionEnded));
+    } else if !connection.congested && connection.unacked_len() > connection.max_unacked_len() / 2 {
+        connection.congested = true;
+        send_to_incoming_ch.send((
+            socket_id,
+            NetworkTrafficEvent::Congested {
+                unsent_data_len: connection.unacked_len(),
+            },
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::char::JobId;
+
+    fn welcome(name: &str) -> ToServerPacket {
+        ToServerPacket::welcome(name.to_owned(), JobId::CRUSADER, None)
+    }
+
+    // moves the written data into the incoming part of the buffer, as if it was received
+    fn into_incoming(buf: &SocketBuffer, len: usize) -> SocketBuffer {
+        let mut incoming = SocketBuffer::new();
+        incoming.buf[0..len].copy_from_slice(&buf.buf[0..len]);
+        incoming.os_pointer = len;
+        return incoming;
+    }
+
+    #[test]
+    fn frames_are_read_back() {
+        let mut out = SocketBuffer::new();
+        out.write_packet(&ToServerPacket::Ping).unwrap();
+        out.write_packet(&welcome("sharp")).unwrap();
+        let mut incoming = into_incoming(&out, out.outgoing_data_len());
+
+        match incoming.read_packet::<ToServerPacket>() {
+            Ok(ToServerPacket::Ping) => {}
+            other => panic!("{:?}", other),
+        }
+        match incoming.read_packet::<ToServerPacket>() {
+            Ok(ToServerPacket::Welcome { name, .. }) => assert_eq!(name, "sharp"),
+            other => panic!("{:?}", other),
+        }
+        assert!(incoming.eof());
+    }
+
+    #[test]
+    fn truncated_frame_is_not_consumed() {
+        let mut out = SocketBuffer::new();
+        out.write_packet(&welcome("sharp")).unwrap();
+        let full_len = out.outgoing_data_len();
+        for len in 0..full_len {
+            let mut incoming = into_incoming(&out, len);
+            match incoming.read_packet::<ToServerPacket>() {
+                Err(PacketReadErr::NotEnoughBytes) => {}
+                other => panic!("{}: {:?}", len, other),
+            }
+            assert_eq!(incoming.incoming_data_len(), len);
+        }
+    }
+
+    #[test]
+    fn invalid_payload_is_reported() {
+        let mut out = SocketBuffer::new();
+        out.write_frame_header(3).unwrap();
+        out.write_all(&[0xFF, 0xFF, 0xFF]).unwrap();
+        let mut incoming = into_incoming(&out, out.outgoing_data_len());
+        match incoming.read_packet::<ToServerPacket>() {
+            Err(PacketReadErr::InvalidValues) => {}
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn too_large_frame_is_reported() {
+        let mut out = SocketBuffer::new();
+        out.write_frame_header(std::u32::MAX).unwrap();
+        let mut incoming = into_incoming(&out, out.outgoing_data_len());
+        match incoming.read_packet::<ToServerPacket>() {
+            Err(PacketReadErr::InvalidValues) => {}
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn buffer_grows_up_to_its_limit() {
+        let mut out = SocketBuffer::with_max_size(3 * INITIAL_SOCKET_BUFFER_SIZE);
+        let name = "x".repeat(INITIAL_SOCKET_BUFFER_SIZE);
+        out.write_packet(&welcome(&name)).unwrap();
+        out.write_packet(&welcome(&name)).unwrap();
+        assert!(out.write_packet(&welcome(&name)).is_err());
+    }
+
+    #[test]
+    fn unsent_data_is_moved_to_make_room() {
+        let mut out = SocketBuffer::with_max_size(INITIAL_SOCKET_BUFFER_SIZE);
+        let name = "x".repeat(INITIAL_SOCKET_BUFFER_SIZE / 2);
+        out.write_packet(&welcome(&name)).unwrap();
+        // as if the OS has sent the first packet
+        out.os_pointer = out.user_pointer;
+        out.write_packet(&welcome(&name)).unwrap();
+        assert_eq!(out.os_pointer, 0);
+
+        let mut incoming = into_incoming(&out, out.outgoing_data_len());
+        match incoming.read_packet::<ToServerPacket>() {
+            Ok(ToServerPacket::Welcome {
+                name: read_name, ..
+            }) => assert_eq!(read_name, name),
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn incompatible_version_is_reported() {
+        let mut out = SocketBuffer::new();
+        out.write_packet(&ToServerPacket::Ping).unwrap();
+        out.buf[0] = PROTOCOL_VERSION + 1;
+        let mut incoming = into_incoming(&out, out.outgoing_data_len());
+        match incoming.read_packet::<ToServerPacket>() {
+            Err(PacketReadErr::IncompatibleVersion(version)) => {
+                assert_eq!(version, PROTOCOL_VERSION + 1)
+            }
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn closed_socket_id_is_reused_only_after_its_closed_event() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut thread = PacketHandlerThread::<ToServerPacket, ToServerPacket>::start_thread(4);
+        let first = thread.handle_socket(TcpStream::connect(addr).unwrap());
+        thread.close(first);
+        let second = thread.handle_socket(TcpStream::connect(addr).unwrap());
+        assert_ne!(first, second);
+
+        let started = Instant::now();
+        let mut events = Vec::new();
+        while !events
+            .iter()
+            .any(|(id, event)| *id == first && matches!(event, NetworkTrafficEvent::Closed))
+        {
+            assert!(started.elapsed().as_secs() < 5, "no Closed event");
+            std::thread::sleep(std::time::Duration::from_millis(1));
+            thread.receive_into(&mut events);
+        }
+        let third = thread.handle_socket(TcpStream::connect(addr).unwrap());
+        assert_eq!(first, third);
     }
 }
@@ -0,0 +1,254 @@
+//! Delta compression of the snapshots.
+//! The server encodes the states of the characters field by field, and sends only the fields
+//! which are different from the last snapshot the client has acknowledged (the baseline).
+use crate::common::{SimulationTick, Vec2};
+use crate::components::char::{ServerCharState, ServerEntityId};
+use crate::packets::to_server::PacketReadErr;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+/// Positions are sent as fixed point numbers with this precision,
+/// it has to be finer than `ALLOWED_F32_DIFF`
+const POS_QUANTIZATION: f32 = 256.0;
+const CHAR_STATE_FIELD_COUNT: usize = 10;
+/// Both the server and the client keeps this many snapshots as possible baselines.
+/// The server keeps the snapshots of the last N ticks, while the client keeps the last N
+/// received ones, so every baseline the server can choose is still available on the client.
+pub const SNAPSHOT_HISTORY_LEN: usize = 64;
+
+/// The state of a character, serialized field by field
+#[derive(Clone, Debug, PartialEq)]
+pub struct EncodedCharState {
+    fields: [Vec<u8>; CHAR_STATE_FIELD_COUNT],
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CharStateDelta {
+    pub id: ServerEntityId,
+    /// one bit for each field of `EncodedCharState`
+    changed_fields: u16,
+    /// the changed fields, each of them is prefixed with its length (u16)
+    data: Vec<u8>,
+}
+
+fn quantize_pos(pos: &Vec2) -> (i32, i32) {
+    (
+        (pos.x * POS_QUANTIZATION).round() as i32,
+        (pos.y * POS_QUANTIZATION).round() as i32,
+    )
+}
+
+fn dequantize_pos((x, y): (i32, i32)) -> Vec2 {
+    Vec2::new(x as f32 / POS_QUANTIZATION, y as f32 / POS_QUANTIZATION)
+}
+
+fn encode_field<T: Serialize>(value: &T) -> Vec<u8> {
+    bincode::serialize(value).expect("Snapshot fields can always be serialized")
+}
+
+fn decode_field<'a, T: Deserialize<'a>>(field: &'a [u8]) -> Result<T, PacketReadErr> {
+    return match bincode::deserialize(field) {
+        Ok(value) => Ok(value),
+        Err(e) => {
+            log::debug!("Invalid snapshot field: {}", e);
+            Err(PacketReadErr::InvalidValues)
+        }
+    };
+}
+
+impl EncodedCharState {
+    pub fn encode(state: &ServerCharState) -> EncodedCharState {
+        EncodedCharState {
+            fields: [
+                encode_field(&quantize_pos(&state.pos)),
+                encode_field(&state.dir),
+                encode_field(&state.state),
+                encode_field(&state.target),
+                encode_field(&state.calculated_attribs),
+                encode_field(&state.attack_delay_ends_at),
+                encode_field(&state.skill_cast_allowed_at),
+                encode_field(&state.cannot_control_until),
+                encode_field(&state.hp),
+                encode_field(&state.statuses),
+            ],
+        }
+    }
+
+    pub fn decode(&self) -> Result<ServerCharState, PacketReadErr> {
+        let f = &self.fields;
+        Ok(ServerCharState {
+            pos: dequantize_pos(decode_field(&f[0])?),
+            dir: decode_field(&f[1])?,
+            state: decode_field(&f[2])?,
+            target: decode_field(&f[3])?,
+            calculated_attribs: decode_field(&f[4])?,
+            attack_delay_ends_at: decode_field(&f[5])?,
+            skill_cast_allowed_at: decode_field(&f[6])?,
+            cannot_control_until: decode_field(&f[7])?,
+            hp: decode_field(&f[8])?,
+            statuses: decode_field(&f[9])?,
+        })
+    }
+
+    /// Without a baseline every field is sent
+    pub fn delta_from(
+        &self,
+        id: ServerEntityId,
+        baseline: Option<&EncodedCharState>,
+    ) -> CharStateDelta {
+        let mut changed_fields = 0u16;
+        let mut data = Vec::new();
+        for (i, field) in self.fields.iter().enumerate() {
+            let changed = baseline
+                .map(|baseline| baseline.fields[i] != *field)
+                .unwrap_or(true);
+            if changed {
+                changed_fields |= 1 << i;
+                data.extend_from_slice(&(field.len() as u16).to_le_bytes());
+                data.extend_from_slice(field);
+            }
+        }
+        CharStateDelta {
+            id,
+            changed_fields,
+            data,
+        }
+    }
+
+    /// The fields which are missing from the delta are taken from the baseline,
+    /// so every field must be present without a baseline
+    pub fn apply_delta(
+        baseline: Option<&EncodedCharState>,
+        delta: &CharStateDelta,
+    ) -> Result<EncodedCharState, PacketReadErr> {
+        let mut fields: [Vec<u8>; CHAR_STATE_FIELD_COUNT] = Default::default();
+        let mut data = delta.data.as_slice();
+        for (i, field) in fields.iter_mut().enumerate() {
+            if delta.changed_fields & (1 << i) == 0 {
+                *field = baseline.ok_or(PacketReadErr::InvalidValues)?.fields[i].clone();
+                continue;
+            }
+            if data.len() < 2 {
+                return Err(PacketReadErr::InvalidValues);
+            }
+            let len = u16::from_le_bytes([data[0], data[1]]) as usize;
+            if data.len() < 2 + len {
+                return Err(PacketReadErr::InvalidValues);
+            }
+            *field = data[2..2 + len].to_vec();
+            data = &data[2 + len..];
+        }
+        return if data.is_empty() {
+            Ok(EncodedCharState { fields })
+        } else {
+            Err(PacketReadErr::InvalidValues)
+        };
+    }
+}
+
+pub struct EncodedWorldSnapshot {
+    pub tick: SimulationTick,
+    /// in the order they were sent
+    entities: Vec<(ServerEntityId, EncodedCharState)>,
+    indices: HashMap<ServerEntityId, usize>,
+}
+
+impl EncodedWorldSnapshot {
+    pub fn new(tick: SimulationTick) -> EncodedWorldSnapshot {
+        EncodedWorldSnapshot {
+            tick,
+            entities: Vec::with_capacity(64),
+            indices: HashMap::with_capacity(64),
+        }
+    }
+
+    pub fn push(&mut self, id: ServerEntityId, state: EncodedCharState) {
+        self.indices.insert(id, self.entities.len());
+        self.entities.push((id, state));
+    }
+
+    pub fn get(&self, id: &ServerEntityId) -> Option<&EncodedCharState> {
+        self.indices.get(id).map(|i| &self.entities[*i].1)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(ServerEntityId, EncodedCharState)> {
+        self.entities.iter()
+    }
+}
+
+/// The last `SNAPSHOT_HISTORY_LEN` snapshots, the deltas are computed against them
+pub struct SnapshotHistory {
+    snapshots: VecDeque<EncodedWorldSnapshot>,
+}
+
+impl SnapshotHistory {
+    pub fn new() -> SnapshotHistory {
+        SnapshotHistory {
+            snapshots: VecDeque::with_capacity(SNAPSHOT_HISTORY_LEN),
+        }
+    }
+
+    pub fn push(&mut self, snapshot: EncodedWorldSnapshot) {
+        if self.snapshots.len() == SNAPSHOT_HISTORY_LEN {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(snapshot);
+    }
+
+    pub fn get(&self, tick: SimulationTick) -> Option<&EncodedWorldSnapshot> {
+        self.snapshots.iter().rev().find(|it| it.tick == tick)
+    }
+
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::v2;
+    use crate::components::char::{LocalCharEntityId, LocalCharStateComp};
+    use specs::prelude::*;
+
+    fn server_state(pos: Vec2, hp: i32) -> ServerCharState {
+        let mut state = LocalCharStateComp::default();
+        state.set_pos(pos);
+        state.hp = hp;
+        // the same layout, as the server sends it
+        unsafe { std::mem::transmute(state) }
+    }
+
+    fn entity_id() -> ServerEntityId {
+        let mut world = World::new();
+        let id = LocalCharEntityId::new(world.create_entity().build());
+        unsafe { std::mem::transmute(id) }
+    }
+
+    #[test]
+    fn only_the_changed_fields_are_sent() {
+        let id = entity_id();
+        let baseline = EncodedCharState::encode(&server_state(v2(10.0, 20.0), 100));
+        let current = EncodedCharState::encode(&server_state(v2(10.5, 20.0), 100));
+        let full = current.delta_from(id, None);
+        let delta = current.delta_from(id, Some(&baseline));
+        assert_eq!(delta.changed_fields, 1);
+        assert!(delta.data.len() * 10 < full.data.len());
+
+        let reconstructed = EncodedCharState::apply_delta(Some(&baseline), &delta).unwrap();
+        assert_eq!(reconstructed, current);
+        assert_eq!(EncodedCharState::apply_delta(None, &full).unwrap(), current);
+        assert!(EncodedCharState::apply_delta(None, &delta).is_err());
+    }
+
+    #[test]
+    fn positions_are_quantized() {
+        let pos = v2(238.123_45, -65.987_65);
+        let decoded = EncodedCharState::encode(&server_state(pos, 100))
+            .decode()
+            .unwrap();
+        assert!((decoded.pos.x - pos.x).abs() < 1.0 / POS_QUANTIZATION);
+        assert!((decoded.pos.y - pos.y).abs() < 1.0 / POS_QUANTIZATION);
+        assert_eq!(decoded.hp, 100);
+    }
+}
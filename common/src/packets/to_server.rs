@@ -1,6 +1,8 @@
 use crate::common::SimulationTick;
+use crate::components::char::{JobId, Team};
 use crate::components::controller::{PlayerIntention, ToServerPlayerIntention};
 use crate::console::CommandArguments;
+use crate::packets::from_server::{RoomId, SessionToken};
 use crate::packets::SocketBuffer;
 use serde::Deserialize;
 use serde::Serialize;
@@ -10,16 +12,49 @@ use strum_macros::EnumDiscriminants;
 
 pub trait Packet: Sized {
     fn write_into(&self, buf: &mut SocketBuffer) -> bincode::Result<()>;
-    fn read_from(buf: &mut SocketBuffer) -> Result<Self, PacketReadErr>;
+    /// `payload` contains exactly one packet, without the frame header
+    fn read_from(payload: &[u8]) -> Result<Self, PacketReadErr>;
+    /// How the packet has to be delivered over an unreliable transport (UDP)
+    fn delivery(&self) -> Delivery {
+        Delivery::ReliableOrdered
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Delivery {
+    /// The packet is resent until it is acknowledged, and the packets are processed
+    /// in the order they were sent
+    ReliableOrdered,
+    /// The packet is sent only once and it is dropped if a newer one has already arrived
+    /// (e.g. snapshots, where only the latest state matters)
+    UnreliableLatest,
 }
 
 #[derive(Debug, EnumDiscriminants, EnumCount, Serialize, Deserialize)]
 pub enum ToServerPacket {
+    /// The first packet of the client, the server answers with `LoginAccepted` or `LoginRejected`
     Welcome {
         name: String,
-        //        job: JobId
+        job_id: JobId,
+        /// the session of a previous connection, the server gives back its character if
+        /// it is still alive
+        session_token: Option<SessionToken>,
     },
     Ping,
+    /// The server answers with `RoomList`
+    ListRooms,
+    /// The room is joined after its creation. Without map name, the map of the default room
+    /// is used
+    CreateRoom {
+        name: String,
+        map_name: Option<String>,
+    },
+    /// The server answers with `Init` and `Configs`, or with `RoomError`.
+    /// The character in the previous room is removed.
+    JoinRoom(RoomId),
+    /// The team of the character which is created on `ReadyForGame`,
+    /// None lets the server balance the teams
+    SelectTeam(Option<Team>),
     ReadyForGame,
     Intention {
         cid: u32,
@@ -27,12 +62,34 @@ pub enum ToServerPacket {
         intention: ToServerPlayerIntention,
     },
     ConsoleCommand(CommandArguments),
+    /// The client has received the snapshot of this tick, the next snapshots can be
+    /// delta compressed against it.
+    /// None: the client could not reconstruct a snapshot, the next one has to be a full one
+    SnapshotAck(Option<SimulationTick>),
 }
 
 #[derive(Debug)]
 pub enum PacketReadErr {
+    /// the frame has not arrived completely yet
     NotEnoughBytes,
+    /// the frame has arrived but its content could not be deserialized
     InvalidValues,
+    /// the remote peer uses a different protocol version (the one in the variant)
+    IncompatibleVersion(u8),
+}
+
+impl ToServerPacket {
+    pub fn welcome(
+        name: String,
+        job_id: JobId,
+        session_token: Option<SessionToken>,
+    ) -> ToServerPacket {
+        ToServerPacket::Welcome {
+            name,
+            job_id,
+            session_token,
+        }
+    }
 }
 
 impl Packet for ToServerPacket {
@@ -71,10 +128,13 @@ impl Packet for ToServerPacket {
         //            }
         //        };
     }
-    fn read_from(buf: &mut SocketBuffer) -> Result<ToServerPacket, PacketReadErr> {
-        return match bincode::deserialize_from(buf) {
+    fn read_from(payload: &[u8]) -> Result<ToServerPacket, PacketReadErr> {
+        return match bincode::deserialize(payload) {
             Ok(packet) => Ok(packet),
-            Err(_e) => Err(PacketReadErr::NotEnoughBytes),
+            Err(e) => {
+                log::debug!("Invalid ToServerPacket: {}", e);
+                Err(PacketReadErr::InvalidValues)
+            }
         };
         //        let packet_id = buf.read_u8() - 1;
         //        if packet_id >= ToServerPacket::count() as u8 {
@@ -112,4 +172,12 @@ impl Packet for ToServerPacket {
         //        };
         //        Ok(packet)
     }
+
+    fn delivery(&self) -> Delivery {
+        return match self {
+            // only the latest one matters
+            ToServerPacket::SnapshotAck(_) => Delivery::UnreliableLatest,
+            _ => Delivery::ReliableOrdered,
+        };
+    }
 }
@@ -0,0 +1,650 @@
+//! A thin connection layer over UDP.
+//! Every datagram carries exactly one packet (or none, in case of control datagrams) and
+//! the cumulative acknowledgement of the reliable packets received so far, so lost
+//! snapshots don't block the packets which were sent after them.
+use crate::packets::to_server::{Delivery, Packet, PacketReadErr};
+use crate::packets::{SocketBuffer, PROTOCOL_VERSION};
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{Error, ErrorKind, Write};
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+/// protocol version (u8) + kind (u8) + sequence number (u32) + ack (u32)
+const DATAGRAM_HEADER_LEN: usize = 10;
+const ACK_OFFSET: usize = 6;
+/// The largest payload which fits into an IPv4 UDP datagram
+pub const MAX_DATAGRAM_SIZE: usize = 65507;
+/// Unacknowledged reliable packets are resent after this interval
+pub const RESEND_INTERVAL: Duration = Duration::from_millis(100);
+/// An empty datagram is sent if nothing else was sent in this interval, so the remote peer
+/// knows that we are still alive
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(1);
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(10);
+const CONNECT_ATTEMPTS: usize = 10;
+const CONNECT_ATTEMPT_TIMEOUT: Duration = Duration::from_millis(500);
+/// Reliable packets which arrived too early are kept until the missing ones arrive,
+/// but not more than this
+const MAX_OUT_OF_ORDER_PACKETS: usize = 1024;
+/// The listener answers the repeated connection requests of a client with the same port
+/// for this long
+const ACCEPTED_PEER_MEMORY: Duration = Duration::from_secs(5);
+/// A closed connection keeps resending its unacknowledged reliable packets for this long
+const CLOSE_LINGER_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DatagramKind {
+    Reliable = 0,
+    Unreliable = 1,
+    /// contains only the acknowledgement (also used as keepalive)
+    AckOnly = 2,
+    /// the remote peer closed the connection
+    Close = 3,
+    ConnectRequest = 4,
+    /// its payload is the port (u16) of the socket created for the client
+    ConnectAccepted = 5,
+}
+
+impl DatagramKind {
+    fn from_u8(value: u8) -> Option<DatagramKind> {
+        return match value {
+            0 => Some(DatagramKind::Reliable),
+            1 => Some(DatagramKind::Unreliable),
+            2 => Some(DatagramKind::AckOnly),
+            3 => Some(DatagramKind::Close),
+            4 => Some(DatagramKind::ConnectRequest),
+            5 => Some(DatagramKind::ConnectAccepted),
+            _ => None,
+        };
+    }
+}
+
+#[derive(Debug)]
+pub enum UdpConnectionErr {
+    /// the remote peer has closed the connection
+    Closed,
+    Packet(PacketReadErr),
+}
+
+struct UnackedDatagram {
+    seq: u32,
+    datagram: Vec<u8>,
+    last_sent: Instant,
+}
+
+/// Sequence numbers are not wrapped around, at 60 packets per second
+/// it would take more than two years to run out of them
+pub struct UdpConnection {
+    socket: mio::net::UdpSocket,
+    scratch: SocketBuffer,
+    next_reliable_seq: u32,
+    unacked: VecDeque<UnackedDatagram>,
+    unacked_len: usize,
+    max_unacked_len: usize,
+    /// every reliable packet before it has been received
+    next_expected_reliable_seq: u32,
+    out_of_order: BTreeMap<u32, Vec<u8>>,
+    next_unreliable_seq: u32,
+    last_received_unreliable_seq: Option<u32>,
+    ack_pending: bool,
+    last_sent: Instant,
+    last_received: Instant,
+    /// the remote peer does not acknowledge the reliable packets fast enough
+    pub(super) congested: bool,
+    /// the application has closed the connection, see `start_closing`
+    closing_since: Option<Instant>,
+}
+
+impl UdpConnection {
+    /// `socket` must be connected to the remote peer
+    pub fn new(socket: mio::net::UdpSocket, max_buffer_size: usize) -> UdpConnection {
+        let now = Instant::now();
+        UdpConnection {
+            socket,
+            scratch: SocketBuffer::with_max_size(MAX_DATAGRAM_SIZE),
+            next_reliable_seq: 0,
+            unacked: VecDeque::with_capacity(64),
+            unacked_len: 0,
+            max_unacked_len: max_buffer_size,
+            next_expected_reliable_seq: 0,
+            out_of_order: BTreeMap::new(),
+            next_unreliable_seq: 0,
+            last_received_unreliable_seq: None,
+            ack_pending: false,
+            last_sent: now,
+            last_received: now,
+            congested: false,
+            closing_since: None,
+        }
+    }
+
+    pub fn socket_mut(&mut self) -> &mut mio::net::UdpSocket {
+        &mut self.socket
+    }
+
+    /// The size of the reliable packets which have not been acknowledged yet
+    pub fn unacked_len(&self) -> usize {
+        self.unacked_len
+    }
+
+    pub fn max_unacked_len(&self) -> usize {
+        self.max_unacked_len
+    }
+
+    /// The packets which don't fit into a single datagram are refused
+    pub fn send_packet<P: Packet>(&mut self, packet: &P, now: Instant) -> Result<usize, Error> {
+        let (kind, seq) = match packet.delivery() {
+            Delivery::ReliableOrdered => (DatagramKind::Reliable, self.next_reliable_seq),
+            Delivery::UnreliableLatest => (DatagramKind::Unreliable, self.next_unreliable_seq),
+        };
+        self.scratch.reset();
+        write_datagram_header(&mut self.scratch, kind, seq)?;
+        // the sequence numbers are not used up by the refused packets, the receiver
+        // would wait for them forever
+        packet.write_into(&mut self.scratch).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "The packet does not fit into a datagram ({} bytes at most): {}",
+                    MAX_DATAGRAM_SIZE, e
+                ),
+            )
+        })?;
+        let mut datagram = self.scratch.buf[0..self.scratch.user_pointer].to_vec();
+        if kind == DatagramKind::Unreliable {
+            self.next_unreliable_seq += 1;
+            // it is not a problem if it can't be sent now, a newer one will come soon
+            return self.send_datagram(&mut datagram, now);
+        }
+        if self.unacked_len + datagram.len() > self.max_unacked_len {
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!(
+                    "Unacknowledged data limit ({} bytes) has been reached",
+                    self.max_unacked_len
+                ),
+            ));
+        }
+        self.next_reliable_seq += 1;
+        let sent = self.send_datagram(&mut datagram, now)?;
+        self.unacked_len += datagram.len();
+        self.unacked.push_back(UnackedDatagram {
+            seq,
+            datagram,
+            last_sent: now,
+        });
+        return Ok(sent);
+    }
+
+    /// Processes a received datagram, the packets which can be handed to the application
+    /// are pushed into `out`
+    pub fn process_datagram<P: Packet>(
+        &mut self,
+        datagram: &[u8],
+        now: Instant,
+        out: &mut Vec<P>,
+    ) -> Result<(), UdpConnectionErr> {
+        let (kind, seq, ack) = read_datagram_header(datagram).map_err(UdpConnectionErr::Packet)?;
+        self.last_received = now;
+        self.acknowledge_until(ack);
+        let payload = &datagram[DATAGRAM_HEADER_LEN..];
+        match kind {
+            DatagramKind::Reliable => {
+                self.ack_pending = true;
+                if seq < self.next_expected_reliable_seq {
+                    // a resent duplicate, our ack was probably lost
+                    return Ok(());
+                }
+                if seq > self.next_expected_reliable_seq {
+                    if self.out_of_order.len() < MAX_OUT_OF_ORDER_PACKETS {
+                        self.out_of_order.insert(seq, payload.to_vec());
+                    }
+                    return Ok(());
+                }
+                out.push(P::read_from(payload).map_err(UdpConnectionErr::Packet)?);
+                self.next_expected_reliable_seq += 1;
+                while let Some(payload) = self.out_of_order.remove(&self.next_expected_reliable_seq)
+                {
+                    out.push(P::read_from(&payload).map_err(UdpConnectionErr::Packet)?);
+                    self.next_expected_reliable_seq += 1;
+                }
+            }
+            DatagramKind::Unreliable => {
+                let is_newer = self
+                    .last_received_unreliable_seq
+                    .map(|last| seq > last)
+                    .unwrap_or(true);
+                if is_newer {
+                    self.last_received_unreliable_seq = Some(seq);
+                    out.push(P::read_from(payload).map_err(UdpConnectionErr::Packet)?);
+                }
+            }
+            DatagramKind::AckOnly => {}
+            DatagramKind::Close => {
+                return Err(UdpConnectionErr::Closed);
+            }
+            DatagramKind::ConnectRequest | DatagramKind::ConnectAccepted => {
+                // repeated handshake datagrams, the connection has already been established
+            }
+        }
+        return Ok(());
+    }
+
+    /// Resends the unacknowledged packets and sends the pending acknowledgement
+    /// (or a keepalive). Returns the number of sent bytes.
+    pub fn update(&mut self, now: Instant) -> Result<usize, Error> {
+        let mut sent = 0;
+        let ack = self.next_expected_reliable_seq;
+        for unacked in self.unacked.iter_mut() {
+            if now.duration_since(unacked.last_sent) < RESEND_INTERVAL {
+                continue;
+            }
+            unacked.datagram[ACK_OFFSET..DATAGRAM_HEADER_LEN].copy_from_slice(&ack.to_le_bytes());
+            match self.socket.send(&unacked.datagram) {
+                Ok(len) => {
+                    sent += len;
+                    unacked.last_sent = now;
+                }
+                Err(ref e) if e.kind() == ErrorKind::WouldBlock => {
+                    break;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        if sent > 0 {
+            self.last_sent = now;
+            self.ack_pending = false;
+        }
+        if self.ack_pending || now.duration_since(self.last_sent) >= KEEPALIVE_INTERVAL {
+            sent += self.send_control_datagram(DatagramKind::AckOnly, now)?;
+        }
+        return Ok(sent);
+    }
+
+    pub fn timed_out(&self, now: Instant) -> bool {
+        now.duration_since(self.last_received) >= CONNECTION_TIMEOUT
+    }
+
+    /// The unacknowledged reliable packets (e.g. the reason of a kick) are still resent
+    /// until they are acknowledged or `CLOSE_LINGER_TIMEOUT` passes, see `can_be_closed`
+    pub fn start_closing(&mut self, now: Instant) {
+        if self.closing_since.is_none() {
+            self.closing_since = Some(now);
+        }
+    }
+
+    pub fn is_closing(&self) -> bool {
+        self.closing_since.is_some()
+    }
+
+    /// The closing connection has nothing to deliver anymore
+    pub fn can_be_closed(&self, now: Instant) -> bool {
+        return self
+            .closing_since
+            .map(|since| {
+                self.unacked.is_empty() || now.duration_since(since) >= CLOSE_LINGER_TIMEOUT
+            })
+            .unwrap_or(false);
+    }
+
+    /// Lets the remote peer know that the connection has been closed, it is not
+    /// resent if it is lost, the remote peer will time out in that case
+    pub fn close(&mut self) {
+        let _ = self.send_control_datagram(DatagramKind::Close, Instant::now());
+    }
+
+    fn send_control_datagram(&mut self, kind: DatagramKind, now: Instant) -> Result<usize, Error> {
+        self.scratch.reset();
+        write_datagram_header(&mut self.scratch, kind, 0)?;
+        let mut datagram = [0; DATAGRAM_HEADER_LEN];
+        datagram.copy_from_slice(&self.scratch.buf[0..DATAGRAM_HEADER_LEN]);
+        return self.send_datagram(&mut datagram, now);
+    }
+
+    fn send_datagram(&mut self, datagram: &mut [u8], now: Instant) -> Result<usize, Error> {
+        datagram[ACK_OFFSET..DATAGRAM_HEADER_LEN]
+            .copy_from_slice(&self.next_expected_reliable_seq.to_le_bytes());
+        return match self.socket.send(datagram) {
+            Ok(len) => {
+                self.last_sent = now;
+                self.ack_pending = false;
+                Ok(len)
+            }
+            Err(ref e) if e.kind() == ErrorKind::WouldBlock => Ok(0),
+            Err(e) => Err(e),
+        };
+    }
+
+    /// The remote peer has received every reliable packet before `ack`
+    fn acknowledge_until(&mut self, ack: u32) {
+        while let Some(unacked) = self.unacked.front() {
+            if unacked.seq >= ack {
+                break;
+            }
+            self.unacked_len -= unacked.datagram.len();
+            self.unacked.pop_front();
+        }
+    }
+}
+
+fn write_datagram_header(
+    buf: &mut SocketBuffer,
+    kind: DatagramKind,
+    seq: u32,
+) -> Result<(), Error> {
+    buf.write_u8(PROTOCOL_VERSION)?;
+    buf.write_u8(kind as u8)?;
+    buf.write_all(&seq.to_le_bytes())?;
+    // the ack is filled right before sending
+    buf.write_all(&0u32.to_le_bytes())
+}
+
+fn read_datagram_header(datagram: &[u8]) -> Result<(DatagramKind, u32, u32), PacketReadErr> {
+    if datagram.len() < DATAGRAM_HEADER_LEN {
+        return Err(PacketReadErr::InvalidValues);
+    }
+    if datagram[0] != PROTOCOL_VERSION {
+        return Err(PacketReadErr::IncompatibleVersion(datagram[0]));
+    }
+    let kind = DatagramKind::from_u8(datagram[1]).ok_or(PacketReadErr::InvalidValues)?;
+    let mut seq = [0; 4];
+    seq.copy_from_slice(&datagram[2..ACK_OFFSET]);
+    let mut ack = [0; 4];
+    ack.copy_from_slice(&datagram[ACK_OFFSET..DATAGRAM_HEADER_LEN]);
+    return Ok((kind, u32::from_le_bytes(seq), u32::from_le_bytes(ack)));
+}
+
+fn handshake_datagram(kind: DatagramKind, port: u16) -> Vec<u8> {
+    let mut datagram = Vec::with_capacity(DATAGRAM_HEADER_LEN + 2);
+    datagram.push(PROTOCOL_VERSION);
+    datagram.push(kind as u8);
+    datagram.extend_from_slice(&[0; 8]);
+    datagram.extend_from_slice(&port.to_le_bytes());
+    return datagram;
+}
+
+/// The UDP counterpart of `TcpListener`. Every accepted client gets its own socket
+/// (connected to the client), so the connections can be handled the same way as TCP streams.
+pub struct UdpListener {
+    socket: UdpSocket,
+    accepted_peers: Vec<(SocketAddr, u16, Instant)>,
+}
+
+impl UdpListener {
+    pub fn bind(addr: SocketAddr) -> Result<UdpListener, Error> {
+        let socket = UdpSocket::bind(addr)?;
+        socket.set_nonblocking(true)?;
+        Ok(UdpListener {
+            socket,
+            accepted_peers: Vec::with_capacity(16),
+        })
+    }
+
+    /// Returns the socket created for a newly connected client, or None if there is
+    /// no new client
+    pub fn accept(&mut self) -> Option<(UdpSocket, SocketAddr)> {
+        let now = Instant::now();
+        self.accepted_peers.retain(|(_addr, _port, accepted)| {
+            now.duration_since(*accepted) < ACCEPTED_PEER_MEMORY
+        });
+        let mut buf = [0; DATAGRAM_HEADER_LEN];
+        loop {
+            let (len, peer_addr) = match self.socket.recv_from(&mut buf) {
+                Ok(received) => received,
+                Err(ref e) if e.kind() == ErrorKind::WouldBlock => return None,
+                Err(e) => {
+                    log::error!("encountered IO error: {}", e);
+                    return None;
+                }
+            };
+            match read_datagram_header(&buf[0..len]) {
+                Ok((DatagramKind::ConnectRequest, _, _)) => {}
+                Err(PacketReadErr::IncompatibleVersion(remote_version)) => {
+                    log::error!(
+                        "{} uses protocol version {}, but version {} is required",
+                        peer_addr,
+                        remote_version,
+                        PROTOCOL_VERSION
+                    );
+                    // let the other side know which version we are speaking
+                    let _ = self.socket.send_to(
+                        &handshake_datagram(DatagramKind::ConnectAccepted, 0),
+                        peer_addr,
+                    );
+                    continue;
+                }
+                _ => {
+                    log::debug!("Invalid connection request from {}", peer_addr);
+                    continue;
+                }
+            }
+            if let Some((_addr, port, _accepted)) = self
+                .accepted_peers
+                .iter()
+                .find(|(addr, _port, _accepted)| *addr == peer_addr)
+            {
+                // our answer was lost
+                let _ = self.socket.send_to(
+                    &handshake_datagram(DatagramKind::ConnectAccepted, *port),
+                    peer_addr,
+                );
+                continue;
+            }
+            match self.create_socket_for(peer_addr) {
+                Ok(client_socket) => {
+                    return Some((client_socket, peer_addr));
+                }
+                Err(e) => {
+                    log::error!("Could not create socket for {}: {}", peer_addr, e);
+                }
+            }
+        }
+    }
+
+    fn create_socket_for(&mut self, peer_addr: SocketAddr) -> Result<UdpSocket, Error> {
+        let mut local_addr = self.socket.local_addr()?;
+        local_addr.set_port(0);
+        let client_socket = UdpSocket::bind(local_addr)?;
+        client_socket.connect(peer_addr)?;
+        let port = client_socket.local_addr()?.port();
+        self.socket.send_to(
+            &handshake_datagram(DatagramKind::ConnectAccepted, port),
+            peer_addr,
+        )?;
+        self.accepted_peers.push((peer_addr, port, Instant::now()));
+        return Ok(client_socket);
+    }
+}
+
+/// Connects to a server which listens with `UdpListener`. It blocks until the server
+/// answers or all the attempts have timed out.
+pub fn connect_udp<A: ToSocketAddrs>(server_addr: A) -> Result<UdpSocket, Error> {
+    let server_addr = server_addr
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "Invalid server address"))?;
+    let local_addr: SocketAddr = if server_addr.is_ipv4() {
+        ([0, 0, 0, 0], 0).into()
+    } else {
+        ([0u16; 8], 0).into()
+    };
+    let socket = UdpSocket::bind(local_addr)?;
+    socket.set_read_timeout(Some(CONNECT_ATTEMPT_TIMEOUT))?;
+    let request = handshake_datagram(DatagramKind::ConnectRequest, 0);
+    let mut buf = [0; DATAGRAM_HEADER_LEN + 2];
+    for _attempt in 0..CONNECT_ATTEMPTS {
+        socket.send_to(&request, server_addr)?;
+        let (len, from) = match socket.recv_from(&mut buf) {
+            Ok(received) => received,
+            Err(ref e) if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::TimedOut => {
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        if from.ip() != server_addr.ip() || len != buf.len() {
+            continue;
+        }
+        match read_datagram_header(&buf) {
+            Ok((DatagramKind::ConnectAccepted, _, _)) => {
+                let port =
+                    u16::from_le_bytes([buf[DATAGRAM_HEADER_LEN], buf[DATAGRAM_HEADER_LEN + 1]]);
+                socket.connect(SocketAddr::new(server_addr.ip(), port))?;
+                socket.set_read_timeout(None)?;
+                return Ok(socket);
+            }
+            Err(PacketReadErr::IncompatibleVersion(remote_version)) => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "The server uses protocol version {}, but this client speaks version {}",
+                        remote_version, PROTOCOL_VERSION
+                    ),
+                ));
+            }
+            _ => {}
+        }
+    }
+    return Err(Error::new(
+        ErrorKind::TimedOut,
+        format!("The server ({}) did not answer", server_addr),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::SimulationTick;
+    use crate::components::char::JobId;
+    use crate::packets::from_server::FromServerPacket;
+    use crate::packets::to_server::ToServerPacket;
+
+    fn connection() -> UdpConnection {
+        let socket = mio::net::UdpSocket::bind(([127, 0, 0, 1], 0).into()).unwrap();
+        let addr = socket.local_addr().unwrap();
+        socket.connect(addr).unwrap();
+        return UdpConnection::new(socket, 1024 * 1024);
+    }
+
+    fn datagram_of<P: Packet>(conn: &mut UdpConnection, packet: &P) -> Vec<u8> {
+        conn.send_packet(packet, Instant::now()).unwrap();
+        let mut buf = vec![0; MAX_DATAGRAM_SIZE];
+        // the socket is connected to itself
+        let len = loop {
+            match conn.socket.recv(&mut buf) {
+                Ok(len) => break len,
+                Err(ref e) if e.kind() == ErrorKind::WouldBlock => std::thread::yield_now(),
+                Err(e) => panic!("{}", e),
+            }
+        };
+        buf.truncate(len);
+        return buf;
+    }
+
+    #[test]
+    fn reliable_packets_are_delivered_in_order() {
+        let mut sender = connection();
+        let first = datagram_of(
+            &mut sender,
+            &ToServerPacket::welcome("sharp".to_owned(), JobId::CRUSADER, None),
+        );
+        let second = datagram_of(&mut sender, &ToServerPacket::Ping);
+        let mut receiver = connection();
+        let mut out = Vec::<ToServerPacket>::new();
+        receiver
+            .process_datagram(&second, Instant::now(), &mut out)
+            .unwrap();
+        assert!(out.is_empty());
+        receiver
+            .process_datagram(&first, Instant::now(), &mut out)
+            .unwrap();
+        receiver
+            .process_datagram(&first, Instant::now(), &mut out)
+            .unwrap();
+        match out.as_slice() {
+            [ToServerPacket::Welcome { .. }, ToServerPacket::Ping] => {}
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(receiver.next_expected_reliable_seq, 2);
+        assert_eq!(sender.unacked.len(), 2);
+    }
+
+    #[test]
+    fn only_the_latest_unreliable_packet_is_delivered() {
+        let mut sender = connection();
+        let ack = |cid| FromServerPacket::Ack {
+            cid,
+            tick: SimulationTick::new(),
+            baseline: None,
+            entries: vec![],
+        };
+        let older = datagram_of(&mut sender, &ack(1));
+        let newer = datagram_of(&mut sender, &ack(2));
+        assert!(sender.unacked.is_empty());
+        let mut receiver = connection();
+        let mut out = Vec::<FromServerPacket>::new();
+        receiver
+            .process_datagram(&newer, Instant::now(), &mut out)
+            .unwrap();
+        receiver
+            .process_datagram(&older, Instant::now(), &mut out)
+            .unwrap();
+        match out.as_slice() {
+            [FromServerPacket::Ack { cid: 2, .. }] => {}
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn acknowledged_packets_are_not_resent() {
+        let mut sender = connection();
+        datagram_of(&mut sender, &ToServerPacket::Ping);
+        datagram_of(&mut sender, &ToServerPacket::Ping);
+        sender.acknowledge_until(1);
+        assert_eq!(sender.unacked.len(), 1);
+        assert_eq!(sender.unacked[0].seq, 1);
+        assert_eq!(sender.unacked_len(), sender.unacked[0].datagram.len());
+    }
+
+    #[test]
+    fn a_closing_connection_waits_for_the_acknowledgements() {
+        let now = Instant::now();
+        let mut sender = connection();
+        sender.start_closing(now);
+        assert!(sender.can_be_closed(now));
+
+        let mut sender = connection();
+        datagram_of(&mut sender, &ToServerPacket::Ping);
+        sender.start_closing(now);
+        assert!(!sender.can_be_closed(now));
+        assert!(sender.can_be_closed(now + CLOSE_LINGER_TIMEOUT));
+        sender.acknowledge_until(1);
+        assert!(sender.can_be_closed(now));
+    }
+
+    #[test]
+    fn the_header_is_little_endian() {
+        let mut sender = connection();
+        datagram_of(&mut sender, &ToServerPacket::Ping);
+        let datagram = datagram_of(&mut sender, &ToServerPacket::Ping);
+        assert_eq!(&datagram[2..ACK_OFFSET], &1u32.to_le_bytes());
+    }
+
+    #[test]
+    fn too_large_packets_are_refused() {
+        let mut sender = connection();
+        let too_large =
+            ToServerPacket::welcome("x".repeat(MAX_DATAGRAM_SIZE), JobId::CRUSADER, None);
+        let err = sender
+            .send_packet(&too_large, Instant::now())
+            .expect_err("it does not fit into a datagram");
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert!(sender.unacked.is_empty());
+        // the next packet gets the sequence number of the refused one
+        let datagram = datagram_of(&mut sender, &ToServerPacket::Ping);
+        let mut receiver = connection();
+        let mut out = Vec::<ToServerPacket>::new();
+        receiver
+            .process_datagram(&datagram, Instant::now(), &mut out)
+            .unwrap();
+        assert_eq!(out.len(), 1);
+    }
+}
@@ -0,0 +1,309 @@
+//! A* path finding over the cells of the map.
+//! The found paths are smoothed, only the corners remain as waypoints.
+use crate::common::Vec2;
+use crate::map::MapWalkingInfo;
+use specs::prelude::*;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+/// The search gives up after visiting this many cells,
+/// and the path leads to the closest cell it has found
+const MAX_VISITED_CELLS: usize = 10_000;
+/// The path is searched again if the destination moves farther than this
+/// (e.g. the targeted entity is walking away)
+const REPATH_DISTANCE: f32 = 1.0;
+const WAYPOINT_REACHED_DISTANCE: f32 = 0.3;
+const LINE_OF_SIGHT_STEP: f32 = 0.25;
+const STRAIGHT_COST: u32 = 10;
+const DIAGONAL_COST: u32 = 14;
+
+type Cell = (i32, i32);
+
+#[derive(Component, Default, Debug)]
+pub struct WalkingPathComponent {
+    destination: Option<Vec2>,
+    /// in reverse order, the next one is the last
+    waypoints: Vec<Vec2>,
+    reaches_destination: bool,
+}
+
+impl WalkingPathComponent {
+    /// The position the character has to walk towards to get to `destination`,
+    /// or None if it can't get any closer
+    pub fn next_waypoint(
+        &mut self,
+        map: &MapWalkingInfo,
+        pos: Vec2,
+        destination: Vec2,
+    ) -> Option<Vec2> {
+        if has_line_of_sight(map, pos, destination) {
+            self.destination = Some(destination);
+            self.waypoints.clear();
+            self.reaches_destination = true;
+            return Some(destination);
+        }
+        let destination_moved = self
+            .destination
+            .map(|it| (it - destination).magnitude() > REPATH_DISTANCE)
+            .unwrap_or(true);
+        let lost_sight_of_next = self
+            .waypoints
+            .last()
+            .map(|next| !has_line_of_sight(map, pos, *next))
+            .unwrap_or(true);
+        if destination_moved || lost_sight_of_next {
+            let (mut waypoints, reaches_destination) = find_path(map, pos, destination);
+            waypoints.reverse();
+            self.destination = Some(destination);
+            self.waypoints = waypoints;
+            self.reaches_destination = reaches_destination;
+        }
+        while let Some(next) = self.waypoints.last() {
+            if (next - pos).magnitude() > WAYPOINT_REACHED_DISTANCE {
+                break;
+            }
+            if self.waypoints.len() == 1 && self.reaches_destination {
+                // the caller decides when the destination is reached
+                break;
+            }
+            self.waypoints.pop();
+        }
+        return self.waypoints.last().cloned();
+    }
+}
+
+fn cell_of(pos: Vec2) -> Cell {
+    (pos.x.floor() as i32, (-pos.y).floor() as i32)
+}
+
+fn cell_center(cell: Cell) -> Vec2 {
+    Vec2::new(cell.0 as f32 + 0.5, -(cell.1 as f32 + 0.5))
+}
+
+fn is_walkable_cell(map: &MapWalkingInfo, cell: Cell) -> bool {
+    return cell.0 >= 0 && cell.1 >= 0 && map.is_walkable(cell.0 as usize, cell.1 as usize);
+}
+
+/// Octile distance
+fn heuristic(from: Cell, to: Cell) -> u32 {
+    let dx = (from.0 - to.0).abs() as u32;
+    let dy = (from.1 - to.1).abs() as u32;
+    return STRAIGHT_COST * dx.max(dy) + (DIAGONAL_COST - STRAIGHT_COST) * dx.min(dy);
+}
+
+pub fn has_line_of_sight(map: &MapWalkingInfo, from: Vec2, to: Vec2) -> bool {
+    let diff = to - from;
+    let step_count = (diff.magnitude() / LINE_OF_SIGHT_STEP).ceil() as usize;
+    for i in 1..=step_count {
+        let pos = from + diff * (i as f32 / step_count as f32);
+        if !map.is_walkable_pos(pos) {
+            return false;
+        }
+    }
+    return true;
+}
+
+#[derive(Eq, PartialEq)]
+struct OpenCell {
+    estimated_cost: u32,
+    cell: Cell,
+}
+
+impl Ord for OpenCell {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // BinaryHeap is a max heap
+        other
+            .estimated_cost
+            .cmp(&self.estimated_cost)
+            .then_with(|| self.cell.cmp(&other.cell))
+    }
+}
+
+impl PartialOrd for OpenCell {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Returns the smoothed waypoints from `from` to `to` (the starting position is not included).
+/// If `to` can't be reached, the path leads to the closest reachable cell and the second
+/// value is false.
+pub fn find_path(map: &MapWalkingInfo, from: Vec2, to: Vec2) -> (Vec<Vec2>, bool) {
+    let start = cell_of(from);
+    let goal = cell_of(to);
+    if !is_walkable_cell(map, start) {
+        return (vec![to], false);
+    }
+    let mut open = BinaryHeap::with_capacity(256);
+    let mut came_from = HashMap::<Cell, Cell>::with_capacity(256);
+    let mut cost_so_far = HashMap::<Cell, u32>::with_capacity(256);
+    let mut closest = (heuristic(start, goal), start);
+    cost_so_far.insert(start, 0);
+    open.push(OpenCell {
+        estimated_cost: closest.0,
+        cell: start,
+    });
+    let mut visited = 0;
+    while let Some(OpenCell {
+        estimated_cost,
+        cell,
+    }) = open.pop()
+    {
+        if cell == goal {
+            break;
+        }
+        let cost = cost_so_far[&cell];
+        if estimated_cost > cost + heuristic(cell, goal) {
+            // a cheaper way has been found to this cell since it was pushed
+            continue;
+        }
+        visited += 1;
+        if visited > MAX_VISITED_CELLS {
+            break;
+        }
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                if dx == 0 && dy == 0 {
+                    continue;
+                }
+                let next = (cell.0 + dx, cell.1 + dy);
+                let diagonal = dx != 0 && dy != 0;
+                // corners can't be cut
+                if !is_walkable_cell(map, next)
+                    || (diagonal
+                        && (!is_walkable_cell(map, (cell.0 + dx, cell.1))
+                            || !is_walkable_cell(map, (cell.0, cell.1 + dy))))
+                {
+                    continue;
+                }
+                let step_cost = if diagonal {
+                    DIAGONAL_COST
+                } else {
+                    STRAIGHT_COST
+                };
+                let new_cost = cost + step_cost;
+                let better = cost_so_far
+                    .get(&next)
+                    .map(|it| new_cost < *it)
+                    .unwrap_or(true);
+                if better {
+                    cost_so_far.insert(next, new_cost);
+                    came_from.insert(next, cell);
+                    let h = heuristic(next, goal);
+                    if h < closest.0 {
+                        closest = (h, next);
+                    }
+                    open.push(OpenCell {
+                        estimated_cost: new_cost + h,
+                        cell: next,
+                    });
+                }
+            }
+        }
+    }
+
+    let reaches_destination = closest.1 == goal;
+    let mut cells = vec![closest.1];
+    while let Some(prev) = came_from.get(cells.last().unwrap()) {
+        cells.push(*prev);
+    }
+    cells.pop(); // the starting cell
+    cells.reverse();
+    let mut points: Vec<Vec2> = cells.into_iter().map(cell_center).collect();
+    if reaches_destination {
+        points.pop();
+        points.push(to);
+    }
+    return (smooth_path(map, from, points), reaches_destination);
+}
+
+/// Removes the waypoints which can be skipped by walking in a straight line
+fn smooth_path(map: &MapWalkingInfo, from: Vec2, points: Vec<Vec2>) -> Vec<Vec2> {
+    let mut smoothed = Vec::with_capacity(8);
+    let mut current = from;
+    let mut i = 0;
+    while i < points.len() {
+        let mut farthest = i;
+        for j in (i + 1)..points.len() {
+            if has_line_of_sight(map, current, points[j]) {
+                farthest = j;
+            } else {
+                break;
+            }
+        }
+        current = points[farthest];
+        smoothed.push(current);
+        i = farthest + 1;
+    }
+    return smoothed;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::v2;
+    use crate::map::CellType;
+
+    /// '#' is a wall
+    fn create_map(rows: &[&str]) -> MapWalkingInfo {
+        MapWalkingInfo {
+            width: rows[0].len() as u32,
+            height: rows.len() as u32,
+            cells: rows
+                .iter()
+                .flat_map(|row| row.chars())
+                .map(|it| {
+                    if it == '#' {
+                        CellType::None as u8
+                    } else {
+                        CellType::Walkable as u8
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn path_goes_around_the_wall() {
+        let map = create_map(&[
+            "......", //
+            "..#...", //
+            "..#...", //
+            "..#...", //
+            "......", //
+        ]);
+        let from = v2(0.5, -2.5);
+        let to = v2(5.5, -2.5);
+        assert!(!has_line_of_sight(&map, from, to));
+        let (path, reaches_destination) = find_path(&map, from, to);
+        assert!(reaches_destination);
+        assert_eq!(*path.last().unwrap(), to);
+        let mut current = from;
+        for waypoint in &path {
+            assert!(has_line_of_sight(&map, current, *waypoint));
+            current = *waypoint;
+        }
+        // smoothed, only the corners remain
+        assert!(path.len() <= 3);
+    }
+
+    #[test]
+    fn unreachable_destination_leads_to_the_closest_cell() {
+        let map = create_map(&[
+            "...#.", //
+            "...#.", //
+            "...#.", //
+        ]);
+        let mut walking_path = WalkingPathComponent::default();
+        let to = v2(4.5, -1.5);
+        let (path, reaches_destination) = find_path(&map, v2(0.5, -1.5), to);
+        assert!(!reaches_destination);
+        assert_eq!(*path.last().unwrap(), v2(2.5, -1.5));
+
+        assert_eq!(
+            walking_path.next_waypoint(&map, v2(0.5, -1.5), to),
+            Some(v2(2.5, -1.5))
+        );
+        assert_eq!(walking_path.next_waypoint(&map, v2(2.5, -1.5), to), None);
+    }
+}
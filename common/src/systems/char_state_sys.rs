@@ -6,8 +6,38 @@ use crate::components::char::{
     CharDir, CharState, EntityTarget, LocalCharEntityId, LocalCharStateComp, ServerEntityId,
     StaticCharDataComponent, Team,
 };
+use crate::components::skills::FinishCast;
+use crate::config::CommonConfigs;
+use crate::lag_compensation::LagCompensation;
+use crate::map::MapWalkingInfo;
+use crate::pathfinding::WalkingPathComponent;
 use std::collections::HashMap;
 
+/// The positions of the characters as the client of `viewer` saw them,
+/// only the looked up ones are rewound
+pub struct CharPositions<'a> {
+    current: &'a HashMap<LocalCharEntityId, (Vec2, Team)>,
+    lag_compensation: Option<&'a LagCompensation>,
+    viewer: LocalCharEntityId,
+}
+
+impl<'a> CharPositions<'a> {
+    pub fn get(&self, char_id: &LocalCharEntityId) -> Option<(Vec2, Team)> {
+        let (pos, team) = self.current.get(char_id)?;
+        let pos = self
+            .lag_compensation
+            .and_then(|it| it.rewound_pos(self.viewer, *char_id))
+            .unwrap_or(*pos);
+        return Some((pos, *team));
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (LocalCharEntityId, (Vec2, Team))> + '_ {
+        self.current
+            .keys()
+            .filter_map(move |char_id| self.get(char_id).map(|it| (*char_id, it)))
+    }
+}
+
 pub struct CharacterStateUpdateSystem;
 
 impl<'a> System<'a> for CharacterStateUpdateSystem {
@@ -15,10 +45,16 @@ impl<'a> System<'a> for CharacterStateUpdateSystem {
         Entities<'a>,
         //        ReadStorage<'a, NpcComponent>,
         WriteStorage<'a, LocalCharStateComp>,
-        ReadStorage<'a, StaticCharDataComponent>,
+        WriteStorage<'a, StaticCharDataComponent>,
+        WriteStorage<'a, WalkingPathComponent>,
         ReadExpect<'a, EngineTime>,
+        ReadExpect<'a, CommonConfigs>,
+        ReadExpect<'a, MapWalkingInfo>,
         WriteExpect<'a, Vec<HpModificationRequest>>,
+        WriteExpect<'a, Vec<FinishCast>>,
         Write<'a, LazyUpdate>,
+        // only the server has it
+        Option<Read<'a, LagCompensation>>,
     );
 
     fn run(
@@ -26,10 +62,15 @@ impl<'a> System<'a> for CharacterStateUpdateSystem {
         (
             entities,
             mut char_state_storage,
-            static_state_storage,
+            mut static_state_storage,
+            mut walking_path_storage,
             time,
+            configs,
+            map_walking_info,
             mut hp_mod_requests,
+            mut just_finished_skill_casts,
             mut updater,
+            lag_compensation,
         ): Self::SystemData,
     ) {
         let now = time.now();
@@ -42,10 +83,9 @@ impl<'a> System<'a> for CharacterStateUpdateSystem {
             for (char_entity_id, auth_state, static_state) in
                 (&entities, &char_state_storage, &static_state_storage).join()
             {
-                // TODO2
-                //                if char_comp.state().is_dead() {
-                //                    continue;
-                //                }
+                if auth_state.state().is_dead() {
+                    continue;
+                }
                 let char_entity_id = LocalCharEntityId::new(char_entity_id);
                 // the third arg is char_comp.team, move team field from charstate first
                 char_positions.insert(char_entity_id, (auth_state.pos(), static_state.team));
@@ -53,83 +93,74 @@ impl<'a> System<'a> for CharacterStateUpdateSystem {
             char_positions
         };
 
-        for (char_entity_id, auth_state, static_state) in
-            (&entities, &mut char_state_storage, &static_state_storage).join()
+        let without_path: Vec<Entity> = (&entities, &char_state_storage, !&walking_path_storage)
+            .join()
+            .map(|(entity, _state, _path)| entity)
+            .collect();
+        for entity in without_path {
+            walking_path_storage
+                .insert(entity, WalkingPathComponent::default())
+                .unwrap();
+        }
+
+        for (char_entity_id, auth_state, static_state, walking_path) in (
+            &entities,
+            &mut char_state_storage,
+            &mut static_state_storage,
+            &mut walking_path_storage,
+        )
+            .join()
         {
             let char_entity_id = LocalCharEntityId::new(char_entity_id);
-            // pakold külön componensbe ugy a dolgokat, hogy innen be tudjam álltiani a
-            // target et None-ra ha az halott, meg a fenti position hack se kelllejn
-            // TODO2
-            //            let is_dead = *char_comp.state() == CharState::Dead;
-            //            if char_comp.hp <= 0 && !is_dead {
-            //                log::debug!("Entity has died {:?}", char_entity_id);
-            //                char_comp.set_state(CharState::Dead, char_comp.dir());
-            //                char_comp.statuses.remove_all();
-            //                char_comp
-            //                    .statuses
-            //                    .add(StatusEnum::DeathStatus(DeathStatus::new(
-            //                        time.now(),
-            //                        npc_storage.get(char_entity_id.into()).is_some(),
-            //                    )));
-            //                // remove rigid bodies from the physic simulation
-            //                collisions_resource.remove_collider_handle(char_comp.collider_handle);
-            //                physics_world.bodies.remove(char_comp.body_handle);
-            //                continue;
-            //            } else if is_dead && npc_storage.get(char_entity_id.into()).is_some() {
-            //                if let StatusEnum::DeathStatus(status) = char_comp
-            //                    .statuses
-            //                    .get_status(StatusEnumDiscriminants::DeathStatus)
-            //                    .unwrap()
-            //                {
-            //                    if status.remove_char_at.has_already_passed(time.now()) {
-            //                        entities.delete(char_entity_id.into()).unwrap();
-            //                    }
-            //                    continue;
-            //                }
-            //            }
-
-            // TODO2
-            //            char_comp.update_statuses(
-            //                char_entity_id,
-            //                &mut sys_vars,
-            //                &time,
-            //                &entities,
-            //                &mut updater,
-            //                &mut physics_world,
-            //            );
+            // dying (hp <= 0) is decided by the server, the clients get the Dead state
+            // through the snapshots
+            auth_state.update_statuses(
+                char_entity_id,
+                static_state,
+                &time,
+                &mut hp_mod_requests,
+                &configs,
+            );
 
             if *auth_state.state() == CharState::Dead {
                 continue;
             }
 
+            // ranges and hits are checked against the positions the controlling client saw
+            let all_char_data = CharPositions {
+                current: &all_char_data,
+                lag_compensation: lag_compensation.as_deref(),
+                viewer: char_entity_id,
+            };
+
             let char_pos = auth_state.pos();
             // TODO: why clone?
             // TODO2
             match auth_state.state().clone() {
-                //                CharState::CastingSkill(casting_info) => {
-                //                    if casting_info.cast_ends.has_already_passed(now) {
-                //                        log::debug!("Skill cast has finished: {:?}", casting_info.skill);
-                //                        let skill_pos = if let Some(target_entity) = casting_info
-                //                            .target_entity
-                //                            .and_then(|it| all_char_data.get(&it))
-                //                        {
-                //                            Some(target_entity.0.clone())
-                //                        } else {
-                //                            casting_info.target_area_pos
-                //                        };
-                //                        sys_vars.just_finished_skill_casts.push(FinishCast {
-                //                            skill: casting_info.skill,
-                //                            caster_pos: char_pos,
-                //                            caster_entity_id: char_entity_id,
-                //                            skill_pos,
-                //                            char_to_skill_dir: casting_info.char_to_skill_dir_when_casted,
-                //                            target_entity: casting_info.target_entity,
-                //                            caster_team: char_comp.team,
-                //                        });
-                //
-                //                        char_comp.set_state(CharState::Idle, char_comp.dir());
-                //                    }
-                //                }
+                CharState::CastingSkill(casting_info) => {
+                    if casting_info.cast_ends.has_already_passed(now) {
+                        log::debug!("Skill cast has finished: {:?}", casting_info.skill);
+                        let skill_pos = if let Some(target_entity) = casting_info
+                            .target_entity
+                            .and_then(|it| all_char_data.get(&it))
+                        {
+                            Some(target_entity.0)
+                        } else {
+                            casting_info.target_area_pos
+                        };
+                        just_finished_skill_casts.push(FinishCast {
+                            skill: casting_info.skill,
+                            caster_pos: char_pos,
+                            caster_entity_id: char_entity_id,
+                            skill_pos,
+                            char_to_skill_dir: casting_info.char_to_skill_dir_when_casted,
+                            target_entity: casting_info.target_entity,
+                            caster_team: static_state.team,
+                        });
+
+                        auth_state.set_state(CharState::Idle, auth_state.dir());
+                    }
+                }
                 CharState::Attacking {
                     target,
                     damage_occurs_at,
@@ -166,8 +197,8 @@ impl<'a> System<'a> for CharacterStateUpdateSystem {
             }
 
             // TODO2
-            if true {
-                // char_comp.can_move(now)
+            // the casting char must not start walking or go Idle until the cast has finished
+            if !auth_state.state().is_casting() && auth_state.statuses.can_move() {
                 if let Some(target) = &auth_state.target.clone() {
                     if let EntityTarget::PosWhileAttacking(pos, current_target) = target {
                         // hack end
@@ -198,8 +229,10 @@ impl<'a> System<'a> for CharacterStateUpdateSystem {
                             CharacterStateUpdateSystem::act_based_on_target(
                                 now,
                                 &all_char_data,
+                                &map_walking_info,
                                 auth_state,
                                 static_state,
+                                walking_path,
                                 &EntityTarget::Pos(*pos),
                             )
                         } else {
@@ -207,8 +240,10 @@ impl<'a> System<'a> for CharacterStateUpdateSystem {
                             CharacterStateUpdateSystem::act_based_on_target(
                                 now,
                                 &all_char_data,
+                                &map_walking_info,
                                 auth_state,
                                 static_state,
+                                walking_path,
                                 &EntityTarget::OtherEntity(current_target.unwrap()),
                             )
                         }
@@ -216,8 +251,10 @@ impl<'a> System<'a> for CharacterStateUpdateSystem {
                         CharacterStateUpdateSystem::act_based_on_target(
                             now,
                             &all_char_data,
+                            &map_walking_info,
                             auth_state,
                             static_state,
+                            walking_path,
                             target,
                         )
                     }
@@ -239,7 +276,15 @@ impl<'a> System<'a> for CharacterStateUpdateSystem {
                     // 100% movement speed = 5 units/second
                     let force =
                         dir * char_comp.calculated_attribs().movement_speed.as_f32() * (0.1);
-                    char_comp.add_pos(force);
+                    // slide along the walls if only one of the axes is blocked
+                    let pos = char_comp.pos();
+                    let allowed_force = [force, Vec2::new(force.x, 0.0), Vec2::new(0.0, force.y)]
+                        .iter()
+                        .find(|it| map_walking_info.is_walkable_pos(pos + *it))
+                        .cloned();
+                    if let Some(force) = allowed_force {
+                        char_comp.add_pos(force);
+                    }
                 }
             }
         }
@@ -248,7 +293,7 @@ impl<'a> System<'a> for CharacterStateUpdateSystem {
 
 impl CharacterStateUpdateSystem {
     pub fn get_closest_enemy_in_area(
-        char_positions: &HashMap<LocalCharEntityId, (Vec2, Team)>,
+        char_positions: &CharPositions,
         center: &Vec2,
         radius: f32,
         self_team: Team,
@@ -257,8 +302,8 @@ impl CharacterStateUpdateSystem {
         let mut ret = None;
         let mut distance = 2000.0;
         let center = v2_to_p2(center);
-        for (char_id, (pos, team)) in char_positions {
-            if *char_id == except
+        for (char_id, (pos, team)) in char_positions.iter() {
+            if char_id == except
                 || !team.is_enemy_to(self_team)
                 || (pos.x - center.x).abs() > radius
             {
@@ -267,7 +312,7 @@ impl CharacterStateUpdateSystem {
             let current_distance = nalgebra::distance(&center, &v2_to_p2(&pos));
             if current_distance <= radius && current_distance < distance {
                 distance = current_distance;
-                ret = Some(*char_id);
+                ret = Some(char_id);
             }
         }
         return ret;
@@ -275,9 +320,11 @@ impl CharacterStateUpdateSystem {
 
     fn act_based_on_target(
         now: LocalTime,
-        char_positions: &HashMap<LocalCharEntityId, (Vec2, Team)>,
+        char_positions: &CharPositions,
+        map_walking_info: &MapWalkingInfo,
         auth_state: &mut LocalCharStateComp,
         static_state: &StaticCharDataComponent,
+        walking_path: &mut WalkingPathComponent,
         target: &EntityTarget<LocalCharEntityId>,
     ) {
         let char_pos = auth_state.pos();
@@ -302,19 +349,24 @@ impl CharacterStateUpdateSystem {
                             };
                             auth_state.set_state(
                                 new_state,
-                                CharDir::determine_dir(target_pos, &char_pos),
+                                CharDir::determine_dir(&target_pos, &char_pos),
                             );
                             let attack_anim_duration = LocalTime::from(attack_anim_duration);
                             auth_state.attack_delay_ends_at = now.add(attack_anim_duration);
                         } else {
                             auth_state.set_state(CharState::Idle, auth_state.dir());
                         }
-                    } else {
+                    } else if let Some(waypoint) =
+                        walking_path.next_waypoint(map_walking_info, char_pos, target_pos)
+                    {
                         //                     move closer
                         auth_state.set_state(
-                            CharState::Walking(*target_pos),
-                            CharDir::determine_dir(target_pos, &char_pos),
+                            CharState::Walking(waypoint),
+                            CharDir::determine_dir(&waypoint, &char_pos),
                         );
+                    } else {
+                        // the target is unreachable
+                        auth_state.set_state(CharState::Idle, auth_state.dir());
                     }
                 } else {
                     auth_state.set_state(CharState::Idle, auth_state.dir());
@@ -340,10 +392,18 @@ impl CharacterStateUpdateSystem {
                         target
                     );
                     // move closer
-                    auth_state.set_state(
-                        CharState::Walking(*target_pos),
-                        CharDir::determine_dir(target_pos, &char_pos),
-                    );
+                    if let Some(waypoint) =
+                        walking_path.next_waypoint(map_walking_info, char_pos, *target_pos)
+                    {
+                        auth_state.set_state(
+                            CharState::Walking(waypoint),
+                            CharDir::determine_dir(&waypoint, &char_pos),
+                        );
+                    } else {
+                        log::debug!("Can't get closer to target: {:?}", target);
+                        auth_state.set_state(CharState::Idle, auth_state.dir());
+                        auth_state.target = None;
+                    }
                 }
             }
             EntityTarget::PosWhileAttacking(_pos, _current_target) => {}
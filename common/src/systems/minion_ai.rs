@@ -0,0 +1,161 @@
+use crate::common::{v2, v2_to_p2, Vec2};
+use crate::components::char::{
+    EntityTarget, LocalCharEntityId, LocalCharStateComp, StaticCharDataComponent, Team,
+};
+use crate::components::controller::PlayerIntention;
+use specs::prelude::*;
+
+/// The minions attack the closest enemy around them, otherwise they walk through the
+/// checkpoints towards the gate of the enemy team.
+pub struct MinionAi;
+
+impl MinionAi {
+    // from left to right
+    pub const CHECKPOINTS: [[i32; 2]; 6] = [
+        [245, -204], // right gate
+        [175, -204], // right side of fountain
+        [156, -220], // top of fountain
+        [156, -188], // bottom of fountain
+        [136, -204], // left side of fountain
+        [64, -204],  // left gate
+    ];
+
+    /// the minions leave their target when it gets farther than this
+    pub const AGGRO_RADIUS: f32 = 10.0;
+
+    /// The gate where the minions of the team are spawned
+    pub fn gate_of(team: Team) -> Vec2 {
+        let gate = if team == Team::Right {
+            MinionAi::CHECKPOINTS[0]
+        } else {
+            MinionAi::CHECKPOINTS[5]
+        };
+        return v2(gate[0] as f32, gate[1] as f32);
+    }
+
+    pub fn get_closest_enemy_in_area(
+        entities: &Entities,
+        char_state_storage: &ReadStorage<StaticCharDataComponent>,
+        auth_char_state_storage: &ReadStorage<LocalCharStateComp>,
+        center: &Vec2,
+        radius: f32,
+        self_team: Team,
+        except: LocalCharEntityId,
+    ) -> Option<LocalCharEntityId> {
+        let mut ret = None;
+        let mut distance = 2000.0;
+        let center = v2_to_p2(center);
+        for (entity_id, char_state, auth_char_state) in
+            (entities, char_state_storage, auth_char_state_storage).join()
+        {
+            let entity_id = LocalCharEntityId::from(entity_id);
+            let pos = auth_char_state.pos();
+            if entity_id == except
+                || !char_state.team.is_enemy_to(self_team)
+                || auth_char_state.state().is_dead()
+                || (pos.x - center.x).abs() > radius
+            {
+                continue;
+            }
+            let current_distance = nalgebra::distance(&center, &v2_to_p2(&pos));
+            if current_distance <= radius && current_distance < distance {
+                distance = current_distance;
+                ret = Some(entity_id);
+            }
+        }
+        return ret;
+    }
+
+    /// The Right team goes from right to left, the Left team from left to right
+    pub fn next_checkpoint(team: Team, pos: Vec2) -> Vec2 {
+        let next_checkpoint = if team == Team::Right {
+            let mut next_checkpoint = MinionAi::CHECKPOINTS[5];
+            for checkpoint in MinionAi::CHECKPOINTS.iter() {
+                if checkpoint[0] < pos.x as i32 {
+                    next_checkpoint = *checkpoint;
+                    break;
+                }
+            }
+            next_checkpoint
+        } else {
+            let mut next_checkpoint = MinionAi::CHECKPOINTS[0];
+            for checkpoint in MinionAi::CHECKPOINTS.iter().rev() {
+                if checkpoint[0] > pos.x as i32 {
+                    next_checkpoint = *checkpoint;
+                    break;
+                }
+            }
+            next_checkpoint
+        };
+        return v2(next_checkpoint[0] as f32, next_checkpoint[1] as f32);
+    }
+
+    /// None if the minion does not exist anymore
+    pub fn decide_intention(
+        entities: &Entities,
+        char_state_storage: &ReadStorage<StaticCharDataComponent>,
+        auth_char_state_storage: &ReadStorage<LocalCharStateComp>,
+        minion_id: LocalCharEntityId,
+    ) -> Option<PlayerIntention> {
+        let char_state = char_state_storage.get(minion_id.into())?;
+        let auth_char_state = auth_char_state_storage.get(minion_id.into())?;
+        let current_target_id = match auth_char_state.target {
+            Some(EntityTarget::OtherEntity(target_id)) => auth_char_state_storage
+                .get(target_id.into())
+                .filter(|target| {
+                    let current_distance = nalgebra::distance(
+                        &v2_to_p2(&target.pos()),
+                        &v2_to_p2(&auth_char_state.pos()),
+                    );
+                    !target.state().is_dead() && current_distance <= MinionAi::AGGRO_RADIUS
+                })
+                .map(|_target| target_id),
+            _ => None,
+        };
+        if let Some(target_id) = current_target_id {
+            return Some(PlayerIntention::Attack(target_id));
+        }
+        let maybe_enemy = MinionAi::get_closest_enemy_in_area(
+            entities,
+            char_state_storage,
+            auth_char_state_storage,
+            &auth_char_state.pos(),
+            MinionAi::AGGRO_RADIUS,
+            char_state.team,
+            minion_id,
+        );
+        return Some(match maybe_enemy {
+            Some(target_id) => PlayerIntention::Attack(target_id),
+            None => PlayerIntention::MoveTo(MinionAi::next_checkpoint(
+                char_state.team,
+                auth_char_state.pos(),
+            )),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_minions_walk_towards_the_enemy_gate() {
+        assert_eq!(
+            v2(175.0, -204.0),
+            MinionAi::next_checkpoint(Team::Right, MinionAi::gate_of(Team::Right))
+        );
+        assert_eq!(
+            v2(136.0, -204.0),
+            MinionAi::next_checkpoint(Team::Left, MinionAi::gate_of(Team::Left))
+        );
+        assert_eq!(
+            v2(64.0, -204.0),
+            MinionAi::next_checkpoint(Team::Right, v2(100.0, -204.0))
+        );
+        // the last checkpoint is kept when the gate has been reached
+        assert_eq!(
+            v2(245.0, -204.0),
+            MinionAi::next_checkpoint(Team::Left, v2(250.0, -204.0))
+        );
+    }
+}
@@ -1,2 +1,4 @@
 pub mod char_state_sys;
 pub mod intention_applier;
+pub mod minion_ai;
+pub mod turret_ai;
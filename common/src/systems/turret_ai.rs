@@ -0,0 +1,149 @@
+use crate::common::{v2_to_p2, Vec2};
+use crate::components::char::{
+    EntityTarget, LocalCharEntityId, LocalCharStateComp, StaticCharDataComponent,
+};
+use crate::components::controller::PlayerIntention;
+use crate::systems::minion_ai::MinionAi;
+use specs::prelude::*;
+
+/// The turrets and the guards attack the enemies in their attack range, but they never
+/// leave their post to chase them.
+pub struct TurretAi;
+
+impl TurretAi {
+    /// The preferred target of the turret is attacked first if it is in range.
+    /// None if there is nothing to do.
+    pub fn decide_intention(
+        entities: &Entities,
+        char_state_storage: &ReadStorage<StaticCharDataComponent>,
+        auth_char_state_storage: &ReadStorage<LocalCharStateComp>,
+        turret_id: LocalCharEntityId,
+        preferred_target: Option<LocalCharEntityId>,
+        post: Vec2,
+    ) -> Option<PlayerIntention> {
+        let char_state = char_state_storage.get(turret_id.into())?;
+        let auth_char_state = auth_char_state_storage.get(turret_id.into())?;
+        // the same distance as the one which CharacterStateUpdateSystem attacks from
+        let radius = auth_char_state.calculated_attribs().attack_range.as_f32() * 2.0;
+        let in_range = |target_id: LocalCharEntityId| {
+            auth_char_state_storage
+                .get(target_id.into())
+                .map(|target| {
+                    let current_distance = nalgebra::distance(
+                        &v2_to_p2(&target.pos()),
+                        &v2_to_p2(&auth_char_state.pos()),
+                    );
+                    !target.state().is_dead() && current_distance <= radius
+                })
+                .unwrap_or(false)
+        };
+        let current_target_id = match auth_char_state.target {
+            Some(EntityTarget::OtherEntity(target_id)) => Some(target_id),
+            _ => None,
+        };
+        let target_id = preferred_target
+            .filter(|it| in_range(*it))
+            .or(current_target_id.filter(|it| in_range(*it)))
+            .or_else(|| {
+                MinionAi::get_closest_enemy_in_area(
+                    entities,
+                    char_state_storage,
+                    auth_char_state_storage,
+                    &auth_char_state.pos(),
+                    radius,
+                    char_state.team,
+                    turret_id,
+                )
+            });
+        return match target_id {
+            Some(target_id) => Some(PlayerIntention::Attack(target_id)),
+            // forget the target which has left the range and go back
+            None if auth_char_state.target.is_some()
+                || nalgebra::distance(&v2_to_p2(&post), &v2_to_p2(&auth_char_state.pos()))
+                    > 0.2 =>
+            {
+                Some(PlayerIntention::MoveTo(post))
+            }
+            None => None,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::char_attr::CharAttributes;
+    use crate::common::v2;
+    use crate::components::char::{CharOutlook, CharType, JobId, MonsterId, Team};
+
+    fn create_char(world: &mut World, pos: Vec2, team: Team) -> LocalCharEntityId {
+        // its attack range is 2.0
+        let state = LocalCharStateComp::new(pos, CharAttributes::OTHER_ATTRIBUTES);
+        let static_data = StaticCharDataComponent::new(
+            "Guard".to_owned(),
+            team,
+            CharType::Guard,
+            JobId::Guard,
+            CharOutlook::Monster(MonsterId::Dimik),
+        );
+        LocalCharEntityId::new(world.create_entity().with(state).with(static_data).build())
+    }
+
+    fn decide(
+        world: &World,
+        turret_id: LocalCharEntityId,
+        preferred_target: Option<LocalCharEntityId>,
+        post: Vec2,
+    ) -> Option<PlayerIntention> {
+        TurretAi::decide_intention(
+            &world.entities(),
+            &world.read_storage(),
+            &world.read_storage(),
+            turret_id,
+            preferred_target,
+            post,
+        )
+    }
+
+    #[test]
+    fn the_turrets_attack_only_in_their_range_and_prefer_their_target() {
+        let mut world = World::new();
+        world.register::<LocalCharStateComp>();
+        world.register::<StaticCharDataComponent>();
+        let post = v2(0.0, 0.0);
+        let turret = create_char(&mut world, post, Team::Left);
+        let _ally = create_char(&mut world, v2(0.5, 0.0), Team::Left);
+        let far_enemy = create_char(&mut world, v2(5.0, 0.0), Team::Right);
+        assert!(decide(&world, turret, Some(far_enemy), post).is_none());
+
+        let close_enemy = create_char(&mut world, v2(1.0, 0.0), Team::Right);
+        let other_enemy = create_char(&mut world, v2(0.0, 1.5), Team::Right);
+        assert!(matches!(
+            decide(&world, turret, None, post),
+            Some(PlayerIntention::Attack(target)) if target == close_enemy
+        ));
+        assert!(matches!(
+            decide(&world, turret, Some(other_enemy), post),
+            Some(PlayerIntention::Attack(target)) if target == other_enemy
+        ));
+        // the preferred target out of range is ignored
+        assert!(matches!(
+            decide(&world, turret, Some(far_enemy), post),
+            Some(PlayerIntention::Attack(target)) if target == close_enemy
+        ));
+    }
+
+    #[test]
+    fn the_guards_go_back_to_their_post() {
+        let mut world = World::new();
+        world.register::<LocalCharStateComp>();
+        world.register::<StaticCharDataComponent>();
+        let guard = create_char(&mut world, v2(3.0, 0.0), Team::Left);
+        let _far_enemy = create_char(&mut world, v2(10.0, 0.0), Team::Right);
+        assert!(matches!(
+            decide(&world, guard, None, v2(0.0, 0.0)),
+            Some(PlayerIntention::MoveTo(pos)) if pos == v2(0.0, 0.0)
+        ));
+        assert!(decide(&world, guard, None, v2(3.0, 0.0)).is_none());
+    }
+}
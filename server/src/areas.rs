@@ -0,0 +1,272 @@
+//! Areas which affect the characters inside them in every interval, e.g. spawned by the
+//! `spawn_area` console command. They are removed together with their caster, the areas
+//! without a caster (e.g. from init.cmd) are team-neutral. The skills spawn areas which
+//! disappear after a while, e.g. the single strikes of Lightning.
+use crate::statuses::create_status_by_name;
+use rustarok_common::attack::{
+    ApplyForceComponent, DamageDisplayType, HpModificationRequest, HpModificationType, WeaponType,
+};
+use rustarok_common::common::{rotate_vec2, EngineTime, LocalTime, Percentage, Vec2};
+use rustarok_common::components::char::{
+    LocalCharEntityId, LocalCharStateComp, StaticCharDataComponent, Team,
+};
+use rustarok_common::components::status::attrib_mod::WalkingSpeedModifierStatus;
+use rustarok_common::components::status::status::{ApplyStatusComponent, StatusEnum};
+use rustarok_common::components::status::stun::StunStatus;
+use specs::prelude::*;
+
+pub enum AreaEffect {
+    Heal(u32),
+    Damage(u32),
+    SpellDamage(u32),
+    /// only the enemies of the area are stunned
+    Stun {
+        duration_seconds: f32,
+    },
+    /// the enemies are pushed away from the center of the area, `force` is in units per second
+    PushBack {
+        force: f32,
+        duration_seconds: f32,
+    },
+    /// only the enemies of the area are slowed
+    Slow {
+        modifier: Percentage,
+        duration_seconds: f32,
+    },
+    Status {
+        name: String,
+        duration_ms: i32,
+        value: i32,
+    },
+}
+
+#[derive(Component)]
+pub struct AreaEffectComponent {
+    pub caster_entity_id: Option<LocalCharEntityId>,
+    /// the team of the caster when the area was spawned, Neutral without a caster
+    pub team: Team,
+    pub center: Vec2,
+    pub shape: AreaShape,
+    pub effect: AreaEffect,
+    pub interval_seconds: f32,
+    /// it can be in the future, e.g. for delayed explosions
+    pub next_effect_at: LocalTime,
+    /// the area is removed after its effect in the frame it has passed, None is forever
+    pub die_at: Option<LocalTime>,
+}
+
+#[derive(Clone)]
+pub enum AreaShape {
+    Rectangle {
+        half_extents: Vec2,
+    },
+    /// its y axis points to the direction the rectangle was rotated to
+    RotatedRectangle {
+        half_extents: Vec2,
+        rotation_rad: f32,
+    },
+    Circle {
+        radius: f32,
+    },
+}
+
+impl AreaShape {
+    /// The rectangle's y axis points to `dir`, e.g. to the direction of a skill
+    pub fn rotated_rectangle(half_extents: Vec2, dir: &Vec2) -> AreaShape {
+        return AreaShape::RotatedRectangle {
+            half_extents,
+            rotation_rad: dir_to_rotation_rad(dir),
+        };
+    }
+
+    pub fn contains(&self, center: Vec2, pos: Vec2) -> bool {
+        return match self {
+            AreaShape::Rectangle { half_extents } => {
+                (pos.x - center.x).abs() <= half_extents.x
+                    && (pos.y - center.y).abs() <= half_extents.y
+            }
+            AreaShape::RotatedRectangle {
+                half_extents,
+                rotation_rad,
+            } => {
+                // transform the position into the coordinate system of the rectangle
+                let local_pos = rotate_vec2(-rotation_rad, &(pos - center));
+                local_pos.x.abs() <= half_extents.x && local_pos.y.abs() <= half_extents.y
+            }
+            AreaShape::Circle { radius } => (pos - center).magnitude() <= *radius,
+        };
+    }
+}
+
+/// The rotation which turns the y axis to `dir`
+pub fn dir_to_rotation_rad(dir: &Vec2) -> f32 {
+    let angle = dir.angle(&Vec2::y());
+    return if dir.x > 0.0 { angle } else { -angle };
+}
+
+pub fn apply_area_effects(ecs_world: &mut specs::World) {
+    let now = ecs_world.read_resource::<EngineTime>().now();
+    let mut orphan_areas = Vec::new();
+    let mut expired_areas = Vec::new();
+    {
+        let entities = ecs_world.entities();
+        let mut area_storage = ecs_world.write_storage::<AreaEffectComponent>();
+        let char_state_storage = ecs_world.read_storage::<LocalCharStateComp>();
+        let static_char_data_storage = ecs_world.read_storage::<StaticCharDataComponent>();
+        let mut hp_mod_requests = ecs_world.write_resource::<Vec<HpModificationRequest>>();
+        let mut new_statuses = ecs_world.write_resource::<Vec<ApplyStatusComponent>>();
+        let mut forces = ecs_world.write_resource::<Vec<ApplyForceComponent>>();
+        for (area_entity_id, area) in (&entities, &mut area_storage).join() {
+            // the area is the source of its own effects without a caster
+            let src_entity = match area.caster_entity_id {
+                Some(caster_entity_id) => {
+                    if static_char_data_storage
+                        .get(caster_entity_id.into())
+                        .is_none()
+                    {
+                        orphan_areas.push(area_entity_id);
+                        continue;
+                    }
+                    caster_entity_id
+                }
+                None => LocalCharEntityId::from(area_entity_id),
+            };
+            if area
+                .die_at
+                .map(|die_at| die_at.has_already_passed(now))
+                .unwrap_or(false)
+            {
+                expired_areas.push(area_entity_id);
+            }
+            if !area.next_effect_at.has_already_passed(now) {
+                continue;
+            }
+            area.next_effect_at = now.add_seconds(area.interval_seconds);
+            for (char_entity_id, char_state, static_data) in
+                (&entities, &char_state_storage, &static_char_data_storage).join()
+            {
+                if !area.shape.contains(area.center, char_state.pos())
+                    || !char_state.state().is_alive()
+                {
+                    continue;
+                }
+                let char_entity_id = LocalCharEntityId::from(char_entity_id);
+                match &area.effect {
+                    AreaEffect::Heal(value) => hp_mod_requests.push(HpModificationRequest {
+                        src_entity,
+                        dst_entity: char_entity_id,
+                        typ: HpModificationType::Heal(*value),
+                    }),
+                    AreaEffect::Damage(value) => hp_mod_requests.push(HpModificationRequest {
+                        src_entity,
+                        dst_entity: char_entity_id,
+                        typ: HpModificationType::BasicDamage(
+                            *value,
+                            DamageDisplayType::SingleNumber,
+                            WeaponType::Sword,
+                        ),
+                    }),
+                    AreaEffect::SpellDamage(value) => hp_mod_requests.push(HpModificationRequest {
+                        src_entity,
+                        dst_entity: char_entity_id,
+                        typ: HpModificationType::SpellDamage(
+                            *value,
+                            DamageDisplayType::SingleNumber,
+                        ),
+                    }),
+                    AreaEffect::Stun { duration_seconds } => {
+                        if area.team.can_attack(static_data.team) {
+                            new_statuses.push(ApplyStatusComponent {
+                                source_entity_id: src_entity,
+                                target_entity_id: char_entity_id,
+                                status: StatusEnum::StunStatus(StunStatus::new(
+                                    src_entity,
+                                    now,
+                                    *duration_seconds,
+                                )),
+                            });
+                        }
+                    }
+                    AreaEffect::PushBack {
+                        force,
+                        duration_seconds,
+                    } => {
+                        if area.team.can_attack(static_data.team) {
+                            let push_dir = char_state.pos() - area.center;
+                            let push_dir = if push_dir.x == 0.0 && push_dir.y == 0.0 {
+                                Vec2::x()
+                            } else {
+                                push_dir.normalize()
+                            };
+                            forces.push(ApplyForceComponent {
+                                src_entity,
+                                dst_entity: char_entity_id,
+                                force: push_dir * *force,
+                                duration: *duration_seconds,
+                            });
+                        }
+                    }
+                    AreaEffect::Slow {
+                        modifier,
+                        duration_seconds,
+                    } => {
+                        if area.team.can_attack(static_data.team) {
+                            new_statuses.push(ApplyStatusComponent {
+                                source_entity_id: src_entity,
+                                target_entity_id: char_entity_id,
+                                status: StatusEnum::WalkingSpeedModifierStatus(
+                                    WalkingSpeedModifierStatus::new(
+                                        now,
+                                        *modifier,
+                                        *duration_seconds,
+                                    ),
+                                ),
+                            });
+                        }
+                    }
+                    AreaEffect::Status {
+                        name,
+                        duration_ms,
+                        value,
+                    } => {
+                        // the name has been validated when the area was spawned
+                        if let Ok(status) = create_status_by_name(
+                            name,
+                            src_entity,
+                            area.team,
+                            now,
+                            *duration_ms,
+                            *value,
+                        ) {
+                            new_statuses.push(ApplyStatusComponent {
+                                source_entity_id: src_entity,
+                                target_entity_id: char_entity_id,
+                                status,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+    for area_entity_id in orphan_areas {
+        log::debug!("The caster of area {:?} has gone", area_entity_id);
+        if let Err(e) = ecs_world.delete_entity(area_entity_id) {
+            log::error!("Could not remove {:?}: {:?}", area_entity_id, e);
+        }
+    }
+    for area_entity_id in expired_areas {
+        if let Err(e) = ecs_world.delete_entity(area_entity_id) {
+            log::error!("Could not remove {:?}: {:?}", area_entity_id, e);
+        }
+    }
+}
+
+/// The team of a caster-less area, the HP modifications and statuses it causes are
+/// validated against it
+pub fn get_area_team(
+    area_storage: &ReadStorage<AreaEffectComponent>,
+    src_entity: LocalCharEntityId,
+) -> Option<Team> {
+    return area_storage.get(src_entity.into()).map(|area| area.team);
+}
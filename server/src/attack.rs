@@ -0,0 +1,76 @@
+use crate::areas::{get_area_team, AreaEffectComponent};
+use rustarok_common::attack::HpModificationRequest;
+use rustarok_common::common::EngineTime;
+use rustarok_common::components::char::{LocalCharStateComp, StaticCharDataComponent};
+use specs::prelude::*;
+
+/// Turns the HpModificationRequests which were collected during the frame
+/// (basic attacks, skills, statuses, areas) into HP changes.
+/// Requests generated by the statuses while processing them will be processed
+/// only in the next frame.
+pub fn apply_hp_modifications(ecs_world: &mut specs::World) {
+    let hp_mod_requests = std::mem::replace(
+        &mut *ecs_world.write_resource::<Vec<HpModificationRequest>>(),
+        Vec::with_capacity(128),
+    );
+    let now = ecs_world.read_resource::<EngineTime>().now();
+    let mut char_state_storage = ecs_world.write_storage::<LocalCharStateComp>();
+    let static_char_data_storage = ecs_world.read_storage::<StaticCharDataComponent>();
+    let area_storage = ecs_world.read_storage::<AreaEffectComponent>();
+    let mut new_hp_mod_requests = ecs_world.write_resource::<Vec<HpModificationRequest>>();
+
+    for hp_mod_req in hp_mod_requests.into_iter() {
+        let attacker_id = hp_mod_req.src_entity;
+        let attacked_id = hp_mod_req.dst_entity;
+        let src_team = static_char_data_storage
+            .get(attacker_id.into())
+            .map(|it| it.team)
+            .or_else(|| get_area_team(&area_storage, attacker_id));
+        let is_valid = match (
+            src_team,
+            static_char_data_storage.get(attacked_id.into()),
+            char_state_storage.get(attacked_id.into()),
+        ) {
+            (Some(src_team), Some(dst_static_data), Some(dst_auth_state)) => {
+                dst_auth_state.state().is_alive()
+                    && hp_mod_req.is_allowed_between(src_team, dst_static_data.team)
+            }
+            _ => false,
+        };
+        if !is_valid {
+            log::debug!("Invalid hp_mod_req: {:?}", hp_mod_req);
+            continue;
+        }
+
+        let hp_mod_req_result = {
+            let attacked_auth_state = char_state_storage.get_mut(attacked_id.into()).unwrap();
+            let hp_mod_req_result =
+                hp_mod_req.apply_armor(attacked_auth_state.calculated_attribs());
+            // Allow statuses to affect incoming damages/heals
+            let hp_mod_req_result = attacked_auth_state
+                .statuses
+                .hp_mod_is_calculated_but_not_applied_yet(
+                    hp_mod_req_result,
+                    &mut new_hp_mod_requests,
+                );
+            attacked_auth_state.apply_hp_mod(&hp_mod_req_result, now);
+            attacked_auth_state.statuses.hp_mod_has_been_applied_on_me(
+                attacked_id,
+                &hp_mod_req_result,
+                &mut new_hp_mod_requests,
+            );
+            hp_mod_req_result
+        };
+        log::trace!("Hp mod has been applied: {:?}", hp_mod_req_result);
+
+        if let Some(attacker_auth_state) = char_state_storage.get_mut(attacker_id.into()) {
+            attacker_auth_state
+                .statuses
+                .hp_mod_has_been_applied_on_enemy(
+                    attacker_id,
+                    &hp_mod_req_result,
+                    &mut new_hp_mod_requests,
+                );
+        }
+    }
+}
@@ -1,20 +1,354 @@
+use rustarok_common::common::Vec2;
+use rustarok_common::components::char::{JobId, Team};
 use rustarok_common::config::{CommonConfigs, DevConfigStats, SkillsConfig};
+use rustarok_common::console::PermissionLevel;
 use serde::Deserialize;
 use serde::Serialize;
+use std::net::IpAddr;
+use std::time::Duration;
 
-#[derive(Debug, Deserialize)]
-pub struct ServerConfig {}
+#[derive(Clone, Debug, Deserialize)]
+pub struct ServerConfig {
+    pub network: NetworkConfig,
+    pub respawn: RespawnConfig,
+    pub interest: InterestConfig,
+    pub lag_compensation: LagCompensationConfig,
+    #[serde(rename = "match")]
+    pub game_match: MatchConfig,
+    pub rooms: RoomsConfig,
+    pub replay: ReplayConfig,
+    pub console: ConsoleConfig,
+    pub operator_console: OperatorConsoleConfig,
+    pub telemetry: TelemetryConfig,
+    pub input_validation: InputValidationConfig,
+    pub minions: MinionsConfig,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct NetworkConfig {
+    /// both the TCP and the UDP listeners are bound to this address
+    pub bind_address: IpAddr,
+    /// connections over it are refused
+    pub max_players: usize,
+    /// simulation ticks per second
+    pub tick_rate: usize,
+    /// snapshots per second, at most `tick_rate`
+    pub snapshot_rate: usize,
+    /// clients which haven't sent anything for this long are disconnected
+    pub idle_timeout_seconds: f32,
+    /// the character of a disconnected player is kept for this long, so she can reconnect
+    pub reconnect_grace_period_seconds: f32,
+}
+
+impl NetworkConfig {
+    pub fn tick_duration(&self) -> Duration {
+        Duration::from_millis(1000 / self.tick_rate as u64)
+    }
+
+    /// a snapshot is sent in every Nth tick
+    pub fn snapshot_interval_ticks(&self) -> u64 {
+        (self.tick_rate / self.snapshot_rate).max(1) as u64
+    }
+
+    pub fn idle_timeout(&self) -> Duration {
+        Duration::from_secs_f32(self.idle_timeout_seconds)
+    }
+
+    pub fn reconnect_grace_period(&self) -> Duration {
+        Duration::from_secs_f32(self.reconnect_grace_period_seconds)
+    }
+
+    /// the clients send at most one intention per tick
+    pub fn intentions_per_second(&self, input_validation: &InputValidationConfig) -> f32 {
+        self.tick_rate as f32 * input_validation.intention_rate_headroom
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RespawnConfig {
+    pub delay_seconds: f32,
+    pub left_team_spawn_pos: Vec2,
+    pub right_team_spawn_pos: Vec2,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct InterestConfig {
+    /// clients receive only the entities which are closer to their character than this
+    pub view_distance: f32,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct LagCompensationConfig {
+    /// attacks are validated against positions at most this old
+    pub max_rewind_ms: u32,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RoomsConfig {
+    /// including the default room
+    pub max_rooms: usize,
+    pub max_players_per_room: usize,
+    /// the maps the players can create rooms on, the spawn points are the same on every map
+    pub maps: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ConsoleConfig {
+    /// the permission level of the clients when they connect
+    pub default_permission: PermissionLevel,
+    /// the `admin <password>` command raises the level of the client to Admin, empty disables it
+    pub admin_password: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct OperatorConsoleConfig {
+    /// the commands are read from the standard input too
+    pub stdin: bool,
+    /// path of a Unix domain socket, empty disables it
+    pub unix_socket: String,
+    /// a port on the loopback interface, 0 disables it
+    pub tcp_port: u16,
+    /// the first line sent over the TCP port, it is not accepted without it
+    pub secret: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct TelemetryConfig {
+    /// `http://127.0.0.1:<metrics_port>/metrics` in Prometheus text format, 0 disables it
+    pub metrics_port: u16,
+    /// the served metrics are updated this often, and a CSV row is written
+    pub interval_seconds: f32,
+    /// empty disables it
+    pub csv_file: String,
+    /// the file is renamed to `<csv_file>.1` when it would get larger
+    pub csv_max_bytes: u64,
+    /// including the current one
+    pub csv_max_files: usize,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct InputValidationConfig {
+    /// the intentions above `tick_rate * intention_rate_headroom` per second are dropped
+    pub intention_rate_headroom: f32,
+    /// this many intentions can be sent at once above the rate
+    pub intention_burst: u32,
+    /// the client is kicked when this many of its intentions are rejected in the window
+    pub kick_after_rejections: u32,
+    pub rejection_window_seconds: f32,
+}
+
+impl InputValidationConfig {
+    /// 0 `kick_after_rejections` never kicks
+    pub fn should_kick(&self, rejections: u32) -> bool {
+        self.kick_after_rejections > 0 && rejections >= self.kick_after_rejections
+    }
+
+    pub fn rejection_window(&self) -> Duration {
+        Duration::from_secs_f32(self.rejection_window_seconds)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct MinionsConfig {
+    /// a wave is spawned at the gate of the team this often, None disables the waves
+    pub left_team_wave_interval_seconds: Option<f32>,
+    pub right_team_wave_interval_seconds: Option<f32>,
+    /// melee and ranged minions alternately
+    pub minions_per_wave: usize,
+    /// a wave is smaller or skipped if the team would have more minions than this
+    pub max_minions_per_team: usize,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ReplayConfig {
+    /// every room is recorded from its creation, see `rustarok-server replay <file>`
+    pub record: bool,
+    pub dir: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct MatchConfig {
+    /// the countdown starts when this many players are in the game, at least one in both teams
+    pub min_players: usize,
+    pub countdown_seconds: f32,
+    pub post_game_seconds: f32,
+    /// a chosen team is refused if it would have more players than the other plus this
+    pub max_team_imbalance: usize,
+    /// the first team reaching it wins
+    pub kill_limit: Option<u32>,
+    /// the team with more kills wins when it is over
+    pub time_limit_seconds: Option<f32>,
+    /// the team whose character with this job dies loses (e.g. "Barricade")
+    pub structure_job: Option<JobId>,
+}
+
+impl RespawnConfig {
+    pub fn get_spawn_pos(&self, team: Team) -> Vec2 {
+        return match team {
+            Team::Left => self.left_team_spawn_pos,
+            _ => self.right_team_spawn_pos,
+        };
+    }
+}
 
 impl ServerConfig {
-    pub fn new(filename: &str) -> Result<Self, config::ConfigError> {
+    /// Returns the content of the file too, so it can be recorded
+    pub fn read(filename: &str) -> Result<(String, Self), String> {
+        let text = std::fs::read_to_string(filename).map_err(|e| e.to_string())?;
+        let server_config = ServerConfig::from_toml(&text).map_err(|e| e.to_string())?;
+        return Ok((text, server_config));
+    }
+
+    /// The replays are simulated with the configs they were recorded with
+    pub fn from_toml(text: &str) -> Result<Self, config::ConfigError> {
         let mut s = config::Config::new();
-        s.merge(config::File::with_name(filename))?;
-        return s.try_into();
+        s.merge(config::File::from_str(text, config::FileFormat::Toml))?;
+        return s.try_into::<ServerConfig>()?.validated();
+    }
+
+    /// The values which the simulation can not run with are refused
+    fn validated(self) -> Result<Self, config::ConfigError> {
+        let network = &self.network;
+        if network.tick_rate == 0 || network.tick_rate > 1000 {
+            return Err(config::ConfigError::Message(format!(
+                "network.tick_rate must be between 1 and 1000, it is {}",
+                network.tick_rate
+            )));
+        }
+        if network.snapshot_rate == 0 || network.snapshot_rate > network.tick_rate {
+            return Err(config::ConfigError::Message(format!(
+                "network.snapshot_rate must be between 1 and network.tick_rate ({}), it is {}",
+                network.tick_rate, network.snapshot_rate
+            )));
+        }
+        let headroom = self.input_validation.intention_rate_headroom;
+        if !headroom.is_finite() || headroom < 1.0 {
+            return Err(config::ConfigError::Message(format!(
+                "input_validation.intention_rate_headroom must be at least 1.0, it is {}",
+                headroom
+            )));
+        }
+        let durations = [
+            ("network.idle_timeout_seconds", network.idle_timeout_seconds),
+            (
+                "network.reconnect_grace_period_seconds",
+                network.reconnect_grace_period_seconds,
+            ),
+            ("respawn.delay_seconds", self.respawn.delay_seconds),
+            ("match.countdown_seconds", self.game_match.countdown_seconds),
+            ("match.post_game_seconds", self.game_match.post_game_seconds),
+            (
+                "telemetry.interval_seconds",
+                self.telemetry.interval_seconds,
+            ),
+            (
+                "input_validation.rejection_window_seconds",
+                self.input_validation.rejection_window_seconds,
+            ),
+        ];
+        let optional_durations = [
+            (
+                "match.time_limit_seconds",
+                self.game_match.time_limit_seconds,
+            ),
+            (
+                "minions.left_team_wave_interval_seconds",
+                self.minions.left_team_wave_interval_seconds,
+            ),
+            (
+                "minions.right_team_wave_interval_seconds",
+                self.minions.right_team_wave_interval_seconds,
+            ),
+        ];
+        let all_durations = durations.iter().cloned().chain(
+            optional_durations
+                .iter()
+                .filter_map(|(name, seconds)| seconds.map(|seconds| (*name, seconds))),
+        );
+        for (name, seconds) in all_durations {
+            // `Duration::from_secs_f32` panics on them
+            if !seconds.is_finite() || seconds < 0.0 {
+                return Err(config::ConfigError::Message(format!(
+                    "{} must be a non-negative number, it is {}",
+                    name, seconds
+                )));
+            }
+        }
+        return Ok(self);
     }
 }
 
-pub fn load_common_configs(filename: &str) -> Result<CommonConfigs, config::ConfigError> {
+pub fn parse_common_configs(text: &str) -> Result<CommonConfigs, config::ConfigError> {
     let mut s = config::Config::new();
-    s.merge(config::File::with_name(filename))?;
+    s.merge(config::File::from_str(text, config::FileFormat::Toml))?;
     return s.try_into();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_rates(
+        tick_rate: &str,
+        snapshot_rate: &str,
+    ) -> Result<ServerConfig, config::ConfigError> {
+        let text = include_str!("../server-conf.toml")
+            .replace("tick_rate = 30", &format!("tick_rate = {}", tick_rate))
+            .replace(
+                "snapshot_rate = 30",
+                &format!("snapshot_rate = {}", snapshot_rate),
+            );
+        return ServerConfig::from_toml(&text);
+    }
+
+    #[test]
+    fn the_rates_are_validated() {
+        assert!(with_rates("30", "30").is_ok());
+        assert!(with_rates("60", "20").is_ok());
+        assert!(with_rates("0", "0").is_err());
+        assert!(with_rates("30", "0").is_err());
+        assert!(with_rates("30", "60").is_err());
+        assert!(with_rates("2000", "30").is_err());
+    }
+
+    #[test]
+    fn the_intention_rate_follows_the_tick_rate() {
+        let config = with_rates("60", "20").unwrap();
+        let rate = config
+            .network
+            .intentions_per_second(&config.input_validation);
+        assert!(rate >= 60.0 && rate < 70.0);
+
+        let text = include_str!("../server-conf.toml");
+        assert!(text.contains("intention_rate_headroom = 1.1"));
+        let with_headroom = |headroom: &str| {
+            ServerConfig::from_toml(&text.replace(
+                "intention_rate_headroom = 1.1",
+                &format!("intention_rate_headroom = {}", headroom),
+            ))
+        };
+        assert!(with_headroom("1.0").is_ok());
+        assert!(with_headroom("0.9").is_err());
+        assert!(with_headroom("nan").is_err());
+    }
+
+    #[test]
+    fn negative_and_infinite_durations_are_refused() {
+        let text = include_str!("../server-conf.toml");
+        for (valid, invalid) in &[
+            ("idle_timeout_seconds = ", "idle_timeout_seconds = -1.0 #"),
+            (
+                "reconnect_grace_period_seconds = ",
+                "reconnect_grace_period_seconds = inf #",
+            ),
+            (
+                "rejection_window_seconds = ",
+                "rejection_window_seconds = nan #",
+            ),
+            ("delay_seconds = ", "delay_seconds = -5.0 #"),
+        ] {
+            assert!(text.contains(valid));
+            assert!(ServerConfig::from_toml(&text.replacen(valid, invalid, 1)).is_err());
+        }
+    }
+}
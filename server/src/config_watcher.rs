@@ -0,0 +1,208 @@
+//! config-runtime.toml is reloaded when it changes, the new configs are applied in every
+//! room and sent to their clients. An invalid file is reported and the current configs stay.
+use crate::replay::{record_event, ReplayEvent};
+use crate::room::{Room, RoomAssets};
+use crate::server_config::parse_common_configs;
+use crate::{OutPacketCollector, PacketTarget};
+use crossbeam_channel::Receiver;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use rustarok_common::components::char::{LocalCharStateComp, StaticCharDataComponent};
+use rustarok_common::config::CommonConfigs;
+use rustarok_common::packets::from_server::FromServerPacket;
+use specs::prelude::*;
+use std::ffi::OsStr;
+use std::path::Path;
+
+pub const COMMON_CONFIGS_FILE: &str = "config-runtime.toml";
+
+pub struct ConfigWatcher {
+    // the watching stops when it is dropped
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<notify::Event>>,
+}
+
+impl ConfigWatcher {
+    pub fn start() -> Result<ConfigWatcher, String> {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let mut watcher: RecommendedWatcher =
+            Watcher::new_immediate(tx).map_err(|e| format!("{:?}", e))?;
+        // the directory is watched, because editors often replace the file instead of writing it
+        watcher
+            .watch(".", RecursiveMode::NonRecursive)
+            .map_err(|e| format!("{:?}", e))?;
+        log::info!("Watching {} for changes", COMMON_CONFIGS_FILE);
+        return Ok(ConfigWatcher {
+            _watcher: watcher,
+            events: rx,
+        });
+    }
+
+    /// A save usually generates more events, they are reported as one change
+    pub fn has_changed(&self) -> bool {
+        let mut changed = false;
+        for event in self.events.try_iter() {
+            match event {
+                Ok(event) => {
+                    let is_write = match event.kind {
+                        EventKind::Access(_) => false,
+                        _ => true,
+                    };
+                    changed |= is_write
+                        && event
+                            .paths
+                            .iter()
+                            .any(|path| path.file_name() == Some(OsStr::new(COMMON_CONFIGS_FILE)));
+                }
+                Err(e) => {
+                    log::warn!("Watching {} has failed: {:?}", COMMON_CONFIGS_FILE, e);
+                }
+            }
+        }
+        return changed;
+    }
+}
+
+/// Returns the content of the file too, so it can be recorded
+pub fn read_common_configs() -> Result<(String, CommonConfigs), String> {
+    return read_common_configs_from(Path::new(COMMON_CONFIGS_FILE));
+}
+
+fn read_common_configs_from(path: &Path) -> Result<(String, CommonConfigs), String> {
+    let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let configs = parse_common_configs(&text).map_err(|e| e.to_string())?;
+    return Ok((text, configs));
+}
+
+pub fn reload_common_configs(rooms: &mut [Room], room_assets: &mut RoomAssets) {
+    reload_common_configs_from(Path::new(COMMON_CONFIGS_FILE), rooms, room_assets);
+}
+
+fn reload_common_configs_from(path: &Path, rooms: &mut [Room], room_assets: &mut RoomAssets) {
+    match read_common_configs_from(path) {
+        Ok((text, configs)) => {
+            log::info!("{} has been reloaded", COMMON_CONFIGS_FILE);
+            for room in rooms.iter_mut() {
+                apply_common_configs(&mut room.ecs_world, text.clone(), configs.clone());
+            }
+            room_assets.set_common_configs(text, configs);
+        }
+        Err(e) => {
+            log::error!(
+                "{} is invalid, the current configs are kept: {}",
+                COMMON_CONFIGS_FILE,
+                e
+            );
+        }
+    }
+}
+
+/// The attributes of the characters are recalculated with the new configs
+pub fn apply_common_configs(ecs_world: &mut specs::World, text: String, configs: CommonConfigs) {
+    record_event(
+        ecs_world,
+        ReplayEvent::ConfigsReloaded {
+            common_configs: text,
+        },
+    );
+    *ecs_world.write_resource::<CommonConfigs>() = configs.clone();
+
+    for (state, static_info) in (
+        &mut ecs_world.write_storage::<LocalCharStateComp>(),
+        &ecs_world.read_storage::<StaticCharDataComponent>(),
+    )
+        .join()
+    {
+        state.recalc_attribs_based_on_statuses(static_info.job_id, &configs);
+    }
+
+    ecs_world
+        .write_resource::<OutPacketCollector>()
+        .push((PacketTarget::All, FromServerPacket::Configs(configs)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server_config::ServerConfig;
+    use rustarok_common::map::{CellType, MapWalkingInfo};
+    use rustarok_common::packets::from_server::RoomId;
+
+    const COMMON_CONFIGS_TEXT: &str = include_str!("../config-runtime.toml");
+
+    fn firewall_damage(room: &Room) -> u32 {
+        room.ecs_world
+            .read_resource::<CommonConfigs>()
+            .skills
+            .firewall
+            .damage
+    }
+
+    fn sent_configs(room: &Room) -> usize {
+        room.ecs_world
+            .read_resource::<OutPacketCollector>()
+            .iter()
+            .filter(|(_target, packet)| match packet {
+                FromServerPacket::Configs(_) => true,
+                _ => false,
+            })
+            .count()
+    }
+
+    #[test]
+    fn an_invalid_file_keeps_the_current_configs() {
+        let server_config_text = include_str!("../server-conf.toml");
+        let server_config = ServerConfig::from_toml(server_config_text).unwrap();
+        let map = MapWalkingInfo {
+            width: 10,
+            height: 10,
+            cells: vec![CellType::Walkable as u8; 10 * 10],
+        };
+        let mut room_assets =
+            RoomAssets::new_for_tests("map", map, server_config_text, COMMON_CONFIGS_TEXT);
+        let mut rooms = vec![Room::new(
+            RoomId(0),
+            "room".to_owned(),
+            "map".to_owned(),
+            true,
+            &server_config,
+            &room_assets,
+        )
+        .unwrap()];
+        assert_eq!(firewall_damage(&rooms[0]), 600);
+        let path = std::env::temp_dir().join(format!(
+            "rustarok_config_watcher_test_{}.toml",
+            std::process::id()
+        ));
+
+        std::fs::write(&path, "[skills\n").unwrap();
+        reload_common_configs_from(&path, &mut rooms, &mut room_assets);
+        assert_eq!(firewall_damage(&rooms[0]), 600);
+        assert_eq!(sent_configs(&rooms[0]), 0);
+        assert_eq!(room_assets.common_configs_text(), COMMON_CONFIGS_TEXT);
+
+        // other skills have the same damage, only the firewall's is changed
+        let firewall_section = COMMON_CONFIGS_TEXT.find("[skills.firewall]").unwrap();
+        let (before_firewall, from_firewall) = COMMON_CONFIGS_TEXT.split_at(firewall_section);
+        let changed_text = format!(
+            "{}{}",
+            before_firewall,
+            from_firewall.replacen("damage = 600", "damage = 700", 1)
+        );
+        let mut changed_configs = parse_common_configs(&changed_text).unwrap();
+        assert_eq!(changed_configs.skills.firewall.damage, 700);
+        changed_configs.skills.firewall.damage = 600;
+        assert_eq!(
+            bincode::serialize(&changed_configs).unwrap(),
+            bincode::serialize(&parse_common_configs(COMMON_CONFIGS_TEXT).unwrap()).unwrap()
+        );
+        std::fs::write(&path, &changed_text).unwrap();
+        reload_common_configs_from(&path, &mut rooms, &mut room_assets);
+        assert_eq!(firewall_damage(&rooms[0]), 700);
+        assert_eq!(sent_configs(&rooms[0]), 1);
+        assert_eq!(room_assets.common_configs_text(), changed_text);
+
+        std::fs::remove_file(&path).unwrap();
+        reload_common_configs_from(&path, &mut rooms, &mut room_assets);
+        assert_eq!(firewall_damage(&rooms[0]), 700);
+    }
+}
@@ -1,126 +1,480 @@
-use crate::server_config::load_common_configs;
-use crate::OutPacketCollector;
-use crate::PacketTarget;
-use rand::Rng;
+use crate::areas::{AreaEffect, AreaEffectComponent, AreaShape};
+use crate::config_watcher::{apply_common_configs, read_common_configs, COMMON_CONFIGS_FILE};
+use crate::minion_ai::create_minion;
+use crate::replay::{controller_index, record_event, ReplayEvent};
+use crate::server_config::ServerConfig;
+use crate::statuses::create_status_by_name;
+use crate::turret_ai::create_guard;
+use crate::{prepare_entity_id_for_sending, OutPacketCollector, PacketTarget, RemoteClient};
+use rustarok_common::attack::{HpModificationRequest, HpModificationType};
 use rustarok_common::char_attr::CharAttributes;
-use rustarok_common::common::{v2, v3_to_v2, Vec2};
+use rustarok_common::common::{v2, EngineTime, Vec2};
 use rustarok_common::components::char::{
-    CharOutlook, CharType, ControllerEntityId, JobId, LocalCharEntityId, LocalCharStateComp,
-    MonsterId, Sex, StaticCharDataComponent, Team,
+    create_common_player_entity, CharOutlook, CharType, ControllerEntityId, JobId,
+    LocalCharEntityId, LocalCharStateComp, MonsterId, Sex, StaticCharDataComponent, Team,
 };
 use rustarok_common::components::controller::ControllerComponent;
 use rustarok_common::components::job_ids::JobSpriteId;
+use rustarok_common::components::status::status::{ApplyStatusComponent, STATUS_NAMES};
 use rustarok_common::config::CommonConfigs;
-use rustarok_common::console::CommandArguments;
-use rustarok_common::map::{CellType, MapWalkingInfo};
+use rustarok_common::console::{CommandArguments, ConsoleCommandError, PermissionLevel};
 use rustarok_common::packets::from_server::FromServerPacket;
-use rustarok_common::packets::SocketId;
 use specs::world::Builder;
 use specs::world::WorldExt;
 use specs::Join;
 use std::str::FromStr;
 
+/// The output of a successful command, it is sent back to the caller
+pub type CommandResult = Result<String, ConsoleCommandError>;
+
+struct ServerCommand {
+    name: &'static str,
+    /// shown by `help`
+    arguments: &'static str,
+    permission: PermissionLevel,
+    /// the character of the caller is passed, if she has one
+    action: fn(Option<LocalCharEntityId>, &CommandArguments, &mut specs::World) -> CommandResult,
+}
+
+/// The commands of the client which affect the game are executed here, the ones which
+/// affect only the client's own view (camera, window, physics debugging) remain there
+const COMMANDS: &[ServerCommand] = &[
+    ServerCommand {
+        name: "list_players",
+        arguments: "",
+        permission: PermissionLevel::Player,
+        action: cmd_list_players,
+    },
+    ServerCommand {
+        name: "list_entities",
+        arguments: "",
+        permission: PermissionLevel::Player,
+        action: cmd_list_entities,
+    },
+    ServerCommand {
+        name: "get_pos",
+        arguments: "[charname]",
+        permission: PermissionLevel::Player,
+        action: cmd_get_pos,
+    },
+    ServerCommand {
+        name: "list_statuses",
+        arguments: "[charname]",
+        permission: PermissionLevel::Player,
+        action: cmd_list_statuses,
+    },
+    ServerCommand {
+        name: "kill_all",
+        arguments: "[all|npc|player|left_team|right_team|job]",
+        permission: PermissionLevel::Admin,
+        action: cmd_kill_all,
+    },
+    ServerCommand {
+        name: "reload_configs",
+        arguments: "",
+        permission: PermissionLevel::Admin,
+        action: cmd_reload_configs,
+    },
+    ServerCommand {
+        name: "spawn_entity",
+        arguments: "type team [count:1] [x] [y] [outlook] [height]",
+        permission: PermissionLevel::Admin,
+        action: cmd_spawn_entity,
+    },
+    ServerCommand {
+        name: "spawn_area",
+        arguments: "name [value] [width:2] [height:3] [interval(ms):500] [time(ms):500] [x] [y]",
+        permission: PermissionLevel::Admin,
+        action: cmd_spawn_area,
+    },
+    ServerCommand {
+        name: "heal",
+        arguments: "value [charname]",
+        permission: PermissionLevel::Admin,
+        action: cmd_heal,
+    },
+    ServerCommand {
+        name: "add_status",
+        arguments: "status_name time(ms) [value] [charname]",
+        permission: PermissionLevel::Admin,
+        action: cmd_add_status,
+    },
+    ServerCommand {
+        name: "resurrect",
+        arguments: "charname",
+        permission: PermissionLevel::Admin,
+        action: cmd_resurrect,
+    },
+    ServerCommand {
+        name: "set_pos",
+        arguments: "x y [charname]",
+        permission: PermissionLevel::Admin,
+        action: cmd_set_pos,
+    },
+    ServerCommand {
+        name: "goto",
+        arguments: "charname",
+        permission: PermissionLevel::Admin,
+        action: cmd_goto,
+    },
+    ServerCommand {
+        name: "set_team",
+        arguments: "left|right [charname]",
+        permission: PermissionLevel::Admin,
+        action: cmd_set_team,
+    },
+    ServerCommand {
+        name: "set_job",
+        arguments: "job [charname]",
+        permission: PermissionLevel::Admin,
+        action: cmd_set_job,
+    },
+    ServerCommand {
+        name: "set_outlook",
+        arguments: "job_sprite|monster [charname]",
+        permission: PermissionLevel::Admin,
+        action: cmd_set_outlook,
+    },
+    ServerCommand {
+        name: "clone",
+        arguments: "[charname]",
+        permission: PermissionLevel::Admin,
+        action: cmd_clone_char,
+    },
+    ServerCommand {
+        name: "add_falcon",
+        arguments: "[charname]",
+        permission: PermissionLevel::Admin,
+        action: cmd_add_falcon,
+    },
+    ServerCommand {
+        name: "remove_falcon",
+        arguments: "[charname]",
+        permission: PermissionLevel::Admin,
+        action: cmd_remove_falcon,
+    },
+];
+
+/// Only the permitted commands are recorded into the replay, they are executed
+/// with Admin level when replaying
 pub fn execute_console_cmd(
     controller_id: Option<ControllerEntityId>,
+    permission: PermissionLevel,
     args: CommandArguments,
     ecs_world: &mut specs::World,
-) {
-    match args.get_command_name() {
-        Some("kill_all") => {
-            cmd_kill_all(controller_id, args, ecs_world);
-        }
-        Some("reload_configs") => {
-            cmd_reload_configs(controller_id, args, ecs_world);
-        }
-        Some("spawn_entity") => {
-            cmd_spawn_entity(controller_id, args, ecs_world);
-        }
-        _ => {
-            log::error!("Unknown command: {:?}", args.get_command_name());
-        }
+) -> CommandResult {
+    let name = args.get_command_name().unwrap_or("");
+    if name == "help" {
+        return Ok(help(permission));
     }
+    let command = COMMANDS
+        .iter()
+        .find(|it| it.name == name)
+        .ok_or_else(|| ConsoleCommandError::UnknownCommand(name.to_owned()))?;
+    if permission < command.permission {
+        return Err(ConsoleCommandError::PermissionDenied {
+            required: command.permission,
+        });
+    }
+    record_event(
+        ecs_world,
+        ReplayEvent::ConsoleCommand {
+            controller_id: controller_id.map(controller_index),
+            args: args.clone(),
+        },
+    );
+    let caller_char_id = get_client_char_id(controller_id, ecs_world);
+    return (command.action)(caller_char_id, &args, ecs_world);
+}
+
+fn help(permission: PermissionLevel) -> String {
+    return COMMANDS
+        .iter()
+        .filter(|it| it.permission <= permission)
+        .map(|it| format!("{} {}", it.name, it.arguments))
+        .collect::<Vec<_>>()
+        .join("\n");
+}
+
+/// `admin <password>`, it is handled outside of the rooms
+pub fn cmd_admin(
+    remote_client: &mut RemoteClient,
+    args: &CommandArguments,
+    server_config: &ServerConfig,
+) -> CommandResult {
+    let password = arg_str(args, 0, "password")?;
+    let admin_password = &server_config.console.admin_password;
+    return if !admin_password.is_empty() && password == admin_password {
+        log::info!("{} has become an admin", remote_client.name);
+        remote_client.permission = PermissionLevel::Admin;
+        Ok("You are an admin now".to_owned())
+    } else {
+        log::warn!("{} has tried to become an admin", remote_client.name);
+        Err(ConsoleCommandError::Failed("Wrong password".to_owned()))
+    };
 }
 
 fn get_client_char_id(
     controller_id: Option<ControllerEntityId>,
-    ecs_world: &mut specs::World,
+    ecs_world: &specs::World,
 ) -> Option<LocalCharEntityId> {
     controller_id.and_then(|controller_id| {
-        let controller_storage = ecs_world.read_storage::<ControllerComponent>();
-        let controller: &ControllerComponent =
-            controller_storage.get(controller_id.into()).unwrap();
-        controller.controlled_entity
+        ecs_world
+            .read_storage::<ControllerComponent>()
+            .get(controller_id.into())
+            .and_then(|controller| controller.controlled_entity)
     })
 }
 
+fn arg_str<'a>(
+    args: &'a CommandArguments,
+    index: usize,
+    name: &str,
+) -> Result<&'a str, ConsoleCommandError> {
+    args.as_str(index)
+        .ok_or_else(|| ConsoleCommandError::MissingArgument(name.to_owned()))
+}
+
+fn arg_int(args: &CommandArguments, index: usize, name: &str) -> Result<i32, ConsoleCommandError> {
+    let text = arg_str(args, index, name)?;
+    return args
+        .as_int(index)
+        .ok_or_else(|| ConsoleCommandError::InvalidArgument {
+            name: name.to_owned(),
+            value: text.to_owned(),
+        });
+}
+
+fn opt_arg_int(
+    args: &CommandArguments,
+    index: usize,
+    name: &str,
+) -> Result<Option<i32>, ConsoleCommandError> {
+    return if args.as_str(index).is_some() {
+        arg_int(args, index, name).map(Some)
+    } else {
+        Ok(None)
+    };
+}
+
+/// The characters of the players are named as "<team> <name>", both can be used
+fn find_char_by_name(ecs_world: &specs::World, name: &str) -> Option<LocalCharEntityId> {
+    for (entity_id, static_data) in (
+        &ecs_world.entities(),
+        &ecs_world.read_storage::<StaticCharDataComponent>(),
+    )
+        .join()
+    {
+        let player_name = if static_data.typ == CharType::Player {
+            static_data.name.splitn(2, ' ').nth(1)
+        } else {
+            None
+        };
+        if static_data.name.eq_ignore_ascii_case(name)
+            || player_name
+                .map(|it| it.eq_ignore_ascii_case(name))
+                .unwrap_or(false)
+        {
+            return Some(LocalCharEntityId::from(entity_id));
+        }
+    }
+    return None;
+}
+
+/// The named character, or the caller's own one if the argument is missing
+fn get_target_char(
+    caller_char_id: Option<LocalCharEntityId>,
+    args: &CommandArguments,
+    index: usize,
+    ecs_world: &specs::World,
+) -> Result<LocalCharEntityId, ConsoleCommandError> {
+    let char_id = if let Some(name) = args.as_str(index) {
+        find_char_by_name(ecs_world, name)
+            .ok_or_else(|| ConsoleCommandError::CharacterNotFound(name.to_owned()))?
+    } else {
+        caller_char_id.ok_or(ConsoleCommandError::NoCharacter)?
+    };
+    return if ecs_world
+        .read_storage::<LocalCharStateComp>()
+        .get(char_id.into())
+        .is_some()
+    {
+        Ok(char_id)
+    } else {
+        Err(ConsoleCommandError::NoCharacter)
+    };
+}
+
+fn get_pos(char_id: LocalCharEntityId, ecs_world: &specs::World) -> Vec2 {
+    ecs_world
+        .read_storage::<LocalCharStateComp>()
+        .get(char_id.into())
+        .map(|it| it.pos())
+        .unwrap_or(v2(0.0, 0.0))
+}
+
+fn parse_team(text: &str) -> Result<Team, ConsoleCommandError> {
+    return match text {
+        "left" => Ok(Team::Left),
+        "right" => Ok(Team::Right),
+        _ => Err(ConsoleCommandError::InvalidArgument {
+            name: "team".to_owned(),
+            value: text.to_owned(),
+        }),
+    };
+}
+
+/// The clients know the static data only from `NewEntity` and `EntityAppeared`
+fn broadcast_char_changed(char_id: LocalCharEntityId, ecs_world: &specs::World) {
+    if let Some(static_data) = ecs_world
+        .read_storage::<StaticCharDataComponent>()
+        .get(char_id.into())
+    {
+        ecs_world.write_resource::<OutPacketCollector>().push((
+            PacketTarget::All,
+            FromServerPacket::CharChanged {
+                id: prepare_entity_id_for_sending(char_id),
+                team: static_data.team,
+                outlook: static_data.outlook.clone(),
+                job_id: static_data.job_id,
+                has_falcon: static_data.has_falcon,
+            },
+        ));
+    }
+}
+
+fn cmd_list_players(
+    _caller_char_id: Option<LocalCharEntityId>,
+    _args: &CommandArguments,
+    ecs_world: &mut specs::World,
+) -> CommandResult {
+    let mut lines = Vec::with_capacity(16);
+    for (char_state, static_data) in (
+        &ecs_world.read_storage::<LocalCharStateComp>(),
+        &ecs_world.read_storage::<StaticCharDataComponent>(),
+    )
+        .join()
+    {
+        if static_data.typ == CharType::Player {
+            lines.push(format!(
+                "{:<24}{:>10}{:>8}",
+                static_data.name, static_data.job_id, char_state.hp
+            ));
+        }
+    }
+    return Ok(lines.join("\n"));
+}
+
+fn cmd_list_entities(
+    _caller_char_id: Option<LocalCharEntityId>,
+    _args: &CommandArguments,
+    ecs_world: &mut specs::World,
+) -> CommandResult {
+    let mut all = 0;
+    let mut left_team = 0;
+    let mut right_team = 0;
+    let mut players = 0;
+    for static_data in (&ecs_world.read_storage::<StaticCharDataComponent>()).join() {
+        all += 1;
+        match static_data.team {
+            Team::Left => left_team += 1,
+            Team::Right => right_team += 1,
+            _ => {}
+        }
+        if static_data.typ == CharType::Player {
+            players += 1;
+        }
+    }
+    return Ok(format!(
+        "all: {}, left_team: {}, right_team: {}, player: {}, npc: {}",
+        all,
+        left_team,
+        right_team,
+        players,
+        all - players
+    ));
+}
+
+fn cmd_get_pos(
+    caller_char_id: Option<LocalCharEntityId>,
+    args: &CommandArguments,
+    ecs_world: &mut specs::World,
+) -> CommandResult {
+    let char_id = get_target_char(caller_char_id, args, 0, ecs_world)?;
+    let pos = get_pos(char_id, ecs_world);
+    return Ok(format!("{}, {}", pos.x as i32, pos.y as i32));
+}
+
+fn cmd_list_statuses(
+    caller_char_id: Option<LocalCharEntityId>,
+    args: &CommandArguments,
+    ecs_world: &mut specs::World,
+) -> CommandResult {
+    let char_id = get_target_char(caller_char_id, args, 0, ecs_world)?;
+    let char_state_storage = ecs_world.read_storage::<LocalCharStateComp>();
+    let char_state = char_state_storage.get(char_id.into()).unwrap();
+    return Ok(char_state
+        .statuses
+        .get_statuses()
+        .iter()
+        .filter_map(|it| it.as_ref())
+        .map(|status| format!("{:?}", status))
+        .collect::<Vec<_>>()
+        .join("\n"));
+}
+
 fn cmd_spawn_entity(
-    _controller_id: Option<ControllerEntityId>,
-    args: CommandArguments,
+    caller_char_id: Option<LocalCharEntityId>,
+    args: &CommandArguments,
     ecs_world: &mut specs::World,
-) -> Result<(), String> {
-    let type_name = args.as_str(0).unwrap();
-    let team = match args.as_str(1).unwrap() {
-        "left" => Team::Left,
-        _ => Team::Right,
-    };
-    let count = args.as_int(2).unwrap_or(1);
-    let pos2d = match (args.as_int(3), args.as_int(4)) {
+) -> CommandResult {
+    let type_name = arg_str(args, 0, "type")?;
+    // the dummies belong to no team
+    let team = parse_team(arg_str(args, 1, "team")?)?;
+    let count = opt_arg_int(args, 2, "count")?.unwrap_or(1).max(0);
+    let pos2d = match (opt_arg_int(args, 3, "x")?, opt_arg_int(args, 4, "y")?) {
         (Some(x), Some(y)) => v2(x as f32, y as f32),
+        _ => get_pos(
+            caller_char_id.ok_or(ConsoleCommandError::NoCharacter)?,
+            ecs_world,
+        ),
+    };
+    // the height of the guards (the last argument) is only a visual of the client
+    let outlook = match args.as_str(5) {
+        Some(name) => {
+            get_outlook(name, None).ok_or_else(|| ConsoleCommandError::InvalidArgument {
+                name: "outlook".to_owned(),
+                value: name.to_owned(),
+            })?
+        }
+        None if team == Team::Left => CharOutlook::Monster(MonsterId::GEFFEN_MAGE_9),
+        None => CharOutlook::Monster(MonsterId::GEFFEN_MAGE_12),
+    };
+    let job_id = match type_name {
+        "dummy_enemy" => JobId::TargetDummy,
+        "dummy_ally" => JobId::HealingDummy,
+        "minion_melee" => JobId::MeleeMinion,
+        "minion_ranged" => JobId::RangedMinion,
+        "guard" => JobId::Guard,
         _ => {
-            let gat = &ecs_world.read_resource::<MapWalkingInfo>();
-            let hero_pos = ecs_world.read_resource::<LocalCharStateComp>().pos();
-            //            let mut rng = rand::thread_rng();
-            //            let (x, y) = loop {
-            //                let x: f32 = rng.gen_range(hero_pos.x - 10.0, hero_pos.x + 10.0);
-            //                let y: f32 = rng.gen_range(hero_pos.y - 10.0, hero_pos.y + 10.0).abs();
-            //                let index =
-            //                    y.max(0.0) as usize * gat.width as usize + x.max(0.0) as usize;
-            //                let walkable =
-            //                    (map_render_data.gat.cells[index].cell_type & CellType::Walkable as u8) != 0;
-            //                if walkable {
-            //                    break (x, y);
-            //                }
-            //            };
-            //            v2(x, -y)
-            hero_pos
+            return Err(ConsoleCommandError::InvalidArgument {
+                name: "type".to_owned(),
+                value: type_name.to_owned(),
+            });
         }
     };
-    let outlook = args
-        .as_str(5)
-        .and_then(|outlook| get_outlook(outlook, None));
-    let y = args.as_f32(6).unwrap_or(0.0);
-
     for _ in 0..count {
-        match type_name {
-            "minion_melee" | "minion_ranged" => {
-                // TODO asd
-                //                let job_id = if type_name == "minion_melee" {
-                //                    JobId::MeleeMinion
-                //                } else {
-                //                    JobId::RangedMinion
-                //                };
-                //                let char_entity_id =
-                //                    create_random_char_minion(ecs_world, pos2d, team, job_id, outlook.clone());
-                //                ecs_world
-                //                    .create_entity()
-                //                    .with(ControllerComponent::new(char_entity_id))
-                //                    .with(MinionComponent { fountain_up: false })
-                //                    .build();
-            }
-            "guard" => {
-                // TODO asd
-                //                let _char_entity_id = create_client_guard_entity(ecs_world, pos2d, team, y);
-            }
-            "dummy_enemy" => {
-                let _char_entity_id = create_dummy(ecs_world, pos2d, JobId::TargetDummy);
+        match job_id {
+            JobId::MeleeMinion | JobId::RangedMinion => {
+                create_minion(ecs_world, pos2d, team, job_id);
             }
-            "dummy_ally" => {
-                let _char_entity_id = create_dummy(ecs_world, pos2d, JobId::HealingDummy);
+            JobId::Guard => {
+                create_guard(ecs_world, pos2d, team, outlook.clone());
             }
-            _ => {}
+            _ => create_dummy(ecs_world, pos2d, job_id),
         }
     }
-    Ok(())
+    return Ok(format!("{} {} has been spawned", count, type_name));
 }
 
 fn get_outlook(name: &str, current_outlook: Option<&CharOutlook>) -> Option<CharOutlook> {
@@ -174,56 +528,14 @@ fn create_dummy(ecs_world: &mut specs::World, pos2d: Vec2, job_id: JobId) {
         .build();
 }
 
-fn create_random_char_minion(
-    ecs_world: &mut specs::World,
-    pos2d: Vec2,
-    team: Team,
-    job_id: JobId,
-    outlook: Option<CharOutlook>,
-) {
-    let mut rng = rand::thread_rng();
-    let sex = if rng.gen::<usize>() % 2 == 0 {
-        Sex::Male
-    } else {
-        Sex::Female
-    };
-
-    let head_index = rng.gen::<usize>() % 5;
-
-    let base_attributes = {
-        let dev_configs = &ecs_world.read_resource::<CommonConfigs>();
-        CharAttributes::get_base_attributes(job_id, &dev_configs).clone()
-    };
-
-    let char_id = ecs_world
-        .create_entity()
-        .with(LocalCharStateComp::new(pos2d, base_attributes))
-        .with(StaticCharDataComponent::new(
-            "Minion".to_owned(),
-            team,
-            CharType::Minion,
-            job_id,
-            CharOutlook::Human {
-                job_sprite_id: if job_id == JobId::MeleeMinion {
-                    JobSpriteId::SWORDMAN
-                } else {
-                    JobSpriteId::ARCHER
-                },
-                head_index,
-                sex,
-            },
-        ))
-        .build();
-}
-
 fn cmd_kill_all(
-    controller_id: Option<ControllerEntityId>,
-    args: CommandArguments,
+    caller_char_id: Option<LocalCharEntityId>,
+    args: &CommandArguments,
     ecs_world: &mut specs::World,
-) -> Result<(), String> {
+) -> CommandResult {
     let type_name = args.as_str(0).unwrap_or("all");
+    let job_id = JobId::from_str(type_name).ok();
     let mut entity_ids = Vec::with_capacity(32);
-    let self_char_id = get_client_char_id(controller_id, ecs_world);
     for (entity_id, char_state) in (
         &ecs_world.entities(),
         &ecs_world.read_storage::<StaticCharDataComponent>(),
@@ -235,60 +547,361 @@ fn cmd_kill_all(
             "all" => true,
             "left_team" => char_state.team == Team::Left,
             "right_team" => char_state.team == Team::Right,
-            // TODO2
-            //            "npc" => ecs_world
-            //                .read_storage::<NpcComponent>()
-            //                .get(entity_id.into())
-            //                .is_some(),
-            //            "player" => ecs_world
-            //                .read_storage::<NpcComponent>()
-            //                .get(entity_id.into())
-            //                .is_none(),
+            "npc" => char_state.typ != CharType::Player,
+            "player" => char_state.typ == CharType::Player,
             _ => {
-                if let Ok(job_id) = JobId::from_str(type_name) {
+                if let Some(job_id) = job_id {
                     char_state.job_id == job_id
                 } else {
-                    false
+                    return Err(ConsoleCommandError::InvalidArgument {
+                        name: "type".to_owned(),
+                        value: type_name.to_owned(),
+                    });
                 }
             }
         };
-        if need_delete && self_char_id.map(|it| it != entity_id).unwrap_or(true) {
+        if need_delete && caller_char_id.map(|it| it != entity_id).unwrap_or(true) {
             entity_ids.push(entity_id);
         }
     }
-    for entity_id in entity_ids {
-        ecs_world
-            .write_storage::<LocalCharStateComp>()
-            .get_mut(entity_id.into())
-            .unwrap()
-            .hp = 0;
+    let mut char_state_storage = ecs_world.write_storage::<LocalCharStateComp>();
+    for entity_id in &entity_ids {
+        if let Some(char_state) = char_state_storage.get_mut((*entity_id).into()) {
+            char_state.hp = 0;
+        }
     }
 
-    Ok(())
+    return Ok(format!("{} characters have been killed", entity_ids.len()));
 }
 
 fn cmd_reload_configs(
-    controller_id: Option<ControllerEntityId>,
-    args: CommandArguments,
+    _caller_char_id: Option<LocalCharEntityId>,
+    _args: &CommandArguments,
     ecs_world: &mut specs::World,
-) -> Result<(), String> {
+) -> CommandResult {
     log::info!("Reloading configs");
-    let configs = load_common_configs("config-runtime").unwrap();
+    let (text, configs) = read_common_configs().map_err(|e| {
+        log::error!("{} is invalid: {}", COMMON_CONFIGS_FILE, e);
+        ConsoleCommandError::Failed(format!("{} is invalid: {}", COMMON_CONFIGS_FILE, e))
+    })?;
+    apply_common_configs(ecs_world, text, configs);
+    return Ok("Configs have been reloaded".to_owned());
+}
+
+fn cmd_spawn_area(
+    caller_char_id: Option<LocalCharEntityId>,
+    args: &CommandArguments,
+    ecs_world: &mut specs::World,
+) -> CommandResult {
+    let name = arg_str(args, 0, "name")?;
+    let value = opt_arg_int(args, 1, "value")?.unwrap_or(0);
+    let width = opt_arg_int(args, 2, "width")?.unwrap_or(2).max(0);
+    let height = opt_arg_int(args, 3, "height")?.unwrap_or(3).max(0);
+    let interval_ms = opt_arg_int(args, 4, "interval")?.unwrap_or(500).max(1);
+    let duration_ms = opt_arg_int(args, 5, "time")?.unwrap_or(500);
+    let x = opt_arg_int(args, 6, "x")?;
+    let y = opt_arg_int(args, 7, "y")?;
+    // the area belongs to the caster's team, it is neutral without a caster (init.cmd,
+    // operator console), then its position is required
+    let center = match (x, y, caller_char_id) {
+        (Some(x), Some(y), _) => v2(x as f32, y as f32),
+        (_, _, Some(caster_id)) => {
+            let caster_pos = get_pos(caster_id, ecs_world);
+            v2(
+                x.map(|it| it as f32).unwrap_or(caster_pos.x),
+                y.map(|it| it as f32).unwrap_or(caster_pos.y),
+            )
+        }
+        (_, _, None) => return Err(ConsoleCommandError::NoCharacter),
+    };
+    let team = match caller_char_id {
+        Some(caster_id) => ecs_world
+            .read_storage::<StaticCharDataComponent>()
+            .get(caster_id.into())
+            .map(|it| it.team)
+            .ok_or(ConsoleCommandError::NoCharacter)?,
+        None => Team::Neutral,
+    };
+
+    let effect = match name {
+        "heal" => AreaEffect::Heal(value.max(0) as u32),
+        "damage" => AreaEffect::Damage(value.max(0) as u32),
+        _ if STATUS_NAMES.contains(&name) => AreaEffect::Status {
+            name: name.to_owned(),
+            duration_ms,
+            value,
+        },
+        _ => {
+            return Err(ConsoleCommandError::InvalidArgument {
+                name: "name".to_owned(),
+                value: name.to_owned(),
+            });
+        }
+    };
+    let now = ecs_world.read_resource::<EngineTime>().now();
+    ecs_world
+        .create_entity()
+        .with(AreaEffectComponent {
+            caster_entity_id: caller_char_id,
+            team,
+            center,
+            shape: AreaShape::Rectangle {
+                half_extents: v2(width as f32 / 2.0, height as f32 / 2.0),
+            },
+            effect,
+            interval_seconds: interval_ms as f32 / 1000.0,
+            next_effect_at: now,
+            die_at: None,
+        })
+        .build();
+    return Ok(format!("{} area has been spawned", name));
+}
 
-    *ecs_world.write_resource::<CommonConfigs>() = configs.clone();
+fn cmd_heal(
+    caller_char_id: Option<LocalCharEntityId>,
+    args: &CommandArguments,
+    ecs_world: &mut specs::World,
+) -> CommandResult {
+    let value = arg_int(args, 0, "value")?.max(0);
+    let char_id = get_target_char(caller_char_id, args, 1, ecs_world)?;
+    // healing herself is allowed regardless of the teams
+    ecs_world
+        .write_resource::<Vec<HpModificationRequest>>()
+        .push(HpModificationRequest {
+            src_entity: char_id,
+            dst_entity: char_id,
+            typ: HpModificationType::Heal(value as u32),
+        });
+    return Ok(String::new());
+}
 
-    for (state, static_info) in (
-        &mut ecs_world.write_storage::<LocalCharStateComp>(),
-        &ecs_world.read_storage::<StaticCharDataComponent>(),
-    )
-        .join()
+fn cmd_add_status(
+    caller_char_id: Option<LocalCharEntityId>,
+    args: &CommandArguments,
+    ecs_world: &mut specs::World,
+) -> CommandResult {
+    let status_name = arg_str(args, 0, "status_name")?;
+    let duration_ms = arg_int(args, 1, "time")?;
+    let value = opt_arg_int(args, 2, "value")?.unwrap_or(0);
+    let char_id = get_target_char(caller_char_id, args, 3, ecs_world)?;
+    let source_id = caller_char_id.unwrap_or(char_id);
+    let now = ecs_world.read_resource::<EngineTime>().now();
+    let team = ecs_world
+        .read_storage::<StaticCharDataComponent>()
+        .get(source_id.into())
+        .map(|it| it.team)
+        .unwrap_or(Team::EnemyForAll);
+    let status = create_status_by_name(status_name, char_id, team, now, duration_ms, value)
+        .map_err(|e| ConsoleCommandError::InvalidArgument {
+            name: "status_name".to_owned(),
+            value: e,
+        })?;
+    ecs_world
+        .write_resource::<Vec<ApplyStatusComponent>>()
+        .push(ApplyStatusComponent {
+            source_entity_id: source_id,
+            target_entity_id: char_id,
+            status,
+        });
+    return Ok(String::new());
+}
+
+fn cmd_resurrect(
+    _caller_char_id: Option<LocalCharEntityId>,
+    args: &CommandArguments,
+    ecs_world: &mut specs::World,
+) -> CommandResult {
+    let name = arg_str(args, 0, "charname")?;
+    let char_id = get_target_char(None, args, 0, ecs_world)?;
+    {
+        let configs = ecs_world.read_resource::<CommonConfigs>();
+        let mut char_state_storage = ecs_world.write_storage::<LocalCharStateComp>();
+        let char_state = char_state_storage.get_mut(char_id.into()).unwrap();
+        if !char_state.state().is_dead() {
+            return Err(ConsoleCommandError::Failed(format!("{} is alive", name)));
+        }
+        let job_id = ecs_world
+            .read_storage::<StaticCharDataComponent>()
+            .get(char_id.into())
+            .unwrap()
+            .job_id;
+        let pos = char_state.pos();
+        char_state.respawn(pos, job_id, &configs);
+    }
+    ecs_world.write_resource::<OutPacketCollector>().push((
+        PacketTarget::All,
+        FromServerPacket::EntityRespawned(prepare_entity_id_for_sending(char_id)),
+    ));
+    return Ok(format!("{} has been resurrected", name));
+}
+
+fn cmd_set_pos(
+    caller_char_id: Option<LocalCharEntityId>,
+    args: &CommandArguments,
+    ecs_world: &mut specs::World,
+) -> CommandResult {
+    let x = arg_int(args, 0, "x")?;
+    let y = arg_int(args, 1, "y")?;
+    let char_id = get_target_char(caller_char_id, args, 2, ecs_world)?;
+    set_pos(char_id, v2(x as f32, y as f32), ecs_world);
+    return Ok(String::new());
+}
+
+fn cmd_goto(
+    caller_char_id: Option<LocalCharEntityId>,
+    args: &CommandArguments,
+    ecs_world: &mut specs::World,
+) -> CommandResult {
+    let target_char_id = get_target_char(None, args, 0, ecs_world)?;
+    let caller_char_id = caller_char_id.ok_or(ConsoleCommandError::NoCharacter)?;
+    let target_pos = get_pos(target_char_id, ecs_world);
+    set_pos(caller_char_id, target_pos, ecs_world);
+    return Ok(String::new());
+}
+
+/// The new position reaches the clients with the next snapshot
+fn set_pos(char_id: LocalCharEntityId, pos: Vec2, ecs_world: &specs::World) {
+    if let Some(char_state) = ecs_world
+        .write_storage::<LocalCharStateComp>()
+        .get_mut(char_id.into())
     {
-        state.recalc_attribs_based_on_statuses(static_info.job_id, &configs);
+        char_state.set_pos(pos);
+        char_state.target = None;
     }
+}
 
+fn cmd_set_team(
+    caller_char_id: Option<LocalCharEntityId>,
+    args: &CommandArguments,
+    ecs_world: &mut specs::World,
+) -> CommandResult {
+    let team = parse_team(arg_str(args, 0, "team")?)?;
+    let char_id = get_target_char(caller_char_id, args, 1, ecs_world)?;
     ecs_world
-        .write_resource::<OutPacketCollector>()
-        .push((PacketTarget::All, FromServerPacket::Configs(configs)));
+        .write_storage::<StaticCharDataComponent>()
+        .get_mut(char_id.into())
+        .unwrap()
+        .team = team;
+    broadcast_char_changed(char_id, ecs_world);
+    return Ok(String::new());
+}
 
-    Ok(())
+fn cmd_set_job(
+    caller_char_id: Option<LocalCharEntityId>,
+    args: &CommandArguments,
+    ecs_world: &mut specs::World,
+) -> CommandResult {
+    let job_name = arg_str(args, 0, "job")?;
+    let job_id = JobId::from_str(job_name).map_err(|_| ConsoleCommandError::InvalidArgument {
+        name: "job".to_owned(),
+        value: job_name.to_owned(),
+    })?;
+    let char_id = get_target_char(caller_char_id, args, 1, ecs_world)?;
+    {
+        let configs = ecs_world.read_resource::<CommonConfigs>();
+        let mut static_data_storage = ecs_world.write_storage::<StaticCharDataComponent>();
+        let static_data = static_data_storage.get_mut(char_id.into()).unwrap();
+        static_data.job_id = job_id;
+        if let CharOutlook::Human { .. } = static_data.outlook {
+            static_data.outlook = get_outlook(
+                &JobSpriteId::from_job_id(job_id).to_string(),
+                Some(&static_data.outlook),
+            )
+            .unwrap_or(static_data.outlook.clone());
+        }
+        ecs_world
+            .write_storage::<LocalCharStateComp>()
+            .get_mut(char_id.into())
+            .unwrap()
+            .recalc_attribs_based_on_statuses(job_id, &configs);
+    }
+    broadcast_char_changed(char_id, ecs_world);
+    return Ok(String::new());
+}
+
+fn cmd_set_outlook(
+    caller_char_id: Option<LocalCharEntityId>,
+    args: &CommandArguments,
+    ecs_world: &mut specs::World,
+) -> CommandResult {
+    let outlook_name = arg_str(args, 0, "outlook")?;
+    let char_id = get_target_char(caller_char_id, args, 1, ecs_world)?;
+    {
+        let mut static_data_storage = ecs_world.write_storage::<StaticCharDataComponent>();
+        let static_data = static_data_storage.get_mut(char_id.into()).unwrap();
+        static_data.outlook =
+            get_outlook(outlook_name, Some(&static_data.outlook)).ok_or_else(|| {
+                ConsoleCommandError::InvalidArgument {
+                    name: "outlook".to_owned(),
+                    value: outlook_name.to_owned(),
+                }
+            })?;
+    }
+    broadcast_char_changed(char_id, ecs_world);
+    return Ok(String::new());
+}
+
+/// The clone stands still, it appears for the clients through their area of interest
+fn cmd_clone_char(
+    caller_char_id: Option<LocalCharEntityId>,
+    args: &CommandArguments,
+    ecs_world: &mut specs::World,
+) -> CommandResult {
+    let char_id = get_target_char(caller_char_id, args, 0, ecs_world)?;
+    let pos = get_pos(char_id, ecs_world);
+    let static_data = ecs_world
+        .read_storage::<StaticCharDataComponent>()
+        .get(char_id.into())
+        .unwrap()
+        .clone();
+    create_common_player_entity(
+        "Clone".to_owned(),
+        ecs_world,
+        CharType::Minion,
+        static_data.job_id,
+        pos,
+        static_data.team,
+        static_data.outlook,
+    )
+    .build();
+    return Ok(String::new());
+}
+
+fn cmd_add_falcon(
+    caller_char_id: Option<LocalCharEntityId>,
+    args: &CommandArguments,
+    ecs_world: &mut specs::World,
+) -> CommandResult {
+    return set_falcon(caller_char_id, args, ecs_world, true);
+}
+
+fn cmd_remove_falcon(
+    caller_char_id: Option<LocalCharEntityId>,
+    args: &CommandArguments,
+    ecs_world: &mut specs::World,
+) -> CommandResult {
+    return set_falcon(caller_char_id, args, ecs_world, false);
+}
+
+fn set_falcon(
+    caller_char_id: Option<LocalCharEntityId>,
+    args: &CommandArguments,
+    ecs_world: &mut specs::World,
+    has_falcon: bool,
+) -> CommandResult {
+    let char_id = get_target_char(caller_char_id, args, 0, ecs_world)?;
+    {
+        let mut static_data_storage = ecs_world.write_storage::<StaticCharDataComponent>();
+        let static_data = static_data_storage.get_mut(char_id.into()).unwrap();
+        if static_data.has_falcon == has_falcon {
+            return Err(ConsoleCommandError::Failed(if has_falcon {
+                "The character already has a falcon".to_owned()
+            } else {
+                "The character does not have a falcon".to_owned()
+            }));
+        }
+        static_data.has_falcon = has_falcon;
+    }
+    broadcast_char_changed(char_id, ecs_world);
+    return Ok(String::new());
 }
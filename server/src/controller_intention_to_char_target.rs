@@ -1,5 +1,7 @@
-use rustarok_common::components::char::{EntityTarget, LocalCharEntityId, LocalCharStateComp};
-use rustarok_common::components::controller::{ControllerComponent, PlayerIntention};
+use rustarok_common::common::EngineTime;
+use rustarok_common::components::char::{LocalCharStateComp, StaticCharDataComponent};
+use rustarok_common::components::controller::ControllerComponent;
+use rustarok_common::config::CommonConfigs;
 use rustarok_common::systems::intention_applier::ControllerIntentionToCharTarget;
 use specs::Join;
 
@@ -8,14 +10,30 @@ pub struct ControllerIntentionToCharTargetSystem;
 impl<'a> specs::System<'a> for ControllerIntentionToCharTargetSystem {
     type SystemData = (
         specs::WriteStorage<'a, LocalCharStateComp>,
+        specs::ReadStorage<'a, StaticCharDataComponent>,
         specs::WriteStorage<'a, ControllerComponent>,
+        specs::ReadExpect<'a, CommonConfigs>,
+        specs::ReadExpect<'a, EngineTime>,
     );
 
-    fn run(&mut self, (mut char_state_storage, mut controller_storage): Self::SystemData) {
+    fn run(
+        &mut self,
+        (
+            mut char_state_storage,
+            static_char_data_storage,
+            mut controller_storage,
+            configs,
+            time,
+        ): Self::SystemData,
+    ) {
+        let now = time.now();
         for mut controller in (&mut controller_storage).join() {
             ControllerIntentionToCharTarget::controller_intention_to_char_target(
                 controller,
                 &mut char_state_storage,
+                &static_char_data_storage,
+                &configs,
+                now,
             );
             controller.intention = None;
         }
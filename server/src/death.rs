@@ -0,0 +1,110 @@
+use crate::server_config::ServerConfig;
+use crate::{prepare_entity_id_for_sending, OutPacketCollector, PacketTarget};
+use rustarok_common::common::EngineTime;
+use rustarok_common::components::char::{
+    CharType, LocalCharEntityId, LocalCharStateComp, StaticCharDataComponent,
+};
+use rustarok_common::components::status::status::{StatusEnum, StatusEnumDiscriminants};
+use rustarok_common::config::CommonConfigs;
+use rustarok_common::packets::from_server::FromServerPacket;
+use specs::prelude::*;
+
+/// Kills the characters whose HP dropped to zero, removes the corpses of NPCs
+/// and respawns the players at their team's spawn point.
+/// Returns the characters which have died in this frame.
+pub fn handle_dead_characters(ecs_world: &mut specs::World) -> Vec<LocalCharEntityId> {
+    let now = ecs_world.read_resource::<EngineTime>().now();
+    let mut died = Vec::new();
+    let mut to_remove = Vec::new();
+    let mut to_respawn = Vec::new();
+    {
+        let respawn_delay = ecs_world
+            .read_resource::<ServerConfig>()
+            .respawn
+            .delay_seconds;
+        let mut char_state_storage = ecs_world.write_storage::<LocalCharStateComp>();
+        let static_char_data_storage = ecs_world.read_storage::<StaticCharDataComponent>();
+        for (char_entity_id, auth_state, static_data) in (
+            &ecs_world.entities(),
+            &mut char_state_storage,
+            &static_char_data_storage,
+        )
+            .join()
+        {
+            let char_entity_id = LocalCharEntityId::new(char_entity_id);
+            let is_npc = static_data.typ != CharType::Player;
+            if !auth_state.state().is_dead() {
+                if auth_state.hp <= 0 {
+                    log::debug!("Entity has died {:?}", char_entity_id);
+                    auth_state.die(now, is_npc);
+                    died.push(char_entity_id);
+                }
+                continue;
+            }
+            if let Some(StatusEnum::DeathStatus(status)) = auth_state
+                .statuses
+                .get_status(StatusEnumDiscriminants::DeathStatus)
+            {
+                if is_npc {
+                    if status.remove_char_at.has_already_passed(now) {
+                        to_remove.push(char_entity_id);
+                    }
+                } else if status
+                    .started
+                    .add_seconds(respawn_delay)
+                    .has_already_passed(now)
+                {
+                    to_respawn.push(char_entity_id);
+                }
+            }
+        }
+    }
+
+    for char_entity_id in &to_respawn {
+        let configs = ecs_world.read_resource::<CommonConfigs>();
+        let server_config = ecs_world.read_resource::<ServerConfig>();
+        let static_char_data_storage = ecs_world.read_storage::<StaticCharDataComponent>();
+        let static_data = static_char_data_storage
+            .get((*char_entity_id).into())
+            .unwrap();
+        let spawn_pos = server_config.respawn.get_spawn_pos(static_data.team);
+        log::debug!(
+            "Entity has respawned {:?} at {:?}",
+            char_entity_id,
+            spawn_pos
+        );
+        ecs_world
+            .write_storage::<LocalCharStateComp>()
+            .get_mut((*char_entity_id).into())
+            .unwrap()
+            .respawn(spawn_pos, static_data.job_id, &configs);
+    }
+
+    for char_entity_id in &to_remove {
+        log::debug!("Corpse has been removed {:?}", char_entity_id);
+        if let Err(e) = ecs_world.delete_entity((*char_entity_id).into()) {
+            log::error!("Could not remove {:?}: {:?}", char_entity_id, e);
+        }
+    }
+
+    let out_packets = &mut ecs_world.write_resource::<OutPacketCollector>();
+    for char_entity_id in &died {
+        out_packets.push((
+            PacketTarget::All,
+            FromServerPacket::EntityDied(prepare_entity_id_for_sending(*char_entity_id)),
+        ));
+    }
+    for char_entity_id in to_respawn {
+        out_packets.push((
+            PacketTarget::All,
+            FromServerPacket::EntityRespawned(prepare_entity_id_for_sending(char_entity_id)),
+        ));
+    }
+    for char_entity_id in to_remove {
+        out_packets.push((
+            PacketTarget::All,
+            FromServerPacket::EntityRemoved(prepare_entity_id_for_sending(char_entity_id)),
+        ));
+    }
+    return died;
+}
@@ -0,0 +1,273 @@
+//! The movements which the characters can not control, e.g. being pushed back by a
+//! FireWall or dashing with AssaBladeDash. They override the walking of the character
+//! until they end, and they are dropped when the character dies.
+use rustarok_common::attack::ApplyForceComponent;
+use rustarok_common::common::{EngineTime, LocalTime, Vec2};
+use rustarok_common::components::char::{LocalCharEntityId, LocalCharStateComp};
+use rustarok_common::map::MapWalkingInfo;
+use specs::prelude::*;
+use std::collections::VecDeque;
+
+/// The distance of the checked points when looking for walls on a path
+const WALL_CHECK_STEP: f32 = 0.25;
+
+pub struct ForcedMovementSegment {
+    pub from: Vec2,
+    pub to: Vec2,
+    pub starts_at: LocalTime,
+    pub ends_at: LocalTime,
+}
+
+#[derive(Component)]
+pub struct ForcedMovementComponent {
+    /// in order, the character stays in place until the first one starts
+    segments: VecDeque<ForcedMovementSegment>,
+}
+
+/// A newer movement replaces the current one of the character
+pub fn start_forced_movement(
+    ecs_world: &specs::World,
+    char_entity_id: LocalCharEntityId,
+    segments: Vec<ForcedMovementSegment>,
+) {
+    let result = ecs_world.write_storage::<ForcedMovementComponent>().insert(
+        char_entity_id.into(),
+        ForcedMovementComponent {
+            segments: segments.into(),
+        },
+    );
+    if let Err(e) = result {
+        log::error!("Could not move {:?}: {:?}", char_entity_id, e);
+    }
+}
+
+/// The pushes stop at the walls, the statuses of the target can prevent them
+pub fn apply_forces(ecs_world: &mut specs::World) {
+    let forces = std::mem::replace(
+        &mut *ecs_world.write_resource::<Vec<ApplyForceComponent>>(),
+        Vec::with_capacity(128),
+    );
+    let now = ecs_world.read_resource::<EngineTime>().now();
+    for force in &forces {
+        let pushed_from = ecs_world
+            .write_storage::<LocalCharStateComp>()
+            .get_mut(force.dst_entity.into())
+            .and_then(|target| {
+                if target.state().is_alive() && target.statuses.allow_push(force) {
+                    Some(target.pos())
+                } else {
+                    None
+                }
+            });
+        if let Some(from) = pushed_from {
+            let to = farthest_walkable_pos(
+                &ecs_world.read_resource::<MapWalkingInfo>(),
+                from,
+                from + force.force * force.duration,
+            );
+            start_forced_movement(
+                ecs_world,
+                force.dst_entity,
+                vec![ForcedMovementSegment {
+                    from,
+                    to,
+                    starts_at: now,
+                    ends_at: now.add_seconds(force.duration),
+                }],
+            );
+        }
+    }
+}
+
+pub fn move_forced_characters(ecs_world: &mut specs::World) {
+    let now = ecs_world.read_resource::<EngineTime>().now();
+    let mut finished = Vec::new();
+    {
+        let entities = ecs_world.entities();
+        let mut movement_storage = ecs_world.write_storage::<ForcedMovementComponent>();
+        let mut char_state_storage = ecs_world.write_storage::<LocalCharStateComp>();
+        for (entity_id, movement, char_state) in
+            (&entities, &mut movement_storage, &mut char_state_storage).join()
+        {
+            if !char_state.state().is_alive() {
+                finished.push(entity_id);
+                continue;
+            }
+            while movement
+                .segments
+                .front()
+                .map(|it| it.ends_at.has_already_passed(now))
+                .unwrap_or(false)
+            {
+                let segment = movement.segments.pop_front().unwrap();
+                char_state.set_pos(segment.to);
+            }
+            match movement.segments.front() {
+                Some(segment) => {
+                    if segment.starts_at.has_already_passed(now) {
+                        let progress = now.percentage_between(segment.starts_at, segment.ends_at);
+                        char_state.set_pos(segment.from + (segment.to - segment.from) * progress);
+                    }
+                }
+                None => finished.push(entity_id),
+            }
+        }
+    }
+    let mut movement_storage = ecs_world.write_storage::<ForcedMovementComponent>();
+    for entity_id in finished {
+        movement_storage.remove(entity_id);
+    }
+}
+
+/// The last walkable point before the first wall on the way
+pub fn farthest_walkable_pos(map: &MapWalkingInfo, from: Vec2, to: Vec2) -> Vec2 {
+    let mut farthest = from;
+    for pos in path_points(from, to) {
+        if !map.is_walkable_pos(pos) {
+            break;
+        }
+        farthest = pos;
+    }
+    return farthest;
+}
+
+/// The last walkable point of the way, it can be behind walls (e.g. for flying)
+pub fn farthest_walkable_pos_through_walls(map: &MapWalkingInfo, from: Vec2, to: Vec2) -> Vec2 {
+    return path_points(from, to)
+        .filter(|pos| map.is_walkable_pos(*pos))
+        .last()
+        .unwrap_or(from);
+}
+
+fn path_points(from: Vec2, to: Vec2) -> impl Iterator<Item = Vec2> {
+    let path = to - from;
+    let step_count = (path.magnitude() / WALL_CHECK_STEP).ceil() as u32;
+    return (1..=step_count).map(move |i| from + path * (i as f32 / step_count as f32));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::room::create_room_world;
+    use crate::server_config::{parse_common_configs, ServerConfig};
+    use rustarok_common::common::v2;
+    use rustarok_common::components::char::{
+        create_common_player_entity, CharOutlook, CharType, JobId, MonsterId, Team,
+    };
+    use rustarok_common::map::CellType;
+    use std::time::Duration;
+
+    /// 10x10 cells, the column at x = 5 is a wall
+    fn create_world() -> specs::World {
+        let mut cells = vec![CellType::Walkable as u8; 10 * 10];
+        for y in 0..10 {
+            cells[y * 10 + 5] = CellType::None as u8;
+        }
+        let map = MapWalkingInfo {
+            width: 10,
+            height: 10,
+            cells,
+        };
+        let (ecs_world, _ecs_dispatcher) = create_room_world(
+            ServerConfig::from_toml(include_str!("../server-conf.toml")).unwrap(),
+            parse_common_configs(include_str!("../config-runtime.toml")).unwrap(),
+            map,
+        );
+        return ecs_world;
+    }
+
+    fn add_char(ecs_world: &mut specs::World, pos: Vec2) -> LocalCharEntityId {
+        let entity = create_common_player_entity(
+            "Char".to_owned(),
+            ecs_world,
+            CharType::Player,
+            JobId::CRUSADER,
+            pos,
+            Team::Left,
+            CharOutlook::Monster(MonsterId::Dimik),
+        )
+        .build();
+        return LocalCharEntityId::from(entity);
+    }
+
+    fn pos_of(ecs_world: &specs::World, char_id: LocalCharEntityId) -> Vec2 {
+        ecs_world
+            .read_storage::<LocalCharStateComp>()
+            .get(char_id.into())
+            .unwrap()
+            .pos()
+    }
+
+    fn tick(ecs_world: &mut specs::World) {
+        ecs_world
+            .write_resource::<EngineTime>()
+            .tick(Duration::from_millis(500));
+        move_forced_characters(ecs_world);
+    }
+
+    #[test]
+    fn a_push_stops_at_the_wall() {
+        let mut ecs_world = create_world();
+        let char_id = add_char(&mut ecs_world, v2(1.5, -1.5));
+        ecs_world
+            .write_resource::<Vec<ApplyForceComponent>>()
+            .push(ApplyForceComponent {
+                src_entity: char_id,
+                dst_entity: char_id,
+                force: v2(8.0, 0.0),
+                duration: 1.0,
+            });
+        apply_forces(&mut ecs_world);
+
+        tick(&mut ecs_world);
+        assert!((pos_of(&ecs_world, char_id) - v2(3.125, -1.5)).magnitude() < 0.01);
+        tick(&mut ecs_world);
+        assert!((pos_of(&ecs_world, char_id) - v2(4.75, -1.5)).magnitude() < 0.01);
+        tick(&mut ecs_world);
+        assert!(ecs_world
+            .read_storage::<ForcedMovementComponent>()
+            .get(char_id.into())
+            .is_none());
+    }
+
+    #[test]
+    fn the_segments_are_followed_in_order() {
+        let mut ecs_world = create_world();
+        let char_id = add_char(&mut ecs_world, v2(1.0, -1.0));
+        let now = ecs_world.read_resource::<EngineTime>().now();
+        start_forced_movement(
+            &ecs_world,
+            char_id,
+            vec![
+                ForcedMovementSegment {
+                    from: v2(1.0, -1.0),
+                    to: v2(1.0, -3.0),
+                    starts_at: now.add_seconds(0.5),
+                    ends_at: now.add_seconds(1.5),
+                },
+                ForcedMovementSegment {
+                    from: v2(1.0, -3.0),
+                    to: v2(1.0, -1.0),
+                    starts_at: now.add_seconds(1.5),
+                    ends_at: now.add_seconds(2.5),
+                },
+            ],
+        );
+
+        let expected_positions = [-1.0, -2.0, -3.0, -2.0, -1.0];
+        for expected_y in expected_positions.iter() {
+            tick(&mut ecs_world);
+            assert!((pos_of(&ecs_world, char_id) - v2(1.0, *expected_y)).magnitude() < 0.01);
+        }
+    }
+
+    #[test]
+    fn flying_can_pass_the_walls() {
+        let ecs_world = create_world();
+        let map = ecs_world.read_resource::<MapWalkingInfo>();
+        let stop = farthest_walkable_pos(&map, v2(1.5, -1.5), v2(12.0, -1.5));
+        assert!((stop - v2(4.75, -1.5)).magnitude() < 0.01);
+        let landing = farthest_walkable_pos_through_walls(&map, v2(1.5, -1.5), v2(12.0, -1.5));
+        assert!(landing.x > 9.5 && landing.x < 10.0);
+    }
+}
@@ -0,0 +1,545 @@
+//! The match goes through Lobby -> Countdown -> InProgress -> PostGame -> Lobby.
+//! Only the kills of the InProgress phase are counted, and the players are respawned
+//! at their team's spawn point when it starts.
+use crate::server_config::ServerConfig;
+use crate::{prepare_entity_id_for_sending, OutPacketCollector, PacketTarget};
+use rustarok_common::common::{EngineTime, LocalTime};
+use rustarok_common::components::char::{
+    CharType, LocalCharEntityId, LocalCharStateComp, StaticCharDataComponent, Team,
+};
+use rustarok_common::config::CommonConfigs;
+use rustarok_common::packets::from_server::{FromServerPacket, MatchPhase, MatchScore};
+use specs::prelude::*;
+
+pub struct MatchState {
+    phase: MatchPhase,
+    phase_started: LocalTime,
+    score: MatchScore,
+}
+
+impl MatchState {
+    pub fn new(min_players: usize) -> MatchState {
+        MatchState {
+            phase: MatchPhase::Lobby {
+                required_players: min_players,
+            },
+            phase_started: LocalTime::from(0.0),
+            score: MatchScore::default(),
+        }
+    }
+
+    pub fn score(&self) -> MatchScore {
+        self.score
+    }
+
+    /// The remaining times are calculated for `now`, e.g. for a client who has just joined
+    pub fn phase(&self, now: LocalTime) -> MatchPhase {
+        let elapsed = now.elapsed_since(self.phase_started).as_seconds_f32();
+        return match self.phase {
+            MatchPhase::Countdown { remaining_seconds } => MatchPhase::Countdown {
+                remaining_seconds: (remaining_seconds - elapsed).max(0.0),
+            },
+            MatchPhase::InProgress { remaining_seconds } => MatchPhase::InProgress {
+                remaining_seconds: remaining_seconds.map(|it| (it - elapsed).max(0.0)),
+            },
+            phase => phase,
+        };
+    }
+}
+
+fn count_players(ecs_world: &specs::World) -> (usize, usize) {
+    let mut left = 0;
+    let mut right = 0;
+    for static_data in (&ecs_world.read_storage::<StaticCharDataComponent>()).join() {
+        if static_data.typ != CharType::Player {
+            continue;
+        }
+        match static_data.team {
+            Team::Left => left += 1,
+            Team::Right => right += 1,
+            _ => {}
+        }
+    }
+    return (left, right);
+}
+
+/// The preferred team is refused if it would make the teams unbalanced,
+/// without preference the smaller team is chosen
+pub fn choose_team(preferred: Option<Team>, ecs_world: &specs::World) -> Team {
+    let max_imbalance = ecs_world
+        .read_resource::<ServerConfig>()
+        .game_match
+        .max_team_imbalance;
+    let (left, right) = count_players(ecs_world);
+    return match preferred {
+        Some(Team::Left) if left + 1 <= right + max_imbalance => Team::Left,
+        Some(Team::Right) if right + 1 <= left + max_imbalance => Team::Right,
+        _ => {
+            if left <= right {
+                Team::Left
+            } else {
+                Team::Right
+            }
+        }
+    };
+}
+
+/// `died` contains the characters which have died in this frame
+pub fn update_match(ecs_world: &mut specs::World, died: &[LocalCharEntityId]) {
+    let now = ecs_world.read_resource::<EngineTime>().now();
+    let (left_players, right_players) = count_players(ecs_world);
+    let (phase, elapsed, mut score) = {
+        let match_state = ecs_world.read_resource::<MatchState>();
+        (
+            match_state.phase,
+            now.elapsed_since(match_state.phase_started)
+                .as_seconds_f32(),
+            match_state.score,
+        )
+    };
+    let next_phase = {
+        let server_config = ecs_world.read_resource::<ServerConfig>();
+        let config = &server_config.game_match;
+        let enough_players = left_players + right_players >= config.min_players
+            && left_players > 0
+            && right_players > 0;
+        match phase {
+            MatchPhase::Lobby { .. } => {
+                if enough_players {
+                    Some(MatchPhase::Countdown {
+                        remaining_seconds: config.countdown_seconds,
+                    })
+                } else {
+                    None
+                }
+            }
+            MatchPhase::Countdown { remaining_seconds } => {
+                if !enough_players {
+                    Some(MatchPhase::Lobby {
+                        required_players: config.min_players,
+                    })
+                } else if elapsed >= remaining_seconds {
+                    Some(MatchPhase::InProgress {
+                        remaining_seconds: config.time_limit_seconds,
+                    })
+                } else {
+                    None
+                }
+            }
+            MatchPhase::InProgress { remaining_seconds } => {
+                let static_data_storage = ecs_world.read_storage::<StaticCharDataComponent>();
+                let mut destroyed_structure_team = None;
+                for static_data in died
+                    .iter()
+                    .filter_map(|char_id| static_data_storage.get((*char_id).into()))
+                {
+                    if static_data.typ == CharType::Player {
+                        match static_data.team {
+                            Team::Left => score.right_team_kills += 1,
+                            Team::Right => score.left_team_kills += 1,
+                            _ => {}
+                        }
+                    }
+                    if config.structure_job == Some(static_data.job_id) {
+                        destroyed_structure_team = Some(static_data.team);
+                    }
+                }
+                let leading_team = if score.left_team_kills > score.right_team_kills {
+                    Some(Team::Left)
+                } else if score.right_team_kills > score.left_team_kills {
+                    Some(Team::Right)
+                } else {
+                    None
+                };
+                if let Some(team) = destroyed_structure_team {
+                    Some(MatchPhase::PostGame {
+                        winner: Some(team.get_opponent_team()),
+                    })
+                } else if left_players == 0 || right_players == 0 {
+                    // the other team has left
+                    Some(MatchPhase::PostGame {
+                        winner: if left_players > 0 {
+                            Some(Team::Left)
+                        } else if right_players > 0 {
+                            Some(Team::Right)
+                        } else {
+                            None
+                        },
+                    })
+                } else if config
+                    .kill_limit
+                    .map(|limit| score.left_team_kills.max(score.right_team_kills) >= limit)
+                    .unwrap_or(false)
+                {
+                    Some(MatchPhase::PostGame {
+                        winner: leading_team,
+                    })
+                } else if remaining_seconds.map(|it| elapsed >= it).unwrap_or(false) {
+                    Some(MatchPhase::PostGame {
+                        winner: leading_team,
+                    })
+                } else {
+                    None
+                }
+            }
+            MatchPhase::PostGame { .. } => {
+                if elapsed >= config.post_game_seconds {
+                    Some(MatchPhase::Lobby {
+                        required_players: config.min_players,
+                    })
+                } else {
+                    None
+                }
+            }
+        }
+    };
+
+    if let Some(MatchPhase::InProgress { .. }) = next_phase {
+        score = MatchScore::default();
+        respawn_players(ecs_world);
+    }
+    let mut match_state = ecs_world.write_resource::<MatchState>();
+    let out_packets = &mut ecs_world.write_resource::<OutPacketCollector>();
+    if score != match_state.score {
+        match_state.score = score;
+        out_packets.push((PacketTarget::All, FromServerPacket::ScoreChanged(score)));
+    }
+    if let Some(next_phase) = next_phase {
+        log::info!("Match phase: {:?}, score: {:?}", next_phase, score);
+        match_state.phase = next_phase;
+        match_state.phase_started = now;
+        out_packets.push((
+            PacketTarget::All,
+            FromServerPacket::MatchPhaseChanged(next_phase),
+        ));
+    }
+}
+
+fn respawn_players(ecs_world: &mut specs::World) {
+    let configs = ecs_world.read_resource::<CommonConfigs>();
+    let server_config = ecs_world.read_resource::<ServerConfig>();
+    let mut char_state_storage = ecs_world.write_storage::<LocalCharStateComp>();
+    let static_char_data_storage = ecs_world.read_storage::<StaticCharDataComponent>();
+    let out_packets = &mut ecs_world.write_resource::<OutPacketCollector>();
+    for (char_entity_id, char_state, static_data) in (
+        &ecs_world.entities(),
+        &mut char_state_storage,
+        &static_char_data_storage,
+    )
+        .join()
+    {
+        if static_data.typ != CharType::Player {
+            continue;
+        }
+        char_state.respawn(
+            server_config.respawn.get_spawn_pos(static_data.team),
+            static_data.job_id,
+            &configs,
+        );
+        out_packets.push((
+            PacketTarget::All,
+            FromServerPacket::EntityRespawned(prepare_entity_id_for_sending(
+                LocalCharEntityId::new(char_entity_id),
+            )),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::room::create_room_world;
+    use crate::server_config::{parse_common_configs, MatchConfig};
+    use rustarok_common::common::v2;
+    use rustarok_common::components::char::{
+        create_common_player_entity, CharOutlook, JobId, MonsterId,
+    };
+    use rustarok_common::map::{CellType, MapWalkingInfo};
+    use std::time::Duration;
+
+    fn create_world(configure: impl FnOnce(&mut MatchConfig)) -> specs::World {
+        let mut server_config =
+            ServerConfig::from_toml(include_str!("../server-conf.toml")).unwrap();
+        configure(&mut server_config.game_match);
+        let map = MapWalkingInfo {
+            width: 100,
+            height: 100,
+            cells: vec![CellType::Walkable as u8; 100 * 100],
+        };
+        let (ecs_world, _ecs_dispatcher) = create_room_world(
+            server_config,
+            parse_common_configs(include_str!("../config-runtime.toml")).unwrap(),
+            map,
+        );
+        return ecs_world;
+    }
+
+    fn add_char(
+        ecs_world: &mut specs::World,
+        typ: CharType,
+        job_id: JobId,
+        team: Team,
+    ) -> LocalCharEntityId {
+        let entity = create_common_player_entity(
+            "Char".to_owned(),
+            ecs_world,
+            typ,
+            job_id,
+            v2(50.0, -50.0),
+            team,
+            CharOutlook::Monster(MonsterId::Dimik),
+        )
+        .build();
+        return LocalCharEntityId::from(entity);
+    }
+
+    fn add_player(ecs_world: &mut specs::World, team: Team) -> LocalCharEntityId {
+        add_char(ecs_world, CharType::Player, JobId::CRUSADER, team)
+    }
+
+    fn remove_char(ecs_world: &mut specs::World, char_id: LocalCharEntityId) {
+        ecs_world.delete_entity(char_id.into()).unwrap();
+        ecs_world.maintain();
+    }
+
+    fn wait(ecs_world: &mut specs::World, seconds: u64) {
+        ecs_world
+            .write_resource::<EngineTime>()
+            .tick(Duration::from_secs(seconds));
+    }
+
+    fn phase(ecs_world: &specs::World) -> MatchPhase {
+        ecs_world.read_resource::<MatchState>().phase
+    }
+
+    fn score(ecs_world: &specs::World) -> MatchScore {
+        ecs_world.read_resource::<MatchState>().score
+    }
+
+    fn start_match(ecs_world: &mut specs::World) {
+        update_match(ecs_world, &[]);
+        let countdown = ecs_world
+            .read_resource::<ServerConfig>()
+            .game_match
+            .countdown_seconds;
+        wait(ecs_world, countdown.ceil() as u64);
+        update_match(ecs_world, &[]);
+        match phase(ecs_world) {
+            MatchPhase::InProgress { .. } => {}
+            phase => panic!("The match has not started: {:?}", phase),
+        }
+    }
+
+    fn is_post_game_won_by(ecs_world: &specs::World, team: Option<Team>) -> bool {
+        match phase(ecs_world) {
+            MatchPhase::PostGame { winner } => winner == team,
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn the_countdown_needs_a_player_in_both_teams() {
+        let mut ecs_world = create_world(|config| config.min_players = 2);
+        add_player(&mut ecs_world, Team::Left);
+        add_player(&mut ecs_world, Team::Left);
+        update_match(&mut ecs_world, &[]);
+        assert_eq!(
+            phase(&ecs_world),
+            MatchPhase::Lobby {
+                required_players: 2
+            }
+        );
+
+        let right_player = add_player(&mut ecs_world, Team::Right);
+        update_match(&mut ecs_world, &[]);
+        assert_eq!(
+            phase(&ecs_world),
+            MatchPhase::Countdown {
+                remaining_seconds: 10.0
+            }
+        );
+
+        remove_char(&mut ecs_world, right_player);
+        update_match(&mut ecs_world, &[]);
+        assert_eq!(
+            phase(&ecs_world),
+            MatchPhase::Lobby {
+                required_players: 2
+            }
+        );
+    }
+
+    #[test]
+    fn the_match_starts_after_the_countdown_with_a_clean_score() {
+        let mut ecs_world = create_world(|config| config.countdown_seconds = 10.0);
+        let left_player = add_player(&mut ecs_world, Team::Left);
+        add_player(&mut ecs_world, Team::Right);
+        update_match(&mut ecs_world, &[]);
+
+        // the kills of the countdown are not counted
+        wait(&mut ecs_world, 5);
+        update_match(&mut ecs_world, &[left_player]);
+        assert_eq!(score(&ecs_world), MatchScore::default());
+        assert_eq!(
+            ecs_world
+                .read_resource::<MatchState>()
+                .phase(ecs_world.read_resource::<EngineTime>().now()),
+            MatchPhase::Countdown {
+                remaining_seconds: 5.0
+            }
+        );
+
+        wait(&mut ecs_world, 5);
+        update_match(&mut ecs_world, &[]);
+        assert_eq!(
+            phase(&ecs_world),
+            MatchPhase::InProgress {
+                remaining_seconds: Some(600.0)
+            }
+        );
+    }
+
+    #[test]
+    fn the_team_reaching_the_kill_limit_wins() {
+        let mut ecs_world = create_world(|config| config.kill_limit = Some(2));
+        let left_player = add_player(&mut ecs_world, Team::Left);
+        add_player(&mut ecs_world, Team::Right);
+        let minion = add_char(
+            &mut ecs_world,
+            CharType::Minion,
+            JobId::MeleeMinion,
+            Team::Left,
+        );
+        start_match(&mut ecs_world);
+
+        update_match(&mut ecs_world, &[left_player, minion]);
+        assert_eq!(
+            score(&ecs_world),
+            MatchScore {
+                left_team_kills: 0,
+                right_team_kills: 1
+            }
+        );
+        assert!(!is_post_game_won_by(&ecs_world, Some(Team::Right)));
+
+        update_match(&mut ecs_world, &[left_player]);
+        assert!(is_post_game_won_by(&ecs_world, Some(Team::Right)));
+    }
+
+    #[test]
+    fn the_leading_team_wins_when_the_time_is_over() {
+        let mut ecs_world = create_world(|config| {
+            config.kill_limit = None;
+            config.time_limit_seconds = Some(60.0);
+        });
+        add_player(&mut ecs_world, Team::Left);
+        let right_player = add_player(&mut ecs_world, Team::Right);
+        start_match(&mut ecs_world);
+
+        update_match(&mut ecs_world, &[right_player]);
+        wait(&mut ecs_world, 59);
+        update_match(&mut ecs_world, &[]);
+        assert!(!is_post_game_won_by(&ecs_world, Some(Team::Left)));
+
+        wait(&mut ecs_world, 1);
+        update_match(&mut ecs_world, &[]);
+        assert!(is_post_game_won_by(&ecs_world, Some(Team::Left)));
+    }
+
+    #[test]
+    fn a_draw_has_no_winner() {
+        let mut ecs_world = create_world(|config| config.time_limit_seconds = Some(60.0));
+        let left_player = add_player(&mut ecs_world, Team::Left);
+        let right_player = add_player(&mut ecs_world, Team::Right);
+        start_match(&mut ecs_world);
+
+        update_match(&mut ecs_world, &[left_player, right_player]);
+        wait(&mut ecs_world, 60);
+        update_match(&mut ecs_world, &[]);
+        assert!(is_post_game_won_by(&ecs_world, None));
+    }
+
+    #[test]
+    fn the_remaining_team_wins_when_the_other_one_leaves() {
+        let mut ecs_world = create_world(|_config| {});
+        add_player(&mut ecs_world, Team::Left);
+        let right_player = add_player(&mut ecs_world, Team::Right);
+        start_match(&mut ecs_world);
+
+        remove_char(&mut ecs_world, right_player);
+        update_match(&mut ecs_world, &[]);
+        assert!(is_post_game_won_by(&ecs_world, Some(Team::Left)));
+    }
+
+    #[test]
+    fn the_team_losing_its_structure_loses() {
+        let mut ecs_world = create_world(|config| config.structure_job = Some(JobId::Barricade));
+        add_player(&mut ecs_world, Team::Left);
+        add_player(&mut ecs_world, Team::Right);
+        let structure = add_char(
+            &mut ecs_world,
+            CharType::Guard,
+            JobId::Barricade,
+            Team::Right,
+        );
+        start_match(&mut ecs_world);
+
+        update_match(&mut ecs_world, &[structure]);
+        assert!(is_post_game_won_by(&ecs_world, Some(Team::Left)));
+        assert_eq!(score(&ecs_world), MatchScore::default());
+    }
+
+    #[test]
+    fn a_new_lobby_follows_the_post_game() {
+        let mut ecs_world = create_world(|config| config.post_game_seconds = 15.0);
+        add_player(&mut ecs_world, Team::Left);
+        let right_player = add_player(&mut ecs_world, Team::Right);
+        start_match(&mut ecs_world);
+        remove_char(&mut ecs_world, right_player);
+        update_match(&mut ecs_world, &[]);
+
+        wait(&mut ecs_world, 14);
+        update_match(&mut ecs_world, &[]);
+        assert!(is_post_game_won_by(&ecs_world, Some(Team::Left)));
+
+        wait(&mut ecs_world, 1);
+        update_match(&mut ecs_world, &[]);
+        assert_eq!(
+            phase(&ecs_world),
+            MatchPhase::Lobby {
+                required_players: 2
+            }
+        );
+    }
+
+    #[test]
+    fn the_teams_are_kept_balanced() {
+        let mut ecs_world = create_world(|config| config.max_team_imbalance = 1);
+        assert_eq!(choose_team(None, &ecs_world), Team::Left);
+        assert_eq!(choose_team(Some(Team::Right), &ecs_world), Team::Right);
+
+        add_player(&mut ecs_world, Team::Left);
+        assert_eq!(choose_team(None, &ecs_world), Team::Right);
+        assert_eq!(choose_team(Some(Team::Left), &ecs_world), Team::Right);
+        assert_eq!(choose_team(Some(Team::Right), &ecs_world), Team::Right);
+
+        // only the players are counted
+        add_char(
+            &mut ecs_world,
+            CharType::Minion,
+            JobId::MeleeMinion,
+            Team::Right,
+        );
+        add_char(
+            &mut ecs_world,
+            CharType::Minion,
+            JobId::MeleeMinion,
+            Team::Right,
+        );
+        assert_eq!(choose_team(Some(Team::Left), &ecs_world), Team::Right);
+
+        add_player(&mut ecs_world, Team::Right);
+        assert_eq!(choose_team(Some(Team::Right), &ecs_world), Team::Right);
+        assert_eq!(choose_team(None, &ecs_world), Team::Left);
+    }
+}
@@ -0,0 +1,292 @@
+//! The intentions of the clients are checked before they are given to their controllers.
+//! Every client can send a limited number of them per second, and the client is kicked
+//! when too many of them are rejected in a short time.
+use crate::client_id_to_server;
+use rustarok_common::common::Vec2;
+use rustarok_common::components::char::{
+    ControllerEntityId, LocalCharEntityId, LocalCharStateComp, ServerEntityId,
+    StaticCharDataComponent,
+};
+use rustarok_common::components::controller::{
+    ControllerComponent, PlayerIntention, ToServerPlayerIntention,
+};
+use rustarok_common::components::skills::Skills;
+use rustarok_common::map::MapWalkingInfo;
+use rustarok_common::packets::from_server::IntentionRejection;
+use specs::prelude::*;
+use std::time::{Duration, Instant};
+
+/// Refills `per_second` tokens in every second, up to `capacity`
+pub struct TokenBucket {
+    capacity: f32,
+    per_second: f32,
+    tokens: f32,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(capacity: u32, per_second: f32) -> TokenBucket {
+        TokenBucket {
+            capacity: capacity as f32,
+            per_second,
+            tokens: capacity as f32,
+            refilled_at: Instant::now(),
+        }
+    }
+
+    pub fn try_take(&mut self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.refilled_at);
+        self.tokens = (self.tokens + elapsed.as_secs_f32() * self.per_second).min(self.capacity);
+        self.refilled_at = now;
+        return if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        };
+    }
+}
+
+/// The rejections are counted from the first one in the window
+pub struct RejectionCounter {
+    count: u32,
+    window_started: Instant,
+}
+
+impl RejectionCounter {
+    pub fn new() -> RejectionCounter {
+        RejectionCounter {
+            count: 0,
+            window_started: Instant::now(),
+        }
+    }
+
+    /// Returns the number of rejections in the current window
+    pub fn add(&mut self, window: Duration, now: Instant) -> u32 {
+        if now.saturating_duration_since(self.window_started) > window {
+            self.count = 0;
+            self.window_started = now;
+        }
+        self.count += 1;
+        return self.count;
+    }
+}
+
+/// Converts the intention to the local entity ids if it is valid
+pub fn validate_intention(
+    ecs_world: &specs::World,
+    controller_id: ControllerEntityId,
+    intention: ToServerPlayerIntention,
+) -> Result<PlayerIntention, IntentionRejection> {
+    let char_id = ecs_world
+        .read_storage::<ControllerComponent>()
+        .get(controller_id.into())
+        .and_then(|controller| controller.controlled_entity)
+        .ok_or(IntentionRejection::NoCharacter)?;
+    let map = ecs_world.read_resource::<MapWalkingInfo>();
+    return match intention {
+        ToServerPlayerIntention::MoveTo(pos) => {
+            check_pos_on_map(pos, &map)?;
+            Ok(PlayerIntention::MoveTo(pos))
+        }
+        ToServerPlayerIntention::MoveTowardsMouse(dir) => {
+            check_finite(dir)?;
+            Ok(PlayerIntention::MoveTowardsMouse(dir))
+        }
+        ToServerPlayerIntention::AttackTowards(pos) => {
+            check_pos_on_map(pos, &map)?;
+            Ok(PlayerIntention::AttackTowards(pos))
+        }
+        ToServerPlayerIntention::Attack(target_id) => {
+            let target_id = check_enemy_target(ecs_world, char_id, target_id)?;
+            Ok(PlayerIntention::Attack(target_id))
+        }
+        ToServerPlayerIntention::Casting(Skills::AttackMove, _is_self_cast, pos, _target_id) => {
+            // it is an order of every job rather than a real skill
+            check_pos_on_map(pos, &map)?;
+            Ok(PlayerIntention::AttackTowards(pos))
+        }
+        ToServerPlayerIntention::Casting(skill, is_self_cast, pos, target_id) => {
+            // the skills check their range and the team of their target
+            let owns_skill = ecs_world
+                .read_storage::<StaticCharDataComponent>()
+                .get(char_id.into())
+                .map(|it| it.job_id.skills().contains(&skill))
+                .unwrap_or(false);
+            if !owns_skill {
+                return Err(IntentionRejection::SkillNotOwned);
+            }
+            check_finite(pos)?;
+            let target_id = match target_id {
+                Some(target_id) => Some(check_existing_target(ecs_world, target_id)?),
+                None => None,
+            };
+            Ok(PlayerIntention::Casting(
+                skill,
+                is_self_cast,
+                pos,
+                target_id,
+            ))
+        }
+    };
+}
+
+fn check_finite(v: Vec2) -> Result<(), IntentionRejection> {
+    return if v.x.is_finite() && v.y.is_finite() {
+        Ok(())
+    } else {
+        Err(IntentionRejection::InvalidNumber)
+    };
+}
+
+fn check_pos_on_map(pos: Vec2, map: &MapWalkingInfo) -> Result<(), IntentionRejection> {
+    check_finite(pos)?;
+    return if map.contains_pos(pos) {
+        Ok(())
+    } else {
+        Err(IntentionRejection::OutOfMap)
+    };
+}
+
+/// The storages check that the entity with this generation is still alive
+fn check_existing_target(
+    ecs_world: &specs::World,
+    target_id: ServerEntityId,
+) -> Result<LocalCharEntityId, IntentionRejection> {
+    let target_id = client_id_to_server(target_id);
+    return if ecs_world
+        .read_storage::<LocalCharStateComp>()
+        .get(target_id.into())
+        .is_some()
+    {
+        Ok(target_id)
+    } else {
+        Err(IntentionRejection::TargetNotFound)
+    };
+}
+
+fn check_enemy_target(
+    ecs_world: &specs::World,
+    char_id: LocalCharEntityId,
+    target_id: ServerEntityId,
+) -> Result<LocalCharEntityId, IntentionRejection> {
+    let target_id = check_existing_target(ecs_world, target_id)?;
+    if target_id == char_id {
+        return Err(IntentionRejection::TargetNotEnemy);
+    }
+    let char_state_storage = ecs_world.read_storage::<LocalCharStateComp>();
+    if char_state_storage
+        .get(target_id.into())
+        .map(|it| it.state().is_dead())
+        .unwrap_or(true)
+    {
+        return Err(IntentionRejection::TargetIsDead);
+    }
+    let static_data_storage = ecs_world.read_storage::<StaticCharDataComponent>();
+    let can_attack = match (
+        static_data_storage.get(char_id.into()),
+        static_data_storage.get(target_id.into()),
+    ) {
+        (Some(attacker), Some(target)) => attacker.team.can_attack(target.team),
+        _ => false,
+    };
+    return if can_attack {
+        Ok(target_id)
+    } else {
+        Err(IntentionRejection::TargetNotEnemy)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server_config::ServerConfig;
+    use rustarok_common::components::char::JobId;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn the_bucket_allows_bursts_up_to_its_capacity() {
+        let mut bucket = TokenBucket::new(3, 2.0);
+        let now = bucket.refilled_at;
+        assert!(bucket.try_take(now));
+        assert!(bucket.try_take(now));
+        assert!(bucket.try_take(now));
+        assert!(!bucket.try_take(now));
+    }
+
+    #[test]
+    fn the_bucket_is_refilled_by_the_rate() {
+        let mut bucket = TokenBucket::new(3, 2.0);
+        let start = bucket.refilled_at;
+        while bucket.try_take(start) {}
+        assert!(!bucket.try_take(start + Duration::from_millis(400)));
+        assert!(bucket.try_take(start + Duration::from_millis(500)));
+        assert!(!bucket.try_take(start + Duration::from_millis(500)));
+        // it is not refilled above its capacity
+        let later = start + Duration::from_secs(60);
+        let taken = (0..10).take_while(|_| bucket.try_take(later)).count();
+        assert_eq!(taken, 3);
+    }
+
+    #[test]
+    fn the_rejections_are_counted_in_the_window() {
+        let window = Duration::from_secs(10);
+        let mut counter = RejectionCounter::new();
+        let start = counter.window_started;
+        assert_eq!(counter.add(window, start), 1);
+        assert_eq!(counter.add(window, start + Duration::from_secs(5)), 2);
+        assert_eq!(counter.add(window, start + window), 3);
+        // a new window starts with the first rejection after the old one
+        let next = start + Duration::from_secs(11);
+        assert_eq!(counter.add(window, next), 1);
+        assert_eq!(counter.add(window, next + Duration::from_secs(1)), 2);
+    }
+
+    #[test]
+    fn the_client_is_kicked_after_too_many_rejections_in_the_window() {
+        let mut config = ServerConfig::from_toml(include_str!("../server-conf.toml"))
+            .unwrap()
+            .input_validation;
+        config.kick_after_rejections = 5;
+        config.rejection_window_seconds = 10.0;
+        let mut counter = RejectionCounter::new();
+        let start = counter.window_started;
+        let slow_kick = (0..20)
+            .map(|i| {
+                counter.add(
+                    config.rejection_window(),
+                    start + Duration::from_secs(i * 3),
+                )
+            })
+            .any(|rejections| config.should_kick(rejections));
+        assert!(!slow_kick);
+
+        let mut counter = RejectionCounter::new();
+        let kicked_at = (0..10)
+            .map(|i| {
+                counter.add(
+                    config.rejection_window(),
+                    start + Duration::from_millis(i * 100),
+                )
+            })
+            .position(|rejections| config.should_kick(rejections));
+        assert_eq!(kicked_at, Some(4));
+
+        config.kick_after_rejections = 0;
+        assert!(!config.should_kick(1000));
+    }
+
+    #[test]
+    fn every_skill_belongs_to_a_playable_job() {
+        for skill in Skills::iter().filter(|it| *it != Skills::AttackMove) {
+            assert!(
+                JobId::iter().any(|job| job.is_playable() && job.skills().contains(&skill)),
+                "{:?}",
+                skill
+            );
+        }
+        for job in JobId::iter().filter(|it| !it.is_playable()) {
+            assert!(job.skills().is_empty(), "{:?}", job);
+        }
+    }
+}
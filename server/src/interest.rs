@@ -0,0 +1,303 @@
+use crate::server_config::ServerConfig;
+use crate::{prepare_charsnapshot_for_sending, prepare_entity_id_for_sending, RemoteClient};
+use rustarok_common::common::{SimulationTick, Vec2};
+use rustarok_common::components::char::{
+    LocalCharEntityId, LocalCharStateComp, StaticCharDataComponent,
+};
+use rustarok_common::components::controller::ControllerComponent;
+use rustarok_common::packets::from_server::{FromServerPacket, RoomId};
+use rustarok_common::packets::snapshot_delta::{
+    CharStateDelta, EncodedWorldSnapshot, SNAPSHOT_HISTORY_LEN,
+};
+use rustarok_common::packets::to_server::ToServerPacket;
+use rustarok_common::packets::{PacketHandlerThread, SocketId};
+use specs::prelude::*;
+use std::collections::{HashMap, VecDeque};
+
+/// Entities leave the area of interest only when they are farther than
+/// `view_distance * LEAVE_DISTANCE_MULTIPLIER`, so the ones at the border don't flicker
+const LEAVE_DISTANCE_MULTIPLIER: f32 = 1.2;
+
+/// Uniform grid over the positions of the characters, it is rebuilt in every frame
+pub struct SpatialGrid {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<(LocalCharEntityId, Vec2)>>,
+}
+
+impl SpatialGrid {
+    pub fn new(cell_size: f32) -> SpatialGrid {
+        SpatialGrid {
+            cell_size,
+            cells: HashMap::with_capacity(64),
+        }
+    }
+
+    pub fn rebuild(&mut self, ecs_world: &specs::World) {
+        for cell in self.cells.values_mut() {
+            cell.clear();
+        }
+        for (char_id, char_state) in (
+            &ecs_world.entities(),
+            &ecs_world.read_storage::<LocalCharStateComp>(),
+        )
+            .join()
+        {
+            let pos = char_state.pos();
+            let cell = self.cell_of(pos);
+            self.cells
+                .entry(cell)
+                .or_insert_with(Vec::new)
+                .push((LocalCharEntityId::new(char_id), pos));
+        }
+    }
+
+    fn cell_of(&self, pos: Vec2) -> (i32, i32) {
+        (
+            (pos.x / self.cell_size).floor() as i32,
+            (pos.y / self.cell_size).floor() as i32,
+        )
+    }
+
+    /// Collects the characters which are not farther than `radius` from `center`
+    pub fn query(&self, center: Vec2, radius: f32, out: &mut Vec<LocalCharEntityId>) {
+        let (min_x, min_y) = self.cell_of(center - Vec2::new(radius, radius));
+        let (max_x, max_y) = self.cell_of(center + Vec2::new(radius, radius));
+        for x in min_x..=max_x {
+            for y in min_y..=max_y {
+                if let Some(cell) = self.cells.get(&(x, y)) {
+                    for (char_id, pos) in cell {
+                        if (pos - center).magnitude() <= radius {
+                            out.push(*char_id);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The entities which were sent to a client in her last `SNAPSHOT_HISTORY_LEN` snapshots.
+/// The client has a baseline state only for these, see `encode_visible_states`.
+pub struct SentEntities {
+    snapshots: VecDeque<(SimulationTick, Vec<LocalCharEntityId>)>,
+}
+
+impl SentEntities {
+    pub fn new() -> SentEntities {
+        SentEntities {
+            snapshots: VecDeque::with_capacity(SNAPSHOT_HISTORY_LEN),
+        }
+    }
+
+    pub fn push(&mut self, tick: SimulationTick, entities: &[LocalCharEntityId]) {
+        if self.snapshots.len() == SNAPSHOT_HISTORY_LEN {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back((tick, entities.to_vec()));
+    }
+
+    pub fn get(&self, tick: SimulationTick) -> Option<&[LocalCharEntityId]> {
+        self.snapshots
+            .iter()
+            .rev()
+            .find(|(sent_tick, _)| *sent_tick == tick)
+            .map(|(_, entities)| entities.as_slice())
+    }
+
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+    }
+}
+
+/// The states of the visible entities in the order they were sent to the client.
+/// An entity is delta encoded only if it was sent to her in the baseline too,
+/// e.g. the ones which have entered her area since then are sent in full.
+pub fn encode_visible_states(
+    snapshot: &EncodedWorldSnapshot,
+    visible_entities: &[LocalCharEntityId],
+    baseline: Option<&EncodedWorldSnapshot>,
+    sent_entities: &SentEntities,
+) -> Vec<CharStateDelta> {
+    let sent_in_baseline = baseline
+        .and_then(|baseline| sent_entities.get(baseline.tick))
+        .unwrap_or(&[]);
+    return visible_entities
+        .iter()
+        .filter_map(|char_id| {
+            let id = prepare_entity_id_for_sending(*char_id);
+            let baseline_state = baseline
+                .filter(|_| sent_in_baseline.contains(char_id))
+                .and_then(|baseline| baseline.get(&id));
+            snapshot
+                .get(&id)
+                .map(|state| state.delta_from(id, baseline_state))
+        })
+        .collect();
+}
+
+pub fn get_controlled_char(
+    remote_client: &RemoteClient,
+    ecs_world: &specs::World,
+) -> Option<LocalCharEntityId> {
+    let controller_id = remote_client.controller_id?;
+    return ecs_world
+        .read_storage::<ControllerComponent>()
+        .get(controller_id.into())
+        .and_then(|controller| controller.controlled_entity);
+}
+
+/// Sends `EntityAppeared` and `EntityDisappeared` packets to the clients as the characters
+/// come into and leave the area around their controlled character.
+/// The snapshots contain only the entities which are visible to the client.
+pub fn update_areas_of_interest(
+    packet_handler_thread: &PacketHandlerThread<ToServerPacket, FromServerPacket>,
+    room_id: RoomId,
+    remote_clients: &mut [Option<RemoteClient>],
+    spatial_grid: &SpatialGrid,
+    ecs_world: &specs::World,
+) {
+    let view_distance = ecs_world
+        .read_resource::<ServerConfig>()
+        .interest
+        .view_distance;
+    let leave_distance = view_distance * LEAVE_DISTANCE_MULTIPLIER;
+    let char_state_storage = ecs_world.read_storage::<LocalCharStateComp>();
+    let static_data_storage = ecs_world.read_storage::<StaticCharDataComponent>();
+    let mut in_range = Vec::with_capacity(64);
+    for remote_client in remote_clients.iter_mut() {
+        let remote_client = if let Some(remote_client) = remote_client {
+            remote_client
+        } else {
+            continue;
+        };
+        if !remote_client.is_in(room_id) {
+            continue;
+        }
+        let controlled_char =
+            if let Some(controlled_char) = get_controlled_char(remote_client, ecs_world) {
+                controlled_char
+            } else {
+                continue;
+            };
+        let center = if let Some(char_state) = char_state_storage.get(controlled_char.into()) {
+            char_state.pos()
+        } else {
+            continue;
+        };
+        let socket_id = remote_client.socket_id;
+
+        remote_client.visible_entities.retain(|visible_char| {
+            let still_visible = *visible_char == controlled_char
+                || char_state_storage
+                    .get((*visible_char).into())
+                    .map(|char_state| (char_state.pos() - center).magnitude() <= leave_distance)
+                    .unwrap_or(false);
+            if !still_visible {
+                packet_handler_thread.send(
+                    socket_id,
+                    FromServerPacket::EntityDisappeared(prepare_entity_id_for_sending(
+                        *visible_char,
+                    )),
+                );
+            }
+            still_visible
+        });
+
+        in_range.clear();
+        spatial_grid.query(center, view_distance, &mut in_range);
+        for char_id in in_range.drain(..) {
+            if remote_client.visible_entities.contains(&char_id) {
+                continue;
+            }
+            if let (Some(char_state), Some(static_data)) = (
+                char_state_storage.get(char_id.into()),
+                static_data_storage.get(char_id.into()),
+            ) {
+                packet_handler_thread.send(
+                    socket_id,
+                    FromServerPacket::EntityAppeared {
+                        id: prepare_entity_id_for_sending(char_id),
+                        name: static_data.name.clone(),
+                        team: static_data.team,
+                        typ: static_data.typ.clone(),
+                        outlook: static_data.outlook.clone(),
+                        job_id: static_data.job_id,
+                        has_falcon: static_data.has_falcon,
+                        state: prepare_charsnapshot_for_sending(char_state.clone()),
+                    },
+                );
+                remote_client.visible_entities.push(char_id);
+            }
+        }
+    }
+}
+
+/// The clients whose area of interest contains `pos`
+pub fn clients_seeing(
+    pos: Vec2,
+    room_id: RoomId,
+    remote_clients: &[Option<RemoteClient>],
+    ecs_world: &specs::World,
+    out: &mut Vec<SocketId>,
+) {
+    let view_distance = ecs_world
+        .read_resource::<ServerConfig>()
+        .interest
+        .view_distance;
+    let char_state_storage = ecs_world.read_storage::<LocalCharStateComp>();
+    for remote_client in remote_clients.iter() {
+        if let Some(remote_client) = remote_client.as_ref().filter(|it| it.is_in(room_id)) {
+            let sees_it = get_controlled_char(remote_client, ecs_world)
+                .and_then(|char_id| char_state_storage.get(char_id.into()))
+                .map(|char_state| (char_state.pos() - pos).magnitude() <= view_distance)
+                .unwrap_or(false);
+            if sees_it {
+                out.push(remote_client.socket_id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustarok_common::packets::snapshot_delta::EncodedCharState;
+
+    fn encoded_state(hp: i32) -> EncodedCharState {
+        let mut state = LocalCharStateComp::default();
+        state.hp = hp;
+        return EncodedCharState::encode(&prepare_charsnapshot_for_sending(state));
+    }
+
+    #[test]
+    fn entities_entering_the_area_after_the_baseline_are_sent_in_full() {
+        let mut ecs_world = specs::World::new();
+        let own_char = LocalCharEntityId::new(ecs_world.create_entity().build());
+        let newcomer = LocalCharEntityId::new(ecs_world.create_entity().build());
+        let mut sent_entities = SentEntities::new();
+
+        // the newcomer exists at the baseline, but it is outside of her area
+        let mut baseline = EncodedWorldSnapshot::new(SimulationTick::from_u64(10));
+        baseline.push(prepare_entity_id_for_sending(own_char), encoded_state(100));
+        baseline.push(prepare_entity_id_for_sending(newcomer), encoded_state(100));
+        sent_entities.push(baseline.tick, &[own_char]);
+
+        let mut snapshot = EncodedWorldSnapshot::new(SimulationTick::from_u64(11));
+        snapshot.push(prepare_entity_id_for_sending(own_char), encoded_state(100));
+        snapshot.push(prepare_entity_id_for_sending(newcomer), encoded_state(100));
+
+        let entries = encode_visible_states(
+            &snapshot,
+            &[own_char, newcomer],
+            Some(&baseline),
+            &sent_entities,
+        );
+        assert_eq!(entries.len(), 2);
+        // nothing has changed, so only the full state can be decoded without a baseline
+        assert!(EncodedCharState::apply_delta(None, &entries[0]).is_err());
+        assert_eq!(
+            EncodedCharState::apply_delta(None, &entries[1]).unwrap(),
+            encoded_state(100)
+        );
+    }
+}
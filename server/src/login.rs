@@ -0,0 +1,161 @@
+//! The first packet of a client is `Welcome`, which is answered with `LoginAccepted`
+//! or `LoginRejected`. The protocol version is checked by the frames of the packets.
+use crate::room::{find_room, send_room_init, Room};
+use crate::session::Sessions;
+use crate::{disconnect_client, AppConfig, RemoteClient};
+use rustarok_common::components::char::JobId;
+use rustarok_common::packets::from_server::{FromServerPacket, LoginRejectReason, SessionToken};
+use rustarok_common::packets::to_server::ToServerPacket;
+use rustarok_common::packets::{PacketHandlerThread, SocketId};
+
+const MIN_NAME_LEN: usize = 3;
+const MAX_NAME_LEN: usize = 16;
+
+pub fn validate_name(name: &str) -> Result<(), LoginRejectReason> {
+    let len = name.chars().count();
+    let valid_chars = name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    return if len >= MIN_NAME_LEN && len <= MAX_NAME_LEN && valid_chars {
+        Ok(())
+    } else {
+        Err(LoginRejectReason::InvalidName)
+    };
+}
+
+/// The names of the disconnected players are reserved until their sessions expire
+fn is_name_taken(name: &str, remote_clients: &[Option<RemoteClient>], sessions: &Sessions) -> bool {
+    let taken_by_client = remote_clients
+        .iter()
+        .filter_map(|it| it.as_ref())
+        .any(|it| it.logged_in && it.name.eq_ignore_ascii_case(name));
+    return taken_by_client || sessions.is_name_taken(name);
+}
+
+pub fn handle_welcome(
+    client_socket: SocketId,
+    name: String,
+    job_id: JobId,
+    session_token: Option<SessionToken>,
+    remote_clients: &mut [Option<RemoteClient>],
+    rooms: &mut [Room],
+    sessions: &mut Sessions,
+    packet_handler_thread: &mut PacketHandlerThread<ToServerPacket, FromServerPacket>,
+    config: &AppConfig,
+) {
+    if remote_clients[client_socket.as_usize()]
+        .as_ref()
+        .unwrap()
+        .logged_in
+    {
+        // she could change her name or take over a session
+        log::warn!(
+            "Client({:?}) is already logged in, Welcome is ignored",
+            client_socket
+        );
+        return;
+    }
+    if let Some(session_token) = session_token {
+        // the old connection might not have been detected as broken yet
+        let old_connection = remote_clients.iter().find_map(|it| {
+            it.as_ref()
+                .filter(|it| it.socket_id != client_socket && it.session_token == session_token)
+                .map(|it| it.socket_id)
+        });
+        if let Some(old_socket_id) = old_connection {
+            disconnect_client(
+                remote_clients,
+                old_socket_id,
+                rooms,
+                sessions,
+                packet_handler_thread,
+            );
+        }
+        // the rooms of the parked sessions are not removed
+        if let Some(session) = sessions.resume(session_token) {
+            log::info!("{} has reconnected", session.name);
+            let remote_client = remote_clients[client_socket.as_usize()].as_mut().unwrap();
+            remote_client.name = session.name;
+            remote_client.session_token = session_token;
+            remote_client.room_id = Some(session.room_id);
+            remote_client.resumed_controller_id = Some(session.controller_id);
+            accept(client_socket, remote_clients, packet_handler_thread);
+            if let Some(room) = find_room(rooms, Some(session.room_id)) {
+                send_room_init(client_socket, room, packet_handler_thread, config);
+            }
+            return;
+        }
+        log::info!("{}'s session has expired, she starts a new one", name);
+    }
+
+    let validation_result = validate_name(&name).and_then(|_| {
+        if !job_id.is_playable() {
+            Err(LoginRejectReason::InvalidJob)
+        } else if is_name_taken(&name, remote_clients, sessions) {
+            Err(LoginRejectReason::NameIsTaken)
+        } else {
+            Ok(())
+        }
+    });
+    match validation_result {
+        Ok(()) => {
+            log::info!("{} welcomed ^^", name);
+            let remote_client = remote_clients[client_socket.as_usize()].as_mut().unwrap();
+            remote_client.name = name;
+            remote_client.job_id = job_id;
+            accept(client_socket, remote_clients, packet_handler_thread);
+        }
+        Err(reason) => {
+            log::info!("{}'s login has been rejected: {:?}", name, reason);
+            reject(client_socket, reason, remote_clients, packet_handler_thread);
+        }
+    }
+}
+
+/// Without a resumed session she chooses a room after it
+fn accept(
+    client_socket: SocketId,
+    remote_clients: &mut [Option<RemoteClient>],
+    packet_handler_thread: &PacketHandlerThread<ToServerPacket, FromServerPacket>,
+) {
+    let remote_client = remote_clients[client_socket.as_usize()].as_mut().unwrap();
+    remote_client.logged_in = true;
+    packet_handler_thread.send(
+        client_socket,
+        FromServerPacket::LoginAccepted {
+            name: remote_client.name.clone(),
+            session_token: remote_client.session_token,
+            resumed_room: remote_client.room_id,
+        },
+    );
+}
+
+fn reject(
+    client_socket: SocketId,
+    reason: LoginRejectReason,
+    remote_clients: &mut [Option<RemoteClient>],
+    packet_handler_thread: &mut PacketHandlerThread<ToServerPacket, FromServerPacket>,
+) {
+    packet_handler_thread.send(client_socket, FromServerPacket::LoginRejected(reason));
+    packet_handler_thread.close(client_socket);
+    remote_clients[client_socket.as_usize()] = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_names_are_validated() {
+        assert!(validate_name("abc").is_ok());
+        assert!(validate_name("Player_1-x").is_ok());
+        assert!(validate_name("abcdefghijklmnop").is_ok());
+        assert!(validate_name("").is_err());
+        assert!(validate_name("ab").is_err());
+        assert!(validate_name("abcdefghijklmnopq").is_err());
+        assert!(validate_name("two words").is_err());
+        assert!(validate_name("name\n").is_err());
+        assert!(validate_name("Ãrpád").is_err());
+        assert!(validate_name("<script>").is_err());
+    }
+}
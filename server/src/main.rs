@@ -14,9 +14,6 @@
 #[macro_use]
 extern crate specs_derive;
 
-use std::fs::File;
-use std::io::BufRead;
-use std::io::BufReader;
 use std::net::{SocketAddr, TcpListener, TcpStream};
 use std::str::FromStr;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
@@ -27,45 +24,77 @@ use specs;
 use specs::prelude::*;
 use strum;
 
-use rustarok_common::attack::{ApplyForceComponent, AreaAttackComponent, HpModificationRequest};
 use rustarok_common::char_attr::CharAttributes;
-use rustarok_common::common::{
-    measure_time, v2, EngineTime, LocalTime, ServerTime, SimulationTick,
-};
+use rustarok_common::common::{v2, EngineTime, LocalTime, ServerTime, SimulationTick, Vec2};
 use rustarok_common::components::char::{
     create_common_player_entity, CharOutlook, CharType, ControllerEntityId, EntityTarget, JobId,
     LocalCharEntityId, LocalCharStateComp, ServerCharState, ServerEntityId, Sex,
     StaticCharDataComponent, Team,
 };
-use rustarok_common::components::controller::{
-    ControllerComponent, PlayerIntention, ToServerPlayerIntention,
-};
+use rustarok_common::components::controller::{ControllerComponent, PlayerIntention};
 use rustarok_common::components::job_ids::JobSpriteId;
-use rustarok_common::config::CommonConfigs;
-use rustarok_common::console::CommandArguments;
-use rustarok_common::grf::asset_loader::CommonAssetLoader;
-use rustarok_common::map::MapWalkingInfo;
-use rustarok_common::packets::from_server::{FromServerPacket, ServerEntityState};
+use rustarok_common::console::{ConsoleCommandError, PermissionLevel};
+use rustarok_common::lag_compensation::LagCompensation;
+use rustarok_common::packets::from_server::{
+    FromServerPacket, IntentionRejection, RoomId, SessionToken,
+};
+use rustarok_common::packets::snapshot_delta::{
+    EncodedCharState, EncodedWorldSnapshot, SnapshotHistory,
+};
 use rustarok_common::packets::to_server::ToServerPacket;
+use rustarok_common::packets::udp::UdpListener;
 use rustarok_common::packets::{NetworkTrafficEvent, PacketHandlerThread, SocketId};
-use rustarok_common::systems::char_state_sys::CharacterStateUpdateSystem;
+use rustarok_common::pathfinding::WalkingPathComponent;
 
-use crate::console_cmd::execute_console_cmd;
-use crate::controller_intention_to_char_target::ControllerIntentionToCharTargetSystem;
-use crate::server_config::{load_common_configs, ServerConfig};
+use crate::areas::{apply_area_effects, AreaEffectComponent};
+use crate::attack::apply_hp_modifications;
+use crate::config_watcher::{reload_common_configs, ConfigWatcher};
+use crate::console_cmd::{cmd_admin, execute_console_cmd};
+use crate::death::handle_dead_characters;
+use crate::forced_movement::{apply_forces, move_forced_characters, ForcedMovementComponent};
+use crate::game_match::{choose_team, update_match, MatchState};
+use crate::intention_validation::{validate_intention, RejectionCounter, TokenBucket};
+use crate::interest::{clients_seeing, encode_visible_states, get_controlled_char, SentEntities};
+use crate::map_export::export_maps;
+use crate::minion_ai::{spawn_minion_waves, MinionControllerComponent};
+use crate::operator_console::create_log_sink;
+use crate::replay::{controller_index, entity_index, record_event, run_replay, ReplayEvent};
+use crate::room::{
+    create_room, find_room, join_room, remove_empty_rooms, Room, RoomAssets, DEFAULT_ROOM_NAME,
+};
+use crate::server_config::ServerConfig;
+use crate::session::Sessions;
+use crate::skills::{
+    execute_finished_skill_castings, update_phase_prism_projectiles, PhasePrismProjectileComponent,
+};
+use crate::statuses::apply_status_changes;
+use crate::telemetry::{measure, ClientStats, Telemetry};
+use crate::turret_ai::{TurretComponent, TurretControllerComponent};
 
+mod areas;
+mod attack;
 mod components;
+mod config_watcher;
 mod console_cmd;
 mod controller_intention_to_char_target;
+mod death;
+mod forced_movement;
+mod game_match;
+mod intention_validation;
+mod interest;
+mod login;
+mod map_export;
+mod minion_ai;
+mod operator_console;
+mod replay;
+mod room;
 #[path = "config.rs"]
 mod server_config;
-
-pub const SIMULATION_FREQ: usize = 30;
-pub const SIMULATION_DURATION_MS: usize = 1000 / SIMULATION_FREQ;
-pub const MAX_SECONDS_ALLOWED_FOR_SINGLE_SIMULATION_FRAME: f32 =
-    SIMULATION_DURATION_MS as f32 / 1000.0;
-pub const MAX_DURATION_ALLOWED_FOR_SINGLE_SIMULATION_FRAME: Duration =
-    Duration::from_millis(SIMULATION_DURATION_MS as u64);
+mod session;
+mod skills;
+mod statuses;
+mod telemetry;
+mod turret_ai;
 
 #[derive(Debug, Deserialize)]
 pub struct AppConfig {
@@ -73,8 +102,13 @@ pub struct AppConfig {
     pub log_level: String,
     pub start_pos_x: f32,
     pub start_pos_y: f32,
-    pub grf_paths: Vec<String>,
+    /// only required for exporting the maps, or if the map file does not exist
+    pub grf_paths: Option<Vec<String>>,
+    /// directory of the exported map files
+    pub map_dir: String,
     pub server_port: u16,
+    /// in bytes, per connection
+    pub max_socket_buffer_size: usize,
 }
 
 impl AppConfig {
@@ -85,14 +119,18 @@ impl AppConfig {
     }
 }
 
-fn bind_server(port: u16) -> TcpListener {
-    let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], port))).unwrap();
+fn bind_server(addr: SocketAddr) -> TcpListener {
+    let listener = TcpListener::bind(addr).unwrap();
     listener
         .set_nonblocking(true)
         .expect("Cannot set non-blocking");
     return listener;
 }
 
+fn bind_udp_server(addr: SocketAddr) -> UdpListener {
+    return UdpListener::bind(addr).unwrap();
+}
+
 fn accept_connection(listener: &mut TcpListener) -> Option<(TcpStream, SocketAddr)> {
     return match listener.accept() {
         Ok(s) => Some(s),
@@ -112,6 +150,45 @@ struct RemoteClient {
     last_action_tick: u64,
     last_command_id: u32,
     name: String,
+    // the client can't keep up with the outgoing data, don't send snapshots for a while
+    congested: bool,
+    // the last snapshot the client has acknowledged, the deltas are computed against it
+    snapshot_baseline: Option<SimulationTick>,
+    // the characters in the client's area of interest, in the order they were sent to her.
+    // The first one is her own character.
+    visible_entities: Vec<LocalCharEntityId>,
+    // the visible entities of her last snapshots, only these can be delta encoded
+    sent_entities: SentEntities,
+    // clients which haven't sent anything for a while are disconnected
+    last_packet_at: Instant,
+    session_token: SessionToken,
+    // the controller of her character from a previous connection, she gets it back
+    // when she is ready for the game
+    resumed_controller_id: Option<ControllerEntityId>,
+    // only `Welcome` and `Ping` are accepted before the login
+    logged_in: bool,
+    job_id: JobId,
+    // she is put into this team if it does not make the teams unbalanced
+    preferred_team: Option<Team>,
+    // the controller and the visible entities belong to the world of this room
+    room_id: Option<RoomId>,
+    // the console commands above this level are refused
+    permission: PermissionLevel,
+    stats: ClientStats,
+    // the intentions above the configured rate are dropped
+    intention_rate: TokenBucket,
+    rejected_intentions: RejectionCounter,
+}
+
+impl RemoteClient {
+    fn is_in(&self, room_id: RoomId) -> bool {
+        self.room_id == Some(room_id)
+    }
+
+    // the client can not load another map during the game, so the room is chosen only once
+    fn can_choose_room(&self) -> bool {
+        self.logged_in && self.room_id.is_none()
+    }
 }
 
 // only the server must implement it
@@ -135,91 +212,129 @@ fn client_id_to_server(id: ServerEntityId) -> LocalCharEntityId {
 }
 
 fn init_connection(
-    world: &mut specs::World,
-    socket_handler: &mut PacketHandlerThread<ToServerPacket, FromServerPacket>,
-    incoming_conn: (TcpStream, SocketAddr),
-    configs: &AppConfig,
+    incoming_conn: (SocketId, SocketAddr),
+    server_config: &ServerConfig,
 ) -> RemoteClient {
-    let socket_id = socket_handler.handle_socket(incoming_conn.0);
-
     RemoteClient {
-        socket_id,
+        socket_id: incoming_conn.0,
         controller_id: None,
         sock_addr: incoming_conn.1,
         last_action_tick: 1,
         last_command_id: 0,
         name: "unknown".to_owned(),
+        congested: false,
+        snapshot_baseline: None,
+        visible_entities: Vec::with_capacity(64),
+        sent_entities: SentEntities::new(),
+        last_packet_at: Instant::now(),
+        session_token: Sessions::new_token(),
+        resumed_controller_id: None,
+        logged_in: false,
+        job_id: JobId::CRUSADER,
+        preferred_team: None,
+        room_id: None,
+        permission: server_config.console.default_permission,
+        stats: ClientStats::default(),
+        intention_rate: TokenBucket::new(
+            server_config.input_validation.intention_burst,
+            server_config
+                .network
+                .intentions_per_second(&server_config.input_validation),
+        ),
+        rejected_intentions: RejectionCounter::new(),
     }
 }
 
 type OutPacketCollector = Vec<(PacketTarget, FromServerPacket)>;
 
 pub enum PacketTarget {
+    /// the clients in the room
     All,
     Client(SocketId),
+    /// the clients whose controlled character is in the team
     Team(Team),
-    Area,
+    /// the clients whose area of interest contains the position
+    Area(Vec2),
 }
 
 fn main() {
     log::info!("Loading config file config.toml");
     let config = AppConfig::new("config").expect("Could not load config file ('config.toml')");
 
-    simple_logging::log_to_stderr(
+    // the log is streamed to the operators connected to the admin socket too
+    let (log_sink, operator_console) = create_log_sink();
+    simple_logging::log_to(
+        log_sink,
         LevelFilter::from_str(&config.log_level)
             .expect("Unknown log level. Please set one of the following values for 'log_level' in 'config.toml': \"OFF\", \"ERROR\", \"WARN\", \"INFO\", \"DEBUG\", \"TRACE\"")
     );
-    log::info!(">>> Loading GRF files");
-    let (elapsed, asset_loader) = measure_time(|| {
-        CommonAssetLoader::new(config.grf_paths.as_slice())
-            .expect("Could not open grf files. Please configure them in 'config.toml'")
-    });
-    log::info!("<<< GRF loading: {}ms", elapsed.as_millis());
-
-    let mut ecs_world = create_ecs_world();
-    ecs_world.insert(Vec::<HpModificationRequest>::with_capacity(128));
-    ecs_world.insert(Vec::<AreaAttackComponent>::with_capacity(128));
-    ecs_world.insert(Vec::<ApplyForceComponent>::with_capacity(128));
-    ecs_world.insert(EngineTime::new(0));
-    ecs_world.insert(OutPacketCollector::with_capacity(128));
-
-    ecs_world.insert(ServerConfig::new("server-conf.toml").unwrap());
-    ecs_world.insert(load_common_configs("config-runtime").unwrap());
-    ecs_world.insert(MapWalkingInfo::new());
-    ecs_world.insert(SimulationTick::new());
-
-    let mut ecs_dispatcher = specs::DispatcherBuilder::new()
-        .with(ControllerIntentionToCharTargetSystem, "char_control", &[])
-        .with(CharacterStateUpdateSystem, "char_state", &["char_control"])
-        .build();
 
-    let mut packet_handler_thread =
-        PacketHandlerThread::<ToServerPacket, FromServerPacket>::start_thread(64);
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(|it| it.as_str()) == Some("export-maps") {
+        let out_dir = args.get(2).map(|it| it.as_str()).unwrap_or(&config.map_dir);
+        let map_names = args.get(3..).unwrap_or(&[]);
+        if let Err(e) = export_maps(&config, out_dir, map_names) {
+            log::error!("Map export failed: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    if args.get(1).map(|it| it.as_str()) == Some("replay") {
+        let path = if let Some(path) = args.get(2) {
+            path
+        } else {
+            log::error!("Usage: rustarok-server replay <file>");
+            std::process::exit(1);
+        };
+        if let Err(e) = run_replay(&config, path) {
+            log::error!("Replay failed: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
 
-    //////// Execute init script
-    {
-        let file = File::open("init.cmd").unwrap();
-        let reader = BufReader::new(file);
+    let (server_config_text, server_config) = ServerConfig::read("server-conf.toml")
+        .expect("Could not load config file ('server-conf.toml')");
+    let mut room_assets = RoomAssets::load(&config, server_config_text, &server_config)
+        .expect("Could not load the maps and the configs of the rooms. Please check 'map_name' in 'config.toml'");
+    let tick_duration = server_config.network.tick_duration();
+    let bind_address = SocketAddr::new(server_config.network.bind_address, config.server_port);
 
-        for line in reader.lines() {
-            let line = line.unwrap();
-            if line.starts_with("//") || line.trim().is_empty() {
-                continue;
-            }
-            execute_console_cmd(None, CommandArguments::new(&line), &mut ecs_world);
-        }
-    };
+    let mut rooms = Vec::<Room>::with_capacity(server_config.rooms.max_rooms);
+    rooms.push(
+        Room::new(
+            RoomId(0),
+            DEFAULT_ROOM_NAME.to_owned(),
+            config.map_name.clone(),
+            true,
+            &server_config,
+            &room_assets,
+        )
+        .expect("Could not create the default room"),
+    );
+    let mut next_room_id = RoomId(1);
+    let mut sessions = Sessions::new();
+    let config_watcher = ConfigWatcher::start()
+        .map_err(|e| log::error!("Configs won't be reloaded automatically: {}", e))
+        .ok();
+    let operator_console = operator_console.start(&server_config.operator_console);
+    let mut telemetry = Telemetry::start(&server_config.telemetry, tick_duration);
+
+    let mut packet_handler_thread =
+        PacketHandlerThread::<ToServerPacket, FromServerPacket>::start_thread_with_buffer_limit(
+            64,
+            config.max_socket_buffer_size,
+        );
 
-    let mut socket_listener = bind_server(config.server_port);
-    log::info!("bind socket on port {}", config.server_port);
+    let mut socket_listener = bind_server(bind_address);
+    log::info!("bind socket on {}", bind_address);
+    // clients which are configured to use UDP connect here, on the same port number
+    let mut udp_listener = bind_udp_server(bind_address);
 
     log::info!("waiting for incoming connections...");
     let mut tmp_vec = Vec::with_capacity(256);
-    const MAX_PLAYER_NUM: usize = 64;
 
     let mut remote_clients = Vec::<Option<RemoteClient>>::with_capacity(64);
-
-    let mut next_player_team = Team::Left;
     let server_started = Instant::now();
 
     ////////////////////////////////////////////////////
@@ -235,171 +350,309 @@ fn main() {
 
         accept_new_connections(
             &mut socket_listener,
+            &mut udp_listener,
             &mut packet_handler_thread,
             &mut remote_clients,
-            &mut ecs_world,
-            &config,
+            &server_config,
         );
 
         process_incoming_packets(
             &mut tmp_vec,
-            &packet_handler_thread,
+            &mut packet_handler_thread,
             &mut remote_clients,
-            &mut ecs_world,
+            &mut rooms,
+            &mut next_room_id,
+            &mut sessions,
+            &server_config,
+            &room_assets,
             &config,
-            &mut next_player_team,
+        );
+        disconnect_idle_clients(
+            &mut packet_handler_thread,
+            &mut remote_clients,
+            &mut rooms,
+            &mut sessions,
+            &server_config,
+        );
+        remove_expired_sessions(
+            &packet_handler_thread,
+            &remote_clients,
+            &mut rooms,
+            &mut sessions,
+            &server_config,
         );
 
-        run_frame(&mut ecs_world, &mut ecs_dispatcher);
+        if config_watcher
+            .as_ref()
+            .map(|it| it.has_changed())
+            .unwrap_or(false)
+        {
+            reload_common_configs(&mut rooms, &mut room_assets);
+        }
 
-        send_snapshots(&packet_handler_thread, &mut remote_clients, &ecs_world);
+        let shutdown = operator_console.execute_commands(
+            &mut packet_handler_thread,
+            &mut remote_clients,
+            &mut rooms,
+            &sessions,
+            server_started,
+        );
+        if shutdown {
+            break;
+        }
 
-        send_packets(&mut packet_handler_thread, &mut ecs_world, &remote_clients);
+        for room in rooms.iter_mut() {
+            room.tick(&mut packet_handler_thread, &mut remote_clients);
+        }
+        remove_empty_rooms(&mut rooms, &remote_clients, &sessions);
 
         let frame_duration = start.elapsed();
-        if frame_duration < MAX_DURATION_ALLOWED_FOR_SINGLE_SIMULATION_FRAME {
-            let to_sleep = MAX_DURATION_ALLOWED_FOR_SINGLE_SIMULATION_FRAME - frame_duration;
-            std::thread::sleep(to_sleep);
+        telemetry.tick_finished(frame_duration);
+        telemetry.update(&remote_clients, &mut rooms);
+        if frame_duration < tick_duration {
+            std::thread::sleep(tick_duration - frame_duration);
         }
 
-        ecs_world
-            .write_resource::<EngineTime>()
-            .tick(MAX_DURATION_ALLOWED_FOR_SINGLE_SIMULATION_FRAME);
+        for room in rooms.iter_mut() {
+            room.advance_time(tick_duration);
+        }
+    }
 
-        ecs_world.write_resource::<SimulationTick>().inc();
+    log::info!("The server is shutting down");
+    for remote_client in remote_clients.iter().filter_map(|it| it.as_ref()) {
+        packet_handler_thread.close(remote_client.socket_id);
     }
 }
 
+/// The collected packets of the room are sent to the clients in the room
 fn send_packets(
     packet_handler_thread: &mut PacketHandlerThread<ToServerPacket, FromServerPacket>,
+    room_id: RoomId,
     ecs_world: &mut specs::World,
-    remote_clients: &Vec<Option<RemoteClient>>,
+    remote_clients: &[Option<RemoteClient>],
 ) -> usize {
     let mut to_client = ecs_world.write_resource::<OutPacketCollector>();
     let sent = to_client.len();
+    let mut target_clients = Vec::with_capacity(remote_clients.len());
 
     for (target, packet) in to_client.drain(..) {
         match target {
             PacketTarget::All => {
-                for remote_client in remote_clients.iter() {
-                    if let Some(remote_client) = remote_client {
-                        packet_handler_thread.send(remote_client.socket_id, packet.clone());
-                    }
-                }
+                send_to_room(&packet, room_id, remote_clients, packet_handler_thread);
             }
             PacketTarget::Client(client_socket) => {
                 packet_handler_thread.send(client_socket, packet);
             }
             PacketTarget::Team(team) => {
-                // TODO
+                let static_data_storage = ecs_world.read_storage::<StaticCharDataComponent>();
+                for remote_client in remote_clients.iter() {
+                    if let Some(remote_client) =
+                        remote_client.as_ref().filter(|it| it.is_in(room_id))
+                    {
+                        let is_in_team = get_controlled_char(remote_client, ecs_world)
+                            .and_then(|char_id| static_data_storage.get(char_id.into()))
+                            .map(|static_data| static_data.team == team)
+                            .unwrap_or(false);
+                        if is_in_team {
+                            packet_handler_thread.send(remote_client.socket_id, packet.clone());
+                        }
+                    }
+                }
             }
-            PacketTarget::Area => {
-                // TODO
+            PacketTarget::Area(pos) => {
+                clients_seeing(pos, room_id, remote_clients, ecs_world, &mut target_clients);
+                for socket_id in target_clients.drain(..) {
+                    packet_handler_thread.send(socket_id, packet.clone());
+                }
             }
         }
     }
     return sent;
 }
 
-fn run_frame(ecs_world: &mut specs::World, ecs_dispatcher: &mut specs::Dispatcher) {
+fn run_frame(
+    ecs_world: &mut specs::World,
+    ecs_dispatcher: &mut specs::Dispatcher<'static, 'static>,
+) {
     ecs_dispatcher.dispatch(ecs_world);
+    measure(ecs_world, "forced_movements", move_forced_characters);
+    measure(ecs_world, "minion_waves", spawn_minion_waves);
+    measure(ecs_world, "projectiles", update_phase_prism_projectiles);
+    measure(ecs_world, "area_effects", apply_area_effects);
+    measure(ecs_world, "forces", apply_forces);
+    measure(ecs_world, "skill_castings", execute_finished_skill_castings);
+    measure(ecs_world, "status_changes", apply_status_changes);
+    measure(ecs_world, "hp_modifications", apply_hp_modifications);
+    let died = measure(ecs_world, "death", handle_dead_characters);
+    measure(ecs_world, "match", |ecs_world| {
+        update_match(ecs_world, &died)
+    });
     ecs_world.maintain();
 }
 
 fn accept_new_connections(
     socket_listener: &mut TcpListener,
+    udp_listener: &mut UdpListener,
     packet_handler_thread: &mut PacketHandlerThread<ToServerPacket, FromServerPacket>,
     remote_clients: &mut Vec<Option<RemoteClient>>,
-    ecs_world: &mut specs::World,
-    config: &AppConfig,
+    server_config: &ServerConfig,
 ) {
-    if let Some(connecting_client) = accept_connection(socket_listener) {
-        let remote_client =
-            init_connection(ecs_world, packet_handler_thread, connecting_client, &config);
+    let connecting_client = if let Some((stream, addr)) = accept_connection(socket_listener) {
+        Some((packet_handler_thread.handle_socket(stream), addr))
+    } else if let Some((socket, addr)) = udp_listener.accept() {
+        Some((packet_handler_thread.handle_udp_socket(socket), addr))
+    } else {
+        None
+    };
+    if let Some(connecting_client) = connecting_client {
+        let max_players = server_config.network.max_players;
+        if remote_clients.iter().filter(|it| it.is_some()).count() >= max_players {
+            log::warn!(
+                "{} has been refused, the server is full ({} players)",
+                connecting_client.1,
+                max_players
+            );
+            packet_handler_thread.send(connecting_client.0, FromServerPacket::ServerFull);
+            packet_handler_thread.close(connecting_client.0);
+            return;
+        }
+        let remote_client = init_connection(connecting_client, server_config);
         log::info!(
             "{:?} - {} has joined",
             &remote_client.socket_id,
             &remote_client.sock_addr
         );
-        if remote_client.socket_id.as_usize() >= remote_clients.len() {
-            remote_clients.push(Some(remote_client));
-        } else {
-            let index = remote_client.socket_id.as_usize();
-            remote_clients[index] = Some(remote_client);
+        // the socket ids are reused, so it does not grow over the number of the connections
+        let index = remote_client.socket_id.as_usize();
+        if index >= remote_clients.len() {
+            remote_clients.resize_with(index + 1, || None);
         }
+        remote_clients[index] = Some(remote_client);
     }
 }
 
 fn send_snapshots(
     packet_handler_thread: &PacketHandlerThread<ToServerPacket, FromServerPacket>,
-    remote_clients: &mut Vec<Option<RemoteClient>>,
+    room_id: RoomId,
+    remote_clients: &mut [Option<RemoteClient>],
+    snapshot_history: &mut SnapshotHistory,
     ecs_world: &specs::World,
 ) {
-    let now = ecs_world.read_resource::<EngineTime>().now();
+    let tick = *ecs_world.read_resource::<SimulationTick>();
+    let auth_char_storage = ecs_world.read_storage::<LocalCharStateComp>();
+    // every state is encoded only once, the deltas are computed from the encoded fields
+    let mut snapshot = EncodedWorldSnapshot::new(tick);
+    for (char_id, char_state) in (&ecs_world.entities(), &auth_char_storage).join() {
+        snapshot.push(
+            prepare_entity_id_for_sending(LocalCharEntityId::from(char_id)),
+            EncodedCharState::encode(&prepare_charsnapshot_for_sending(char_state.clone())),
+        );
+    }
     for remote_client in remote_clients.iter_mut() {
         let remote_client = if let Some(remote_client) = remote_client {
             remote_client
         } else {
             continue;
         };
-        if let Some(controller_id) = remote_client.controller_id {
-            let controller_storage = ecs_world.read_storage::<ControllerComponent>();
-            let controller = controller_storage.get(controller_id.into()).unwrap();
-            if let Some(controlled_entity) = controller.controlled_entity {
-                let auth_char_storage = ecs_world.read_storage::<LocalCharStateComp>();
-                let char_state = auth_char_storage.get(controlled_entity.into()).unwrap();
-
-                let mut entries = vec![ServerEntityState {
-                    id: prepare_entity_id_for_sending(controlled_entity),
-                    char_snapshot: prepare_charsnapshot_for_sending(char_state.clone()),
-                }];
-                for (other_char_id, other_char_state) in
-                    (&ecs_world.entities(), &auth_char_storage).join()
-                {
-                    let other_char_id = LocalCharEntityId::from(other_char_id);
-                    if other_char_id == controlled_entity.into() {
-                        continue;
-                    }
-                    entries.push(ServerEntityState {
-                        id: prepare_entity_id_for_sending(other_char_id),
-                        char_snapshot: prepare_charsnapshot_for_sending(other_char_state.clone()),
-                    })
-                }
-                packet_handler_thread.send(
-                    remote_client.socket_id,
-                    FromServerPacket::Ack {
-                        cid: remote_client.last_command_id,
-                        entries,
-                    },
-                );
-                remote_client.last_action_tick += 1;
-            }
+        if !remote_client.is_in(room_id) {
+            continue;
+        }
+        if remote_client.congested {
+            // snapshots are sent against the acknowledged baseline, so it is enough to send the next one
+            continue;
+        }
+        if remote_client.visible_entities.is_empty() {
+            // she has not joined the game yet
+            continue;
         }
+        let baseline = remote_client
+            .snapshot_baseline
+            .and_then(|baseline_tick| snapshot_history.get(baseline_tick));
+        let entries = encode_visible_states(
+            &snapshot,
+            &remote_client.visible_entities,
+            baseline,
+            &remote_client.sent_entities,
+        );
+        remote_client
+            .sent_entities
+            .push(tick, &remote_client.visible_entities);
+        packet_handler_thread.send(
+            remote_client.socket_id,
+            FromServerPacket::Ack {
+                cid: remote_client.last_command_id,
+                tick,
+                baseline: baseline.map(|it| it.tick),
+                entries,
+            },
+        );
+        remote_client.stats.snapshot_sent(tick);
+        remote_client.last_action_tick += 1;
     }
+    snapshot_history.push(snapshot);
 }
 
 fn process_incoming_packets(
     tmp_vec: &mut Vec<(SocketId, NetworkTrafficEvent<ToServerPacket>)>,
-    packet_handler_thread: &PacketHandlerThread<ToServerPacket, FromServerPacket>,
+    packet_handler_thread: &mut PacketHandlerThread<ToServerPacket, FromServerPacket>,
     remote_clients: &mut Vec<Option<RemoteClient>>,
-    ecs_world: &mut specs::World,
+    rooms: &mut Vec<Room>,
+    next_room_id: &mut RoomId,
+    sessions: &mut Sessions,
+    server_config: &ServerConfig,
+    room_assets: &RoomAssets,
     config: &AppConfig,
-    next_player_team: &mut Team,
 ) {
     tmp_vec.clear();
     packet_handler_thread.receive_into(tmp_vec);
     for (client_socket, packet) in tmp_vec.drain(..) {
+        if let Some(Some(remote_client)) = remote_clients.get_mut(client_socket.as_usize()) {
+            // only the traffic of the client proves that she is still there
+            match &packet {
+                NetworkTrafficEvent::IncomingTraffic { received_data_len } => {
+                    remote_client.last_packet_at = Instant::now();
+                    remote_client.stats.bytes_received += *received_data_len as u64;
+                }
+                NetworkTrafficEvent::OutgoingTraffic { sent_data_len } => {
+                    remote_client.stats.bytes_sent += *sent_data_len as u64;
+                }
+                NetworkTrafficEvent::Packet(_) => {
+                    remote_client.last_packet_at = Instant::now();
+                    remote_client.stats.packets_received += 1;
+                }
+                _ => {}
+            }
+        } else {
+            // e.g. the remaining traffic of a refused connection
+            continue;
+        }
         match packet {
-            NetworkTrafficEvent::IncomingTraffic { received_data_len } => {
-                //
+            NetworkTrafficEvent::IncomingTraffic { .. }
+            | NetworkTrafficEvent::OutgoingTraffic { .. }
+            | NetworkTrafficEvent::Closed => {}
+            NetworkTrafficEvent::Congested { unsent_data_len } => {
+                log::warn!(
+                    "Client({:?}) can't keep up, {} bytes are waiting to be sent",
+                    client_socket,
+                    unsent_data_len
+                );
+                if let Some(remote_client) = remote_clients[client_socket.as_usize()].as_mut() {
+                    remote_client.congested = true;
+                }
+            }
+            NetworkTrafficEvent::CongestionEnded => {
+                log::info!("Client({:?}) has caught up", client_socket);
+                if let Some(remote_client) = remote_clients[client_socket.as_usize()].as_mut() {
+                    remote_client.congested = false;
+                }
             }
-            NetworkTrafficEvent::OutgoingTraffic { sent_data_len } => {}
             NetworkTrafficEvent::Disconnected => {
                 log::debug!("Client({:?}) has been disconnected", client_socket);
                 disconnect_client(
                     remote_clients,
                     client_socket,
-                    ecs_world,
+                    rooms,
+                    sessions,
                     packet_handler_thread,
                 );
             }
@@ -408,72 +661,199 @@ fn process_incoming_packets(
                 disconnect_client(
                     remote_clients,
                     client_socket,
-                    ecs_world,
+                    rooms,
+                    sessions,
                     packet_handler_thread,
                 );
             }
             NetworkTrafficEvent::Packet(p) => {
                 match p {
-                    ToServerPacket::Welcome { name } => {
-                        log::info!("{} welcomed ^^", name);
-                        let remote_client =
-                            remote_clients[client_socket.as_usize()].as_mut().unwrap();
-                        remote_client.name = name;
-                        packet_handler_thread.send(
+                    ToServerPacket::Welcome {
+                        name,
+                        job_id,
+                        session_token,
+                    } => {
+                        login::handle_welcome(
                             client_socket,
-                            FromServerPacket::Init {
-                                //    let map_name = "bat_a01"; // battle ground
-                                map_name: "prontera".to_string(),
-                                start_x: config.start_pos_x,
-                                start_y: config.start_pos_y,
-                            },
+                            name,
+                            job_id,
+                            session_token,
+                            remote_clients,
+                            rooms,
+                            sessions,
+                            packet_handler_thread,
+                            config,
                         );
-                        let configs = (*ecs_world.read_resource::<CommonConfigs>()).clone();
-                        packet_handler_thread
-                            .send(client_socket, FromServerPacket::Configs(configs));
                     }
                     ToServerPacket::Ping => {
-                        packet_handler_thread.send(
+                        let room_id = remote_clients[client_socket.as_usize()]
+                            .as_ref()
+                            .unwrap()
+                            .room_id;
+                        // the clock of the room is synchronized
+                        if let Some(room) = find_room(rooms, room_id) {
+                            packet_handler_thread.send(
+                                client_socket,
+                                FromServerPacket::Pong {
+                                    server_time: ServerTime(
+                                        room.ecs_world
+                                            .read_resource::<EngineTime>()
+                                            .now()
+                                            .as_millis(),
+                                    ),
+                                    server_tick: *room.ecs_world.read_resource::<SimulationTick>(),
+                                },
+                            );
+                        }
+                    }
+                    ToServerPacket::ListRooms => {
+                        let remote_client =
+                            remote_clients[client_socket.as_usize()].as_ref().unwrap();
+                        if !remote_client.can_choose_room() {
+                            log::warn!("{} can not list the rooms now", remote_client.name);
+                            continue;
+                        }
+                        let room_list =
+                            rooms.iter().map(|room| room.info(remote_clients)).collect();
+                        packet_handler_thread
+                            .send(client_socket, FromServerPacket::RoomList(room_list));
+                    }
+                    ToServerPacket::CreateRoom { name, map_name } => {
+                        let remote_client =
+                            remote_clients[client_socket.as_usize()].as_ref().unwrap();
+                        if !remote_client.can_choose_room() {
+                            log::warn!("{} can not create a room now", remote_client.name);
+                            continue;
+                        }
+                        let map_name = map_name.unwrap_or_else(|| config.map_name.clone());
+                        let result = create_room(
+                            name,
+                            map_name,
+                            rooms,
+                            next_room_id,
+                            server_config,
+                            room_assets,
+                        )
+                        .and_then(|room_id| {
+                            join_room(
+                                client_socket,
+                                room_id,
+                                remote_clients,
+                                rooms,
+                                packet_handler_thread,
+                                config,
+                            )
+                        });
+                        if let Err(e) = result {
+                            packet_handler_thread
+                                .send(client_socket, FromServerPacket::RoomError(e));
+                        }
+                    }
+                    ToServerPacket::JoinRoom(room_id) => {
+                        let remote_client =
+                            remote_clients[client_socket.as_usize()].as_ref().unwrap();
+                        if !remote_client.can_choose_room() {
+                            log::warn!("{} can not join a room now", remote_client.name);
+                            continue;
+                        }
+                        if let Err(e) = join_room(
                             client_socket,
-                            FromServerPacket::Pong {
-                                server_time: ServerTime(
-                                    ecs_world.read_resource::<EngineTime>().now().as_millis(),
-                                ),
-                                server_tick: *ecs_world.read_resource::<SimulationTick>(),
-                            },
-                        );
+                            room_id,
+                            remote_clients,
+                            rooms,
+                            packet_handler_thread,
+                            config,
+                        ) {
+                            packet_handler_thread
+                                .send(client_socket, FromServerPacket::RoomError(e));
+                        }
+                    }
+                    ToServerPacket::SelectTeam(team) => {
+                        let remote_client =
+                            remote_clients[client_socket.as_usize()].as_mut().unwrap();
+                        if !remote_client.logged_in {
+                            continue;
+                        }
+                        match team {
+                            None | Some(Team::Left) | Some(Team::Right) => {
+                                remote_client.preferred_team = team;
+                            }
+                            Some(team) => {
+                                log::warn!(
+                                    "{} has selected an invalid team: {:?}",
+                                    remote_client.name,
+                                    team
+                                );
+                            }
+                        }
                     }
                     ToServerPacket::ReadyForGame => {
-                        let (connecting_char_id, connecting_char_snapshot) = {
+                        {
                             let remote_client =
                                 remote_clients[client_socket.as_usize()].as_mut().unwrap();
-                            remote_client.name =
-                                format!("{} {}", next_player_team.to_str(), remote_client.name);
-                            log::info!("{} is ready to play", remote_client.name);
-
-                            let char_id = LocalCharEntityId::from(
-                                create_common_player_entity(
-                                    remote_client.name.clone(),
+                            if !remote_client.logged_in {
+                                log::warn!(
+                                    "Client({:?}) is not logged in, ReadyForGame is ignored",
+                                    client_socket
+                                );
+                                continue;
+                            }
+                            if remote_client.controller_id.is_some() {
+                                // a second character would be created for her
+                                log::warn!(
+                                    "{} is already playing, ReadyForGame is ignored",
+                                    remote_client.name
+                                );
+                                continue;
+                            }
+                            let ecs_world =
+                                if let Some(room) = find_room(rooms, remote_client.room_id) {
+                                    &mut room.ecs_world
+                                } else {
+                                    log::warn!(
+                                        "{} has not joined a room, ReadyForGame is ignored",
+                                        remote_client.name
+                                    );
+                                    continue;
+                                };
+                            let resumed_char = remote_client.resumed_controller_id.take().and_then(
+                                |controller_id| {
+                                    ecs_world
+                                        .read_storage::<ControllerComponent>()
+                                        .get(controller_id.into())
+                                        .and_then(|controller| controller.controlled_entity)
+                                        .map(|char_id| (controller_id, char_id))
+                                },
+                            );
+                            let char_id = if let Some((controller_id, char_id)) = resumed_char {
+                                log::info!("{} continues playing", remote_client.name);
+                                remote_client.controller_id = Some(controller_id);
+                                char_id
+                            } else {
+                                let team = choose_team(remote_client.preferred_team, ecs_world);
+                                log::info!(
+                                    "{} is ready to play in team {}",
+                                    remote_client.name,
+                                    team.to_str()
+                                );
+                                let char_name = format!("{} {}", team.to_str(), remote_client.name);
+                                let (char_id, controller_id) = create_player(
                                     ecs_world,
-                                    CharType::Player,
-                                    JobId::CRUSADER,
+                                    char_name,
+                                    remote_client.job_id,
                                     v2(config.start_pos_x, config.start_pos_y),
-                                    *next_player_team,
+                                    team,
                                     CharOutlook::Human {
-                                        job_sprite_id: JobSpriteId::from_job_id(JobId::CRUSADER),
+                                        job_sprite_id: JobSpriteId::from_job_id(
+                                            remote_client.job_id,
+                                        ),
                                         head_index: 0,
                                         sex: Sex::Male,
                                     },
-                                )
-                                .build(),
-                            );
-                            *next_player_team = next_player_team.get_opponent_team();
-                            let network_player_id = ecs_world
-                                .create_entity()
-                                .with(ControllerComponent::new(char_id))
-                                .build();
-                            remote_client.controller_id =
-                                Some(ControllerEntityId::new(network_player_id));
+                                );
+                                remote_client.controller_id = Some(controller_id);
+                                char_id
+                            };
 
                             let auth_char_storage = ecs_world.read_storage::<LocalCharStateComp>();
                             let char_state = auth_char_storage.get(char_id.into()).unwrap();
@@ -490,73 +870,25 @@ fn process_incoming_packets(
                                     typ: static_char_state.typ.clone(),
                                     outlook: static_char_state.outlook.clone(),
                                     job_id: static_char_state.job_id,
+                                    has_falcon: static_char_state.has_falcon,
                                     state: prepare_charsnapshot_for_sending(char_state.clone()),
                                 },
                             );
 
-                            // send her the player list
-                            {
-                                for (other_char_id, other_char_state, other_static_data) in (
-                                    &ecs_world.entities(),
-                                    &auth_char_storage,
-                                    &static_data_storage,
-                                )
-                                    .join()
-                                {
-                                    let other_char_id = LocalCharEntityId::new(other_char_id);
-                                    if other_char_id == char_id {
-                                        continue;
-                                    }
-                                    packet_handler_thread.send(
-                                        remote_client.socket_id,
-                                        FromServerPacket::NewEntity {
-                                            id: prepare_entity_id_for_sending(other_char_id),
-                                            name: other_static_data.name.to_owned(),
-                                            team: other_static_data.team,
-                                            typ: other_static_data.typ.clone(),
-                                            outlook: other_static_data.outlook.clone(),
-                                            job_id: other_static_data.job_id,
-                                            state: prepare_charsnapshot_for_sending(
-                                                other_char_state.clone(),
-                                            ),
-                                        },
-                                    );
-                                }
-                            }
-
-                            (char_id, char_state.clone())
-                        };
-
-                        // inform others about this player
-                        let (packet, connecting_client_controller_id) = {
-                            let static_data_storage =
-                                ecs_world.read_storage::<StaticCharDataComponent>();
-                            let static_data =
-                                static_data_storage.get(connecting_char_id.into()).unwrap();
-                            let remote_client =
-                                remote_clients[client_socket.as_usize()].as_mut().unwrap();
-                            (
-                                FromServerPacket::NewEntity {
-                                    id: prepare_entity_id_for_sending(connecting_char_id),
-                                    name: remote_client.name.clone(),
-                                    team: static_data.team,
-                                    typ: static_data.typ.clone(),
-                                    outlook: static_data.outlook.clone(),
-                                    job_id: static_data.job_id,
-                                    state: prepare_charsnapshot_for_sending(
-                                        connecting_char_snapshot.clone(),
-                                    ),
-                                },
-                                remote_client.controller_id.unwrap(),
-                            )
-                        };
+                            let match_state = ecs_world.read_resource::<MatchState>();
+                            let now = ecs_world.read_resource::<EngineTime>().now();
+                            packet_handler_thread.send(
+                                remote_client.socket_id,
+                                FromServerPacket::MatchPhaseChanged(match_state.phase(now)),
+                            );
+                            packet_handler_thread.send(
+                                remote_client.socket_id,
+                                FromServerPacket::ScoreChanged(match_state.score()),
+                            );
 
-                        send_to_all_except(
-                            connecting_client_controller_id,
-                            &packet,
-                            remote_clients,
-                            packet_handler_thread,
-                        );
+                            // the other characters are sent to her as they come into her area of interest
+                            remote_client.visible_entities = vec![char_id];
+                        }
                     }
                     ToServerPacket::Intention {
                         cid,
@@ -565,38 +897,108 @@ fn process_incoming_packets(
                     } => {
                         let remote_client =
                             remote_clients[client_socket.as_usize()].as_mut().unwrap();
-                        if let Some(controller_id) = remote_client.controller_id {
-                            let mut controller_storage =
-                                ecs_world.write_storage::<ControllerComponent>();
-                            let controller: &mut ControllerComponent =
-                                controller_storage.get_mut(controller_id.into()).unwrap();
-                            controller.intention = Some(match intention {
-                                ToServerPlayerIntention::MoveTo(v) => PlayerIntention::MoveTo(v),
-                                ToServerPlayerIntention::MoveTowardsMouse(v) => {
-                                    PlayerIntention::MoveTowardsMouse(v)
-                                }
-                                ToServerPlayerIntention::AttackTowards(v) => {
-                                    PlayerIntention::AttackTowards(v)
-                                }
-                                ToServerPlayerIntention::Attack(id) => {
-                                    PlayerIntention::Attack(client_id_to_server(id))
+                        let validated = if !remote_client.intention_rate.try_take(Instant::now()) {
+                            Err(IntentionRejection::RateLimited)
+                        } else {
+                            match (
+                                find_room(rooms, remote_client.room_id),
+                                remote_client.controller_id,
+                            ) {
+                                (Some(room), Some(controller_id)) => {
+                                    validate_intention(&room.ecs_world, controller_id, intention)
+                                        .map(|intention| (room, controller_id, intention))
                                 }
-                            });
-                            remote_client.last_command_id = cid;
-                            log::debug!(
-                                "client tick: {:?}, cid: {}, intention: {:?}",
-                                client_tick,
-                                cid,
-                                &controller.intention
+                                _ => Err(IntentionRejection::NoCharacter),
+                            }
+                        };
+                        let reason = match validated {
+                            Ok((room, controller_id, intention)) => {
+                                log::debug!(
+                                    "client tick: {:?}, cid: {}, intention: {:?}",
+                                    client_tick,
+                                    cid,
+                                    &intention
+                                );
+                                remote_client.last_command_id = cid;
+                                set_intention(
+                                    &mut room.ecs_world,
+                                    controller_id,
+                                    client_tick,
+                                    intention,
+                                );
+                                continue;
+                            }
+                            Err(reason) => reason,
+                        };
+                        log::debug!(
+                            "{}'s intention has been rejected: {:?}",
+                            remote_client.name,
+                            reason
+                        );
+                        // answering every rate limited one would multiply the traffic
+                        if reason != IntentionRejection::RateLimited {
+                            packet_handler_thread.send(
+                                client_socket,
+                                FromServerPacket::IntentionRejected { cid, reason },
                             );
-                        } else {
-                            // TODO: close connection
                         }
+                        let validation_config = &server_config.input_validation;
+                        let rejections = remote_client
+                            .rejected_intentions
+                            .add(validation_config.rejection_window(), Instant::now());
+                        if validation_config.should_kick(rejections) {
+                            let remote_client = kick_client(
+                                client_socket,
+                                remote_clients,
+                                rooms,
+                                packet_handler_thread,
+                            );
+                            log::warn!(
+                                "{} has been kicked, {} intentions have been rejected in {}s",
+                                remote_client.name,
+                                rejections,
+                                validation_config.rejection_window_seconds
+                            );
+                        }
+                    }
+                    ToServerPacket::SnapshotAck(tick) => {
+                        let remote_client =
+                            remote_clients[client_socket.as_usize()].as_mut().unwrap();
+                        if let Some(tick) = tick {
+                            remote_client.stats.snapshot_acked(tick);
+                        }
+                        remote_client.snapshot_baseline =
+                            match (remote_client.snapshot_baseline, tick) {
+                                // an older ack might arrive later
+                                (Some(current), Some(tick)) if tick < current => Some(current),
+                                _ => tick,
+                            };
                     }
                     ToServerPacket::ConsoleCommand(cmd) => {
                         let remote_client =
                             remote_clients[client_socket.as_usize()].as_mut().unwrap();
-                        execute_console_cmd(remote_client.controller_id, cmd, ecs_world);
+                        let command = cmd.get_command_name().unwrap_or("").to_owned();
+                        let result = if command == "admin" {
+                            cmd_admin(remote_client, &cmd, server_config)
+                        } else if let Some(room) = find_room(rooms, remote_client.room_id) {
+                            execute_console_cmd(
+                                remote_client.controller_id,
+                                remote_client.permission,
+                                cmd,
+                                &mut room.ecs_world,
+                            )
+                        } else {
+                            Err(ConsoleCommandError::Failed(
+                                "You are not in a room".to_owned(),
+                            ))
+                        };
+                        if let Err(e) = &result {
+                            log::debug!("{}: '{}' has failed: {}", remote_client.name, command, e);
+                        }
+                        packet_handler_thread.send(
+                            client_socket,
+                            FromServerPacket::ConsoleReply { command, result },
+                        );
                     }
                 }
             }
@@ -604,74 +1006,275 @@ fn process_incoming_packets(
     }
 }
 
-fn send_to_all(
+fn send_to_room(
     packet: &FromServerPacket,
+    room_id: RoomId,
     remote_clients: &[Option<RemoteClient>],
     packet_handler_thread: &PacketHandlerThread<ToServerPacket, FromServerPacket>,
 ) {
-    for other_remote_client in remote_clients.iter() {
-        if let Some(other_client) = other_remote_client {
-            if let Some(other_id) = other_client.controller_id {
-                packet_handler_thread.send(other_client.socket_id, packet.clone());
-            }
+    for remote_client in remote_clients.iter().filter_map(|it| it.as_ref()) {
+        if remote_client.is_in(room_id) {
+            packet_handler_thread.send(remote_client.socket_id, packet.clone());
         }
     }
 }
 
-fn send_to_all_except(
-    except_id: ControllerEntityId,
-    packet: &FromServerPacket,
-    remote_clients: &[Option<RemoteClient>],
-    packet_handler_thread: &PacketHandlerThread<ToServerPacket, FromServerPacket>,
+fn disconnect_idle_clients(
+    packet_handler_thread: &mut PacketHandlerThread<ToServerPacket, FromServerPacket>,
+    remote_clients: &mut [Option<RemoteClient>],
+    rooms: &mut [Room],
+    sessions: &mut Sessions,
+    server_config: &ServerConfig,
 ) {
-    for other_remote_client in remote_clients.iter() {
-        if let Some(other_client) = other_remote_client {
-            if let Some(other_id) = other_client.controller_id {
-                if other_id != except_id {
-                    packet_handler_thread.send(other_client.socket_id, packet.clone());
-                }
-            }
-        }
+    let idle_timeout = server_config.network.idle_timeout();
+    let idle_clients: Vec<SocketId> = remote_clients
+        .iter()
+        .filter_map(|it| it.as_ref())
+        .filter(|remote_client| remote_client.last_packet_at.elapsed() > idle_timeout)
+        .map(|remote_client| remote_client.socket_id)
+        .collect();
+    for socket_id in idle_clients {
+        log::info!("Client({:?}) has been idle for too long", socket_id);
+        disconnect_client(
+            remote_clients,
+            socket_id,
+            rooms,
+            sessions,
+            packet_handler_thread,
+        );
     }
 }
 
 fn disconnect_client(
     remote_clients: &mut [Option<RemoteClient>],
     socket_id: SocketId,
-    ecs_world: &mut specs::World,
-    packet_handler_thread: &PacketHandlerThread<ToServerPacket, FromServerPacket>,
+    rooms: &mut [Room],
+    sessions: &mut Sessions,
+    packet_handler_thread: &mut PacketHandlerThread<ToServerPacket, FromServerPacket>,
 ) {
-    let controller_id = remote_clients[socket_id.as_usize()]
-        .as_ref()
-        .unwrap()
-        .controller_id;
-    remote_clients[socket_id.as_usize()] = None;
+    packet_handler_thread.close(socket_id);
+    let remote_client = remote_clients[socket_id.as_usize()].take().unwrap();
+    let room = if let Some(room) = find_room(rooms, remote_client.room_id) {
+        room
+    } else {
+        return;
+    };
+    let ecs_world = &mut room.ecs_world;
+    // she might have disconnected before her resumed character was sent to her
+    let controller_id = remote_client
+        .controller_id
+        .or(remote_client.resumed_controller_id);
     if let Some(controller_id) = controller_id {
-        // TODO: remove its original char!
-
-        let mut controlled_entity = ecs_world
-            .read_storage::<ControllerComponent>()
-            .get(controller_id.into())
-            .unwrap()
-            .controlled_entity;
-        if let Some(controlled_entity) = controlled_entity {
-            ecs_world.delete_entity(controlled_entity.into());
-            send_to_all(
-                &FromServerPacket::PlayerDisconnected(prepare_entity_id_for_sending(
-                    controlled_entity,
-                )),
+        idle_player(ecs_world, controller_id);
+        log::info!(
+            "{}'s character is kept until she reconnects",
+            remote_client.name
+        );
+        sessions.park(
+            remote_client.session_token,
+            remote_client.name,
+            room.id,
+            controller_id,
+        );
+    }
+}
+
+/// Unlike a disconnection, the character of the kicked player is removed immediately,
+/// so she can't continue with her session
+fn kick_client(
+    socket_id: SocketId,
+    remote_clients: &mut [Option<RemoteClient>],
+    rooms: &mut [Room],
+    packet_handler_thread: &mut PacketHandlerThread<ToServerPacket, FromServerPacket>,
+) -> RemoteClient {
+    packet_handler_thread.close(socket_id);
+    let remote_client = remote_clients[socket_id.as_usize()].take().unwrap();
+    let controller_id = remote_client
+        .controller_id
+        .or(remote_client.resumed_controller_id);
+    if let (Some(room), Some(controller_id)) =
+        (find_room(rooms, remote_client.room_id), controller_id)
+    {
+        remove_player(controller_id, room, remote_clients, packet_handler_thread);
+    }
+    return remote_client;
+}
+
+/// The characters of the players who haven't reconnected in the grace period are removed
+fn remove_expired_sessions(
+    packet_handler_thread: &PacketHandlerThread<ToServerPacket, FromServerPacket>,
+    remote_clients: &[Option<RemoteClient>],
+    rooms: &mut [Room],
+    sessions: &mut Sessions,
+    server_config: &ServerConfig,
+) {
+    let grace_period = server_config.network.reconnect_grace_period();
+    for session in sessions.remove_expired(grace_period) {
+        log::info!("{}'s session has expired", session.name);
+        if let Some(room) = find_room(rooms, Some(session.room_id)) {
+            remove_player(
+                session.controller_id,
+                room,
                 remote_clients,
                 packet_handler_thread,
-            )
+            );
         }
-        ecs_world.delete_entity(controller_id.into());
     }
 }
 
+fn remove_player(
+    controller_id: ControllerEntityId,
+    room: &mut Room,
+    remote_clients: &[Option<RemoteClient>],
+    packet_handler_thread: &PacketHandlerThread<ToServerPacket, FromServerPacket>,
+) {
+    if let Some(controlled_entity) = delete_player(&mut room.ecs_world, controller_id) {
+        send_to_room(
+            &FromServerPacket::PlayerDisconnected(prepare_entity_id_for_sending(controlled_entity)),
+            room.id,
+            remote_clients,
+            packet_handler_thread,
+        )
+    }
+}
+
+/// The character and its controller of a player who is ready for the game,
+/// the players start with a falcon
+fn create_player(
+    ecs_world: &mut specs::World,
+    name: String,
+    job_id: JobId,
+    pos: Vec2,
+    team: Team,
+    outlook: CharOutlook,
+) -> (LocalCharEntityId, ControllerEntityId) {
+    let char_entity_id = create_common_player_entity(
+        name.clone(),
+        ecs_world,
+        CharType::Player,
+        job_id,
+        pos,
+        team,
+        outlook.clone(),
+    )
+    .build();
+    if let Some(static_data) = ecs_world
+        .write_storage::<StaticCharDataComponent>()
+        .get_mut(char_entity_id)
+    {
+        static_data.has_falcon = true;
+    }
+    let controller_entity_id = ecs_world
+        .create_entity()
+        .with(ControllerComponent::new(LocalCharEntityId::from(
+            char_entity_id,
+        )))
+        .build();
+    record_event(
+        ecs_world,
+        ReplayEvent::PlayerJoined {
+            char_id: entity_index(char_entity_id),
+            controller_id: entity_index(controller_entity_id),
+            name,
+            job_id,
+            pos,
+            team,
+            outlook,
+        },
+    );
+    return (
+        LocalCharEntityId::from(char_entity_id),
+        ControllerEntityId::new(controller_entity_id),
+    );
+}
+
+fn set_intention(
+    ecs_world: &mut specs::World,
+    controller_id: ControllerEntityId,
+    client_tick: SimulationTick,
+    intention: PlayerIntention,
+) {
+    record_event(
+        ecs_world,
+        ReplayEvent::Intention {
+            controller_id: controller_index(controller_id),
+            client_tick,
+            intention: intention.clone(),
+        },
+    );
+    let mut controller_storage = ecs_world.write_storage::<ControllerComponent>();
+    if let Some(controller) = controller_storage.get_mut(controller_id.into()) {
+        controller.intention = Some(intention);
+        if let Some(char_id) = controller.controlled_entity {
+            let now = *ecs_world.read_resource::<SimulationTick>();
+            ecs_world
+                .write_resource::<LagCompensation>()
+                .set_client_tick(char_id, now, client_tick);
+        }
+    }
+}
+
+/// The character of a disconnected player stands still until she reconnects
+fn idle_player(ecs_world: &mut specs::World, controller_id: ControllerEntityId) {
+    record_event(
+        ecs_world,
+        ReplayEvent::PlayerDisconnected {
+            controller_id: controller_index(controller_id),
+        },
+    );
+    if let Some(controlled_char) = ecs_world
+        .read_storage::<ControllerComponent>()
+        .get(controller_id.into())
+        .and_then(|controller| controller.controlled_entity)
+    {
+        if let Some(char_state) = ecs_world
+            .write_storage::<LocalCharStateComp>()
+            .get_mut(controlled_char.into())
+        {
+            char_state.target = None;
+        }
+    }
+    if let Some(controller) = ecs_world
+        .write_storage::<ControllerComponent>()
+        .get_mut(controller_id.into())
+    {
+        controller.intention = None;
+    }
+}
+
+/// Returns the deleted character
+fn delete_player(
+    ecs_world: &mut specs::World,
+    controller_id: ControllerEntityId,
+) -> Option<LocalCharEntityId> {
+    record_event(
+        ecs_world,
+        ReplayEvent::PlayerRemoved {
+            controller_id: controller_index(controller_id),
+        },
+    );
+    let controlled_entity = ecs_world
+        .read_storage::<ControllerComponent>()
+        .get(controller_id.into())
+        .and_then(|controller| controller.controlled_entity);
+    if let Some(controlled_entity) = controlled_entity {
+        ecs_world.delete_entity(controlled_entity.into());
+    }
+    ecs_world.delete_entity(controller_id.into());
+    return controlled_entity;
+}
+
 pub fn create_ecs_world() -> specs::World {
     let mut ecs_world = specs::World::new();
     ecs_world.register::<LocalCharStateComp>();
     ecs_world.register::<ControllerComponent>();
     ecs_world.register::<StaticCharDataComponent>();
+    ecs_world.register::<WalkingPathComponent>();
+    ecs_world.register::<AreaEffectComponent>();
+    ecs_world.register::<ForcedMovementComponent>();
+    ecs_world.register::<PhasePrismProjectileComponent>();
+    ecs_world.register::<MinionControllerComponent>();
+    ecs_world.register::<TurretComponent>();
+    ecs_world.register::<TurretControllerComponent>();
     ecs_world
 }
@@ -0,0 +1,86 @@
+//! The server reads the collision data of the maps from map files, which can be
+//! extracted from the GRF archives with
+//! `rustarok-server export-maps <output dir> [map names...]`
+use crate::AppConfig;
+use rustarok_common::common::measure_time;
+use rustarok_common::grf::asset_loader::CommonAssetLoader;
+use rustarok_common::map::{MapWalkingInfo, MAP_FILE_EXTENSION};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+fn load_grf_files(grf_paths: &[String]) -> Result<CommonAssetLoader, String> {
+    log::info!(">>> Loading GRF files");
+    let (elapsed, asset_loader) = measure_time(|| CommonAssetLoader::new(grf_paths));
+    log::info!("<<< GRF loading: {}ms", elapsed.as_millis());
+    return asset_loader.map_err(|e| {
+        format!(
+            "Could not open grf files, please configure them in 'config.toml': {}",
+            e
+        )
+    });
+}
+
+fn map_file_path(map_dir: &str, map_name: &str) -> PathBuf {
+    Path::new(map_dir).join(format!("{}.{}", map_name, MAP_FILE_EXTENSION))
+}
+
+/// Without map names every map is exported which can be found in the GRF archives
+pub fn export_maps(config: &AppConfig, out_dir: &str, map_names: &[String]) -> Result<(), String> {
+    let grf_paths = config
+        .grf_paths
+        .as_ref()
+        .ok_or("'grf_paths' has to be configured in 'config.toml' for exporting maps")?;
+    let asset_loader = load_grf_files(grf_paths)?;
+    let map_names = if map_names.is_empty() {
+        asset_loader
+            .read_dir("data")
+            .into_iter()
+            .filter(|file_name| file_name.ends_with(".gat"))
+            .map(|mut file_name| {
+                file_name.drain(.."data\\".len());
+                let len = file_name.len();
+                file_name.truncate(len - ".gat".len());
+                file_name
+            })
+            .collect()
+    } else {
+        map_names.to_vec()
+    };
+    std::fs::create_dir_all(out_dir).map_err(|e| e.to_string())?;
+    for map_name in &map_names {
+        let (gat, rectangles) = asset_loader.load_gat(map_name)?;
+        let path = map_file_path(out_dir, map_name);
+        let mut file = BufWriter::new(File::create(&path).map_err(|e| e.to_string())?);
+        MapWalkingInfo::from_gat(&gat)
+            .write_map_file(&rectangles, &mut file)
+            .map_err(|e| e.to_string())?;
+        log::info!("{} exported into {}", map_name, path.display());
+    }
+    log::info!("{} maps were exported", map_names.len());
+    return Ok(());
+}
+
+/// The map file is preferred, the GRF archives are opened only if it does not exist
+pub fn load_map(config: &AppConfig, map_name: &str) -> Result<MapWalkingInfo, String> {
+    let path = map_file_path(&config.map_dir, map_name);
+    log::info!(">>> Loading map {}", map_name);
+    let (elapsed, map_walking_info) = measure_time(|| {
+        if let Ok(file) = File::open(&path) {
+            MapWalkingInfo::read_map_file(&mut BufReader::new(file))
+                .map(|(map_walking_info, _rectangles)| map_walking_info)
+                .map_err(|e| format!("Could not load {}: {}", path.display(), e))
+        } else {
+            let grf_paths = config.grf_paths.as_ref().ok_or_else(|| {
+                format!(
+                    "{} does not exist and 'grf_paths' is not configured in 'config.toml'",
+                    path.display()
+                )
+            })?;
+            let (gat, _rectangles) = load_grf_files(grf_paths)?.load_gat(map_name)?;
+            Ok(MapWalkingInfo::from_gat(&gat))
+        }
+    });
+    log::info!("<<< Map loading: {}ms", elapsed.as_millis());
+    return map_walking_info;
+}
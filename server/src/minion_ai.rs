@@ -0,0 +1,206 @@
+//! The minions are controlled by the server, their controllers get a new intention
+//! in every frame. The waves are spawned at the gates of the teams on their own timers,
+//! the clients see the minions through their area of interest like any other character.
+use crate::server_config::{MinionsConfig, ServerConfig};
+use rustarok_common::common::{v2, EngineTime, LocalTime, Vec2};
+use rustarok_common::components::char::{
+    create_common_player_entity, CharOutlook, CharType, ControllerEntityId, JobId,
+    LocalCharEntityId, LocalCharStateComp, Sex, StaticCharDataComponent, Team,
+};
+use rustarok_common::components::controller::ControllerComponent;
+use rustarok_common::components::job_ids::JobSpriteId;
+use rustarok_common::systems::minion_ai::MinionAi;
+use specs::prelude::*;
+
+/// The minions of a wave are spawned in a column, this far from each other
+const WAVE_SPACING: f32 = 1.5;
+const HEAD_COUNT: usize = 5;
+
+/// Marks the controllers which are driven by the `MinionAiSystem`
+#[derive(Component)]
+pub struct MinionControllerComponent;
+
+pub struct MinionAiSystem;
+
+impl<'a> System<'a> for MinionAiSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, ControllerComponent>,
+        ReadStorage<'a, StaticCharDataComponent>,
+        ReadStorage<'a, LocalCharStateComp>,
+        ReadStorage<'a, MinionControllerComponent>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            mut controller_storage,
+            static_char_data_storage,
+            char_state_storage,
+            minion_controller_storage,
+        ): Self::SystemData,
+    ) {
+        for (controller_id, controller, _minion) in (
+            &entities,
+            &mut controller_storage,
+            &minion_controller_storage,
+        )
+            .join()
+        {
+            let intention = controller.controlled_entity.and_then(|minion_id| {
+                MinionAi::decide_intention(
+                    &entities,
+                    &static_char_data_storage,
+                    &char_state_storage,
+                    minion_id,
+                )
+            });
+            if intention.is_some() {
+                controller.intention = intention;
+            } else {
+                // the corpse of the minion has been removed
+                if let Err(e) = entities.delete(controller_id) {
+                    log::error!(
+                        "Could not remove the controller {:?}: {:?}",
+                        ControllerEntityId::new(controller_id),
+                        e
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// The time of the next wave of the teams, a resource of the room's world
+pub struct MinionWaves {
+    left_team_next_wave: Option<LocalTime>,
+    right_team_next_wave: Option<LocalTime>,
+    /// the heads of the minions are chosen by it, so the replays spawn the same ones
+    spawned_count: usize,
+}
+
+impl MinionWaves {
+    pub fn new(config: &MinionsConfig) -> MinionWaves {
+        let start = LocalTime::from(0.0);
+        MinionWaves {
+            left_team_next_wave: config
+                .left_team_wave_interval_seconds
+                .map(|interval| start.add_seconds(interval)),
+            right_team_next_wave: config
+                .right_team_wave_interval_seconds
+                .map(|interval| start.add_seconds(interval)),
+            spawned_count: 0,
+        }
+    }
+}
+
+pub fn spawn_minion_waves(ecs_world: &mut specs::World) {
+    let now = ecs_world.read_resource::<EngineTime>().now();
+    for team in &[Team::Left, Team::Right] {
+        let (interval, minions_per_wave, max_minions_per_team) = {
+            let config = &ecs_world.read_resource::<ServerConfig>().minions;
+            let interval = if *team == Team::Left {
+                config.left_team_wave_interval_seconds
+            } else {
+                config.right_team_wave_interval_seconds
+            };
+            (
+                interval,
+                config.minions_per_wave,
+                config.max_minions_per_team,
+            )
+        };
+        let interval = if let Some(interval) = interval {
+            interval
+        } else {
+            continue;
+        };
+        {
+            let mut waves = ecs_world.write_resource::<MinionWaves>();
+            let next_wave = if *team == Team::Left {
+                &mut waves.left_team_next_wave
+            } else {
+                &mut waves.right_team_next_wave
+            };
+            match next_wave {
+                Some(next_wave) if next_wave.has_already_passed(now) => {
+                    *next_wave = now.add_seconds(interval);
+                }
+                _ => continue,
+            }
+        }
+        let count = minions_per_wave
+            .min(max_minions_per_team.saturating_sub(count_minions(ecs_world, *team)));
+        let gate = MinionAi::gate_of(*team);
+        for i in 0..count {
+            let offset = (i as f32 - (count - 1) as f32 / 2.0) * WAVE_SPACING;
+            let job_id = if i % 2 == 0 {
+                JobId::MeleeMinion
+            } else {
+                JobId::RangedMinion
+            };
+            create_minion(ecs_world, v2(gate.x, gate.y + offset), *team, job_id);
+        }
+        if count > 0 {
+            log::debug!(
+                "A wave of {} minions has been spawned for {:?}",
+                count,
+                team
+            );
+        }
+    }
+}
+
+fn count_minions(ecs_world: &specs::World, team: Team) -> usize {
+    return (&ecs_world.read_storage::<StaticCharDataComponent>())
+        .join()
+        .filter(|it| it.typ == CharType::Minion && it.team == team)
+        .filter(|it| it.job_id == JobId::MeleeMinion || it.job_id == JobId::RangedMinion)
+        .count();
+}
+
+/// The minion and its controller
+pub fn create_minion(
+    ecs_world: &mut specs::World,
+    pos: Vec2,
+    team: Team,
+    job_id: JobId,
+) -> LocalCharEntityId {
+    let spawned_count = {
+        let mut waves = ecs_world.write_resource::<MinionWaves>();
+        waves.spawned_count += 1;
+        waves.spawned_count
+    };
+    let outlook = CharOutlook::Human {
+        job_sprite_id: if job_id == JobId::MeleeMinion {
+            JobSpriteId::SWORDMAN
+        } else {
+            JobSpriteId::ARCHER
+        },
+        head_index: spawned_count % HEAD_COUNT,
+        sex: if spawned_count % 2 == 0 {
+            Sex::Male
+        } else {
+            Sex::Female
+        },
+    };
+    let char_entity_id = create_common_player_entity(
+        "Minion".to_owned(),
+        ecs_world,
+        CharType::Minion,
+        job_id,
+        pos,
+        team,
+        outlook,
+    )
+    .build();
+    ecs_world
+        .create_entity()
+        .with(ControllerComponent::new(LocalCharEntityId::from(
+            char_entity_id,
+        )))
+        .with(MinionControllerComponent)
+        .build();
+    return LocalCharEntityId::from(char_entity_id);
+}
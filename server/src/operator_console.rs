@@ -0,0 +1,575 @@
+//! Server commands typed by the operator into the terminal of the server, or sent over a
+//! local admin socket. The socket connections receive the log of the server as well.
+//! The lines are read on background threads, the commands are executed in the main loop.
+//! Every operator has a writer thread, so a stalled operator blocks neither the logging
+//! nor the main loop.
+use crate::console_cmd::{execute_console_cmd, CommandResult};
+use crate::game_match::MatchState;
+use crate::room::{find_room, Room};
+use crate::server_config::OperatorConsoleConfig;
+use crate::session::Sessions;
+use crate::{kick_client, RemoteClient};
+use crossbeam_channel::{Receiver, Sender, TrySendError};
+use rustarok_common::common::EngineTime;
+use rustarok_common::console::{CommandArguments, ConsoleCommandError, PermissionLevel};
+use rustarok_common::packets::from_server::{FromServerPacket, RoomId};
+use rustarok_common::packets::to_server::ToServerPacket;
+use rustarok_common::packets::PacketHandlerThread;
+use specs::WorldExt;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const COMMANDS: &[(&str, &str)] = &[
+    ("help", ""),
+    ("status", ""),
+    ("list_clients", ""),
+    ("kick", "name"),
+    ("room", "room_id command [args]"),
+    ("shutdown", ""),
+];
+
+// the writer thread of an operator who doesn't read her socket gives up after this
+const WRITE_TIMEOUT: Duration = Duration::from_millis(500);
+// a TCP connection which does not send the secret in time is closed
+const SECRET_READ_TIMEOUT: Duration = Duration::from_secs(5);
+// the secret line is not read further than this, it can not be longer
+const MAX_SECRET_LINE_LEN: u64 = 1024;
+/// The lines over this are dropped while the writer thread of the operator is stalled
+const OUTPUT_QUEUE_SIZE: usize = 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct OperatorId(u32);
+
+const STDIN_OPERATOR: OperatorId = OperatorId(0);
+
+struct Operator {
+    /// to the writer thread of the operator
+    output: Sender<Vec<u8>>,
+    receives_log: bool,
+}
+
+impl Operator {
+    fn new(mut output: Box<dyn Write + Send>, receives_log: bool) -> Operator {
+        let (tx, rx) = crossbeam_channel::bounded::<Vec<u8>>(OUTPUT_QUEUE_SIZE);
+        std::thread::spawn(move || {
+            // the operator is removed at her next line when the channel is closed here
+            for bytes in rx.iter() {
+                if output.write_all(&bytes).is_err() || output.flush().is_err() {
+                    break;
+                }
+            }
+        });
+        return Operator {
+            output: tx,
+            receives_log,
+        };
+    }
+
+    /// False if the operator is gone, the line is dropped when her queue is full
+    fn send(&self, bytes: &[u8]) -> bool {
+        return match self.output.try_send(bytes.to_vec()) {
+            Ok(()) | Err(TrySendError::Full(_)) => true,
+            Err(TrySendError::Disconnected(_)) => false,
+        };
+    }
+}
+
+/// Shared with the logger and the reading threads
+#[derive(Clone)]
+struct Operators {
+    operators: Arc<Mutex<HashMap<OperatorId, Operator>>>,
+    next_id: Arc<Mutex<u32>>,
+}
+
+impl Operators {
+    fn add(&self, output: Box<dyn Write + Send>, receives_log: bool) -> OperatorId {
+        let id = {
+            let mut next_id = self.next_id.lock().unwrap();
+            *next_id += 1;
+            OperatorId(*next_id)
+        };
+        self.operators
+            .lock()
+            .unwrap()
+            .insert(id, Operator::new(output, receives_log));
+        return id;
+    }
+
+    fn remove(&self, id: OperatorId) {
+        self.operators.lock().unwrap().remove(&id);
+    }
+
+    /// Nothing can be logged here, the logger locks the operators too
+    fn write(&self, id: OperatorId, text: &str) {
+        let mut operators = self.operators.lock().unwrap();
+        let failed = operators
+            .get(&id)
+            .map(|operator| !operator.send(text.as_bytes()))
+            .unwrap_or(false);
+        if failed {
+            operators.remove(&id);
+        }
+    }
+}
+
+/// The sink of the logger, it writes into stderr and into the admin sockets
+pub struct LogSink {
+    operators: Operators,
+}
+
+impl Write for LogSink {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        {
+            let mut operators = self.operators.operators.lock().unwrap();
+            operators.retain(|_id, operator| !operator.receives_log || operator.send(buf));
+        }
+        std::io::stderr().write_all(buf)?;
+        return Ok(buf.len());
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        return std::io::stderr().flush();
+    }
+}
+
+pub struct OperatorConsole {
+    operators: Operators,
+    commands: Receiver<(OperatorId, String)>,
+    unix_socket: Option<String>,
+}
+
+/// The logger has to be set up before anything is logged, the console is started
+/// later with the same operator list
+pub fn create_log_sink() -> (LogSink, OperatorConsoleBuilder) {
+    let operators = Operators {
+        operators: Arc::new(Mutex::new(HashMap::new())),
+        next_id: Arc::new(Mutex::new(STDIN_OPERATOR.0)),
+    };
+    return (
+        LogSink {
+            operators: operators.clone(),
+        },
+        OperatorConsoleBuilder { operators },
+    );
+}
+
+pub struct OperatorConsoleBuilder {
+    operators: Operators,
+}
+
+impl OperatorConsoleBuilder {
+    pub fn start(self, config: &OperatorConsoleConfig) -> OperatorConsole {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let operators = self.operators;
+        if config.stdin {
+            operators.operators.lock().unwrap().insert(
+                STDIN_OPERATOR,
+                // the log is already written into stderr
+                Operator::new(Box::new(std::io::stdout()), false),
+            );
+            let tx = tx.clone();
+            std::thread::spawn(move || {
+                read_commands(STDIN_OPERATOR, std::io::stdin(), tx);
+            });
+        }
+        let unix_socket = if config.unix_socket.is_empty() {
+            None
+        } else {
+            listen_on_unix_socket(&config.unix_socket, &operators, &tx)
+        };
+        if config.tcp_port != 0 {
+            if config.secret.is_empty() {
+                log::error!("The operator console is not available over TCP without a secret");
+            } else {
+                listen_on_tcp(config.tcp_port, config.secret.clone(), &operators, &tx);
+            }
+        }
+        return OperatorConsole {
+            operators,
+            commands: rx,
+            unix_socket,
+        };
+    }
+}
+
+impl Drop for OperatorConsole {
+    fn drop(&mut self) {
+        if let Some(path) = &self.unix_socket {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+fn read_commands(operator_id: OperatorId, input: impl Read, tx: Sender<(OperatorId, String)>) {
+    for line in BufReader::new(input).lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        if tx.send((operator_id, line)).is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(unix)]
+fn listen_on_unix_socket(
+    path: &str,
+    operators: &Operators,
+    tx: &Sender<(OperatorId, String)>,
+) -> Option<String> {
+    use std::os::unix::net::UnixListener;
+    // the socket of a previous run which has not been shut down properly
+    let _ = std::fs::remove_file(path);
+    let listener = match UnixListener::bind(path) {
+        Ok(listener) => listener,
+        Err(e) => {
+            log::error!("Could not bind the operator console to {}: {}", path, e);
+            return None;
+        }
+    };
+    log::info!("Operator console is listening on {}", path);
+    let operators = operators.clone();
+    let tx = tx.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    log::warn!("Operator connection has failed: {}", e);
+                    continue;
+                }
+            };
+            let output = match stream.try_clone() {
+                Ok(output) => output,
+                Err(e) => {
+                    log::warn!("Operator connection has failed: {}", e);
+                    continue;
+                }
+            };
+            let _ = output.set_write_timeout(Some(WRITE_TIMEOUT));
+            let operators = operators.clone();
+            let tx = tx.clone();
+            std::thread::spawn(move || {
+                serve_operator(stream, Box::new(output), operators, tx);
+            });
+        }
+    });
+    return Some(path.to_owned());
+}
+
+#[cfg(not(unix))]
+fn listen_on_unix_socket(
+    path: &str,
+    _operators: &Operators,
+    _tx: &Sender<(OperatorId, String)>,
+) -> Option<String> {
+    log::error!(
+        "Unix domain sockets are not supported on this platform: {}",
+        path
+    );
+    return None;
+}
+
+fn listen_on_tcp(
+    port: u16,
+    secret: String,
+    operators: &Operators,
+    tx: &Sender<(OperatorId, String)>,
+) {
+    let addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), port);
+    let listener = match TcpListener::bind(addr) {
+        Ok(listener) => listener,
+        Err(e) => {
+            log::error!("Could not bind the operator console to {}: {}", addr, e);
+            return;
+        }
+    };
+    log::info!("Operator console is listening on {}", addr);
+    let operators = operators.clone();
+    let tx = tx.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    log::warn!("Operator connection has failed: {}", e);
+                    continue;
+                }
+            };
+            let operators = operators.clone();
+            let tx = tx.clone();
+            let secret = secret.clone();
+            std::thread::spawn(move || {
+                if let Err(e) = authenticate_and_serve(stream, &secret, operators, tx) {
+                    log::warn!("Operator connection has failed: {}", e);
+                }
+            });
+        }
+    });
+}
+
+fn authenticate_and_serve(
+    stream: TcpStream,
+    secret: &str,
+    operators: Operators,
+    tx: Sender<(OperatorId, String)>,
+) -> std::io::Result<()> {
+    let peer_addr = stream.peer_addr()?;
+    stream.set_read_timeout(Some(SECRET_READ_TIMEOUT))?;
+    let mut output = stream.try_clone()?;
+    output.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    let mut reader = BufReader::new(stream);
+    let first_line = read_secret_line(&mut reader)?;
+    if !constant_time_eq(first_line.trim_end().as_bytes(), secret.as_bytes()) {
+        log::warn!("Operator from {} has sent a wrong secret", peer_addr);
+        output.write_all(b"Wrong secret\n")?;
+        return Ok(());
+    }
+    // the operator can be idle as long as she wants
+    output.set_read_timeout(None)?;
+    serve_operator(reader, Box::new(output), operators, tx);
+    return Ok(());
+}
+
+fn read_secret_line(reader: &mut impl BufRead) -> std::io::Result<String> {
+    let mut line = Vec::new();
+    reader
+        .take(MAX_SECRET_LINE_LEN)
+        .read_until(b'\n', &mut line)?;
+    return Ok(String::from_utf8_lossy(&line).into_owned());
+}
+
+/// The time of the comparison does not tell how much of the secret has been guessed
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    let mut diff = a.len() ^ b.len();
+    for i in 0..a.len().max(b.len()) {
+        let x = a.get(i).copied().unwrap_or(0);
+        let y = b.get(i).copied().unwrap_or(0);
+        diff |= (x ^ y) as usize;
+    }
+    return diff == 0;
+}
+
+fn serve_operator(
+    input: impl Read,
+    mut output: Box<dyn Write + Send>,
+    operators: Operators,
+    tx: Sender<(OperatorId, String)>,
+) {
+    let _ = output.write_all(b"rustarok-server operator console, type 'help' for the commands\n");
+    let operator_id = operators.add(output, true);
+    log::info!("Operator({}) has connected", operator_id.0);
+    read_commands(operator_id, input, tx);
+    operators.remove(operator_id);
+    log::info!("Operator({}) has disconnected", operator_id.0);
+}
+
+impl OperatorConsole {
+    /// Returns true if the operator has asked for shutting down the server
+    pub fn execute_commands(
+        &self,
+        packet_handler_thread: &mut PacketHandlerThread<ToServerPacket, FromServerPacket>,
+        remote_clients: &mut Vec<Option<RemoteClient>>,
+        rooms: &mut Vec<Room>,
+        sessions: &Sessions,
+        server_started: Instant,
+    ) -> bool {
+        let mut shutdown = false;
+        for (operator_id, line) in self.commands.try_iter() {
+            let args = CommandArguments::new(&line);
+            log::info!("Operator({}): {}", operator_id.0, line);
+            let result = match args.get_command_name().unwrap_or("") {
+                "help" => Ok(COMMANDS
+                    .iter()
+                    .map(|(name, arguments)| format!("{} {}", name, arguments))
+                    .collect::<Vec<_>>()
+                    .join("\n")),
+                "status" => Ok(cmd_status(remote_clients, rooms, sessions, server_started)),
+                "list_clients" => Ok(cmd_list_clients(remote_clients, rooms)),
+                "kick" => cmd_kick(&args, packet_handler_thread, remote_clients, rooms),
+                "room" => cmd_room(&args, rooms),
+                "shutdown" => {
+                    shutdown = true;
+                    Ok("Shutting down".to_owned())
+                }
+                name => Err(ConsoleCommandError::UnknownCommand(name.to_owned())),
+            };
+            let reply = match result {
+                Ok(text) if text.is_empty() => "Ok\n".to_owned(),
+                Ok(text) => format!("{}\n", text),
+                Err(e) => format!("Error: {}\n", e),
+            };
+            self.operators.write(operator_id, &reply);
+        }
+        return shutdown;
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    return format!(
+        "{}h {:02}m {:02}s",
+        seconds / 3600,
+        (seconds / 60) % 60,
+        seconds % 60
+    );
+}
+
+fn cmd_status(
+    remote_clients: &[Option<RemoteClient>],
+    rooms: &[Room],
+    sessions: &Sessions,
+    server_started: Instant,
+) -> String {
+    let clients = remote_clients.iter().filter_map(|it| it.as_ref());
+    let mut lines = vec![
+        format!("uptime: {}", format_duration(server_started.elapsed())),
+        format!(
+            "clients: {}, logged in: {}, disconnected players kept: {}",
+            clients.clone().count(),
+            clients.filter(|it| it.logged_in).count(),
+            sessions.count()
+        ),
+    ];
+    for room in rooms {
+        let now = room.ecs_world.read_resource::<EngineTime>().now();
+        lines.push(format!(
+            "room {} '{}' on {}: {} players, {:?}",
+            room.id.0,
+            room.name,
+            room.map_name,
+            room.player_count(remote_clients),
+            room.ecs_world.read_resource::<MatchState>().phase(now)
+        ));
+    }
+    return lines.join("\n");
+}
+
+fn cmd_list_clients(remote_clients: &[Option<RemoteClient>], rooms: &[Room]) -> String {
+    let mut lines = vec![format!(
+        "{:<8}{:<24}{:<24}{:<16}{:<8}{:>8}",
+        "socket", "name", "address", "room", "level", "idle[s]"
+    )];
+    for remote_client in remote_clients.iter().filter_map(|it| it.as_ref()) {
+        let room_name = remote_client
+            .room_id
+            .and_then(|room_id| rooms.iter().find(|room| room.id == room_id))
+            .map(|room| room.name.as_str())
+            .unwrap_or("-");
+        lines.push(format!(
+            "{:<8}{:<24}{:<24}{:<16}{:<8}{:>8}",
+            remote_client.socket_id.as_usize(),
+            remote_client.name,
+            remote_client.sock_addr.to_string(),
+            room_name,
+            format!("{:?}", remote_client.permission),
+            remote_client.last_packet_at.elapsed().as_secs()
+        ));
+    }
+    return lines.join("\n");
+}
+
+fn cmd_kick(
+    args: &CommandArguments,
+    packet_handler_thread: &mut PacketHandlerThread<ToServerPacket, FromServerPacket>,
+    remote_clients: &mut Vec<Option<RemoteClient>>,
+    rooms: &mut Vec<Room>,
+) -> CommandResult {
+    let name = args
+        .as_str(0)
+        .ok_or_else(|| ConsoleCommandError::MissingArgument("name".to_owned()))?;
+    let socket_id = remote_clients
+        .iter()
+        .filter_map(|it| it.as_ref())
+        .find(|it| it.logged_in && it.name.eq_ignore_ascii_case(name))
+        .map(|it| it.socket_id)
+        .ok_or_else(|| ConsoleCommandError::CharacterNotFound(name.to_owned()))?;
+    let remote_client = kick_client(socket_id, remote_clients, rooms, packet_handler_thread);
+    log::info!("{} has been kicked by an operator", remote_client.name);
+    return Ok(format!("{} has been kicked", remote_client.name));
+}
+
+/// `room <room_id> <command> [args]` executes a game console command in the room
+fn cmd_room(args: &CommandArguments, rooms: &mut Vec<Room>) -> CommandResult {
+    let room_id = args
+        .as_int(0)
+        .ok_or_else(|| ConsoleCommandError::MissingArgument("room_id".to_owned()))?;
+    let room = find_room(rooms, Some(RoomId(room_id as u32)))
+        .ok_or_else(|| ConsoleCommandError::Failed(format!("No room with id {}", room_id)))?;
+    let command = CommandArguments {
+        args: args.args.iter().skip(2).cloned().collect(),
+    };
+    if command.get_command_name().is_none() {
+        return Err(ConsoleCommandError::MissingArgument("command".to_owned()));
+    }
+    return execute_console_cmd(None, PermissionLevel::Admin, command, &mut room.ecs_world);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An operator who never reads her socket
+    struct StalledOutput;
+
+    impl Write for StalledOutput {
+        fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+            std::thread::sleep(Duration::from_secs(3600));
+            return Ok(0);
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            return Ok(());
+        }
+    }
+
+    #[test]
+    fn a_stalled_operator_does_not_block_the_logging() {
+        let (mut log_sink, builder) = create_log_sink();
+        let id = builder.operators.add(Box::new(StalledOutput), true);
+        let started = Instant::now();
+        {
+            let operators = builder.operators.operators.lock().unwrap();
+            for _ in 0..OUTPUT_QUEUE_SIZE * 2 {
+                assert!(operators[&id].send(b"line\n"));
+            }
+        }
+        assert_eq!(14, log_sink.write(b"log_sink test\n").unwrap());
+        builder.operators.write(id, "reply\n");
+        assert!(started.elapsed() < WRITE_TIMEOUT);
+        // the operator is kept, only her lines are dropped
+        assert!(builder
+            .operators
+            .operators
+            .lock()
+            .unwrap()
+            .contains_key(&id));
+    }
+
+    #[test]
+    fn the_secret_line_is_capped() {
+        let mut input = "secret\nhelp\n".as_bytes();
+        assert_eq!(read_secret_line(&mut input).unwrap(), "secret\n");
+        assert_eq!(input, b"help\n");
+
+        let endless_line = vec![b'a'; 10 * MAX_SECRET_LINE_LEN as usize];
+        let line = read_secret_line(&mut endless_line.as_slice()).unwrap();
+        assert_eq!(line.len(), MAX_SECRET_LINE_LEN as usize);
+    }
+
+    #[test]
+    fn the_secrets_are_compared_fully() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret2"));
+        assert!(!constant_time_eq(b"", b"secret"));
+        assert!(constant_time_eq(b"", b""));
+    }
+}
@@ -0,0 +1,454 @@
+//! A recording contains the configs of a room when it was created, then every input of
+//! the simulation with the tick it was applied in, and the hash of the world state after
+//! every tick. The room is recorded from its empty world, so the commands of init.cmd are
+//! the first events of tick 0, and every entity and resource they create is replayed.
+//! `rustarok-server replay <file>` simulates it again without network and stops at the
+//! first tick whose hash differs from the recorded one.
+use crate::config_watcher::apply_common_configs;
+use crate::game_match::MatchState;
+use crate::map_export::load_map;
+use crate::room::{create_room_world, simulate_frame};
+use crate::server_config::{parse_common_configs, ServerConfig};
+use crate::{
+    create_player, delete_player, execute_console_cmd, idle_player, set_intention, AppConfig,
+    OutPacketCollector,
+};
+use rustarok_common::common::{EngineTime, SimulationTick, Vec2};
+use rustarok_common::components::char::{
+    CharOutlook, ControllerEntityId, JobId, LocalCharStateComp, StaticCharDataComponent, Team,
+};
+use rustarok_common::components::controller::PlayerIntention;
+use rustarok_common::console::{CommandArguments, PermissionLevel};
+use rustarok_common::map::MapWalkingInfo;
+use serde::{Deserialize, Serialize};
+use specs::prelude::*;
+use std::fs::File;
+use std::hash::Hasher;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+const REPLAY_FORMAT_VERSION: u8 = 3;
+
+#[derive(Serialize, Deserialize)]
+pub struct ReplayHeader {
+    format_version: u8,
+    map_name: String,
+    /// the content of server-conf.toml
+    server_config: String,
+    /// the content of config-runtime.toml
+    common_configs: String,
+}
+
+/// The entities are identified by their indices, they are the same in the replay
+/// as long as the simulation is deterministic
+#[derive(Serialize, Deserialize)]
+pub enum ReplayEvent {
+    PlayerJoined {
+        char_id: u32,
+        controller_id: u32,
+        name: String,
+        job_id: JobId,
+        pos: Vec2,
+        team: Team,
+        outlook: CharOutlook,
+    },
+    Intention {
+        controller_id: u32,
+        client_tick: SimulationTick,
+        intention: PlayerIntention,
+    },
+    ConsoleCommand {
+        controller_id: Option<u32>,
+        args: CommandArguments,
+    },
+    PlayerDisconnected {
+        controller_id: u32,
+    },
+    PlayerRemoved {
+        controller_id: u32,
+    },
+    ConfigsReloaded {
+        common_configs: String,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+enum ReplayEntry {
+    Event(SimulationTick, ReplayEvent),
+    TickHash(SimulationTick, u64),
+}
+
+/// A resource of the room's world, the rooms without it are not recorded
+pub struct ReplayRecorder {
+    writer: Option<BufWriter<File>>,
+}
+
+impl ReplayRecorder {
+    /// It has to be inserted into the world before anything is created in it
+    pub fn create(
+        path: &Path,
+        map_name: &str,
+        server_config: String,
+        common_configs: String,
+    ) -> Result<ReplayRecorder, String> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let mut writer = BufWriter::new(File::create(path).map_err(|e| e.to_string())?);
+        let header = ReplayHeader {
+            format_version: REPLAY_FORMAT_VERSION,
+            map_name: map_name.to_owned(),
+            server_config,
+            common_configs,
+        };
+        bincode::serialize_into(&mut writer, &header).map_err(|e| e.to_string())?;
+        log::info!("Recording replay into {}", path.display());
+        return Ok(ReplayRecorder {
+            writer: Some(writer),
+        });
+    }
+
+    fn write(&mut self, entry: &ReplayEntry, flush: bool) {
+        if let Some(writer) = &mut self.writer {
+            let result = bincode::serialize_into(&mut *writer, entry)
+                .map_err(|e| e.to_string())
+                .and_then(|_| {
+                    if flush {
+                        writer.flush().map_err(|e| e.to_string())
+                    } else {
+                        Ok(())
+                    }
+                });
+            if let Err(e) = result {
+                log::error!("Replay recording has been stopped: {}", e);
+                self.writer = None;
+            }
+        }
+    }
+}
+
+pub fn entity_index(entity: Entity) -> u32 {
+    entity.id()
+}
+
+pub fn controller_index(controller_id: ControllerEntityId) -> u32 {
+    entity_index(controller_id.into())
+}
+
+/// The event belongs to the current tick of the room, before the frame is simulated
+pub fn record_event(ecs_world: &specs::World, event: ReplayEvent) {
+    if let Some(mut recorder) = ecs_world.try_fetch_mut::<ReplayRecorder>() {
+        let tick = *ecs_world.read_resource::<SimulationTick>();
+        recorder.write(&ReplayEntry::Event(tick, event), false);
+    }
+}
+
+pub fn record_tick_hash(ecs_world: &specs::World) {
+    if let Some(mut recorder) = ecs_world.try_fetch_mut::<ReplayRecorder>() {
+        let tick = *ecs_world.read_resource::<SimulationTick>();
+        recorder.write(
+            &ReplayEntry::TickHash(tick, world_state_hash(ecs_world)),
+            true,
+        );
+    }
+}
+
+/// 64 bit FNV-1a. Unlike `DefaultHasher`, its output is specified, so the recorded hashes
+/// can be checked by other builds and platforms too.
+struct FnvHasher(u64);
+
+impl FnvHasher {
+    fn new() -> FnvHasher {
+        FnvHasher(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for FnvHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+}
+
+/// The characters are joined in the order of their entity ids, so it doesn't depend on
+/// the insertion order of the storages
+pub fn world_state_hash(ecs_world: &specs::World) -> u64 {
+    let mut hasher = FnvHasher::new();
+    for (entity, char_state, static_data) in (
+        &ecs_world.entities(),
+        &ecs_world.read_storage::<LocalCharStateComp>(),
+        &ecs_world.read_storage::<StaticCharDataComponent>(),
+    )
+        .join()
+    {
+        hasher.write(&entity.id().to_le_bytes());
+        hasher.write(&bincode::serialize(char_state).unwrap());
+        hasher.write(&bincode::serialize(static_data).unwrap());
+    }
+    let match_state = ecs_world.read_resource::<MatchState>();
+    let now = ecs_world.read_resource::<EngineTime>().now();
+    hasher.write(&bincode::serialize(&match_state.phase(now)).unwrap());
+    hasher.write(&bincode::serialize(&match_state.score()).unwrap());
+    return hasher.finish();
+}
+
+pub fn run_replay(config: &AppConfig, path: &str) -> Result<(), String> {
+    let file = File::open(path).map_err(|e| format!("{}: {}", path, e))?;
+    let replayed_ticks = replay(&mut BufReader::new(file), |map_name| {
+        load_map(config, map_name)
+    })?;
+    log::info!(
+        "{} ticks have been replayed, every state hash matches",
+        replayed_ticks
+    );
+    return Ok(());
+}
+
+/// Returns the number of the replayed ticks
+fn replay<R: Read>(
+    reader: &mut R,
+    load_map: impl Fn(&str) -> Result<MapWalkingInfo, String>,
+) -> Result<u64, String> {
+    let header: ReplayHeader =
+        bincode::deserialize_from(&mut *reader).map_err(|e| e.to_string())?;
+    if header.format_version != REPLAY_FORMAT_VERSION {
+        return Err(format!(
+            "Unsupported replay format version: {}",
+            header.format_version
+        ));
+    }
+    let server_config =
+        ServerConfig::from_toml(&header.server_config).map_err(|e| e.to_string())?;
+    let tick_duration = server_config.network.tick_duration();
+    let common_configs = parse_common_configs(&header.common_configs).map_err(|e| e.to_string())?;
+    let map = load_map(&header.map_name)?;
+    let (mut ecs_world, mut ecs_dispatcher) = create_room_world(server_config, common_configs, map);
+
+    let mut replayed_ticks = 0;
+    loop {
+        let entry: ReplayEntry = match bincode::deserialize_from(&mut *reader) {
+            Ok(entry) => entry,
+            Err(e) => match *e {
+                bincode::ErrorKind::Io(ref io_error)
+                    if io_error.kind() == std::io::ErrorKind::UnexpectedEof =>
+                {
+                    break;
+                }
+                _ => return Err(e.to_string()),
+            },
+        };
+        let tick = *ecs_world.read_resource::<SimulationTick>();
+        match entry {
+            ReplayEntry::Event(event_tick, event) => {
+                if event_tick != tick {
+                    return Err(format!(
+                        "An event of tick {} has been found in tick {}",
+                        event_tick.as_u64(),
+                        tick.as_u64()
+                    ));
+                }
+                apply_event(&mut ecs_world, event)?;
+            }
+            ReplayEntry::TickHash(hash_tick, recorded_hash) => {
+                if hash_tick != tick {
+                    return Err(format!(
+                        "The hash of tick {} has been found in tick {}",
+                        hash_tick.as_u64(),
+                        tick.as_u64()
+                    ));
+                }
+                simulate_frame(&mut ecs_world, &mut ecs_dispatcher);
+                ecs_world.write_resource::<OutPacketCollector>().clear();
+                if world_state_hash(&ecs_world) != recorded_hash {
+                    return Err(format!(
+                        "The simulation has diverged in tick {}",
+                        tick.as_u64()
+                    ));
+                }
+                replayed_ticks += 1;
+                ecs_world.write_resource::<EngineTime>().tick(tick_duration);
+                ecs_world.write_resource::<SimulationTick>().inc();
+            }
+        }
+    }
+    return Ok(replayed_ticks);
+}
+
+fn expect_index(entity: Entity, index: u32) -> Result<(), String> {
+    return if entity_index(entity) == index {
+        Ok(())
+    } else {
+        Err(format!(
+            "Entity {} has been created instead of {}",
+            entity_index(entity),
+            index
+        ))
+    };
+}
+
+fn controller_entity(ecs_world: &specs::World, index: u32) -> ControllerEntityId {
+    ControllerEntityId::from(ecs_world.entities().entity(index))
+}
+
+fn apply_event(ecs_world: &mut specs::World, event: ReplayEvent) -> Result<(), String> {
+    match event {
+        ReplayEvent::PlayerJoined {
+            char_id,
+            controller_id,
+            name,
+            job_id,
+            pos,
+            team,
+            outlook,
+        } => {
+            let (created_char_id, created_controller_id) =
+                create_player(ecs_world, name, job_id, pos, team, outlook);
+            expect_index(created_char_id.into(), char_id)?;
+            expect_index(created_controller_id.into(), controller_id)?;
+        }
+        ReplayEvent::Intention {
+            controller_id,
+            client_tick,
+            intention,
+        } => {
+            let controller_id = controller_entity(ecs_world, controller_id);
+            set_intention(ecs_world, controller_id, client_tick, intention);
+        }
+        ReplayEvent::ConsoleCommand {
+            controller_id,
+            args,
+        } => {
+            let controller_id = controller_id.map(|it| controller_entity(ecs_world, it));
+            // only the permitted commands have been recorded
+            if let Err(e) =
+                execute_console_cmd(controller_id, PermissionLevel::Admin, args, ecs_world)
+            {
+                log::debug!("A replayed console command has failed: {}", e);
+            }
+        }
+        ReplayEvent::PlayerDisconnected { controller_id } => {
+            let controller_id = controller_entity(ecs_world, controller_id);
+            idle_player(ecs_world, controller_id);
+        }
+        ReplayEvent::PlayerRemoved { controller_id } => {
+            let controller_id = controller_entity(ecs_world, controller_id);
+            delete_player(ecs_world, controller_id);
+        }
+        ReplayEvent::ConfigsReloaded { common_configs } => {
+            let configs = parse_common_configs(&common_configs).map_err(|e| e.to_string())?;
+            apply_common_configs(ecs_world, common_configs, configs);
+        }
+    }
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustarok_common::common::v2;
+    use rustarok_common::components::char::Sex;
+    use rustarok_common::components::job_ids::JobSpriteId;
+    use rustarok_common::map::CellType;
+
+    const TICKS: u64 = 300;
+
+    fn walkable_map() -> MapWalkingInfo {
+        MapWalkingInfo {
+            width: 400,
+            height: 400,
+            cells: vec![CellType::Walkable as u8; 400 * 400],
+        }
+    }
+
+    #[test]
+    fn the_state_hash_is_fnv1a() {
+        let hash = |bytes: &[u8]| {
+            let mut hasher = FnvHasher::new();
+            hasher.write(bytes);
+            hasher.finish()
+        };
+        assert_eq!(hash(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(hash(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(hash(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn a_recorded_room_can_be_replayed() {
+        let server_config_text = include_str!("../server-conf.toml");
+        let common_configs_text = include_str!("../config-runtime.toml");
+        let server_config = ServerConfig::from_toml(server_config_text).unwrap();
+        let tick_duration = server_config.network.tick_duration();
+        let (mut ecs_world, mut ecs_dispatcher) = create_room_world(
+            server_config,
+            parse_common_configs(common_configs_text).unwrap(),
+            walkable_map(),
+        );
+        let path = std::env::temp_dir().join(format!(
+            "rustarok_replay_test_{}.replay",
+            std::process::id()
+        ));
+        let recorder = ReplayRecorder::create(
+            &path,
+            "test_map",
+            server_config_text.to_owned(),
+            common_configs_text.to_owned(),
+        )
+        .unwrap();
+        ecs_world.insert(recorder);
+
+        // the guards, dummies and areas of init.cmd are created by the replay too
+        for line in include_str!("../init.cmd").lines() {
+            if line.starts_with("//") || line.trim().is_empty() {
+                continue;
+            }
+            execute_console_cmd(
+                None,
+                PermissionLevel::Admin,
+                CommandArguments::new(line),
+                &mut ecs_world,
+            )
+            .unwrap();
+        }
+        let (_char_id, controller_id) = create_player(
+            &mut ecs_world,
+            "Tester".to_owned(),
+            JobId::CRUSADER,
+            v2(219.0, -68.0),
+            Team::Right,
+            CharOutlook::Human {
+                job_sprite_id: JobSpriteId::from_job_id(JobId::CRUSADER),
+                head_index: 0,
+                sex: Sex::Male,
+            },
+        );
+
+        for _ in 0..TICKS {
+            let tick = *ecs_world.read_resource::<SimulationTick>();
+            if tick.as_u64() == 10 {
+                set_intention(
+                    &mut ecs_world,
+                    controller_id,
+                    tick,
+                    PlayerIntention::MoveTo(v2(219.0, -66.0)),
+                );
+            }
+            simulate_frame(&mut ecs_world, &mut ecs_dispatcher);
+            ecs_world.write_resource::<OutPacketCollector>().clear();
+            record_tick_hash(&ecs_world);
+            ecs_world.write_resource::<EngineTime>().tick(tick_duration);
+            ecs_world.write_resource::<SimulationTick>().inc();
+        }
+        drop(ecs_world);
+
+        let file = File::open(&path).unwrap();
+        let result = replay(&mut BufReader::new(file), |_map_name| Ok(walkable_map()));
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(result, Ok(TICKS));
+    }
+}
@@ -0,0 +1,481 @@
+//! Every room has its own world, map and match, the players of a room don't see the others.
+//! The default room is created on the configured map at startup and it is never removed,
+//! the rooms created by the players are removed when nobody is in them.
+use crate::config_watcher::read_common_configs;
+use crate::controller_intention_to_char_target::ControllerIntentionToCharTargetSystem;
+use crate::game_match::MatchState;
+use crate::interest::{update_areas_of_interest, SpatialGrid};
+use crate::map_export::load_map;
+use crate::minion_ai::{MinionAiSystem, MinionWaves};
+use crate::replay::{record_tick_hash, ReplayRecorder};
+use crate::server_config::ServerConfig;
+use crate::session::Sessions;
+use crate::telemetry::{SystemTimings, TimedSystem};
+use crate::turret_ai::TurretAiSystem;
+use crate::{
+    create_ecs_world, execute_console_cmd, remove_player, run_frame, send_packets, send_snapshots,
+    AppConfig, OutPacketCollector, RemoteClient,
+};
+use rustarok_common::attack::{ApplyForceComponent, AreaAttackComponent, HpModificationRequest};
+use rustarok_common::common::{EngineTime, SimulationTick};
+use rustarok_common::components::skills::FinishCast;
+use rustarok_common::components::status::status::{ApplyStatusComponent, RemoveStatusComponent};
+use rustarok_common::config::CommonConfigs;
+use rustarok_common::console::{CommandArguments, PermissionLevel};
+use rustarok_common::lag_compensation::LagCompensation;
+use rustarok_common::map::MapWalkingInfo;
+use rustarok_common::packets::from_server::{FromServerPacket, RoomError, RoomId, RoomInfo};
+use rustarok_common::packets::snapshot_delta::SnapshotHistory;
+use rustarok_common::packets::to_server::ToServerPacket;
+use rustarok_common::packets::{PacketHandlerThread, SocketId};
+use rustarok_common::systems::char_state_sys::CharacterStateUpdateSystem;
+use specs::prelude::*;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const DEFAULT_ROOM_NAME: &str = "main";
+const MAX_ROOM_NAME_LEN: usize = 24;
+
+/// The configs, the maps and the init script are read at startup, so creating a room
+/// does not block the main loop with file reads and map loading
+pub struct RoomAssets {
+    /// the rooms are recorded with the texts of the configs
+    server_config_text: String,
+    common_configs_text: String,
+    common_configs: CommonConfigs,
+    /// the configured map and `rooms.maps`
+    maps: HashMap<String, MapWalkingInfo>,
+    default_map_name: String,
+    /// the lines of init.cmd, they are executed in the rooms of the configured map
+    init_commands: Vec<String>,
+}
+
+impl RoomAssets {
+    /// The maps of `rooms.maps` which can't be loaded are reported and left out
+    pub fn load(
+        config: &AppConfig,
+        server_config_text: String,
+        server_config: &ServerConfig,
+    ) -> Result<RoomAssets, String> {
+        let (common_configs_text, common_configs) = read_common_configs()?;
+        let mut maps = HashMap::new();
+        maps.insert(config.map_name.clone(), load_map(config, &config.map_name)?);
+        for map_name in &server_config.rooms.maps {
+            if maps.contains_key(map_name) {
+                continue;
+            }
+            match load_map(config, map_name) {
+                Ok(map) => {
+                    maps.insert(map_name.clone(), map);
+                }
+                Err(e) => log::error!("Rooms can't be created on {}: {}", map_name, e),
+            }
+        }
+        let file = File::open("init.cmd").map_err(|e| e.to_string())?;
+        let mut init_commands = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(|e| e.to_string())?;
+            if line.starts_with("//") || line.trim().is_empty() {
+                continue;
+            }
+            init_commands.push(line);
+        }
+        return Ok(RoomAssets {
+            server_config_text,
+            common_configs_text,
+            common_configs,
+            maps,
+            default_map_name: config.map_name.clone(),
+            init_commands,
+        });
+    }
+
+    pub fn has_map(&self, map_name: &str) -> bool {
+        self.maps.contains_key(map_name)
+    }
+
+    /// The new rooms are created with the reloaded configs
+    pub fn set_common_configs(&mut self, text: String, configs: CommonConfigs) {
+        self.common_configs_text = text;
+        self.common_configs = configs;
+    }
+}
+
+#[cfg(test)]
+impl RoomAssets {
+    /// The assets of a single map without init commands
+    pub fn new_for_tests(
+        map_name: &str,
+        map: MapWalkingInfo,
+        server_config_text: &str,
+        common_configs_text: &str,
+    ) -> RoomAssets {
+        let mut maps = HashMap::new();
+        maps.insert(map_name.to_owned(), map);
+        RoomAssets {
+            server_config_text: server_config_text.to_owned(),
+            common_configs_text: common_configs_text.to_owned(),
+            common_configs: crate::server_config::parse_common_configs(common_configs_text)
+                .unwrap(),
+            maps,
+            default_map_name: map_name.to_owned(),
+            init_commands: Vec::new(),
+        }
+    }
+
+    pub fn common_configs_text(&self) -> &str {
+        &self.common_configs_text
+    }
+}
+
+pub struct Room {
+    pub id: RoomId,
+    pub name: String,
+    pub map_name: String,
+    /// the default room, it is not removed when it gets empty
+    pub persistent: bool,
+    pub ecs_world: specs::World,
+    ecs_dispatcher: specs::Dispatcher<'static, 'static>,
+    snapshot_history: SnapshotHistory,
+    spatial_grid: SpatialGrid,
+}
+
+impl Room {
+    pub fn new(
+        id: RoomId,
+        name: String,
+        map_name: String,
+        persistent: bool,
+        server_config: &ServerConfig,
+        assets: &RoomAssets,
+    ) -> Result<Room, String> {
+        let map = assets
+            .maps
+            .get(&map_name)
+            .cloned()
+            .ok_or_else(|| format!("{} has not been loaded", map_name))?;
+        let spatial_grid = SpatialGrid::new(server_config.interest.view_distance);
+        let replay_path = if server_config.replay.record {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|it| it.as_secs())
+                .unwrap_or(0);
+            Some(Path::new(&server_config.replay.dir).join(format!("{}_{}.replay", name, now)))
+        } else {
+            None
+        };
+        let (mut ecs_world, ecs_dispatcher) =
+            create_room_world(server_config.clone(), assets.common_configs.clone(), map);
+
+        if let Some(replay_path) = replay_path {
+            match ReplayRecorder::create(
+                &replay_path,
+                &map_name,
+                assets.server_config_text.clone(),
+                assets.common_configs_text.clone(),
+            ) {
+                Ok(recorder) => ecs_world.insert(recorder),
+                Err(e) => log::error!("The room is not recorded: {}", e),
+            }
+        }
+
+        // the coordinates in the init script belong to the configured map, and the
+        // recorder records the commands in tick 0
+        if map_name == assets.default_map_name {
+            for line in &assets.init_commands {
+                if let Err(e) = execute_console_cmd(
+                    None,
+                    PermissionLevel::Admin,
+                    CommandArguments::new(line),
+                    &mut ecs_world,
+                ) {
+                    log::warn!("init.cmd: '{}' has failed: {}", line, e);
+                }
+            }
+        }
+
+        log::info!("Room {:?} '{}' has been created on {}", id, name, map_name);
+        return Ok(Room {
+            id,
+            name,
+            map_name,
+            persistent,
+            ecs_world,
+            ecs_dispatcher,
+            snapshot_history: SnapshotHistory::new(),
+            spatial_grid,
+        });
+    }
+
+    pub fn player_count(&self, remote_clients: &[Option<RemoteClient>]) -> usize {
+        remote_clients
+            .iter()
+            .filter_map(|it| it.as_ref())
+            .filter(|it| it.is_in(self.id))
+            .count()
+    }
+
+    pub fn info(&self, remote_clients: &[Option<RemoteClient>]) -> RoomInfo {
+        RoomInfo {
+            id: self.id,
+            name: self.name.clone(),
+            map_name: self.map_name.clone(),
+            player_count: self.player_count(remote_clients),
+            max_players: self
+                .ecs_world
+                .read_resource::<ServerConfig>()
+                .rooms
+                .max_players_per_room,
+        }
+    }
+
+    pub fn is_full(&self, remote_clients: &[Option<RemoteClient>]) -> bool {
+        let info = self.info(remote_clients);
+        return info.player_count >= info.max_players;
+    }
+
+    /// Simulates the next frame, then sends the results to the players in the room
+    pub fn tick(
+        &mut self,
+        packet_handler_thread: &mut PacketHandlerThread<ToServerPacket, FromServerPacket>,
+        remote_clients: &mut [Option<RemoteClient>],
+    ) {
+        simulate_frame(&mut self.ecs_world, &mut self.ecs_dispatcher);
+        record_tick_hash(&self.ecs_world);
+        let tick = *self.ecs_world.read_resource::<SimulationTick>();
+
+        self.spatial_grid.rebuild(&self.ecs_world);
+        update_areas_of_interest(
+            packet_handler_thread,
+            self.id,
+            remote_clients,
+            &self.spatial_grid,
+            &self.ecs_world,
+        );
+
+        let snapshot_interval_ticks = self
+            .ecs_world
+            .read_resource::<ServerConfig>()
+            .network
+            .snapshot_interval_ticks();
+        if tick.as_u64() % snapshot_interval_ticks == 0 {
+            send_snapshots(
+                packet_handler_thread,
+                self.id,
+                remote_clients,
+                &mut self.snapshot_history,
+                &self.ecs_world,
+            );
+        }
+
+        send_packets(
+            packet_handler_thread,
+            self.id,
+            &mut self.ecs_world,
+            remote_clients,
+        );
+    }
+
+    pub fn advance_time(&mut self, tick_duration: Duration) {
+        self.ecs_world
+            .write_resource::<EngineTime>()
+            .tick(tick_duration);
+        self.ecs_world.write_resource::<SimulationTick>().inc();
+    }
+}
+
+/// Shared by the rooms and the replays, the characters are created after it
+pub fn create_room_world(
+    server_config: ServerConfig,
+    common_configs: CommonConfigs,
+    map: MapWalkingInfo,
+) -> (specs::World, specs::Dispatcher<'static, 'static>) {
+    let mut ecs_world = create_ecs_world();
+    ecs_world.insert(Vec::<HpModificationRequest>::with_capacity(128));
+    ecs_world.insert(Vec::<AreaAttackComponent>::with_capacity(128));
+    ecs_world.insert(Vec::<ApplyForceComponent>::with_capacity(128));
+    ecs_world.insert(Vec::<FinishCast>::with_capacity(128));
+    ecs_world.insert(Vec::<ApplyStatusComponent>::with_capacity(128));
+    ecs_world.insert(Vec::<RemoveStatusComponent>::with_capacity(128));
+    ecs_world.insert(EngineTime::new(0));
+    ecs_world.insert(OutPacketCollector::with_capacity(128));
+    ecs_world.insert(SystemTimings::default());
+
+    let max_rewind_ticks = server_config.lag_compensation.max_rewind_ms as u64
+        * server_config.network.tick_rate as u64
+        / 1000;
+    ecs_world.insert(MatchState::new(server_config.game_match.min_players));
+    ecs_world.insert(MinionWaves::new(&server_config.minions));
+    ecs_world.insert(server_config);
+    ecs_world.insert(common_configs);
+    ecs_world.insert(map);
+    ecs_world.insert(SimulationTick::new());
+    ecs_world.insert(LagCompensation::new(max_rewind_ticks));
+
+    let ecs_dispatcher = specs::DispatcherBuilder::new()
+        .with(
+            TimedSystem::new("minion_ai", MinionAiSystem),
+            "minion_ai",
+            &[],
+        )
+        .with(
+            TimedSystem::new("turret_ai", TurretAiSystem),
+            "turret_ai",
+            &[],
+        )
+        .with(
+            TimedSystem::new("char_control", ControllerIntentionToCharTargetSystem),
+            "char_control",
+            &["minion_ai", "turret_ai"],
+        )
+        .with(
+            TimedSystem::new("char_state", CharacterStateUpdateSystem),
+            "char_state",
+            &["char_control"],
+        )
+        .build();
+    return (ecs_world, ecs_dispatcher);
+}
+
+/// The positions of the frame are kept for the lag compensation
+pub fn simulate_frame(
+    ecs_world: &mut specs::World,
+    ecs_dispatcher: &mut specs::Dispatcher<'static, 'static>,
+) {
+    run_frame(ecs_world, ecs_dispatcher);
+    let tick = *ecs_world.read_resource::<SimulationTick>();
+    ecs_world
+        .write_resource::<LagCompensation>()
+        .record(tick, ecs_world);
+}
+
+/// The name is stored without the surrounding whitespaces
+pub fn validate_room_name(name: &str, rooms: &[Room]) -> Result<String, RoomError> {
+    let name = name.trim();
+    let len = name.chars().count();
+    let used = rooms
+        .iter()
+        .any(|room| room.name.eq_ignore_ascii_case(name));
+    return if len > 0 && len <= MAX_ROOM_NAME_LEN && !used {
+        Ok(name.to_owned())
+    } else {
+        Err(RoomError::InvalidName)
+    };
+}
+
+pub fn find_room(rooms: &mut [Room], room_id: Option<RoomId>) -> Option<&mut Room> {
+    let room_id = room_id?;
+    return rooms.iter_mut().find(|room| room.id == room_id);
+}
+
+/// The creator joins the room after it
+pub fn create_room(
+    name: String,
+    map_name: String,
+    rooms: &mut Vec<Room>,
+    next_room_id: &mut RoomId,
+    server_config: &ServerConfig,
+    room_assets: &RoomAssets,
+) -> Result<RoomId, RoomError> {
+    if rooms.len() >= server_config.rooms.max_rooms {
+        return Err(RoomError::TooManyRooms);
+    }
+    let name = validate_room_name(&name, rooms)?;
+    if !room_assets.has_map(&map_name) {
+        return Err(RoomError::InvalidMap);
+    }
+    let room_id = *next_room_id;
+    let room =
+        Room::new(room_id, name, map_name, false, server_config, room_assets).map_err(|e| {
+            log::error!("Could not create the room: {}", e);
+            RoomError::InvalidMap
+        })?;
+    next_room_id.0 += 1;
+    rooms.push(room);
+    return Ok(room_id);
+}
+
+/// Her character in the previous room is removed, a new one is created in the joined room
+/// when she is ready for the game
+pub fn join_room(
+    client_socket: SocketId,
+    room_id: RoomId,
+    remote_clients: &mut [Option<RemoteClient>],
+    rooms: &mut [Room],
+    packet_handler_thread: &PacketHandlerThread<ToServerPacket, FromServerPacket>,
+    config: &AppConfig,
+) -> Result<(), RoomError> {
+    let room = find_room(rooms, Some(room_id)).ok_or(RoomError::NotFound)?;
+    if room.is_full(remote_clients) {
+        return Err(RoomError::Full);
+    }
+    let (prev_room_id, controller_id) = {
+        let remote_client = remote_clients[client_socket.as_usize()].as_mut().unwrap();
+        log::info!("{} has joined room '{}'", remote_client.name, room.name);
+        remote_client.visible_entities.clear();
+        remote_client.snapshot_baseline = None;
+        remote_client.sent_entities.clear();
+        (
+            remote_client.room_id.replace(room_id),
+            remote_client
+                .controller_id
+                .take()
+                .or(remote_client.resumed_controller_id.take()),
+        )
+    };
+    send_room_init(client_socket, room, packet_handler_thread, config);
+    if let (Some(controller_id), Some(prev_room)) = (controller_id, find_room(rooms, prev_room_id))
+    {
+        remove_player(
+            controller_id,
+            prev_room,
+            remote_clients,
+            packet_handler_thread,
+        );
+    }
+    return Ok(());
+}
+
+/// The client loads the map of the room after it
+pub fn send_room_init(
+    client_socket: SocketId,
+    room: &Room,
+    packet_handler_thread: &PacketHandlerThread<ToServerPacket, FromServerPacket>,
+    config: &AppConfig,
+) {
+    packet_handler_thread.send(
+        client_socket,
+        FromServerPacket::Init {
+            map_name: room.map_name.clone(),
+            start_x: config.start_pos_x,
+            start_y: config.start_pos_y,
+            tick_rate: room
+                .ecs_world
+                .read_resource::<ServerConfig>()
+                .network
+                .tick_rate as u32,
+        },
+    );
+    let configs = (*room.ecs_world.read_resource::<CommonConfigs>()).clone();
+    packet_handler_thread.send(client_socket, FromServerPacket::Configs(configs));
+}
+
+/// The rooms of the parked sessions are kept, so their players can come back
+pub fn remove_empty_rooms(
+    rooms: &mut Vec<Room>,
+    remote_clients: &[Option<RemoteClient>],
+    sessions: &Sessions,
+) {
+    rooms.retain(|room| {
+        let keep = room.persistent
+            || room.player_count(remote_clients) > 0
+            || sessions.has_session_in(room.id);
+        if !keep {
+            log::info!("Room {:?} '{}' has been removed", room.id, room.name);
+        }
+        keep
+    });
+}
@@ -0,0 +1,126 @@
+//! The character of a disconnected player stays in the game for a grace period,
+//! a new connection with the session token of the player continues controlling it.
+use rustarok_common::components::char::ControllerEntityId;
+use rustarok_common::packets::from_server::{RoomId, SessionToken};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+pub struct ParkedSession {
+    pub name: String,
+    /// the controller belongs to the world of this room
+    pub room_id: RoomId,
+    pub controller_id: ControllerEntityId,
+    pub disconnected_at: Instant,
+}
+
+pub struct Sessions {
+    parked: HashMap<SessionToken, ParkedSession>,
+}
+
+impl Sessions {
+    pub fn new() -> Sessions {
+        Sessions {
+            parked: HashMap::with_capacity(16),
+        }
+    }
+
+    pub fn new_token() -> SessionToken {
+        SessionToken(rand::random())
+    }
+
+    pub fn park(
+        &mut self,
+        token: SessionToken,
+        name: String,
+        room_id: RoomId,
+        controller_id: ControllerEntityId,
+    ) {
+        self.parked.insert(
+            token,
+            ParkedSession {
+                name,
+                room_id,
+                controller_id,
+                disconnected_at: Instant::now(),
+            },
+        );
+    }
+
+    pub fn resume(&mut self, token: SessionToken) -> Option<ParkedSession> {
+        self.parked.remove(&token)
+    }
+
+    pub fn is_name_taken(&self, name: &str) -> bool {
+        self.parked
+            .values()
+            .any(|session| session.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn has_session_in(&self, room_id: RoomId) -> bool {
+        self.parked
+            .values()
+            .any(|session| session.room_id == room_id)
+    }
+
+    pub fn count(&self) -> usize {
+        self.parked.len()
+    }
+
+    pub fn remove_expired(&mut self, grace_period: Duration) -> Vec<ParkedSession> {
+        let expired_tokens: Vec<SessionToken> = self
+            .parked
+            .iter()
+            .filter(|(_token, session)| session.disconnected_at.elapsed() > grace_period)
+            .map(|(token, _session)| *token)
+            .collect();
+        return expired_tokens
+            .iter()
+            .filter_map(|token| self.parked.remove(token))
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use specs::prelude::*;
+
+    #[test]
+    fn only_the_expired_sessions_are_removed() {
+        let mut ecs_world = World::new();
+        let controller_id = ControllerEntityId::from(ecs_world.create_entity().build());
+        let grace_period = Duration::from_secs(30);
+        let mut sessions = Sessions::new();
+        sessions.park(
+            SessionToken(1),
+            "fresh".to_owned(),
+            RoomId(0),
+            controller_id,
+        );
+        sessions.park(
+            SessionToken(2),
+            "expired".to_owned(),
+            RoomId(1),
+            controller_id,
+        );
+        sessions
+            .parked
+            .get_mut(&SessionToken(2))
+            .unwrap()
+            .disconnected_at = Instant::now()
+            .checked_sub(grace_period + Duration::from_secs(1))
+            .unwrap();
+
+        let expired = sessions.remove_expired(grace_period);
+
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].name, "expired");
+        assert_eq!(sessions.count(), 1);
+        assert!(sessions.is_name_taken("FRESH"));
+        assert!(!sessions.is_name_taken("expired"));
+        assert!(!sessions.has_session_in(RoomId(1)));
+        assert!(sessions.remove_expired(grace_period).is_empty());
+        assert!(sessions.resume(SessionToken(1)).is_some());
+        assert!(sessions.resume(SessionToken(2)).is_none());
+    }
+}
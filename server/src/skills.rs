@@ -0,0 +1,894 @@
+use crate::areas::{dir_to_rotation_rad, AreaEffect, AreaEffectComponent, AreaShape};
+use crate::forced_movement::{
+    farthest_walkable_pos, farthest_walkable_pos_through_walls, start_forced_movement,
+    ForcedMovementSegment,
+};
+use crate::turret_ai::{create_turret, TurretComponent};
+use rustarok_common::attack::{DamageDisplayType, HpModificationRequest, HpModificationType};
+use rustarok_common::common::{v2, EngineTime, LocalTime, Vec2};
+use rustarok_common::components::char::{
+    create_common_player_entity, CharOutlook, CharType, JobId, LocalCharEntityId,
+    LocalCharStateComp, MonsterId, StaticCharDataComponent, StatusNature,
+};
+use rustarok_common::components::skills::{FinishCast, Skills};
+use rustarok_common::components::status::skill_statuses::{
+    AbsorbStatus, AssaBladeDashStatus, AssaPhasePrismStatus, ExoSkeletonStatus, FalconCarryStatus,
+    FireBombStatus,
+};
+use rustarok_common::components::status::status::{
+    calc_mounted_speedup, ApplyStatusComponent, PoisonStatus, RemoveStatusComponent,
+    RemoveStatusComponentPayload, StatusEnum, StatusEnumDiscriminants,
+};
+use rustarok_common::config::{AssaPhasePrismSkillConfig, CommonConfigs};
+use rustarok_common::map::MapWalkingInfo;
+use specs::prelude::*;
+
+/// The server is the only one who applies the effects of a skill, the clients
+/// get to know about the results through the snapshots.
+pub fn execute_finished_skill_castings(ecs_world: &mut specs::World) {
+    let finished_casts = std::mem::replace(
+        &mut *ecs_world.write_resource::<Vec<FinishCast>>(),
+        Vec::with_capacity(128),
+    );
+    for finish_cast in &finished_casts {
+        finish_skill_cast(finish_cast, ecs_world);
+    }
+}
+
+fn finish_skill_cast(params: &FinishCast, ecs_world: &mut specs::World) {
+    let configs = ecs_world.read_resource::<CommonConfigs>().skills.clone();
+    let now = ecs_world.read_resource::<EngineTime>().now();
+    match params.skill {
+        Skills::Heal => {
+            if let Some(target_entity_id) = params.target_entity {
+                ecs_world
+                    .write_resource::<Vec<HpModificationRequest>>()
+                    .push(HpModificationRequest {
+                        src_entity: params.caster_entity_id,
+                        dst_entity: target_entity_id,
+                        typ: HpModificationType::Heal(configs.heal.heal),
+                    });
+            }
+        }
+        Skills::WizPyroBlast => {
+            // TODO2: the projectile travel time is not simulated yet, the damage is applied instantly
+            if let Some(target_entity_id) = params.target_entity {
+                ecs_world
+                    .write_resource::<Vec<HpModificationRequest>>()
+                    .push(HpModificationRequest {
+                        src_entity: params.caster_entity_id,
+                        dst_entity: target_entity_id,
+                        typ: HpModificationType::SpellDamage(
+                            configs.wiz_pyroblast.inner.damage,
+                            DamageDisplayType::SingleNumber,
+                        ),
+                    });
+            }
+        }
+        Skills::BrutalTestSkill => {
+            if let Some(skill_pos) = params.skill_pos {
+                let half_extents = Vec2::new(
+                    configs.brutal_test_skill.width / 2.0,
+                    configs.brutal_test_skill.height / 2.0,
+                );
+                damage_enemies_in_rect(
+                    params,
+                    &skill_pos,
+                    &half_extents,
+                    HpModificationType::SpellDamage(
+                        configs.brutal_test_skill.damage,
+                        DamageDisplayType::Combo(10),
+                    ),
+                    ecs_world,
+                );
+            }
+        }
+        Skills::Poison => {
+            if let Some(target_entity_id) = params.target_entity {
+                apply_status(
+                    params,
+                    target_entity_id,
+                    StatusEnum::PoisonStatus(PoisonStatus {
+                        poison_caster_entity_id: params.caster_entity_id,
+                        started: now,
+                        until: now.add_seconds(configs.poison.duration_seconds),
+                        next_damage_at: now,
+                        damage: configs.poison.damage,
+                    }),
+                    ecs_world,
+                );
+            }
+        }
+        Skills::Cure => {
+            if let Some(target_entity_id) = params.target_entity {
+                ecs_world
+                    .write_resource::<Vec<RemoveStatusComponent>>()
+                    .push(RemoveStatusComponent::by_status_nature(
+                        params.caster_entity_id,
+                        target_entity_id,
+                        StatusNature::Harmful,
+                    ));
+            }
+        }
+        Skills::FireBomb => {
+            if let Some(target_entity_id) = params.target_entity {
+                apply_status(
+                    params,
+                    target_entity_id,
+                    StatusEnum::FireBombStatus(FireBombStatus {
+                        caster_entity_id: params.caster_entity_id,
+                        started: now,
+                        until: now.add_seconds(configs.firebomb.duration_seconds),
+                        damage: configs.firebomb.damage,
+                        spread_count: 0,
+                        caster_team: params.caster_team,
+                    }),
+                    ecs_world,
+                );
+            }
+        }
+        Skills::AbsorbShield => {
+            if let Some(target_entity_id) = params.target_entity {
+                apply_status(
+                    params,
+                    target_entity_id,
+                    StatusEnum::AbsorbStatus(AbsorbStatus::new(
+                        params.caster_entity_id,
+                        now,
+                        configs.absorb_shield.duration_seconds,
+                    )),
+                    ecs_world,
+                );
+            }
+        }
+        Skills::ExoSkeleton => {
+            let exoskeleton = &configs.exoskeleton;
+            apply_status(
+                params,
+                params.caster_entity_id,
+                StatusEnum::ExoSkeletonStatus(ExoSkeletonStatus::new(
+                    now,
+                    exoskeleton.duration_seconds,
+                    exoskeleton.armor,
+                    exoskeleton.attack_range,
+                    exoskeleton.movement_speed,
+                    exoskeleton.attack_damage,
+                    exoskeleton.attack_speed,
+                )),
+                ecs_world,
+            );
+        }
+        Skills::Mounting => {
+            let is_mounted = ecs_world
+                .read_storage::<LocalCharStateComp>()
+                .get(params.caster_entity_id.into())
+                .map(|caster| caster.statuses.is_mounted());
+            let caster_job_id = ecs_world
+                .read_storage::<StaticCharDataComponent>()
+                .get(params.caster_entity_id.into())
+                .map(|caster| caster.job_id);
+            match (is_mounted, caster_job_id) {
+                (Some(true), _) => {
+                    ecs_world
+                        .write_resource::<Vec<RemoveStatusComponent>>()
+                        .push(RemoveStatusComponent {
+                            source_entity_id: params.caster_entity_id,
+                            target_entity_id: params.caster_entity_id,
+                            status: RemoveStatusComponentPayload::RemovingStatusDiscr(
+                                StatusEnumDiscriminants::MountedStatus,
+                            ),
+                        });
+                }
+                (Some(false), Some(job_id)) => {
+                    let speedup =
+                        calc_mounted_speedup(job_id, &ecs_world.read_resource::<CommonConfigs>());
+                    apply_status(
+                        params,
+                        params.caster_entity_id,
+                        StatusEnum::MountedStatus { speedup },
+                        ecs_world,
+                    );
+                }
+                _ => {}
+            }
+        }
+        Skills::Sanctuary => {
+            if let Some(skill_pos) = params.skill_pos {
+                let sanctuary = &configs.sanctuary;
+                spawn_skill_area(
+                    params,
+                    skill_pos,
+                    AreaShape::Rectangle {
+                        half_extents: v2(sanctuary.width / 2.0, sanctuary.height / 2.0),
+                    },
+                    AreaEffect::Heal(sanctuary.heal),
+                    sanctuary.heal_freq_seconds,
+                    now,
+                    now.add_seconds(sanctuary.duration),
+                    ecs_world,
+                );
+            }
+        }
+        Skills::Lightning => {
+            if let Some(skill_pos) = params.skill_pos {
+                let lightning = &configs.lightning;
+                // each strike hits once, a while after it appeared
+                for (i, step) in lightning.strike_steps.iter().enumerate() {
+                    let strike_at = now.add_seconds(
+                        i as f32 * lightning.strike_interval_seconds
+                            + lightning.first_strike_delay_seconds,
+                    );
+                    spawn_skill_area(
+                        params,
+                        skill_pos
+                            + params.char_to_skill_dir * (*step as f32 * lightning.strike_distance),
+                        AreaShape::Circle {
+                            radius: lightning.strike_radius,
+                        },
+                        AreaEffect::SpellDamage(lightning.damage),
+                        0.0,
+                        strike_at,
+                        strike_at,
+                        ecs_world,
+                    );
+                }
+            }
+        }
+        Skills::GazXplodiumCharge => {
+            if let Some(skill_pos) = params.skill_pos {
+                let xplodium = &configs.gaz_xplodium_charge.inner;
+                let explode_at = now.add_seconds(
+                    xplodium.missile_travel_duration_seconds + xplodium.detonation_duration,
+                );
+                let effects = vec![
+                    AreaEffect::SpellDamage(xplodium.damage),
+                    AreaEffect::Stun {
+                        duration_seconds: xplodium.stun_duration_seconds,
+                    },
+                ];
+                for effect in effects {
+                    spawn_skill_area(
+                        params,
+                        skill_pos,
+                        AreaShape::Circle {
+                            radius: xplodium.explosion_area,
+                        },
+                        effect,
+                        0.0,
+                        explode_at,
+                        explode_at,
+                        ecs_world,
+                    );
+                }
+            }
+        }
+        Skills::GazTurret => {
+            if let Some(skill_pos) = params.skill_pos {
+                create_turret(
+                    ecs_world,
+                    params.caster_entity_id,
+                    skill_pos,
+                    params.caster_team,
+                );
+            }
+        }
+        Skills::GazDestroyTurret => {
+            let own_turret = params.target_entity.filter(|target_entity_id| {
+                ecs_world
+                    .read_storage::<TurretComponent>()
+                    .get((*target_entity_id).into())
+                    .map(|turret| turret.owner_entity_id == params.caster_entity_id)
+                    .unwrap_or(false)
+            });
+            if let Some(turret_id) = own_turret {
+                if let Some(turret) = ecs_world
+                    .write_storage::<LocalCharStateComp>()
+                    .get_mut(turret_id.into())
+                {
+                    turret.hp = 0;
+                }
+            }
+        }
+        Skills::GazTurretTarget => {
+            for turret in (&mut ecs_world.write_storage::<TurretComponent>()).join() {
+                if turret.owner_entity_id == params.caster_entity_id {
+                    turret.preferred_target = params.target_entity;
+                }
+            }
+        }
+        Skills::FireWall => {
+            if let Some(skill_pos) = params.skill_pos {
+                let firewall = &configs.firewall;
+                // the wall is perpendicular to the direction of the skill
+                let half_extents = v2(firewall.width as f32 / 2.0, firewall.depth / 2.0);
+                let effects = vec![
+                    AreaEffect::SpellDamage(firewall.damage),
+                    AreaEffect::PushBack {
+                        force: firewall.pushback_force,
+                        duration_seconds: firewall.force_duration_seconds,
+                    },
+                ];
+                for effect in effects {
+                    spawn_skill_area(
+                        params,
+                        skill_pos,
+                        AreaShape::rotated_rectangle(half_extents, &params.char_to_skill_dir),
+                        effect,
+                        firewall.force_duration_seconds,
+                        now,
+                        now.add_seconds(firewall.duration_seconds),
+                        ecs_world,
+                    );
+                }
+            }
+        }
+        Skills::AssaBladeDash => {
+            let dash = &configs.assa_blade_dash;
+            let start_pos = params.caster_pos;
+            let end_pos = farthest_walkable_pos(
+                &ecs_world.read_resource::<MapWalkingInfo>(),
+                start_pos,
+                start_pos + params.char_to_skill_dir * dash.attributes.casting_range,
+            );
+            let vector = end_pos - start_pos;
+            let center = start_pos + vector / 2.0;
+            let turns_at = now.add_seconds(dash.duration_seconds / 2.0);
+            let ends_at = now.add_seconds(dash.duration_seconds);
+            start_forced_movement(
+                ecs_world,
+                params.caster_entity_id,
+                vec![
+                    ForcedMovementSegment {
+                        from: start_pos,
+                        to: end_pos,
+                        starts_at: now,
+                        ends_at: turns_at,
+                    },
+                    ForcedMovementSegment {
+                        from: end_pos,
+                        to: start_pos,
+                        starts_at: turns_at,
+                        ends_at,
+                    },
+                ],
+            );
+            let shape = AreaShape::rotated_rectangle(
+                v2(
+                    dash.attributes.width.unwrap_or(1.0) / 2.0,
+                    vector.magnitude() / 2.0,
+                ),
+                &params.char_to_skill_dir,
+            );
+            // she hits when she is halfway forward and when she is halfway back
+            let hits = vec![
+                (
+                    dash.first_damage,
+                    now.add_seconds(dash.duration_seconds / 4.0),
+                ),
+                (
+                    dash.second_damage,
+                    now.add_seconds(dash.duration_seconds * 3.0 / 4.0),
+                ),
+            ];
+            for (damage, hit_at) in hits {
+                spawn_skill_area(
+                    params,
+                    center,
+                    shape.clone(),
+                    AreaEffect::Damage(damage),
+                    0.0,
+                    hit_at,
+                    hit_at,
+                    ecs_world,
+                );
+            }
+            apply_status(
+                params,
+                params.caster_entity_id,
+                StatusEnum::AssaBladeDashStatus(AssaBladeDashStatus {
+                    caster_entity_id: params.caster_entity_id,
+                    started_at: now,
+                    ends_at,
+                    start_pos,
+                    center,
+                    rot_radian: dir_to_rotation_rad(&params.char_to_skill_dir),
+                    half_duration: (dash.duration_seconds * 1000.0) as u32 / 2,
+                    vector,
+                    shadow1_pos: start_pos,
+                    shadow2_pos: start_pos,
+                    forward_damage_done: false,
+                    backward_damage_done: false,
+                    configs: dash.clone(),
+                }),
+                ecs_world,
+            );
+        }
+        Skills::AssaPhasePrism => {
+            let prism = &configs.assa_phase_prism;
+            ecs_world
+                .create_entity()
+                .with(PhasePrismProjectileComponent {
+                    caster_entity_id: params.caster_entity_id,
+                    start_pos: params.caster_pos,
+                    path: params.char_to_skill_dir * prism.attributes.casting_range,
+                    started_at: now,
+                    ends_at: now.add_seconds(prism.duration_seconds),
+                    configs: prism.clone(),
+                })
+                .build();
+        }
+        Skills::FalconAttack => {
+            let falcon_attack = &configs.falcon_attack;
+            let range = falcon_attack.attributes.casting_range;
+            // the falcon flies through the whole path, it hits the enemies when it is halfway
+            let hit_at = now.add_seconds(falcon_attack.duration_in_seconds / 2.0);
+            let half_extents = v2(falcon_attack.attributes.width.unwrap_or(1.0), range / 2.0);
+            let effects = vec![
+                AreaEffect::SpellDamage(falcon_attack.damage),
+                AreaEffect::Slow {
+                    modifier: falcon_attack.slow,
+                    duration_seconds: falcon_attack.slow_duration,
+                },
+            ];
+            for effect in effects {
+                spawn_skill_area(
+                    params,
+                    params.caster_pos + params.char_to_skill_dir * (range / 2.0),
+                    AreaShape::rotated_rectangle(half_extents, &params.char_to_skill_dir),
+                    effect,
+                    0.0,
+                    hit_at,
+                    hit_at,
+                    ecs_world,
+                );
+            }
+        }
+        Skills::FalconCarry => {
+            let carry = &configs.falcon_carry;
+            if params.target_entity == Some(params.caster_entity_id) {
+                // the falcon carries her over the walls to the direction of the skill
+                let ends_at = now.add_seconds(carry.carry_owner_duration);
+                let end_pos = farthest_walkable_pos_through_walls(
+                    &ecs_world.read_resource::<MapWalkingInfo>(),
+                    params.caster_pos,
+                    params.caster_pos + params.char_to_skill_dir * carry.attributes.casting_range,
+                );
+                carry_by_falcon(
+                    params,
+                    params.caster_entity_id,
+                    ForcedMovementSegment {
+                        from: params.caster_pos,
+                        to: end_pos,
+                        starts_at: now,
+                        ends_at,
+                    },
+                    true,
+                    now,
+                    ecs_world,
+                );
+            } else if let Some(ally_id) = params.target_entity {
+                // the falcon catches her, then carries her to the owner
+                let ally_pos = ecs_world
+                    .read_storage::<LocalCharStateComp>()
+                    .get(ally_id.into())
+                    .map(|ally| ally.pos());
+                if let Some(ally_pos) = ally_pos {
+                    carry_by_falcon(
+                        params,
+                        ally_id,
+                        ForcedMovementSegment {
+                            from: ally_pos,
+                            to: params.caster_pos,
+                            starts_at: now.add_seconds(carry.catch_ally_duration),
+                            ends_at: now.add_seconds(carry.carry_ally_duration),
+                        },
+                        false,
+                        now,
+                        ecs_world,
+                    );
+                }
+            }
+        }
+        Skills::GazBarricade => {
+            if let Some(skill_pos) = params.skill_pos {
+                // the barricades are aligned to the cells, one cell holds one of them
+                let pos = v2(skill_pos.x.trunc(), skill_pos.y.trunc());
+                let is_walkable = ecs_world
+                    .read_resource::<MapWalkingInfo>()
+                    .is_walkable_pos(pos);
+                let is_occupied = (
+                    &ecs_world.read_storage::<LocalCharStateComp>(),
+                    &ecs_world.read_storage::<StaticCharDataComponent>(),
+                )
+                    .join()
+                    .any(|(char_state, static_data)| {
+                        static_data.job_id == JobId::Barricade
+                            && char_state.state().is_alive()
+                            && char_state.pos() == pos
+                    });
+                if is_walkable && !is_occupied {
+                    create_common_player_entity(
+                        "Barricade".to_owned(),
+                        ecs_world,
+                        CharType::Minion,
+                        JobId::Barricade,
+                        pos,
+                        params.caster_team,
+                        CharOutlook::Monster(MonsterId::Barricade),
+                    )
+                    .build();
+                } else {
+                    log::debug!("No place for a barricade at {:?}", pos);
+                }
+            }
+        }
+        Skills::AttackMove => {
+            // the validation turns it into an AttackTowards intention
+        }
+    }
+}
+
+/// The projectile of AssaPhasePrism, the first character it hits swaps places with the caster
+#[derive(Component)]
+pub struct PhasePrismProjectileComponent {
+    caster_entity_id: LocalCharEntityId,
+    start_pos: Vec2,
+    path: Vec2,
+    started_at: LocalTime,
+    ends_at: LocalTime,
+    configs: AssaPhasePrismSkillConfig,
+}
+
+pub fn update_phase_prism_projectiles(ecs_world: &mut specs::World) {
+    let now = ecs_world.read_resource::<EngineTime>().now();
+    let mut finished_projectiles = Vec::new();
+    let mut swaps = Vec::new();
+    {
+        let entities = ecs_world.entities();
+        let projectile_storage = ecs_world.read_storage::<PhasePrismProjectileComponent>();
+        let char_state_storage = ecs_world.read_storage::<LocalCharStateComp>();
+        for (projectile_id, projectile) in (&entities, &projectile_storage).join() {
+            let caster = match char_state_storage.get(projectile.caster_entity_id.into()) {
+                Some(caster) if caster.state().is_alive() => caster,
+                _ => {
+                    finished_projectiles.push(projectile_id);
+                    continue;
+                }
+            };
+            let progress = now
+                .percentage_between(projectile.started_at, projectile.ends_at)
+                .min(1.0);
+            let pos = projectile.start_pos + projectile.path * progress;
+            // the projectile hits the characters within its width
+            let hit_radius = projectile.configs.attributes.width.unwrap_or(1.0);
+            let hit_char = (&entities, &char_state_storage)
+                .join()
+                .find(|(char_id, target)| {
+                    *char_id != projectile.caster_entity_id.into()
+                        && target.state().is_alive()
+                        && (target.pos() - pos).magnitude() <= hit_radius
+                });
+            if let Some((target_id, target)) = hit_char {
+                swaps.push((
+                    projectile.caster_entity_id,
+                    caster.pos(),
+                    LocalCharEntityId::from(target_id),
+                    target.pos(),
+                    projectile.configs.clone(),
+                ));
+                finished_projectiles.push(projectile_id);
+            } else if projectile.ends_at.has_already_passed(now) {
+                finished_projectiles.push(projectile_id);
+            }
+        }
+    }
+    for (caster_id, caster_pos, target_id, target_pos, configs) in swaps {
+        let distance = (target_pos - caster_pos).magnitude();
+        let ends_at = now.add_seconds(
+            (distance * configs.swap_duration_unit_per_second).max(configs.min_swap_duration),
+        );
+        ecs_world
+            .write_resource::<Vec<RemoveStatusComponent>>()
+            .push(RemoveStatusComponent {
+                source_entity_id: caster_id,
+                target_entity_id: caster_id,
+                status: RemoveStatusComponentPayload::RemovingStatusDiscr(
+                    StatusEnumDiscriminants::AssaBladeDashStatus,
+                ),
+            });
+        for (char_id, from, to) in &[
+            (caster_id, caster_pos, target_pos),
+            (target_id, target_pos, caster_pos),
+        ] {
+            start_forced_movement(
+                ecs_world,
+                *char_id,
+                vec![ForcedMovementSegment {
+                    from: *from,
+                    to: *to,
+                    starts_at: now,
+                    ends_at,
+                }],
+            );
+            ecs_world
+                .write_resource::<Vec<ApplyStatusComponent>>()
+                .push(ApplyStatusComponent::from_status(
+                    caster_id,
+                    *char_id,
+                    StatusEnum::AssaPhasePrismStatus(AssaPhasePrismStatus {
+                        caster_entity_id: caster_id,
+                        started_at: now,
+                        ends_at,
+                        start_pos: *from,
+                        vector: to - from,
+                    }),
+                ));
+        }
+        let is_enemy = {
+            let static_char_data_storage = ecs_world.read_storage::<StaticCharDataComponent>();
+            match (
+                static_char_data_storage.get(caster_id.into()),
+                static_char_data_storage.get(target_id.into()),
+            ) {
+                (Some(caster), Some(target)) => caster.team.can_attack(target.team),
+                _ => false,
+            }
+        };
+        if is_enemy {
+            ecs_world
+                .write_resource::<Vec<HpModificationRequest>>()
+                .push(HpModificationRequest {
+                    src_entity: caster_id,
+                    dst_entity: target_id,
+                    typ: HpModificationType::SpellDamage(
+                        configs.damage,
+                        DamageDisplayType::SingleNumber,
+                    ),
+                });
+        }
+    }
+    for projectile_id in finished_projectiles {
+        if let Err(e) = ecs_world.delete_entity(projectile_id) {
+            log::error!("Could not remove {:?}: {:?}", projectile_id, e);
+        }
+    }
+}
+
+fn carry_by_falcon(
+    params: &FinishCast,
+    carried_entity_id: LocalCharEntityId,
+    flight: ForcedMovementSegment,
+    carry_owner: bool,
+    now: LocalTime,
+    ecs_world: &mut specs::World,
+) {
+    apply_status(
+        params,
+        carried_entity_id,
+        StatusEnum::FalconCarryStatus(FalconCarryStatus {
+            started_at: now,
+            ends_at: flight.ends_at,
+            carry_owner,
+            end_pos: flight.to,
+        }),
+        ecs_world,
+    );
+    start_forced_movement(ecs_world, carried_entity_id, vec![flight]);
+}
+
+/// The first effect happens at `first_effect_at`, then in every `interval_seconds`
+/// until `die_at`
+fn spawn_skill_area(
+    params: &FinishCast,
+    center: Vec2,
+    shape: AreaShape,
+    effect: AreaEffect,
+    interval_seconds: f32,
+    first_effect_at: LocalTime,
+    die_at: LocalTime,
+    ecs_world: &mut specs::World,
+) {
+    ecs_world
+        .create_entity()
+        .with(AreaEffectComponent {
+            caster_entity_id: Some(params.caster_entity_id),
+            team: params.caster_team,
+            center,
+            shape,
+            effect,
+            interval_seconds,
+            next_effect_at: first_effect_at,
+            die_at: Some(die_at),
+        })
+        .build();
+}
+
+fn apply_status(
+    params: &FinishCast,
+    target_entity_id: LocalCharEntityId,
+    status: StatusEnum,
+    ecs_world: &mut specs::World,
+) {
+    ecs_world
+        .write_resource::<Vec<ApplyStatusComponent>>()
+        .push(ApplyStatusComponent::from_status(
+            params.caster_entity_id,
+            target_entity_id,
+            status,
+        ));
+}
+
+fn damage_enemies_in_rect(
+    params: &FinishCast,
+    center: &Vec2,
+    half_extents: &Vec2,
+    typ: HpModificationType,
+    ecs_world: &mut specs::World,
+) {
+    let shape = AreaShape::rotated_rectangle(*half_extents, &params.char_to_skill_dir);
+    let entities = ecs_world.entities();
+    let char_state_storage = ecs_world.read_storage::<LocalCharStateComp>();
+    let static_char_data_storage = ecs_world.read_storage::<StaticCharDataComponent>();
+    let mut hp_mod_requests = ecs_world.write_resource::<Vec<HpModificationRequest>>();
+    for (entity, char_state, static_char_data) in
+        (&entities, &char_state_storage, &static_char_data_storage).join()
+    {
+        if !char_state.state().is_alive() || !params.caster_team.can_attack(static_char_data.team) {
+            continue;
+        }
+        if shape.contains(*center, char_state.pos()) {
+            hp_mod_requests.push(HpModificationRequest {
+                src_entity: params.caster_entity_id,
+                dst_entity: entity.into(),
+                typ,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::forced_movement::move_forced_characters;
+    use crate::room::create_room_world;
+    use crate::server_config::{parse_common_configs, ServerConfig};
+    use rustarok_common::components::char::Team;
+    use rustarok_common::map::CellType;
+    use std::time::Duration;
+
+    fn create_world() -> specs::World {
+        let map = MapWalkingInfo {
+            width: 100,
+            height: 100,
+            cells: vec![CellType::Walkable as u8; 100 * 100],
+        };
+        let (ecs_world, _ecs_dispatcher) = create_room_world(
+            ServerConfig::from_toml(include_str!("../server-conf.toml")).unwrap(),
+            parse_common_configs(include_str!("../config-runtime.toml")).unwrap(),
+            map,
+        );
+        return ecs_world;
+    }
+
+    fn add_char(ecs_world: &mut specs::World, pos: Vec2, team: Team) -> LocalCharEntityId {
+        let entity = create_common_player_entity(
+            "Char".to_owned(),
+            ecs_world,
+            CharType::Player,
+            JobId::ASSASSIN,
+            pos,
+            team,
+            CharOutlook::Monster(MonsterId::Dimik),
+        )
+        .build();
+        return LocalCharEntityId::from(entity);
+    }
+
+    fn cast(
+        ecs_world: &mut specs::World,
+        skill: Skills,
+        caster_entity_id: LocalCharEntityId,
+        skill_pos: Vec2,
+    ) {
+        let caster_pos = ecs_world
+            .read_storage::<LocalCharStateComp>()
+            .get(caster_entity_id.into())
+            .unwrap()
+            .pos();
+        let caster_team = ecs_world
+            .read_storage::<StaticCharDataComponent>()
+            .get(caster_entity_id.into())
+            .unwrap()
+            .team;
+        ecs_world
+            .write_resource::<Vec<FinishCast>>()
+            .push(FinishCast {
+                skill,
+                caster_entity_id,
+                caster_pos,
+                caster_team,
+                skill_pos: Some(skill_pos),
+                char_to_skill_dir: (skill_pos - caster_pos).normalize(),
+                target_entity: None,
+            });
+        execute_finished_skill_castings(ecs_world);
+        ecs_world.maintain();
+    }
+
+    fn pos_of(ecs_world: &specs::World, char_id: LocalCharEntityId) -> Vec2 {
+        ecs_world
+            .read_storage::<LocalCharStateComp>()
+            .get(char_id.into())
+            .unwrap()
+            .pos()
+    }
+
+    fn barricade_count(ecs_world: &specs::World) -> usize {
+        ecs_world
+            .read_storage::<StaticCharDataComponent>()
+            .join()
+            .filter(|it| it.job_id == JobId::Barricade)
+            .count()
+    }
+
+    #[test]
+    fn a_cell_holds_one_barricade() {
+        let mut ecs_world = create_world();
+        let caster_id = add_char(&mut ecs_world, v2(50.0, -50.0), Team::Left);
+
+        cast(
+            &mut ecs_world,
+            Skills::GazBarricade,
+            caster_id,
+            v2(51.2, -50.4),
+        );
+        cast(
+            &mut ecs_world,
+            Skills::GazBarricade,
+            caster_id,
+            v2(51.7, -50.9),
+        );
+        assert_eq!(barricade_count(&ecs_world), 1);
+
+        cast(
+            &mut ecs_world,
+            Skills::GazBarricade,
+            caster_id,
+            v2(52.2, -50.4),
+        );
+        assert_eq!(barricade_count(&ecs_world), 2);
+    }
+
+    #[test]
+    fn the_phase_prism_swaps_the_caster_and_the_first_char_it_hits() {
+        let mut ecs_world = create_world();
+        let caster_id = add_char(&mut ecs_world, v2(50.0, -50.0), Team::Left);
+        let first_id = add_char(&mut ecs_world, v2(53.0, -50.0), Team::Right);
+        let second_id = add_char(&mut ecs_world, v2(55.0, -50.0), Team::Right);
+
+        cast(
+            &mut ecs_world,
+            Skills::AssaPhasePrism,
+            caster_id,
+            v2(60.0, -50.0),
+        );
+        for _ in 0..30 {
+            ecs_world
+                .write_resource::<EngineTime>()
+                .tick(Duration::from_millis(100));
+            update_phase_prism_projectiles(&mut ecs_world);
+            move_forced_characters(&mut ecs_world);
+            ecs_world.maintain();
+        }
+
+        assert_eq!(pos_of(&ecs_world, caster_id), v2(53.0, -50.0));
+        assert_eq!(pos_of(&ecs_world, first_id), v2(50.0, -50.0));
+        assert_eq!(pos_of(&ecs_world, second_id), v2(55.0, -50.0));
+        assert_eq!(
+            (&ecs_world.read_storage::<PhasePrismProjectileComponent>())
+                .join()
+                .count(),
+            0
+        );
+    }
+}
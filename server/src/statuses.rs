@@ -0,0 +1,112 @@
+use rustarok_common::common::{percentage, LocalTime};
+use rustarok_common::components::char::{
+    LocalCharEntityId, LocalCharStateComp, StaticCharDataComponent, Team,
+};
+use rustarok_common::components::status::attrib_mod::ArmorModifierStatus;
+use rustarok_common::components::status::skill_statuses::{AbsorbStatus, FireBombStatus};
+use rustarok_common::components::status::status::{
+    ApplyStatusComponent, PoisonStatus, RemoveStatusComponent, RemoveStatusComponentPayload,
+    StatusEnum,
+};
+use rustarok_common::config::CommonConfigs;
+use specs::prelude::*;
+
+/// Statuses are added and removed only on the server, the clients get them
+/// through the snapshots.
+pub fn apply_status_changes(ecs_world: &mut specs::World) {
+    let new_statuses = std::mem::replace(
+        &mut *ecs_world.write_resource::<Vec<ApplyStatusComponent>>(),
+        Vec::with_capacity(128),
+    );
+    let removed_statuses = std::mem::replace(
+        &mut *ecs_world.write_resource::<Vec<RemoveStatusComponent>>(),
+        Vec::with_capacity(128),
+    );
+    let configs = ecs_world.read_resource::<CommonConfigs>();
+    let mut char_state_storage = ecs_world.write_storage::<LocalCharStateComp>();
+    let mut static_char_data_storage = ecs_world.write_storage::<StaticCharDataComponent>();
+
+    for status_change in removed_statuses.into_iter() {
+        let target_entity = status_change.target_entity_id.into();
+        if let (Some(target_char), Some(target_static_data)) = (
+            char_state_storage.get_mut(target_entity),
+            static_char_data_storage.get(target_entity),
+        ) {
+            match &status_change.status {
+                RemoveStatusComponentPayload::RemovingStatusType(status_type) => {
+                    target_char.statuses.remove_by_nature(*status_type);
+                }
+                RemoveStatusComponentPayload::RemovingStatusDiscr(discr) => {
+                    target_char.statuses.remove(*discr)
+                }
+            }
+            target_char.recalc_attribs_based_on_statuses(target_static_data.job_id, &configs);
+            log::trace!(
+                "Status removed. Attributes({:?}): current: {:?}",
+                status_change.target_entity_id,
+                target_char.calculated_attribs()
+            );
+        }
+    }
+
+    for status_change in new_statuses.into_iter() {
+        let target_entity = status_change.target_entity_id.into();
+        if let (Some(target_char), Some(target_static_data)) = (
+            char_state_storage.get_mut(target_entity),
+            static_char_data_storage.get_mut(target_entity),
+        ) {
+            if target_char.hp <= 0 {
+                continue;
+            }
+            log::debug!(
+                "Applying state '{:?}' on {:?}",
+                status_change.status,
+                status_change.target_entity_id
+            );
+            target_char.add_status(status_change.status, target_static_data, &configs);
+            log::trace!(
+                "Status added. Attributes({:?}): current: {:?}",
+                status_change.target_entity_id,
+                target_char.calculated_attribs()
+            );
+        }
+    }
+}
+
+pub fn create_status_by_name(
+    name: &str,
+    caster_entity_id: LocalCharEntityId,
+    caster_team: Team,
+    now: LocalTime,
+    duration_ms: i32,
+    value: i32,
+) -> Result<StatusEnum, String> {
+    let duration_seconds = duration_ms.max(0) as f32 / 1000.0;
+    return match name {
+        "absorb" => Ok(StatusEnum::AbsorbStatus(AbsorbStatus::new(
+            caster_entity_id,
+            now,
+            duration_seconds,
+        ))),
+        "firebomb" => Ok(StatusEnum::FireBombStatus(FireBombStatus {
+            caster_entity_id,
+            started: now,
+            until: now.add_seconds(duration_seconds),
+            damage: value.max(1) as u32,
+            spread_count: 0,
+            caster_team,
+        })),
+        "poison" => Ok(StatusEnum::PoisonStatus(PoisonStatus {
+            poison_caster_entity_id: caster_entity_id,
+            started: now,
+            until: now.add_seconds(duration_seconds),
+            next_damage_at: now,
+            damage: value.max(1) as u32,
+        })),
+        "armor" => Ok(StatusEnum::ArmorModifierStatus(ArmorModifierStatus::new(
+            now,
+            percentage(value),
+        ))),
+        _ => Err(format!("Unknown status: {}", name)),
+    };
+}
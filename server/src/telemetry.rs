@@ -0,0 +1,600 @@
+//! Tick timings, per-system durations of the rooms, per-client traffic and entity counts.
+//! They are served on `http://127.0.0.1:<metrics_port>/metrics` in Prometheus text format,
+//! and can be appended to a CSV file, which is rotated when it gets too large.
+use crate::areas::AreaEffectComponent;
+use crate::room::Room;
+use crate::server_config::TelemetryConfig;
+use crate::RemoteClient;
+use rustarok_common::common::SimulationTick;
+use rustarok_common::components::char::{CharType, StaticCharDataComponent};
+use rustarok_common::components::controller::ControllerComponent;
+use specs::prelude::*;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Write as FmtWrite;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+#[derive(Default)]
+pub struct SystemTiming {
+    pub total: Duration,
+    pub runs: u64,
+    /// since the last update of the metrics
+    pub max: Duration,
+}
+
+/// A resource of the room's world, the systems are ordered by their names in the output
+#[derive(Default)]
+pub struct SystemTimings(BTreeMap<&'static str, SystemTiming>);
+
+impl SystemTimings {
+    pub fn system_finished(&mut self, name: &'static str, duration: Duration) {
+        let timing = self.0.entry(name).or_insert_with(SystemTiming::default);
+        timing.total += duration;
+        timing.runs += 1;
+        timing.max = timing.max.max(duration);
+    }
+}
+
+/// Measures a system of the dispatcher
+pub struct TimedSystem<S> {
+    name: &'static str,
+    system: S,
+}
+
+impl<S> TimedSystem<S> {
+    pub fn new(name: &'static str, system: S) -> TimedSystem<S> {
+        TimedSystem { name, system }
+    }
+}
+
+impl<'a, S> System<'a> for TimedSystem<S>
+where
+    S: System<'a>,
+    S::SystemData: SystemData<'a>,
+{
+    type SystemData = (S::SystemData, WriteExpect<'a, SystemTimings>);
+
+    fn run(&mut self, (data, mut timings): Self::SystemData) {
+        let started = Instant::now();
+        self.system.run(data);
+        timings.system_finished(self.name, started.elapsed());
+    }
+}
+
+/// Measures a step of the frame which runs outside of the dispatcher
+pub fn measure<R>(
+    ecs_world: &mut specs::World,
+    name: &'static str,
+    step: impl FnOnce(&mut specs::World) -> R,
+) -> R {
+    let started = Instant::now();
+    let result = step(ecs_world);
+    ecs_world
+        .write_resource::<SystemTimings>()
+        .system_finished(name, started.elapsed());
+    return result;
+}
+
+/// The round trip time is the time between sending a snapshot and receiving its ack
+#[derive(Default)]
+pub struct ClientStats {
+    pub bytes_received: u64,
+    pub bytes_sent: u64,
+    pub packets_received: u64,
+    pub snapshots_sent: u64,
+    pub rtt: Option<Duration>,
+    unacked_snapshots: VecDeque<(SimulationTick, Instant)>,
+}
+
+impl ClientStats {
+    const MAX_UNACKED_SNAPSHOTS: usize = 64;
+
+    pub fn snapshot_sent(&mut self, tick: SimulationTick) {
+        self.snapshots_sent += 1;
+        if self.unacked_snapshots.len() >= ClientStats::MAX_UNACKED_SNAPSHOTS {
+            self.unacked_snapshots.pop_front();
+        }
+        self.unacked_snapshots.push_back((tick, Instant::now()));
+    }
+
+    /// It is smoothed like the RTT of TCP
+    pub fn snapshot_acked(&mut self, tick: SimulationTick) {
+        while let Some((sent_tick, sent_at)) = self.unacked_snapshots.pop_front() {
+            if sent_tick == tick {
+                let sample = sent_at.elapsed();
+                self.rtt = Some(match self.rtt {
+                    Some(rtt) => (rtt * 7 + sample) / 8,
+                    None => sample,
+                });
+                break;
+            } else if tick < sent_tick {
+                // an older ack, it was measured already
+                self.unacked_snapshots.push_front((sent_tick, sent_at));
+                break;
+            }
+        }
+    }
+}
+
+struct CsvFile {
+    path: String,
+    max_bytes: u64,
+    max_files: usize,
+    file: File,
+}
+
+impl CsvFile {
+    const HEADER: &'static str = "timestamp,uptime_seconds,ticks,tick_overruns,\
+                                  max_tick_ms,clients,rooms,entities,bytes_received,bytes_sent\n";
+
+    fn open(path: &str, max_bytes: u64, max_files: usize) -> std::io::Result<CsvFile> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let mut csv = CsvFile {
+            path: path.to_owned(),
+            max_bytes,
+            max_files,
+            file,
+        };
+        if csv.file.metadata()?.len() == 0 {
+            csv.file.write_all(CsvFile::HEADER.as_bytes())?;
+        }
+        return Ok(csv);
+    }
+
+    /// <path> is renamed to <path>.1, <path>.1 to <path>.2 and so on, the oldest is deleted
+    fn rotate(&mut self) -> std::io::Result<()> {
+        for i in (1..self.max_files.max(1)).rev() {
+            let from = if i == 1 {
+                self.path.clone()
+            } else {
+                format!("{}.{}", self.path, i - 1)
+            };
+            let _ = std::fs::rename(&from, format!("{}.{}", self.path, i));
+        }
+        if self.max_files <= 1 {
+            std::fs::remove_file(&self.path)?;
+        }
+        *self = CsvFile::open(&self.path, self.max_bytes, self.max_files)?;
+        return Ok(());
+    }
+
+    fn append(&mut self, row: &str) -> std::io::Result<()> {
+        if self.file.metadata()?.len() + row.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        return self.file.write_all(row.as_bytes());
+    }
+}
+
+pub struct Telemetry {
+    started: Instant,
+    interval: Duration,
+    next_update_at: Instant,
+    tick_budget: Duration,
+    ticks: u64,
+    tick_overruns: u64,
+    interval_tick_overruns: u64,
+    tick_duration_total: Duration,
+    last_tick_duration: Duration,
+    max_tick_duration: Duration,
+    // served by the HTTP thread
+    metrics: Arc<Mutex<String>>,
+    csv: Option<CsvFile>,
+}
+
+impl Telemetry {
+    pub fn start(config: &TelemetryConfig, tick_budget: Duration) -> Telemetry {
+        let metrics = Arc::new(Mutex::new(String::new()));
+        if config.metrics_port != 0 {
+            serve_metrics(config.metrics_port, metrics.clone());
+        }
+        let csv = if config.csv_file.is_empty() {
+            None
+        } else {
+            CsvFile::open(&config.csv_file, config.csv_max_bytes, config.csv_max_files)
+                .map_err(|e| log::error!("Could not open {}: {}", config.csv_file, e))
+                .ok()
+        };
+        let interval = Duration::from_secs_f32(config.interval_seconds);
+        return Telemetry {
+            started: Instant::now(),
+            interval,
+            next_update_at: Instant::now() + interval,
+            tick_budget,
+            ticks: 0,
+            tick_overruns: 0,
+            interval_tick_overruns: 0,
+            tick_duration_total: Duration::from_secs(0),
+            last_tick_duration: Duration::from_secs(0),
+            max_tick_duration: Duration::from_secs(0),
+            metrics,
+            csv,
+        };
+    }
+
+    /// The duration of the whole frame of the main loop, without the sleeping
+    pub fn tick_finished(&mut self, duration: Duration) {
+        self.ticks += 1;
+        self.tick_duration_total += duration;
+        self.last_tick_duration = duration;
+        self.max_tick_duration = self.max_tick_duration.max(duration);
+        if duration > self.tick_budget {
+            self.tick_overruns += 1;
+            self.interval_tick_overruns += 1;
+        }
+    }
+
+    pub fn update(&mut self, remote_clients: &[Option<RemoteClient>], rooms: &mut [Room]) {
+        if Instant::now() < self.next_update_at {
+            return;
+        }
+        self.next_update_at = Instant::now() + self.interval;
+        if self.interval_tick_overruns > 0 {
+            log::warn!(
+                "{} ticks took longer than {}ms in the last {:.1}s, the longest took {}ms",
+                self.interval_tick_overruns,
+                self.tick_budget.as_millis(),
+                self.interval.as_secs_f32(),
+                self.max_tick_duration.as_millis()
+            );
+        }
+        let text = self.render(remote_clients, rooms);
+        *self.metrics.lock().unwrap() = text;
+        if self.csv.is_some() {
+            let row = self.csv_row(remote_clients, rooms);
+            if let Err(e) = self.csv.as_mut().unwrap().append(&row) {
+                log::error!("Metrics won't be written into the CSV file: {}", e);
+                self.csv = None;
+            }
+        }
+
+        self.interval_tick_overruns = 0;
+        self.max_tick_duration = Duration::from_secs(0);
+        for room in rooms.iter_mut() {
+            for timing in room
+                .ecs_world
+                .write_resource::<SystemTimings>()
+                .0
+                .values_mut()
+            {
+                timing.max = Duration::from_secs(0);
+            }
+        }
+    }
+
+    fn render(&self, remote_clients: &[Option<RemoteClient>], rooms: &[Room]) -> String {
+        let mut out = MetricsText(String::with_capacity(4096));
+        out.header("rustarok_uptime_seconds", "gauge", "");
+        out.value("rustarok_uptime_seconds", &[], secs(self.started.elapsed()));
+        out.header("rustarok_ticks_total", "counter", "");
+        out.value("rustarok_ticks_total", &[], self.ticks as f64);
+        out.header(
+            "rustarok_tick_overruns_total",
+            "counter",
+            "ticks which took longer than the tick duration",
+        );
+        out.value(
+            "rustarok_tick_overruns_total",
+            &[],
+            self.tick_overruns as f64,
+        );
+        out.header("rustarok_tick_budget_seconds", "gauge", "");
+        out.value("rustarok_tick_budget_seconds", &[], secs(self.tick_budget));
+        out.header("rustarok_tick_duration_seconds_total", "counter", "");
+        out.value(
+            "rustarok_tick_duration_seconds_total",
+            &[],
+            secs(self.tick_duration_total),
+        );
+        out.header(
+            "rustarok_tick_duration_seconds",
+            "gauge",
+            "the last and the longest tick since the previous update",
+        );
+        out.value(
+            "rustarok_tick_duration_seconds",
+            &[("stat", "last")],
+            secs(self.last_tick_duration),
+        );
+        out.value(
+            "rustarok_tick_duration_seconds",
+            &[("stat", "max")],
+            secs(self.max_tick_duration),
+        );
+
+        let clients: Vec<&RemoteClient> =
+            remote_clients.iter().filter_map(|it| it.as_ref()).collect();
+        out.header("rustarok_clients", "gauge", "");
+        out.value("rustarok_clients", &[], clients.len() as f64);
+        out.header("rustarok_client_rtt_seconds", "gauge", "");
+        for client in clients.iter() {
+            if let Some(rtt) = client.stats.rtt {
+                let socket = client.socket_id.as_usize().to_string();
+                let labels = [("socket", socket.as_str()), ("name", client.name.as_str())];
+                out.value("rustarok_client_rtt_seconds", &labels, secs(rtt));
+            }
+        }
+        let client_counters: [(&str, fn(&RemoteClient) -> u64); 4] = [
+            ("rustarok_client_received_bytes_total", |it| {
+                it.stats.bytes_received
+            }),
+            ("rustarok_client_sent_bytes_total", |it| it.stats.bytes_sent),
+            ("rustarok_client_received_packets_total", |it| {
+                it.stats.packets_received
+            }),
+            ("rustarok_client_sent_snapshots_total", |it| {
+                it.stats.snapshots_sent
+            }),
+        ];
+        for (name, get) in client_counters.iter() {
+            out.header(name, "counter", "");
+            for client in clients.iter() {
+                let socket = client.socket_id.as_usize().to_string();
+                let labels = [("socket", socket.as_str()), ("name", client.name.as_str())];
+                out.value(name, &labels, get(client) as f64);
+            }
+        }
+
+        out.header("rustarok_room_entities", "gauge", "");
+        for room in rooms {
+            let room_id = room.id.0.to_string();
+            for (kind, count) in count_entities(&room.ecs_world).iter() {
+                let labels = [("room", room_id.as_str()), ("kind", *kind)];
+                out.value("rustarok_room_entities", &labels, *count as f64);
+            }
+        }
+        let system_metrics: [(&str, &str, &str, fn(&SystemTiming) -> f64); 3] = [
+            (
+                "rustarok_system_duration_seconds_total",
+                "counter",
+                "",
+                |it| secs(it.total),
+            ),
+            ("rustarok_system_runs_total", "counter", "", |it| {
+                it.runs as f64
+            }),
+            (
+                "rustarok_system_max_duration_seconds",
+                "gauge",
+                "the longest run since the previous update",
+                |it| secs(it.max),
+            ),
+        ];
+        // the samples of a metric must follow each other
+        for (name, typ, help, get) in system_metrics.iter() {
+            out.header(name, typ, help);
+            for room in rooms {
+                let room_id = room.id.0.to_string();
+                let timings = room.ecs_world.read_resource::<SystemTimings>();
+                for (system, timing) in timings.0.iter() {
+                    let labels = [("room", room_id.as_str()), ("system", *system)];
+                    out.value(name, &labels, get(timing));
+                }
+            }
+        }
+        return out.0;
+    }
+
+    fn csv_row(&self, remote_clients: &[Option<RemoteClient>], rooms: &[Room]) -> String {
+        let clients = remote_clients.iter().filter_map(|it| it.as_ref());
+        let entities: usize = rooms
+            .iter()
+            .map(|room| room.ecs_world.entities().join().count())
+            .sum();
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|it| it.as_secs())
+            .unwrap_or(0);
+        return format!(
+            "{},{},{},{},{},{},{},{},{},{}\n",
+            timestamp,
+            self.started.elapsed().as_secs(),
+            self.ticks,
+            self.tick_overruns,
+            self.max_tick_duration.as_millis(),
+            clients.clone().count(),
+            rooms.len(),
+            entities,
+            clients
+                .clone()
+                .map(|it| it.stats.bytes_received)
+                .sum::<u64>(),
+            clients.map(|it| it.stats.bytes_sent).sum::<u64>(),
+        );
+    }
+}
+
+fn secs(duration: Duration) -> f64 {
+    duration.as_secs_f64()
+}
+
+fn count_entities(ecs_world: &specs::World) -> [(&'static str, usize); 4] {
+    let static_data_storage = ecs_world.read_storage::<StaticCharDataComponent>();
+    let players = static_data_storage
+        .join()
+        .filter(|it| it.typ == CharType::Player)
+        .count();
+    let chars = static_data_storage.join().count();
+    return [
+        ("player", players),
+        ("npc", chars - players),
+        (
+            "controller",
+            ecs_world
+                .read_storage::<ControllerComponent>()
+                .join()
+                .count(),
+        ),
+        (
+            "area",
+            ecs_world
+                .read_storage::<AreaEffectComponent>()
+                .join()
+                .count(),
+        ),
+    ];
+}
+
+struct MetricsText(String);
+
+impl MetricsText {
+    fn header(&mut self, name: &str, typ: &str, help: &str) {
+        if !help.is_empty() {
+            let _ = writeln!(self.0, "# HELP {} {}", name, help);
+        }
+        let _ = writeln!(self.0, "# TYPE {} {}", name, typ);
+    }
+
+    fn value(&mut self, name: &str, labels: &[(&str, &str)], value: f64) {
+        self.0.push_str(name);
+        if !labels.is_empty() {
+            let labels = labels
+                .iter()
+                .map(|(key, value)| {
+                    format!(
+                        "{}=\"{}\"",
+                        key,
+                        value
+                            .replace('\\', "\\\\")
+                            .replace('"', "\\\"")
+                            .replace('\n', "\\n")
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(",");
+            let _ = write!(self.0, "{{{}}}", labels);
+        }
+        let _ = writeln!(self.0, " {}", value);
+    }
+}
+
+/// The requests are answered one by one on a background thread, with the text rendered
+/// in the last update
+fn serve_metrics(port: u16, metrics: Arc<Mutex<String>>) {
+    let addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), port);
+    let listener = match TcpListener::bind(addr) {
+        Ok(listener) => listener,
+        Err(e) => {
+            log::error!("Could not bind the metrics endpoint to {}: {}", addr, e);
+            return;
+        }
+    };
+    log::info!("Metrics are served on http://{}/metrics", addr);
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let result = stream.and_then(|stream| answer_metrics_request(stream, &metrics));
+            if let Err(e) = result {
+                log::debug!("Metrics request has failed: {}", e);
+            }
+        }
+    });
+}
+
+fn answer_metrics_request(stream: TcpStream, metrics: &Mutex<String>) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(1)))?;
+    stream.set_write_timeout(Some(Duration::from_secs(1)))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // the headers are not needed
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+    }
+    let mut parts = request_line.split_whitespace();
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => ("200 OK", metrics.lock().unwrap().clone()),
+        _ => ("404 Not Found", "Not found, see /metrics\n".to_owned()),
+    };
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    return stream.flush();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(i: usize) -> String {
+        format!("row {:06}\n", i)
+    }
+
+    fn rows(range: std::ops::RangeInclusive<usize>) -> String {
+        range.map(row).collect()
+    }
+
+    fn remove_files(path: &str, max_files: usize) {
+        let _ = std::fs::remove_file(path);
+        for i in 1..=max_files {
+            let _ = std::fs::remove_file(format!("{}.{}", path, i));
+        }
+    }
+
+    #[test]
+    fn the_csv_file_is_rotated_when_it_is_full() {
+        let path = std::env::temp_dir()
+            .join(format!(
+                "rustarok_telemetry_test_{}.csv",
+                std::process::id()
+            ))
+            .to_str()
+            .unwrap()
+            .to_owned();
+        remove_files(&path, 3);
+        // two rows fit into a file
+        let max_bytes = (CsvFile::HEADER.len() + 2 * row(0).len()) as u64;
+        let mut csv = CsvFile::open(&path, max_bytes, 3).unwrap();
+        for i in 1..=7 {
+            csv.append(&row(i)).unwrap();
+        }
+        let read = |suffix: &str| std::fs::read_to_string(format!("{}{}", path, suffix));
+
+        assert_eq!(read("").unwrap(), format!("{}{}", CsvFile::HEADER, row(7)));
+        assert_eq!(
+            read(".1").unwrap(),
+            format!("{}{}", CsvFile::HEADER, rows(5..=6))
+        );
+        assert_eq!(
+            read(".2").unwrap(),
+            format!("{}{}", CsvFile::HEADER, rows(3..=4))
+        );
+        assert!(read(".3").is_err());
+        remove_files(&path, 3);
+    }
+
+    #[test]
+    fn a_single_csv_file_is_started_again_when_it_is_full() {
+        let path = std::env::temp_dir()
+            .join(format!(
+                "rustarok_telemetry_single_test_{}.csv",
+                std::process::id()
+            ))
+            .to_str()
+            .unwrap()
+            .to_owned();
+        remove_files(&path, 1);
+        let max_bytes = (CsvFile::HEADER.len() + 2 * row(0).len()) as u64;
+        let mut csv = CsvFile::open(&path, max_bytes, 1).unwrap();
+        for i in 1..=3 {
+            csv.append(&row(i)).unwrap();
+        }
+
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            format!("{}{}", CsvFile::HEADER, row(3))
+        );
+        assert!(std::fs::metadata(format!("{}.1", path)).is_err());
+        remove_files(&path, 1);
+    }
+}
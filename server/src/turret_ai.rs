@@ -0,0 +1,139 @@
+//! The turrets of Gazebo and the guards of the teams are controlled by the server,
+//! they attack the enemies in their range without leaving their post.
+use rustarok_common::common::Vec2;
+use rustarok_common::components::char::{
+    create_common_player_entity, CharOutlook, CharType, ControllerEntityId, JobId,
+    LocalCharEntityId, LocalCharStateComp, MonsterId, StaticCharDataComponent, Team,
+};
+use rustarok_common::components::controller::ControllerComponent;
+use rustarok_common::systems::turret_ai::TurretAi;
+use specs::prelude::*;
+
+/// The turret of a Gazebo, on the turret character
+#[derive(Component)]
+pub struct TurretComponent {
+    pub owner_entity_id: LocalCharEntityId,
+    /// set by `GazTurretTarget`
+    pub preferred_target: Option<LocalCharEntityId>,
+}
+
+/// Marks the controllers which are driven by the `TurretAiSystem`, the guards have one too
+#[derive(Component)]
+pub struct TurretControllerComponent {
+    /// where the character was spawned, it goes back there
+    pub post: Vec2,
+}
+
+pub struct TurretAiSystem;
+
+impl<'a> System<'a> for TurretAiSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, ControllerComponent>,
+        ReadStorage<'a, StaticCharDataComponent>,
+        ReadStorage<'a, LocalCharStateComp>,
+        ReadStorage<'a, TurretControllerComponent>,
+        ReadStorage<'a, TurretComponent>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            mut controller_storage,
+            static_char_data_storage,
+            char_state_storage,
+            turret_controller_storage,
+            turret_storage,
+        ): Self::SystemData,
+    ) {
+        for (controller_id, controller, turret_controller) in (
+            &entities,
+            &mut controller_storage,
+            &turret_controller_storage,
+        )
+            .join()
+        {
+            let turret_id = match controller.controlled_entity {
+                Some(turret_id) if char_state_storage.get(turret_id.into()).is_some() => turret_id,
+                _ => {
+                    // the corpse of the turret has been removed
+                    if let Err(e) = entities.delete(controller_id) {
+                        log::error!(
+                            "Could not remove the controller {:?}: {:?}",
+                            ControllerEntityId::new(controller_id),
+                            e
+                        );
+                    }
+                    continue;
+                }
+            };
+            controller.intention = TurretAi::decide_intention(
+                &entities,
+                &static_char_data_storage,
+                &char_state_storage,
+                turret_id,
+                turret_storage
+                    .get(turret_id.into())
+                    .and_then(|it| it.preferred_target),
+                turret_controller.post,
+            );
+        }
+    }
+}
+
+/// The turret and its controller
+pub fn create_turret(
+    ecs_world: &mut specs::World,
+    owner_entity_id: LocalCharEntityId,
+    pos: Vec2,
+    team: Team,
+) -> LocalCharEntityId {
+    let turret_id = create_common_player_entity(
+        "Turret".to_owned(),
+        ecs_world,
+        CharType::Minion,
+        JobId::Turret,
+        pos,
+        team,
+        CharOutlook::Monster(MonsterId::Dimik),
+    )
+    .with(TurretComponent {
+        owner_entity_id,
+        preferred_target: None,
+    })
+    .build();
+    create_controller(ecs_world, turret_id, pos);
+    return LocalCharEntityId::from(turret_id);
+}
+
+/// The guard and its controller
+pub fn create_guard(
+    ecs_world: &mut specs::World,
+    pos: Vec2,
+    team: Team,
+    outlook: CharOutlook,
+) -> LocalCharEntityId {
+    let guard_id = create_common_player_entity(
+        "Guard".to_owned(),
+        ecs_world,
+        CharType::Guard,
+        JobId::Guard,
+        pos,
+        team,
+        outlook,
+    )
+    .build();
+    create_controller(ecs_world, guard_id, pos);
+    return LocalCharEntityId::from(guard_id);
+}
+
+fn create_controller(ecs_world: &mut specs::World, char_entity_id: Entity, post: Vec2) {
+    ecs_world
+        .create_entity()
+        .with(ControllerComponent::new(LocalCharEntityId::from(
+            char_entity_id,
+        )))
+        .with(TurretControllerComponent { post })
+        .build();
+}